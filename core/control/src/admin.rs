@@ -1,8 +1,10 @@
 use base64::Engine as _;
 
 use crate::auth::{ensure_admin, ensure_livekit};
+use crate::bug_reports::{BugStatus, BugWorkflowView};
 use crate::config::*;
 use crate::rooms::SessionEvent;
+use crate::stats_series::record_stats_sample;
 use crate::AppState;
 
 use axum::{
@@ -244,6 +246,9 @@ pub(crate) struct BugReport {
     pub(crate) github_issue_number: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) github_issue_url: Option<String>,
+    /// Desktop installation UUID, used to link diagnostics incidents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) install_id: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -283,6 +288,8 @@ pub(crate) struct BugReportRequest {
     pub(crate) participant_count: Option<u32>,
     #[serde(default)]
     pub(crate) connection_state: Option<String>,
+    #[serde(default)]
+    pub(crate) install_id: Option<String>,
 }
 
 // ── Response structs ─────────────────────────────────────────────────────
@@ -439,9 +446,23 @@ pub(crate) struct UserMetrics {
     ice_remote_type: Option<String>,
 }
 
+#[derive(Serialize)]
+pub(crate) struct AdminBugReport {
+    #[serde(flatten)]
+    report: BugReport,
+    #[serde(flatten)]
+    workflow: BugWorkflowView,
+}
+
 #[derive(Serialize)]
 pub(crate) struct BugReportsResponse {
-    reports: Vec<BugReport>,
+    reports: Vec<AdminBugReport>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BugReportsQuery {
+    #[serde(default)]
+    status: Option<BugStatus>,
 }
 
 #[derive(Clone, Serialize)]
@@ -892,7 +913,6 @@ pub(crate) async fn admin_report_stats(
     Json(payload): Json<ClientStats>,
 ) -> Result<StatusCode, StatusCode> {
     ensure_admin(&state, &headers)?;
-    let mut stats = state.client_stats.lock().unwrap_or_else(|e| e.into_inner());
     let mut entry = payload;
     entry.updated_at = now_ts();
 
@@ -909,7 +929,14 @@ pub(crate) async fn admin_report_stats(
         ice_remote_type: entry.ice_remote_type.clone(),
     };
 
-    stats.insert(entry.identity.clone(), entry);
+    let sample = entry.clone();
+    state
+        .client_stats
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(entry.identity.clone(), entry);
+
+    record_stats_sample(&state, sample).await;
 
     {
         let mut history = state
@@ -1007,19 +1034,27 @@ pub(crate) async fn client_stats_report(
     // Trust the JWT subject as the identity, not what the client sent — prevents
     // a client from posting stats under a different identity.
     let identity = claims.sub;
-    let mut stats = state.client_stats.lock().unwrap_or_else(|e| e.into_inner());
     let now = now_ts();
-    match stats.get_mut(&identity) {
-        Some(existing) => {
-            merge_client_stats(existing, payload, now);
-        }
-        None => {
-            let mut entry = payload;
-            entry.identity = identity.clone();
-            entry.updated_at = now;
-            stats.insert(identity, entry);
+    let mut sample = payload.clone();
+    sample.identity = identity.clone();
+    {
+        let mut stats = state.client_stats.lock().unwrap_or_else(|e| e.into_inner());
+        match stats.get_mut(&identity) {
+            Some(existing) => {
+                if sample.room.is_empty() {
+                    sample.room = existing.room.clone();
+                }
+                merge_client_stats(existing, payload, now);
+            }
+            None => {
+                let mut entry = payload;
+                entry.identity = identity.clone();
+                entry.updated_at = now;
+                stats.insert(identity, entry);
+            }
         }
     }
+    record_stats_sample(&state, sample).await;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -1172,9 +1207,14 @@ pub(crate) async fn submit_bug_report(
 
     let now = now_ts();
     info!("Bug report received (len={})", payload.description.len());
+    let (id, status_token) = state.bug_workflow.register(now_ts_ms());
+    let install_id = payload
+        .install_id
+        .map(|install_id| install_id.trim().to_ascii_lowercase())
+        .filter(|install_id| crate::diagnostics::valid_install_id(install_id));
 
     let mut report = BugReport {
-        id: now,
+        id,
         identity: payload
             .identity
             .clone()
@@ -1202,6 +1242,7 @@ pub(crate) async fn submit_bug_report(
         connection_state: payload.connection_state,
         github_issue_number: None,
         github_issue_url: None,
+        install_id,
     };
 
     // Create GitHub Issue if configured (10s timeout so we don't block the user)
@@ -1237,15 +1278,38 @@ pub(crate) async fn submit_bug_report(
         }
     }
 
-    Ok(Json(serde_json::json!({ "ok": true })))
+    // The status token is only ever returned here; it unlocks the reporter's
+    // status view and attachment uploads for this report.
+    Ok(Json(serde_json::json!({ "ok": true, "id": id, "status_token": status_token })))
 }
 
 pub(crate) async fn admin_bug_reports(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(query): Query<BugReportsQuery>,
 ) -> Result<Json<BugReportsResponse>, StatusCode> {
     ensure_admin(&state, &headers)?;
 
+    let workflows = state.bug_workflow.views();
+    let reports = load_bug_reports(&state)
+        .into_iter()
+        .map(|report| AdminBugReport {
+            workflow: workflows.get(&report.id).cloned().unwrap_or_default(),
+            report,
+        })
+        .filter(|entry| query.status.is_none_or(|status| entry.workflow.status == status))
+        .take(200)
+        .collect();
+
+    Ok(Json(BugReportsResponse { reports }))
+}
+
+pub(crate) fn find_bug_report(state: &AppState, id: u64) -> Option<BugReport> {
+    load_bug_reports(state).into_iter().find(|report| report.id == id)
+}
+
+/// All reports, newest first, from memory and the daily logs on disk.
+fn load_bug_reports(state: &AppState) -> Vec<BugReport> {
     let in_mem = state.bug_reports.lock().unwrap_or_else(|e| e.into_inner());
     let mut all: Vec<BugReport> = in_mem.clone();
     drop(in_mem);
@@ -1275,9 +1339,7 @@ pub(crate) async fn admin_bug_reports(
     }
 
     all.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-    all.truncate(200);
-
-    Ok(Json(BugReportsResponse { reports: all }))
+    all
 }
 
 pub(crate) async fn admin_deploys(
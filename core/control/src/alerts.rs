@@ -0,0 +1,721 @@
+//! Alert rules evaluated over diagnostics incidents and live client stats.
+//!
+//! Rules are loaded once at startup from the JSON file named by
+//! `CORE_ALERT_RULES_FILE` and evaluated every 30 seconds. Each firing is keyed
+//! by rule and subject (a rule, or a rule plus an incident group). A key fires
+//! only when its condition starts holding, and never again within the rule's
+//! cool-down, so a flapping condition cannot page repeatedly. Firings and
+//! recoveries are appended to a local JSONL log; firings are also POSTed to
+//! every configured webhook.
+
+use crate::auth::ensure_admin;
+use crate::config::{now_ts, now_ts_ms};
+use crate::diagnostics::DiagnosticSeverity;
+use crate::diagnostics_groups::IncidentGroupSummary;
+use crate::AppState;
+
+use axum::{
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tracing::{info, warn};
+
+pub(crate) const ALERT_EVALUATION_INTERVAL: Duration = Duration::from_secs(30);
+/// Client stats older than this no longer count toward client-wide rules.
+const CLIENT_STATS_FRESH_SECS: u64 = 120;
+const RECENT_ALERTS: usize = 100;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum AlertCondition {
+    /// At least `count` incidents with a `fatal` event within the window.
+    FatalIncidents { count: usize, window_minutes: u64 },
+    /// An incident group first seen after the latest deploy, for
+    /// `window_minutes` after that deploy.
+    NewGroupAfterDeploy {
+        #[serde(default = "default_new_group_window_minutes")]
+        window_minutes: u64,
+    },
+    /// At least `min_clients` clients currently report `Red` capture health.
+    CaptureHealthRed { min_clients: usize },
+    /// Average `screen_fps` across at least `min_clients` reporting clients
+    /// is below `threshold`.
+    ScreenFpsBelow {
+        threshold: f64,
+        #[serde(default = "default_min_clients")]
+        min_clients: usize,
+    },
+}
+
+fn default_new_group_window_minutes() -> u64 {
+    24 * 60
+}
+
+fn default_min_clients() -> usize {
+    1
+}
+
+fn default_cooldown_minutes() -> u64 {
+    60
+}
+
+impl AlertCondition {
+    fn kind(&self) -> &'static str {
+        match self {
+            AlertCondition::FatalIncidents { .. } => "fatal_incidents",
+            AlertCondition::NewGroupAfterDeploy { .. } => "new_group_after_deploy",
+            AlertCondition::CaptureHealthRed { .. } => "capture_health_red",
+            AlertCondition::ScreenFpsBelow { .. } => "screen_fps_below",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct AlertRule {
+    pub(crate) id: String,
+    #[serde(flatten)]
+    pub(crate) condition: AlertCondition,
+    #[serde(default = "default_cooldown_minutes")]
+    pub(crate) cooldown_minutes: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub(crate) struct AlertConfig {
+    #[serde(default)]
+    pub(crate) webhooks: Vec<String>,
+    #[serde(default)]
+    pub(crate) rules: Vec<AlertRule>,
+}
+
+impl AlertConfig {
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("read {:?}: {}", path, e))?;
+        let config: AlertConfig =
+            serde_json::from_str(&text).map_err(|e| format!("parse {:?}: {}", path, e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for webhook in &self.webhooks {
+            if !webhook.starts_with("https://") && !webhook.starts_with("http://") {
+                return Err(format!("webhook {:?} is not an http(s) URL", webhook));
+            }
+        }
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            if rule.id.is_empty()
+                || rule.id.len() > 64
+                || !rule
+                    .id
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_'))
+            {
+                return Err(format!("invalid rule id {:?}", rule.id));
+            }
+            if !ids.insert(rule.id.as_str()) {
+                return Err(format!("duplicate rule id {:?}", rule.id));
+            }
+            let valid = match &rule.condition {
+                AlertCondition::FatalIncidents {
+                    count,
+                    window_minutes,
+                } => *count > 0 && *window_minutes > 0,
+                AlertCondition::NewGroupAfterDeploy { window_minutes } => *window_minutes > 0,
+                AlertCondition::CaptureHealthRed { min_clients } => *min_clients > 0,
+                AlertCondition::ScreenFpsBelow {
+                    threshold,
+                    min_clients,
+                } => threshold.is_finite() && *threshold > 0.0 && *min_clients > 0,
+            };
+            if !valid {
+                return Err(format!("rule {:?} has out-of-range parameters", rule.id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Longest fatal-incident window, so one index query serves every rule.
+    fn fatal_window_ms(&self) -> Option<u64> {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule.condition {
+                AlertCondition::FatalIncidents { window_minutes, .. } => {
+                    Some(window_minutes * 60_000)
+                }
+                _ => None,
+            })
+            .max()
+    }
+
+    fn wants_groups(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| matches!(rule.condition, AlertCondition::NewGroupAfterDeploy { .. }))
+    }
+}
+
+/// One client's contribution to the client-wide rules.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ClientSample {
+    pub(crate) capture_red: bool,
+    pub(crate) screen_fps: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AlertInputs {
+    pub(crate) now_ms: u64,
+    pub(crate) deploy_at_ms: u64,
+    /// Arrival times of fatal incidents within the longest fatal window.
+    pub(crate) fatal_received_at_ms: Vec<u64>,
+    pub(crate) groups: Vec<IncidentGroupSummary>,
+    pub(crate) clients: Vec<ClientSample>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AlertState {
+    Firing,
+    Resolved,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct AlertEvent {
+    pub(crate) rule_id: String,
+    pub(crate) kind: &'static str,
+    pub(crate) key: String,
+    pub(crate) state: AlertState,
+    pub(crate) summary: String,
+    pub(crate) at_ms: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct AlertEngine {
+    config: AlertConfig,
+    last_fired_ms: HashMap<String, u64>,
+    /// Keys whose condition currently holds, and whether that activation
+    /// was notified (it is not when it started inside the cool-down).
+    active: HashMap<String, bool>,
+    recent: VecDeque<AlertEvent>,
+}
+
+impl AlertEngine {
+    pub(crate) fn new(config: AlertConfig) -> Self {
+        Self {
+            config,
+            last_fired_ms: HashMap::new(),
+            active: HashMap::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Returns the firings and recoveries produced by this evaluation.
+    pub(crate) fn evaluate(&mut self, inputs: &AlertInputs) -> Vec<AlertEvent> {
+        let mut holding: Vec<(usize, String, String)> = Vec::new();
+        for (index, rule) in self.config.rules.iter().enumerate() {
+            for (key, summary) in conditions_holding(rule, inputs) {
+                holding.push((index, key, summary));
+            }
+        }
+
+        let mut events = Vec::new();
+        let holding_keys: HashSet<String> = holding.iter().map(|(_, key, _)| key.clone()).collect();
+        for (index, key, summary) in holding {
+            if self.active.contains_key(&key) {
+                continue;
+            }
+            let rule = &self.config.rules[index];
+            let cooldown_ms = rule.cooldown_minutes * 60_000;
+            if self
+                .last_fired_ms
+                .get(&key)
+                .is_some_and(|at| inputs.now_ms.saturating_sub(*at) < cooldown_ms)
+            {
+                self.active.insert(key, false);
+                continue;
+            }
+            self.active.insert(key.clone(), true);
+            self.last_fired_ms.insert(key.clone(), inputs.now_ms);
+            events.push(AlertEvent {
+                rule_id: rule.id.clone(),
+                kind: rule.condition.kind(),
+                key,
+                state: AlertState::Firing,
+                summary,
+                at_ms: inputs.now_ms,
+            });
+        }
+
+        let cleared: Vec<String> = self
+            .active
+            .keys()
+            .filter(|key| !holding_keys.contains(*key))
+            .cloned()
+            .collect();
+        for key in cleared {
+            if self.active.remove(&key) != Some(true) {
+                continue;
+            }
+            let Some(rule) = self
+                .config
+                .rules
+                .iter()
+                .find(|rule| key == rule.id || key.starts_with(&format!("{}:", rule.id)))
+            else {
+                continue;
+            };
+            events.push(AlertEvent {
+                rule_id: rule.id.clone(),
+                kind: rule.condition.kind(),
+                key,
+                state: AlertState::Resolved,
+                summary: "Condition cleared".to_string(),
+                at_ms: inputs.now_ms,
+            });
+        }
+        // Expired cool-downs no longer affect anything; keep the map bounded
+        // as per-group keys come and go.
+        let longest_cooldown_ms = self
+            .config
+            .rules
+            .iter()
+            .map(|rule| rule.cooldown_minutes * 60_000)
+            .max()
+            .unwrap_or(0);
+        self.last_fired_ms
+            .retain(|_, at| inputs.now_ms.saturating_sub(*at) < longest_cooldown_ms);
+
+        for event in &events {
+            self.recent.push_back(event.clone());
+        }
+        while self.recent.len() > RECENT_ALERTS {
+            self.recent.pop_front();
+        }
+        events
+    }
+}
+
+fn conditions_holding(rule: &AlertRule, inputs: &AlertInputs) -> Vec<(String, String)> {
+    match &rule.condition {
+        AlertCondition::FatalIncidents {
+            count,
+            window_minutes,
+        } => {
+            let since = inputs.now_ms.saturating_sub(window_minutes * 60_000);
+            let seen = inputs
+                .fatal_received_at_ms
+                .iter()
+                .filter(|at| **at >= since)
+                .count();
+            if seen >= *count {
+                vec![(
+                    rule.id.clone(),
+                    format!(
+                        "{} fatal incident(s) in the last {} minute(s)",
+                        seen, window_minutes
+                    ),
+                )]
+            } else {
+                Vec::new()
+            }
+        }
+        AlertCondition::NewGroupAfterDeploy { window_minutes } => {
+            let until = inputs.deploy_at_ms + window_minutes * 60_000;
+            if inputs.now_ms > until {
+                return Vec::new();
+            }
+            inputs
+                .groups
+                .iter()
+                .filter(|group| group.first_seen_ms >= inputs.deploy_at_ms)
+                .map(|group| {
+                    (
+                        format!("{}:{}", rule.id, group.group_id),
+                        format!(
+                            "New {:?} incident group {} ({}) since the latest deploy",
+                            group.highest_severity, group.code, group.group_id
+                        ),
+                    )
+                })
+                .collect()
+        }
+        AlertCondition::CaptureHealthRed { min_clients } => {
+            let red = inputs
+                .clients
+                .iter()
+                .filter(|client| client.capture_red)
+                .count();
+            if red >= *min_clients {
+                vec![(
+                    rule.id.clone(),
+                    format!("{} client(s) report Red capture health", red),
+                )]
+            } else {
+                Vec::new()
+            }
+        }
+        AlertCondition::ScreenFpsBelow {
+            threshold,
+            min_clients,
+        } => {
+            let samples: Vec<f64> = inputs
+                .clients
+                .iter()
+                .filter_map(|client| client.screen_fps)
+                .collect();
+            if samples.len() < *min_clients {
+                return Vec::new();
+            }
+            let average = samples.iter().sum::<f64>() / samples.len() as f64;
+            if average < *threshold {
+                vec![(
+                    rule.id.clone(),
+                    format!(
+                        "Average screen fps {:.1} across {} client(s) is below {}",
+                        average,
+                        samples.len(),
+                        threshold
+                    ),
+                )]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Runs the evaluation loop for the lifetime of the process.
+pub(crate) async fn run_alerts(
+    state: AppState,
+    engine: Arc<Mutex<AlertEngine>>,
+    log_path: PathBuf,
+) {
+    let (fatal_window_ms, wants_groups, webhooks) = {
+        let engine = engine.lock().unwrap_or_else(|e| e.into_inner());
+        (
+            engine.config.fatal_window_ms(),
+            engine.config.wants_groups(),
+            engine.config.webhooks.clone(),
+        )
+    };
+    // Every deploy restarts the control plane; a viewer-only deploy is seen
+    // as a change of the viewer stamp.
+    let mut deploy_at_ms = now_ts_ms();
+    let mut viewer_stamp = state
+        .viewer_stamp
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let mut interval = tokio::time::interval(ALERT_EVALUATION_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        let now_ms = now_ts_ms();
+        let current_stamp = state
+            .viewer_stamp
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if current_stamp != viewer_stamp {
+            viewer_stamp = current_stamp;
+            deploy_at_ms = now_ms;
+        }
+
+        let mut inputs = AlertInputs {
+            now_ms,
+            deploy_at_ms,
+            clients: client_samples(&state),
+            ..AlertInputs::default()
+        };
+        if let Some(runtime) = state.diagnostics.clone() {
+            let result = tokio::task::spawn_blocking(move || {
+                let store = runtime.store();
+                let fatal = match fatal_window_ms {
+                    Some(window_ms) => store.incidents_received_since(
+                        now_ms.saturating_sub(window_ms),
+                        DiagnosticSeverity::Fatal,
+                    )?,
+                    None => Vec::new(),
+                };
+                let groups = if wants_groups {
+                    store.list_groups(None, usize::MAX)?
+                } else {
+                    Vec::new()
+                };
+                Ok::<_, crate::diagnostics::DiagnosticsError>((fatal, groups))
+            })
+            .await;
+            match result {
+                Ok(Ok((fatal, groups))) => {
+                    inputs.fatal_received_at_ms = fatal;
+                    inputs.groups = groups;
+                }
+                Ok(Err(error)) => warn!("alert diagnostics input failed: {}", error),
+                Err(error) => warn!("alert diagnostics task failed: {}", error),
+            }
+        }
+
+        let events = engine
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .evaluate(&inputs);
+        for event in events {
+            match event.state {
+                AlertState::Firing => warn!("alert {} firing: {}", event.key, event.summary),
+                AlertState::Resolved => info!("alert {} resolved", event.key),
+            }
+            append_alert_log(&log_path, &event);
+            if event.state == AlertState::Firing {
+                for webhook in &webhooks {
+                    let client = state.http_client.clone();
+                    let webhook = webhook.clone();
+                    let event = event.clone();
+                    tokio::spawn(async move {
+                        if let Err(error) = send_webhook(&client, &webhook, &event).await {
+                            warn!("alert webhook delivery failed: {}", error);
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn client_samples(state: &AppState) -> Vec<ClientSample> {
+    let now = now_ts();
+    let stats = state.client_stats.lock().unwrap_or_else(|e| e.into_inner());
+    stats
+        .values()
+        .filter(|stats| now.saturating_sub(stats.updated_at) <= CLIENT_STATS_FRESH_SECS)
+        .map(|stats| ClientSample {
+            capture_red: stats
+                .capture_health
+                .as_ref()
+                .is_some_and(|health| health.level.eq_ignore_ascii_case("red")),
+            screen_fps: stats.screen_fps.filter(|fps| fps.is_finite()),
+        })
+        .collect()
+}
+
+fn append_alert_log(path: &Path, event: &AlertEvent) {
+    let Ok(line) = serde_json::to_string(event) else {
+        return;
+    };
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(error) = result {
+        warn!("alert log write failed: {}", error);
+    }
+}
+
+/// `text` and `content` carry the same line so Slack- and Discord-style
+/// incoming webhooks both render it; `alert` has the structured event.
+async fn send_webhook(
+    client: &reqwest::Client,
+    url: &str,
+    event: &AlertEvent,
+) -> Result<(), String> {
+    let line = format!("[Echo alert] {}: {}", event.rule_id, event.summary);
+    let response = client
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&serde_json::json!({ "text": line, "content": line, "alert": event }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("webhook returned {}", response.status()))
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct AdminAlertsResponse {
+    enabled: bool,
+    rules: Vec<AlertRule>,
+    webhook_count: usize,
+    active: Vec<String>,
+    recent: Vec<AlertEvent>,
+}
+
+/// GET /admin/api/alerts — configured rules, active keys, and recent events
+/// (newest first). Webhook URLs are secrets and only counted.
+pub(crate) async fn admin_alerts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<AdminAlertsResponse>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    let Some(engine) = state.alerts.clone() else {
+        return Ok(Json(AdminAlertsResponse {
+            enabled: false,
+            rules: Vec::new(),
+            webhook_count: 0,
+            active: Vec::new(),
+            recent: Vec::new(),
+        }));
+    };
+    let engine = engine.lock().unwrap_or_else(|e| e.into_inner());
+    let mut active: Vec<String> = engine.active.keys().cloned().collect();
+    active.sort();
+    Ok(Json(AdminAlertsResponse {
+        enabled: true,
+        rules: engine.config.rules.clone(),
+        webhook_count: engine.config.webhooks.len(),
+        active,
+        recent: engine.recent.iter().rev().cloned().collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::DiagnosticEventType;
+    use crate::diagnostics_groups::GroupStatus;
+
+    const MINUTE: u64 = 60_000;
+
+    fn engine(rules: serde_json::Value) -> AlertEngine {
+        let config: AlertConfig =
+            serde_json::from_value(serde_json::json!({ "rules": rules })).unwrap();
+        config.validate().unwrap();
+        AlertEngine::new(config)
+    }
+
+    fn group(group_id: &str, first_seen_ms: u64) -> IncidentGroupSummary {
+        IncidentGroupSummary {
+            group_id: group_id.to_string(),
+            event_type: DiagnosticEventType::JavascriptError,
+            code: "javascript.window_error".to_string(),
+            status: GroupStatus::Open,
+            highest_severity: DiagnosticSeverity::Error,
+            first_seen_ms,
+            last_seen_ms: first_seen_ms,
+            incident_count: 1,
+            event_count: 1,
+            affected_identities: 1,
+            affected_installs: 1,
+            app_versions: Vec::new(),
+            operating_systems: Vec::new(),
+            resolved_at_ms: None,
+            regressed_at_ms: None,
+        }
+    }
+
+    #[test]
+    fn fatal_bursts_fire_once_and_respect_cooldowns() {
+        let mut engine = engine(serde_json::json!([{
+            "id": "fatal-burst", "kind": "fatal_incidents",
+            "count": 2, "window_minutes": 10, "cooldown_minutes": 30
+        }]));
+        let mut inputs = AlertInputs {
+            now_ms: 100 * MINUTE,
+            fatal_received_at_ms: vec![85 * MINUTE, 95 * MINUTE],
+            ..AlertInputs::default()
+        };
+        assert!(engine.evaluate(&inputs).is_empty());
+
+        inputs.fatal_received_at_ms.push(99 * MINUTE);
+        let fired = engine.evaluate(&inputs);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].state, AlertState::Firing);
+        // Still holding: de-duplicated.
+        inputs.now_ms += MINUTE;
+        assert!(engine.evaluate(&inputs).is_empty());
+
+        // Clears, then flaps back inside the cool-down: recovery only, and
+        // the silent activation does not log a second recovery.
+        inputs.now_ms = 120 * MINUTE;
+        let cleared = engine.evaluate(&inputs);
+        assert_eq!(cleared.len(), 1);
+        assert_eq!(cleared[0].state, AlertState::Resolved);
+        inputs.fatal_received_at_ms = vec![118 * MINUTE, 119 * MINUTE];
+        assert!(engine.evaluate(&inputs).is_empty());
+
+        // After the cool-down a fresh burst fires again.
+        inputs.now_ms = 140 * MINUTE;
+        inputs.fatal_received_at_ms = Vec::new();
+        assert!(engine.evaluate(&inputs).is_empty());
+        inputs.fatal_received_at_ms = vec![139 * MINUTE, 140 * MINUTE];
+        assert_eq!(engine.evaluate(&inputs).len(), 1);
+    }
+
+    #[test]
+    fn new_groups_and_client_rules_fire_per_subject() {
+        let mut engine = engine(serde_json::json!([
+            { "id": "new-group", "kind": "new_group_after_deploy", "window_minutes": 60 },
+            { "id": "capture", "kind": "capture_health_red", "min_clients": 2 },
+            { "id": "fps", "kind": "screen_fps_below", "threshold": 20.0, "min_clients": 2 }
+        ]));
+        let red = ClientSample {
+            capture_red: true,
+            screen_fps: Some(12.0),
+        };
+        let inputs = AlertInputs {
+            now_ms: 30 * MINUTE,
+            deploy_at_ms: 10 * MINUTE,
+            groups: vec![group("grp_old", 5 * MINUTE), group("grp_new", 20 * MINUTE)],
+            clients: vec![red.clone(), red],
+            ..AlertInputs::default()
+        };
+        let mut keys: Vec<String> = engine
+            .evaluate(&inputs)
+            .into_iter()
+            .map(|event| event.key)
+            .collect();
+        keys.sort();
+        assert_eq!(keys, vec!["capture", "fps", "new-group:grp_new"]);
+
+        // The deploy watch window has passed; one client is not "several".
+        let later = AlertInputs {
+            now_ms: 80 * MINUTE,
+            deploy_at_ms: 10 * MINUTE,
+            groups: inputs.groups.clone(),
+            clients: vec![ClientSample {
+                capture_red: true,
+                screen_fps: Some(30.0),
+            }],
+            ..AlertInputs::default()
+        };
+        let events = engine.evaluate(&later);
+        assert_eq!(events.len(), 3);
+        assert!(events
+            .iter()
+            .all(|event| event.state == AlertState::Resolved));
+    }
+
+    #[test]
+    fn rejects_invalid_rule_files() {
+        let config: AlertConfig = serde_json::from_value(serde_json::json!({
+            "webhooks": ["ftp://example.invalid"],
+            "rules": []
+        }))
+        .unwrap();
+        assert!(config.validate().is_err());
+        let config: AlertConfig = serde_json::from_value(serde_json::json!({
+            "rules": [
+                { "id": "a", "kind": "capture_health_red", "min_clients": 1 },
+                { "id": "a", "kind": "capture_health_red", "min_clients": 2 }
+            ]
+        }))
+        .unwrap();
+        assert!(config.validate().is_err());
+        assert!(serde_json::from_value::<AlertConfig>(serde_json::json!({
+            "rules": [{ "id": "a", "kind": "unknown" }]
+        }))
+        .is_err());
+    }
+}
@@ -106,6 +106,8 @@ pub(crate) struct AuthenticatedParticipant {
 pub(crate) struct JamActor {
     pub(crate) actor_id: String,
     pub(crate) display_name: String,
+    /// The LiveKit room whose Jam this actor acts on.
+    pub(crate) room: String,
 }
 
 pub(crate) fn bounded_jam_actor_display_name(name: Option<&str>, fallback: &str) -> String {
@@ -697,6 +699,7 @@ pub(crate) fn jam_actor_from_claims(claims: &LiveKitClaims) -> Result<JamActor,
     Ok(JamActor {
         actor_id: actor_id.to_string(),
         display_name: bounded_jam_actor_display_name(claims.name.as_deref(), &claims.sub),
+        room: claims.video.room.clone(),
     })
 }
 
@@ -795,6 +798,7 @@ mod tests {
         let actor = jam_actor_from_claims(&claims).unwrap();
         assert_eq!(actor.actor_id, "ea1_test");
         assert_eq!(actor.display_name, "Sam");
+        assert_eq!(actor.room, claims.video.room);
 
         claims.echo_actor_id = None;
         assert_eq!(
@@ -0,0 +1,710 @@
+//! Bug report workflow: status, admin comments, attachments, and the reporter
+//! status view.
+//!
+//! The daily `bugs-*.json` logs stay append-only. Everything that changes after
+//! submission lives in `workflow-v1.json` beside them, keyed by report ID, and
+//! attachment bodies are stored under `attachments/{report_id}/`. Reports
+//! submitted before the workflow existed read as `new` until an admin touches
+//! them.
+//!
+//! A reporter gets a random status token once, in the submit response. Only
+//! its SHA-256 is kept; the token is required to read the reporter view and to
+//! attach files, so another participant cannot read or extend someone else's
+//! report.
+
+use crate::admin::find_bug_report;
+use crate::auth::ensure_admin;
+use crate::config::now_ts_ms;
+use crate::jam_library::{favorite_backup_path, write_atomic};
+use crate::AppState;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Json, Path, Query, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, fs, io, path::PathBuf, sync::Mutex};
+use tracing::warn;
+
+pub(crate) const BUG_WORKFLOW_SCHEMA_VERSION: u16 = 1;
+pub(crate) const BUG_TOKEN_HEADER: &str = "x-echo-bug-token";
+pub(crate) const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024;
+const MAX_ATTACHMENTS_PER_REPORT: usize = 8;
+const MAX_ATTACHMENT_NAME_CHARS: usize = 120;
+const MAX_COMMENT_CHARS: usize = 4_000;
+const MAX_COMMENTS_PER_REPORT: usize = 200;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BugStatus {
+    #[default]
+    New,
+    Triaged,
+    Fixed,
+    Wontfix,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct BugComment {
+    pub(crate) id: u32,
+    pub(crate) body: String,
+    /// Shown to the reporter in their status view.
+    pub(crate) public: bool,
+    pub(crate) created_at_ms: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct BugAttachment {
+    pub(crate) id: u32,
+    pub(crate) name: String,
+    pub(crate) content_type: String,
+    pub(crate) size_bytes: u64,
+    pub(crate) uploaded_at_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+struct BugWorkflow {
+    #[serde(default)]
+    status: BugStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status_changed_at_ms: Option<u64>,
+    #[serde(default)]
+    comments: Vec<BugComment>,
+    #[serde(default)]
+    attachments: Vec<BugAttachment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reporter_token_sha256: Option<String>,
+}
+
+/// The admin-facing workflow state of one report.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub(crate) struct BugWorkflowView {
+    pub(crate) status: BugStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) status_changed_at_ms: Option<u64>,
+    pub(crate) comments: Vec<BugComment>,
+    pub(crate) attachments: Vec<BugAttachment>,
+}
+
+impl From<&BugWorkflow> for BugWorkflowView {
+    fn from(workflow: &BugWorkflow) -> Self {
+        Self {
+            status: workflow.status,
+            status_changed_at_ms: workflow.status_changed_at_ms,
+            comments: workflow.comments.clone(),
+            attachments: workflow.attachments.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct BugWorkflowFile {
+    schema_version: u16,
+    /// Highest report ID handed out, so IDs stay unique within a millisecond
+    /// and across restarts.
+    #[serde(default)]
+    last_id: u64,
+    #[serde(default)]
+    reports: BTreeMap<u64, BugWorkflow>,
+}
+
+impl Default for BugWorkflowFile {
+    fn default() -> Self {
+        Self {
+            schema_version: BUG_WORKFLOW_SCHEMA_VERSION,
+            last_id: 0,
+            reports: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum AttachmentError {
+    Empty,
+    TooLarge,
+    TooMany,
+    UnsupportedType,
+    Io(String),
+}
+
+impl AttachmentError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AttachmentError::Empty => StatusCode::BAD_REQUEST,
+            AttachmentError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            AttachmentError::TooMany => StatusCode::CONFLICT,
+            AttachmentError::UnsupportedType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AttachmentError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+pub(crate) struct BugWorkflowStore {
+    path: PathBuf,
+    attachments_dir: PathBuf,
+    writable: bool,
+    inner: Mutex<BugWorkflowFile>,
+}
+
+impl BugWorkflowStore {
+    pub(crate) fn open(bug_log_dir: &std::path::Path) -> io::Result<Self> {
+        fs::create_dir_all(bug_log_dir)?;
+        let path = bug_log_dir.join("workflow-v1.json");
+        let backup = favorite_backup_path(&path);
+        let data = if path.exists() {
+            load_workflow_file(&path)?
+        } else if backup.exists() {
+            let recovered = load_workflow_file(&backup)?;
+            fs::rename(&backup, &path)?;
+            warn!(
+                "Recovered bug report workflow from {:?} after an interrupted atomic write",
+                backup
+            );
+            recovered
+        } else {
+            BugWorkflowFile::default()
+        };
+        Ok(Self {
+            path,
+            attachments_dir: bug_log_dir.join("attachments"),
+            writable: true,
+            inner: Mutex::new(data),
+        })
+    }
+
+    /// Reports still submit, but nothing after submission is persisted.
+    pub(crate) fn disabled(bug_log_dir: &std::path::Path) -> Self {
+        Self {
+            path: bug_log_dir.join("workflow-v1.json"),
+            attachments_dir: bug_log_dir.join("attachments"),
+            writable: false,
+            inner: Mutex::new(BugWorkflowFile::default()),
+        }
+    }
+
+    fn persist(&self, candidate: &BugWorkflowFile) -> io::Result<()> {
+        if !self.writable {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "bug report workflow store is read-only because its file could not be loaded",
+            ));
+        }
+        let bytes = serde_json::to_vec_pretty(candidate)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        write_atomic(&self.path, &bytes)
+    }
+
+    /// Hands out a unique report ID and, when the workflow is persisted, a
+    /// reporter status token for it.
+    pub(crate) fn register(&self, now_ms: u64) -> (u64, Option<String>) {
+        let mut data = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let id = now_ms.max(data.last_id + 1);
+        let mut token_bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut token_bytes);
+        let token = hex(&token_bytes);
+        let mut candidate = data.clone();
+        candidate.last_id = id;
+        candidate.reports.insert(
+            id,
+            BugWorkflow {
+                reporter_token_sha256: Some(token_digest(&token)),
+                ..BugWorkflow::default()
+            },
+        );
+        match self.persist(&candidate) {
+            Ok(()) => {
+                *data = candidate;
+                (id, Some(token))
+            }
+            Err(error) => {
+                if self.writable {
+                    warn!("bug report workflow write failed: {}", error);
+                }
+                data.last_id = id;
+                (id, None)
+            }
+        }
+    }
+
+    pub(crate) fn view(&self, id: u64) -> BugWorkflowView {
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .reports
+            .get(&id)
+            .map(BugWorkflowView::from)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn views(&self) -> BTreeMap<u64, BugWorkflowView> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .reports
+            .iter()
+            .map(|(id, workflow)| (*id, BugWorkflowView::from(workflow)))
+            .collect()
+    }
+
+    pub(crate) fn verify_reporter(&self, id: u64, token: &str) -> bool {
+        let data = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(expected) = data
+            .reports
+            .get(&id)
+            .and_then(|workflow| workflow.reporter_token_sha256.as_ref())
+        else {
+            return false;
+        };
+        constant_time_eq(expected.as_bytes(), token_digest(token).as_bytes())
+    }
+
+    pub(crate) fn set_status(
+        &self,
+        id: u64,
+        status: BugStatus,
+        now_ms: u64,
+    ) -> io::Result<BugWorkflowView> {
+        let mut data = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut candidate = data.clone();
+        let workflow = candidate.reports.entry(id).or_default();
+        if workflow.status != status {
+            workflow.status = status;
+            workflow.status_changed_at_ms = Some(now_ms);
+        }
+        let view = BugWorkflowView::from(&*workflow);
+        self.persist(&candidate)?;
+        *data = candidate;
+        Ok(view)
+    }
+
+    pub(crate) fn add_comment(
+        &self,
+        id: u64,
+        body: &str,
+        public: bool,
+        now_ms: u64,
+    ) -> io::Result<BugComment> {
+        let mut data = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut candidate = data.clone();
+        let workflow = candidate.reports.entry(id).or_default();
+        if workflow.comments.len() >= MAX_COMMENTS_PER_REPORT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "comment limit reached",
+            ));
+        }
+        let comment = BugComment {
+            id: workflow.comments.last().map_or(1, |last| last.id + 1),
+            body: body.to_string(),
+            public,
+            created_at_ms: now_ms,
+        };
+        workflow.comments.push(comment.clone());
+        self.persist(&candidate)?;
+        *data = candidate;
+        Ok(comment)
+    }
+
+    /// Stores an attachment body; only screenshots (PNG, JPEG, GIF, WebP) and
+    /// UTF-8 text such as log excerpts are accepted, by content.
+    pub(crate) fn add_attachment(
+        &self,
+        id: u64,
+        name: &str,
+        bytes: &[u8],
+        now_ms: u64,
+    ) -> Result<BugAttachment, AttachmentError> {
+        if bytes.is_empty() {
+            return Err(AttachmentError::Empty);
+        }
+        if bytes.len() > MAX_ATTACHMENT_BYTES {
+            return Err(AttachmentError::TooLarge);
+        }
+        let content_type = sniff_attachment_type(bytes).ok_or(AttachmentError::UnsupportedType)?;
+
+        let mut data = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut candidate = data.clone();
+        let workflow = candidate.reports.entry(id).or_default();
+        if workflow.attachments.len() >= MAX_ATTACHMENTS_PER_REPORT {
+            return Err(AttachmentError::TooMany);
+        }
+        let attachment = BugAttachment {
+            id: workflow.attachments.last().map_or(1, |last| last.id + 1),
+            name: attachment_display_name(name),
+            content_type: content_type.to_string(),
+            size_bytes: bytes.len() as u64,
+            uploaded_at_ms: now_ms,
+        };
+        workflow.attachments.push(attachment.clone());
+
+        let file = self.attachment_path(id, attachment.id);
+        write_atomic(&file, bytes).map_err(|error| AttachmentError::Io(error.to_string()))?;
+        if let Err(error) = self.persist(&candidate) {
+            let _ = fs::remove_file(&file);
+            return Err(AttachmentError::Io(error.to_string()));
+        }
+        *data = candidate;
+        Ok(attachment)
+    }
+
+    pub(crate) fn attachment(
+        &self,
+        id: u64,
+        attachment_id: u32,
+    ) -> Option<(BugAttachment, PathBuf)> {
+        let data = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let attachment = data
+            .reports
+            .get(&id)?
+            .attachments
+            .iter()
+            .find(|attachment| attachment.id == attachment_id)?
+            .clone();
+        Some((attachment, self.attachment_path(id, attachment_id)))
+    }
+
+    fn attachment_path(&self, id: u64, attachment_id: u32) -> PathBuf {
+        self.attachments_dir
+            .join(id.to_string())
+            .join(format!("{attachment_id}.bin"))
+    }
+}
+
+fn load_workflow_file(path: &std::path::Path) -> io::Result<BugWorkflowFile> {
+    let bytes = fs::read(path)?;
+    let parsed: BugWorkflowFile = serde_json::from_slice(&bytes)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    if parsed.schema_version != BUG_WORKFLOW_SCHEMA_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported bug workflow schema {}", parsed.schema_version),
+        ));
+    }
+    Ok(parsed)
+}
+
+fn sniff_attachment_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if !bytes.contains(&0) && std::str::from_utf8(bytes).is_ok() {
+        Some("text/plain; charset=utf-8")
+    } else {
+        None
+    }
+}
+
+/// Keeps a short, header-safe display name; the stored file name never
+/// derives from it.
+fn attachment_display_name(name: &str) -> String {
+    let cleaned: String = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ' '))
+        .take(MAX_ATTACHMENT_NAME_CHARS)
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.');
+    if cleaned.is_empty() {
+        "attachment".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+fn token_digest(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0_u8, |difference, (left, right)| {
+                difference | (left ^ right)
+            })
+            == 0
+}
+
+fn ensure_reporter(state: &AppState, headers: &HeaderMap, id: u64) -> Result<(), StatusCode> {
+    ensure_admin(state, headers)?;
+    let token = headers
+        .get(BUG_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    if token.is_empty() || !state.bug_workflow.verify_reporter(id, token) {
+        // Indistinguishable from a missing report.
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(())
+}
+
+// ── Reporter endpoints ──────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub(crate) struct ReporterComment {
+    body: String,
+    created_at_ms: u64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ReporterBugStatus {
+    id: u64,
+    status: BugStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status_changed_at_ms: Option<u64>,
+    comments: Vec<ReporterComment>,
+    attachment_count: usize,
+}
+
+/// GET /api/bug-report/:id — the reporter's view: status and public comments.
+pub(crate) async fn bug_report_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+) -> Result<Json<ReporterBugStatus>, StatusCode> {
+    ensure_reporter(&state, &headers, id)?;
+    let view = state.bug_workflow.view(id);
+    Ok(Json(ReporterBugStatus {
+        id,
+        status: view.status,
+        status_changed_at_ms: view.status_changed_at_ms,
+        comments: view
+            .comments
+            .into_iter()
+            .filter(|comment| comment.public)
+            .map(|comment| ReporterComment {
+                body: comment.body,
+                created_at_ms: comment.created_at_ms,
+            })
+            .collect(),
+        attachment_count: view.attachments.len(),
+    }))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AttachmentUploadQuery {
+    #[serde(default)]
+    name: String,
+}
+
+/// POST /api/bug-report/:id/attachments?name= — raw body, reporter only.
+pub(crate) async fn bug_report_attachment_upload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+    Query(query): Query<AttachmentUploadQuery>,
+    body: Bytes,
+) -> Result<Json<BugAttachment>, StatusCode> {
+    ensure_reporter(&state, &headers, id)?;
+    match state
+        .bug_workflow
+        .add_attachment(id, &query.name, &body, now_ts_ms())
+    {
+        Ok(attachment) => Ok(Json(attachment)),
+        Err(error) => {
+            if let AttachmentError::Io(message) = &error {
+                warn!("bug report attachment write failed: {}", message);
+            }
+            Err(error.status())
+        }
+    }
+}
+
+// ── Admin endpoints ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub(crate) struct BugStatusRequest {
+    status: BugStatus,
+}
+
+/// POST /admin/api/bugs/:id/status — `{ "status": "triaged" }`.
+pub(crate) async fn admin_bug_report_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+    Json(request): Json<BugStatusRequest>,
+) -> Result<Json<BugWorkflowView>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    if find_bug_report(&state, id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state
+        .bug_workflow
+        .set_status(id, request.status, now_ts_ms())
+        .map(Json)
+        .map_err(|error| {
+            warn!("bug report status update failed: {}", error);
+            StatusCode::SERVICE_UNAVAILABLE
+        })
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BugCommentRequest {
+    body: String,
+    #[serde(default)]
+    public: bool,
+}
+
+/// POST /admin/api/bugs/:id/comments — `{ "body": "...", "public": false }`.
+pub(crate) async fn admin_bug_report_comment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+    Json(request): Json<BugCommentRequest>,
+) -> Result<Json<BugComment>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    let body = request.body.trim();
+    if body.is_empty() || body.chars().count() > MAX_COMMENT_CHARS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if find_bug_report(&state, id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    match state
+        .bug_workflow
+        .add_comment(id, body, request.public, now_ts_ms())
+    {
+        Ok(comment) => Ok(Json(comment)),
+        Err(error) if error.kind() == io::ErrorKind::InvalidInput => Err(StatusCode::CONFLICT),
+        Err(error) => {
+            warn!("bug report comment failed: {}", error);
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+/// GET /admin/api/bugs/:id/attachments/:attachment_id
+pub(crate) async fn admin_bug_report_attachment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, attachment_id)): Path<(u64, u32)>,
+) -> Result<Response, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    let (attachment, path) = state
+        .bug_workflow
+        .attachment(id, attachment_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let bytes = fs::read(path).map_err(|_| StatusCode::NOT_FOUND)?;
+    let mut response = Body::from(bytes).into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_str(&attachment.content_type)
+            .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+    );
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    // Text is downloaded rather than rendered; the name is already
+    // restricted to header-safe characters.
+    let disposition = if attachment.content_type.starts_with("image/") {
+        "inline"
+    } else {
+        "attachment"
+    };
+    if let Ok(value) =
+        HeaderValue::from_str(&format!("{disposition}; filename=\"{}\"", attachment.name))
+    {
+        headers.insert(CONTENT_DISPOSITION, value);
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "echo-bug-workflow-{label}-{}",
+            rand::random::<u64>()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn workflow_persists_status_comments_and_attachments() {
+        let dir = temp_dir("persist");
+        let store = BugWorkflowStore::open(&dir).unwrap();
+        let (first, token) = store.register(1_000);
+        let (second, _) = store.register(1_000);
+        assert_eq!(second, first + 1);
+        let token = token.unwrap();
+        assert!(store.verify_reporter(first, &token));
+        assert!(!store.verify_reporter(second, &token));
+        assert!(!store.verify_reporter(first, "wrong"));
+
+        store.set_status(first, BugStatus::Triaged, 2_000).unwrap();
+        store
+            .add_comment(first, "Looking into it", true, 2_100)
+            .unwrap();
+        store
+            .add_comment(first, "Internal note", false, 2_200)
+            .unwrap();
+        let attachment = store
+            .add_attachment(first, "../../logs/echo.log", b"line one\nline two\n", 2_300)
+            .unwrap();
+        assert_eq!(attachment.name, "echo.log");
+        assert_eq!(attachment.content_type, "text/plain; charset=utf-8");
+        assert_eq!(
+            store.add_attachment(first, "blob", &[0, 1, 2, 3], 2_400),
+            Err(AttachmentError::UnsupportedType)
+        );
+
+        let reopened = BugWorkflowStore::open(&dir).unwrap();
+        let view = reopened.view(first);
+        assert_eq!(view.status, BugStatus::Triaged);
+        assert_eq!(view.status_changed_at_ms, Some(2_000));
+        assert_eq!(view.comments.len(), 2);
+        let (_, path) = reopened.attachment(first, attachment.id).unwrap();
+        assert_eq!(fs::read(path).unwrap(), b"line one\nline two\n");
+        assert!(reopened.verify_reporter(first, &token));
+        // IDs keep increasing across restarts.
+        assert!(reopened.register(500).0 > second);
+        // Reports that predate the workflow read as new.
+        assert_eq!(reopened.view(42).status, BugStatus::New);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn attachments_are_sniffed_and_capped() {
+        let dir = temp_dir("attachments");
+        let store = BugWorkflowStore::open(&dir).unwrap();
+        let (id, _) = store.register(1);
+        let png = b"\x89PNG\r\n\x1a\nrest";
+        for index in 0..MAX_ATTACHMENTS_PER_REPORT {
+            let attachment = store.add_attachment(id, "shot.png", png, 10).unwrap();
+            assert_eq!(attachment.id as usize, index + 1);
+            assert_eq!(attachment.content_type, "image/png");
+        }
+        assert_eq!(
+            store.add_attachment(id, "shot.png", png, 10),
+            Err(AttachmentError::TooMany)
+        );
+        assert_eq!(
+            store.add_attachment(id, "empty", b"", 10),
+            Err(AttachmentError::Empty)
+        );
+        assert_eq!(attachment_display_name("..\\.."), "attachment");
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -437,37 +437,55 @@ pub(crate) async fn chime_upload(
 ) -> Result<Json<ChatUploadResponse>, StatusCode> {
     ensure_admin(&state, &headers)?;
 
-    if body.is_empty() {
-        return Ok(Json(ChatUploadResponse {
+    let content_type = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream");
+    // Strip -XXXX numeric suffix to get identity base
+    let identity_base = query
+        .identity
+        .rsplitn(2, '-')
+        .last()
+        .unwrap_or(&query.identity);
+    match store_chime(&state, identity_base, &query.kind, content_type, &body) {
+        Ok(url) => Ok(Json(ChatUploadResponse {
+            ok: true,
+            url: Some(url),
+            error: None,
+        })),
+        Err(error) => Ok(Json(ChatUploadResponse {
             ok: false,
             url: None,
-            error: Some("Empty file".into()),
-        }));
+            error: Some(error),
+        })),
+    }
+}
+
+/// Validate and store an enter/exit chime, replacing any previous one for
+/// the identity base. Shared by `chime_upload` and soundboard bundle import.
+/// Returns the chime's URL.
+pub(crate) fn store_chime(
+    state: &AppState,
+    identity_base: &str,
+    kind: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<String, String> {
+    if body.is_empty() {
+        return Err("Empty file".into());
     }
 
     // 2 MB limit for chimes
     if body.len() > 2 * 1024 * 1024 {
-        return Ok(Json(ChatUploadResponse {
-            ok: false,
-            url: None,
-            error: Some("Chime too large (max 2MB)".into()),
-        }));
+        return Err("Chime too large (max 2MB)".into());
     }
 
     // Validate kind
-    if query.kind != "enter" && query.kind != "exit" {
-        return Ok(Json(ChatUploadResponse {
-            ok: false,
-            url: None,
-            error: Some("kind must be 'enter' or 'exit'".into()),
-        }));
+    if kind != "enter" && kind != "exit" {
+        return Err("kind must be 'enter' or 'exit'".into());
     }
 
     // Determine extension from content-type
-    let content_type = headers
-        .get(axum::http::header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("application/octet-stream");
     let ext = match content_type {
         "audio/mpeg" | "audio/mp3" => "mp3",
         "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
@@ -480,27 +498,18 @@ pub(crate) async fn chime_upload(
             ct.strip_prefix("audio/").unwrap_or("bin")
         }
         _ => {
-            return Ok(Json(ChatUploadResponse {
-                ok: false,
-                url: None,
-                error: Some(format!(
-                    "Unsupported type: {}. Upload an audio file (mp3, wav, ogg, m4a, etc.)",
-                    content_type
-                )),
-            }));
+            return Err(format!(
+                "Unsupported type: {}. Upload an audio file (mp3, wav, ogg, m4a, etc.)",
+                content_type
+            ));
         }
     };
 
-    // Strip -XXXX numeric suffix to get identity base
-    let identity_base = query
-        .identity
-        .rsplitn(2, '-')
-        .last()
-        .unwrap_or(&query.identity)
-        .to_string();
-
-    let key = format!("{}-{}", identity_base, query.kind);
+    let key = format!("{}-{}", identity_base, kind);
     let file_name = format!("chime-{}.{}", key, ext);
+    if !crate::is_safe_path_component(&file_name) {
+        return Err("Invalid identity".into());
+    }
     let file_path = state.chimes_dir.join(&file_name);
 
     // Remove any old chime for this key (might have a different extension)
@@ -515,24 +524,15 @@ pub(crate) async fn chime_upload(
     }
 
     let _ = fs::create_dir_all(&state.chimes_dir);
-    match fs::write(&file_path, &body) {
+    match fs::write(&file_path, body) {
         Ok(_) => {
             let mime = chime_mime_from_ext(&file_name);
             let mut chimes = state.chimes.lock().unwrap_or_else(|e| e.into_inner());
             chimes.insert(key.clone(), ChimeEntry { file_name, mime });
-            let url = format!("/api/chime/{}/{}", identity_base, query.kind);
             info!("chime uploaded: key={}", key);
-            Ok(Json(ChatUploadResponse {
-                ok: true,
-                url: Some(url),
-                error: None,
-            }))
+            Ok(format!("/api/chime/{}/{}", identity_base, kind))
         }
-        Err(err) => Ok(Json(ChatUploadResponse {
-            ok: false,
-            url: None,
-            error: Some(format!("Chime upload failed: {}", err)),
-        })),
+        Err(err) => Err(format!("Chime upload failed: {}", err)),
     }
 }
 
@@ -24,7 +24,12 @@ pub struct Config {
     pub livekit_token_ttl_secs: u64,
     pub soundboard_dir: PathBuf,
     pub soundboard_max_bytes: usize,
+    pub soundboard_max_duration_ms: u64,
     pub soundboard_max_sounds_per_room: usize,
+    pub soundboard_user_cooldown_ms: u64,
+    pub soundboard_room_cooldown_ms: u64,
+    pub soundboard_user_max_concurrent: usize,
+    pub soundboard_room_max_concurrent: usize,
     pub chat_dir: PathBuf,
     pub chat_uploads_dir: PathBuf,
     pub chat_max_upload_bytes: usize,
@@ -38,6 +43,32 @@ pub struct Config {
     pub jam_source_token: Option<String>,
     pub spotify_device_id: Option<String>,
     pub spotify_device_name: Option<String>,
+    /// Optional directory of audio files served as the Jam local library.
+    pub jam_local_library_dir: Option<PathBuf>,
+    /// Interleave pending Jam tracks round-robin by contributor.
+    pub jam_queue_fair_share: bool,
+    /// Cap on one contributor's pending Jam tracks; `None` is unlimited.
+    pub jam_queue_max_pending_per_actor: Option<usize>,
+    /// Refuse to queue a track that is already in the Jam queue.
+    pub jam_queue_reject_duplicates: bool,
+    /// Percentage of Jam listeners whose votes trigger a skip; `None` lets any
+    /// participant skip immediately.
+    pub jam_skip_vote_percent: Option<u8>,
+    /// Extra desktop Jam sources that can take over an active Jam when the
+    /// primary `JAM_SOURCE_ID` source goes away.
+    pub jam_standby_sources: Vec<JamSourceAgentConfig>,
+}
+
+/// One standby desktop Jam source from `CORE_JAM_STANDBY_SOURCES`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JamSourceAgentConfig {
+    pub id: String,
+    pub token: String,
+    /// Lower values are preferred; the primary source is priority 0.
+    pub priority: u32,
+    /// Spotify Connect device name on that PC. Falls back to
+    /// `SPOTIFY_DEVICE_NAME` when unset.
+    pub spotify_device_name: Option<String>,
 }
 
 pub fn load_dotenv() {
@@ -6,13 +6,19 @@
 //! IDs are generated by the server; client-provided identifiers are never used as
 //! path components.
 
+use crate::diagnostics_groups::{
+    load_resolutions, save_resolutions, valid_group_id, GroupResolution, GroupStatus,
+    IncidentGroupDetail, IncidentGroupSummary, IncidentGroups, GROUP_TREND_DAYS,
+    MAX_GROUP_LIST_LIMIT,
+};
+
 use hmac::{Hmac, Mac};
 use rand::{rngs::OsRng, RngCore};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::Sha256;
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
     fmt,
     fs::{self, File, OpenOptions, TryLockError},
     io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
@@ -390,6 +396,7 @@ struct IndexEntry {
 struct StoreIndex {
     by_envelope_key: HashMap<(String, String), IndexEntry>,
     by_incident_id: HashMap<String, IndexEntry>,
+    groups: IncidentGroups,
 }
 
 pub struct DiagnosticStore {
@@ -399,6 +406,7 @@ pub struct DiagnosticStore {
     poisoned: AtomicBool,
     io_lock: Mutex<()>,
     index: Mutex<StoreIndex>,
+    resolutions: Mutex<BTreeMap<String, GroupResolution>>,
     identity_key: [u8; IDENTITY_KEY_BYTES],
 }
 
@@ -605,12 +613,14 @@ impl DiagnosticStore {
             recover_store_state(&root)?;
         }
         let index = load_index(&root)?;
+        let resolutions = load_resolutions(&root)?;
         Ok(Self {
             root,
             _process_lock: process_lock,
             poisoned: AtomicBool::new(false),
             io_lock: Mutex::new(()),
             index: Mutex::new(index),
+            resolutions: Mutex::new(resolutions),
             identity_key,
         })
     }
@@ -761,6 +771,7 @@ impl DiagnosticStore {
         };
         index.by_envelope_key.insert(envelope_key, entry.clone());
         index.by_incident_id.insert(incident_id.clone(), entry);
+        index.groups.add(&record);
 
         Ok(AppendOutcome::Stored { incident_id })
     }
@@ -805,6 +816,37 @@ impl DiagnosticStore {
         Ok(summaries)
     }
 
+    /// Lists one installation's incidents received within `from_ms..=to_ms`,
+    /// newest first. Used to link bug reports to the crashes around them.
+    pub fn list_install_summaries(
+        &self,
+        install_id: &str,
+        from_ms: u64,
+        to_ms: u64,
+        limit: usize,
+    ) -> Result<Vec<IncidentSummary>, DiagnosticsError> {
+        self.ensure_healthy()?;
+        validate_uuid(install_id, "install_id")?;
+        let limit = limit.min(MAX_LIST_LIMIT);
+        if limit == 0 || from_ms > to_ms {
+            return Ok(Vec::new());
+        }
+
+        let _io_guard = lock(&self.io_lock)?;
+        self.ensure_healthy()?;
+        let mut summaries = Vec::new();
+        for_each_record(&self.root, |record, _| {
+            if record.envelope.install_id == install_id
+                && (from_ms..=to_ms).contains(&record.received_at_ms)
+            {
+                summaries.push(IncidentSummary::from(&record));
+            }
+        })?;
+        sort_summaries(&mut summaries);
+        summaries.truncate(limit);
+        Ok(summaries)
+    }
+
     /// Looks up a record only by the opaque server-generated incident ID.
     pub fn get_incident(
         &self,
@@ -859,6 +901,9 @@ impl DiagnosticStore {
             return Ok(false);
         };
         let path = checked_storage_path(&self.root, &entry.file_name)?;
+        // Group aggregates are decremented from the record itself, so read it
+        // before the rewrite removes it.
+        let record = find_record(&path, incident_id)?;
         match rewrite_without_incident(&path, incident_id) {
             Ok(true) => {}
             Ok(false) => return Ok(false),
@@ -872,6 +917,10 @@ impl DiagnosticStore {
         index
             .by_envelope_key
             .retain(|_, candidate| candidate.incident_id != incident_id);
+        match record {
+            Some(record) => index.groups.remove(&record),
+            None => self.reconcile_or_poison(&mut index),
+        }
         Ok(true)
     }
 
@@ -895,6 +944,126 @@ impl DiagnosticStore {
         report
     }
 
+    /// Lists incident groups, most recently seen first, optionally filtered by
+    /// status. `Open` also matches regressed groups.
+    pub fn list_groups(
+        &self,
+        status: Option<GroupStatus>,
+        limit: usize,
+    ) -> Result<Vec<IncidentGroupSummary>, DiagnosticsError> {
+        self.ensure_healthy()?;
+        let limit = limit.min(MAX_GROUP_LIST_LIMIT);
+        let index = lock(&self.index)?;
+        let resolutions = lock(&self.resolutions)?;
+        let mut groups = index.groups.summaries(&resolutions);
+        if let Some(status) = status {
+            groups.retain(|group| {
+                group.status == status
+                    || (status == GroupStatus::Open && group.status == GroupStatus::Regressed)
+            });
+        }
+        groups.truncate(limit);
+        Ok(groups)
+    }
+
+    /// Arrival times of grouped incidents at or above `severity` since
+    /// `since_ms`, answered from the in-memory index.
+    pub fn incidents_received_since(
+        &self,
+        since_ms: u64,
+        severity: DiagnosticSeverity,
+    ) -> Result<Vec<u64>, DiagnosticsError> {
+        self.ensure_healthy()?;
+        Ok(lock(&self.index)?.groups.received_since(since_ms, severity))
+    }
+
+    /// Returns a group with its daily trend and a newest-first page of its
+    /// incident summaries.
+    pub fn get_group(
+        &self,
+        group_id: &str,
+        limit: usize,
+        before: Option<(u64, String)>,
+        now_ms: u64,
+    ) -> Result<Option<IncidentGroupDetail>, DiagnosticsError> {
+        self.ensure_healthy()?;
+        validate_group_id(group_id)?;
+        if let Some((_, incident_id)) = &before {
+            validate_incident_id(incident_id)?;
+        }
+        let limit = limit.min(MAX_LIST_LIMIT);
+        let _io_guard = lock(&self.io_lock)?;
+        self.ensure_healthy()?;
+        let (group, trend, files) = {
+            let index = lock(&self.index)?;
+            let resolutions = lock(&self.resolutions)?;
+            let Some(group) = index.groups.summary(group_id, resolutions.get(group_id)) else {
+                return Ok(None);
+            };
+            let trend = index.groups.trend(group_id, now_ms, GROUP_TREND_DAYS);
+            let mut files: BTreeMap<String, HashSet<String>> = BTreeMap::new();
+            for (_, incident_id) in index.groups.incident_page(group_id, limit, before.as_ref()) {
+                if let Some(entry) = index.by_incident_id.get(&incident_id) {
+                    files
+                        .entry(entry.file_name.clone())
+                        .or_default()
+                        .insert(incident_id);
+                }
+            }
+            (group, trend, files)
+        };
+
+        let mut incidents = Vec::new();
+        for (file_name, mut wanted) in files {
+            let path = checked_storage_path(&self.root, &file_name)?;
+            read_jsonl_records(&path, |record| {
+                if wanted.remove(&record.incident_id) {
+                    incidents.push(IncidentSummary::from(&record));
+                }
+                wanted.is_empty()
+            })?;
+        }
+        sort_summaries(&mut incidents);
+        Ok(Some(IncidentGroupDetail {
+            group,
+            trend,
+            incidents,
+        }))
+    }
+
+    /// Marks a group resolved as of `now_ms`, or reopens it. Returns `None`
+    /// for a group with no retained incidents.
+    pub fn set_group_resolved(
+        &self,
+        group_id: &str,
+        resolved: bool,
+        now_ms: u64,
+    ) -> Result<Option<IncidentGroupSummary>, DiagnosticsError> {
+        self.ensure_healthy()?;
+        validate_group_id(group_id)?;
+        let _io_guard = lock(&self.io_lock)?;
+        self.ensure_healthy()?;
+        let index = lock(&self.index)?;
+        if !index.groups.contains(group_id) {
+            return Ok(None);
+        }
+        let mut resolutions = lock(&self.resolutions)?;
+        let mut updated = resolutions.clone();
+        if resolved {
+            updated.insert(
+                group_id.to_owned(),
+                GroupResolution {
+                    resolved_at_ms: now_ms,
+                },
+            );
+        } else {
+            updated.remove(group_id);
+        }
+        save_resolutions(&self.root, &updated)?;
+        *resolutions = updated;
+        Ok(index.groups.summary(group_id, resolutions.get(group_id)))
+    }
+
     fn ensure_healthy(&self) -> Result<(), DiagnosticsError> {
         if self.poisoned.load(Ordering::Acquire) {
             Err(DiagnosticsError::LockPoisoned)
@@ -1056,6 +1225,11 @@ fn validate_envelope_timestamp(
     Ok(())
 }
 
+/// True for the lowercase hyphenated UUIDs accepted as envelope and install IDs.
+pub fn valid_install_id(value: &str) -> bool {
+    validate_uuid(value, "install_id").is_ok()
+}
+
 fn validate_uuid(value: &str, field: &'static str) -> Result<(), ValidationError> {
     if value.len() != 36 {
         return Err(ValidationError::InvalidIdentifier(field));
@@ -1086,6 +1260,14 @@ fn validate_incident_id(value: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+fn validate_group_id(value: &str) -> Result<(), ValidationError> {
+    if valid_group_id(value) {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidIdentifier("group_id"))
+    }
+}
+
 fn validate_git_sha(value: &str) -> Result<(), ValidationError> {
     // Echo's stamped viewer build uses a short Git revision. Keeping this
     // deliberately short prevents arbitrary 32/64-byte credentials from being
@@ -1267,6 +1449,7 @@ fn is_allowed_detail_key(key: &str) -> bool {
             | "ice_state"
             | "jitter_ms"
             | "kind"
+            | "module"
             | "line"
             | "media_kind"
             | "microphone"
@@ -1274,6 +1457,7 @@ fn is_allowed_detail_key(key: &str) -> bool {
             | "nack_count"
             | "network_state"
             | "operation"
+            | "offset"
             | "output"
             | "packet_loss_percent"
             | "permission"
@@ -1293,6 +1477,7 @@ fn is_allowed_detail_key(key: &str) -> bool {
             | "screen"
             | "selected"
             | "source_category"
+            | "stack"
             | "stage"
             | "started"
             | "state"
@@ -2097,10 +2282,10 @@ fn load_index(root: &Path) -> Result<StoreIndex, DiagnosticsError> {
                 record.envelope.envelope_id.clone(),
             ))
             .or_insert_with(|| entry.clone());
-        index
-            .by_incident_id
-            .entry(record.incident_id)
-            .or_insert(entry);
+        if let Entry::Vacant(vacant) = index.by_incident_id.entry(record.incident_id.clone()) {
+            vacant.insert(entry);
+            index.groups.add(&record);
+        }
     })?;
     Ok(index)
 }
@@ -3435,6 +3620,128 @@ mod tests {
         .expect("normalize parent components"));
     }
 
+    fn javascript_error_envelope(number: u32, code: &str) -> IncidentEnvelope {
+        let mut envelope = envelope(number);
+        envelope.events.push(IncidentEvent {
+            sequence: 2,
+            timestamp_ms: NOW - 400,
+            event_type: DiagnosticEventType::JavascriptError,
+            severity: DiagnosticSeverity::Error,
+            code: code.to_owned(),
+            fingerprint: None,
+            message: None,
+            details: BTreeMap::new(),
+        });
+        envelope
+    }
+
+    fn stored_id(outcome: AppendOutcome) -> String {
+        match outcome {
+            AppendOutcome::Stored { incident_id } => incident_id,
+            other => panic!("expected a stored incident, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn groups_track_counts_resolution_and_regressions() {
+        let directory = TestDirectory::new();
+        let store = DiagnosticStore::open(&directory.0).expect("open store");
+        store
+            .append(
+                "alice",
+                javascript_error_envelope(1, "javascript.type_error"),
+                NOW,
+            )
+            .expect("append first");
+        let mut second = javascript_error_envelope(2, "javascript.Type-Error");
+        second.install_id = uuid(10_001);
+        second.app.version = "0.6.34".to_owned();
+        second.platform.client_kind = ClientKind::Desktop;
+        second.platform.operating_system = OperatingSystem::Windows;
+        store
+            .append("bob", second, NOW + 1_000)
+            .expect("append second");
+
+        // The permission warning in every envelope is not a problem event.
+        let groups = store.list_groups(None, 10).expect("list groups");
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.code, "javascript.type_error");
+        assert_eq!(group.status, GroupStatus::Open);
+        assert_eq!(group.incident_count, 2);
+        assert_eq!(group.event_count, 2);
+        assert_eq!(group.affected_identities, 2);
+        assert_eq!(group.affected_installs, 2);
+        assert_eq!(
+            (group.first_seen_ms, group.last_seen_ms),
+            (NOW, NOW + 1_000)
+        );
+        assert_eq!(group.app_versions.len(), 2);
+        assert_eq!(group.operating_systems.len(), 2);
+        let group_id = group.group_id.clone();
+
+        let resolved = store
+            .set_group_resolved(&group_id, true, NOW + 2_000)
+            .expect("resolve")
+            .expect("group exists");
+        assert_eq!(resolved.status, GroupStatus::Resolved);
+        assert!(store
+            .list_groups(Some(GroupStatus::Open), 10)
+            .expect("list open")
+            .is_empty());
+
+        let regression = stored_id(
+            store
+                .append(
+                    "alice",
+                    javascript_error_envelope(3, "javascript.type_error"),
+                    NOW + 3_000,
+                )
+                .expect("append regression"),
+        );
+        let open = store
+            .list_groups(Some(GroupStatus::Open), 10)
+            .expect("list open");
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].status, GroupStatus::Regressed);
+        assert_eq!(open[0].regressed_at_ms, Some(NOW + 3_000));
+
+        let detail = store
+            .get_group(&group_id, 2, None, NOW + 3_000)
+            .expect("get group")
+            .expect("group exists");
+        assert_eq!(detail.incidents.len(), 2);
+        assert_eq!(detail.incidents[0].incident_id, regression);
+        assert_eq!(detail.trend.len(), GROUP_TREND_DAYS as usize);
+        assert_eq!(
+            detail
+                .trend
+                .iter()
+                .map(|point| point.incidents)
+                .sum::<u64>(),
+            3
+        );
+
+        assert!(store
+            .delete_incident(&regression)
+            .expect("delete regression"));
+        drop(store);
+        let store = DiagnosticStore::open(&directory.0).expect("reopen store");
+        let groups = store.list_groups(None, 10).expect("list after reopen");
+        assert_eq!(groups[0].status, GroupStatus::Resolved);
+        assert_eq!(groups[0].incident_count, 2);
+        assert!(matches!(
+            store.get_group("grp_../etc", 10, None, NOW),
+            Err(DiagnosticsError::Validation(
+                ValidationError::InvalidIdentifier("group_id")
+            ))
+        ));
+        assert!(store
+            .set_group_resolved(&format!("grp_{:016x}", 0), true, NOW)
+            .expect("resolve missing")
+            .is_none());
+    }
+
     #[test]
     fn diagnostics_storage_rejects_links_below_a_static_root() {
         let directory = TestDirectory::new();
@@ -5,6 +5,8 @@ use crate::{
         AppendOutcome, DiagnosticStore, DiagnosticsError, IncidentSummary, RetentionPolicy,
         MAX_REQUEST_BYTES,
     },
+    diagnostics_groups::{GroupStatus, IncidentGroupSummary},
+    diagnostics_symbols::{SymbolBuild, SymbolError, SymbolKind, SymbolStore},
     AppState,
 };
 
@@ -39,6 +41,7 @@ const MAX_RATE_KEYS: usize = 4_096;
 
 pub(crate) struct DiagnosticsRuntime {
     store: DiagnosticStore,
+    symbols: SymbolStore,
     retention: RetentionPolicy,
     admission: Arc<Mutex<DiagnosticsAdmissionState>>,
 }
@@ -48,14 +51,19 @@ impl DiagnosticsRuntime {
         root: impl AsRef<FsPath>,
         retention: RetentionPolicy,
     ) -> Result<Self, DiagnosticsError> {
-        let store = DiagnosticStore::open_with_policy(root, now_ts_ms(), &retention)?;
+        let store = DiagnosticStore::open_with_policy(&root, now_ts_ms(), &retention)?;
         Ok(Self {
             store,
+            symbols: SymbolStore::new(root.as_ref()),
             retention,
             admission: Arc::new(Mutex::new(DiagnosticsAdmissionState::default())),
         })
     }
 
+    pub(crate) fn store(&self) -> &DiagnosticStore {
+        &self.store
+    }
+
     pub(crate) fn prune(&self, now_ms: u64) -> Result<(), DiagnosticsError> {
         self.store.prune(now_ms, &self.retention).map(|_| ())
     }
@@ -425,8 +433,14 @@ pub(crate) async fn diagnostics_get(
     let Some(runtime) = state.diagnostics.clone() else {
         return no_store_status(StatusCode::SERVICE_UNAVAILABLE);
     };
-    let result =
-        tokio::task::spawn_blocking(move || runtime.store.get_incident(&incident_id)).await;
+    let result = tokio::task::spawn_blocking(move || {
+        let mut incident = runtime.store.get_incident(&incident_id)?;
+        if let Some(incident) = incident.as_mut() {
+            runtime.symbols.symbolicate(incident);
+        }
+        Ok::<_, DiagnosticsError>(incident)
+    })
+    .await;
     match result {
         Ok(Ok(Some(incident))) => no_store_json(StatusCode::OK, &incident),
         Ok(Ok(None)) => no_store_status(StatusCode::NOT_FOUND),
@@ -497,6 +511,256 @@ pub(crate) async fn diagnostics_delete(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct DiagnosticsGroupsQuery {
+    status: Option<GroupStatus>,
+    #[serde(default = "default_group_list_limit")]
+    limit: usize,
+}
+
+fn default_group_list_limit() -> usize {
+    100
+}
+
+#[derive(Debug, Serialize)]
+struct DiagnosticsGroupsResponse {
+    groups: Vec<IncidentGroupSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DiagnosticsGroupResolveRequest {
+    #[serde(default = "default_resolved")]
+    resolved: bool,
+}
+
+fn default_resolved() -> bool {
+    true
+}
+
+pub(crate) async fn diagnostics_groups_list(
+    State(state): State<AppState>,
+    Query(query): Query<DiagnosticsGroupsQuery>,
+) -> Response {
+    let Some(runtime) = state.diagnostics.clone() else {
+        return no_store_status(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let result =
+        tokio::task::spawn_blocking(move || runtime.store.list_groups(query.status, query.limit))
+            .await;
+    match result {
+        Ok(Ok(groups)) => no_store_json(StatusCode::OK, &DiagnosticsGroupsResponse { groups }),
+        Ok(Err(error)) => no_store_diagnostics_error(error),
+        Err(error) => {
+            warn!("diagnostics group list task failed: {}", error);
+            no_store_status(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+/// A group with its trend and a page of incidents, paged with the same cursor
+/// as the incident list.
+pub(crate) async fn diagnostics_group_get(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Query(query): Query<DiagnosticsListQuery>,
+) -> Response {
+    let Some(runtime) = state.diagnostics.clone() else {
+        return no_store_status(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let before = match diagnostics_list_cursor(&query) {
+        Ok(before) => before,
+        Err(status) => return no_store_status(status),
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        runtime
+            .store
+            .get_group(&group_id, query.limit, before, now_ts_ms())
+    })
+    .await;
+    match result {
+        Ok(Ok(Some(group))) => no_store_json(StatusCode::OK, &group),
+        Ok(Ok(None)) => no_store_status(StatusCode::NOT_FOUND),
+        Ok(Err(error)) => no_store_diagnostics_error(error),
+        Err(error) => {
+            warn!("diagnostics group get task failed: {}", error);
+            no_store_status(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+pub(crate) async fn diagnostics_group_resolve(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Json(request): Json<DiagnosticsGroupResolveRequest>,
+) -> Response {
+    let Some(runtime) = state.diagnostics.clone() else {
+        return no_store_status(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        runtime
+            .store
+            .set_group_resolved(&group_id, request.resolved, now_ts_ms())
+    })
+    .await;
+    match result {
+        Ok(Ok(Some(group))) => no_store_json(StatusCode::OK, &group),
+        Ok(Ok(None)) => no_store_status(StatusCode::NOT_FOUND),
+        Ok(Err(error)) => no_store_diagnostics_error(error),
+        Err(error) => {
+            warn!("diagnostics group resolve task failed: {}", error);
+            no_store_status(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+const MAX_BUG_LINK_WINDOW_MINUTES: u64 = 24 * 60;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DiagnosticsBugLinkQuery {
+    #[serde(default = "default_bug_link_window_minutes")]
+    window_minutes: u64,
+    #[serde(default = "default_list_limit")]
+    limit: usize,
+}
+
+fn default_bug_link_window_minutes() -> u64 {
+    60
+}
+
+/// Incidents from a bug report's installation received within
+/// `window_minutes` either side of the report. Reports without an install ID
+/// link to nothing.
+pub(crate) async fn diagnostics_bug_report_incidents(
+    State(state): State<AppState>,
+    Path(report_id): Path<u64>,
+    Query(query): Query<DiagnosticsBugLinkQuery>,
+) -> Response {
+    let Some(runtime) = state.diagnostics.clone() else {
+        return no_store_status(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    if query.window_minutes == 0 || query.window_minutes > MAX_BUG_LINK_WINDOW_MINUTES {
+        return no_store_status(StatusCode::BAD_REQUEST);
+    }
+    let result = tokio::task::spawn_blocking(move || {
+        let Some(report) = crate::admin::find_bug_report(&state, report_id) else {
+            return Ok(None);
+        };
+        let Some(install_id) = report.install_id else {
+            return Ok(Some(Vec::new()));
+        };
+        let reported_at_ms = report.timestamp.saturating_mul(1000);
+        let window_ms = query.window_minutes * 60_000;
+        runtime
+            .store
+            .list_install_summaries(
+                &install_id,
+                reported_at_ms.saturating_sub(window_ms),
+                reported_at_ms.saturating_add(window_ms),
+                query.limit,
+            )
+            .map(Some)
+    })
+    .await;
+    match result {
+        Ok(Ok(Some(incidents))) => {
+            no_store_json(StatusCode::OK, &DiagnosticsListResponse { incidents })
+        }
+        Ok(Ok(None)) => no_store_status(StatusCode::NOT_FOUND),
+        Ok(Err(error)) => no_store_diagnostics_error(error),
+        Err(error) => {
+            warn!("diagnostics bug report link task failed: {}", error);
+            no_store_status(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DiagnosticsSymbolUploadQuery {
+    kind: SymbolKind,
+}
+
+#[derive(Debug, Serialize)]
+struct DiagnosticsSymbolsResponse {
+    builds: Vec<SymbolBuild>,
+}
+
+pub(crate) async fn diagnostics_symbols_list(State(state): State<AppState>) -> Response {
+    let Some(runtime) = state.diagnostics.clone() else {
+        return no_store_status(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let result = tokio::task::spawn_blocking(move || runtime.symbols.list()).await;
+    match result {
+        Ok(Ok(builds)) => no_store_json(StatusCode::OK, &DiagnosticsSymbolsResponse { builds }),
+        Ok(Err(error)) => symbol_error_response(error),
+        Err(error) => {
+            warn!("diagnostics symbol list task failed: {}", error);
+            no_store_status(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+/// Stores a source map or Breakpad symbol file for one module of a build.
+/// The body is the raw file; it is parsed before anything is written.
+pub(crate) async fn diagnostics_symbols_upload(
+    State(state): State<AppState>,
+    Path((git_sha, version, module)): Path<(String, String, String)>,
+    Query(query): Query<DiagnosticsSymbolUploadQuery>,
+    body: Bytes,
+) -> Response {
+    let Some(runtime) = state.diagnostics.clone() else {
+        return no_store_status(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        runtime
+            .symbols
+            .upload(&git_sha, &version, &module, query.kind, &body)
+    })
+    .await;
+    match result {
+        Ok(Ok(file)) => no_store_json(StatusCode::CREATED, &file),
+        Ok(Err(error)) => symbol_error_response(error),
+        Err(error) => {
+            warn!("diagnostics symbol upload task failed: {}", error);
+            no_store_status(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+pub(crate) async fn diagnostics_symbols_delete(
+    State(state): State<AppState>,
+    Path((git_sha, version)): Path<(String, String)>,
+) -> Response {
+    let Some(runtime) = state.diagnostics.clone() else {
+        return no_store_status(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let result =
+        tokio::task::spawn_blocking(move || runtime.symbols.delete_build(&git_sha, &version)).await;
+    match result {
+        Ok(Ok(true)) => no_store_status(StatusCode::NO_CONTENT),
+        Ok(Ok(false)) => no_store_status(StatusCode::NOT_FOUND),
+        Ok(Err(error)) => symbol_error_response(error),
+        Err(error) => {
+            warn!("diagnostics symbol delete task failed: {}", error);
+            no_store_status(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+fn symbol_error_response(error: SymbolError) -> Response {
+    match error {
+        SymbolError::InvalidName(_) => no_store_status(StatusCode::BAD_REQUEST),
+        SymbolError::TooLarge => no_store_status(StatusCode::PAYLOAD_TOO_LARGE),
+        SymbolError::Unparseable(_) => no_store_json(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            &serde_json::json!({ "error": error.to_string() }),
+        ),
+        SymbolError::Io(error) => {
+            warn!("diagnostics symbol storage failed: {}", error);
+            no_store_status(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
 fn diagnostics_error_response(error: DiagnosticsError) -> Response {
     match error {
         DiagnosticsError::BodyTooLarge => StatusCode::PAYLOAD_TOO_LARGE.into_response(),
@@ -79,6 +79,12 @@ pub(crate) fn diagnostics_owner_secret_is_safe(config: &crate::config::Config) -
     ];
     other_secrets.extend(config.turn_pass.as_deref());
     other_secrets.extend(config.jam_source_token.as_deref());
+    other_secrets.extend(
+        config
+            .jam_standby_sources
+            .iter()
+            .map(|source| source.token.as_str()),
+    );
     other_secrets.extend(config.github_pat.as_deref());
 
     owner_secret_is_safe(
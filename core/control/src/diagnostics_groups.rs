@@ -0,0 +1,524 @@
+//! Incident groups derived from the diagnostics store.
+//!
+//! Client fingerprints are dropped during sanitization, so a group is keyed by
+//! the closed event type plus the attested event code, normalized for case and
+//! separator spelling. Groups live next to the store index and are rebuilt with
+//! it, which keeps them consistent with deletes, retention, and crash recovery.
+//! Only resolution state is persisted separately, in a small JSON file beside
+//! the incident records. A resolved group regresses as soon as an incident is
+//! received after the resolution time.
+
+use crate::diagnostics::{
+    DiagnosticEventType, DiagnosticSeverity, IncidentEvent, IncidentSummary, OperatingSystem,
+    StoredIncident,
+};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+pub const GROUP_ID_PREFIX: &str = "grp_";
+pub const MAX_GROUP_LIST_LIMIT: usize = 500;
+pub const GROUP_TREND_DAYS: u64 = 14;
+
+const RESOLUTIONS_FILE: &str = ".echo-diagnostics-groups.json";
+const RESOLUTIONS_VERSION: u16 = 1;
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupStatus {
+    Open,
+    Resolved,
+    Regressed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct GroupResolution {
+    pub resolved_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct GroupCount {
+    pub value: String,
+    pub incidents: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct IncidentGroupSummary {
+    pub group_id: String,
+    pub event_type: DiagnosticEventType,
+    pub code: String,
+    pub status: GroupStatus,
+    pub highest_severity: DiagnosticSeverity,
+    pub first_seen_ms: u64,
+    pub last_seen_ms: u64,
+    pub incident_count: usize,
+    pub event_count: u64,
+    /// Distinct authenticated identity digests; identities are never listed.
+    pub affected_identities: usize,
+    pub affected_installs: usize,
+    pub app_versions: Vec<GroupCount>,
+    pub operating_systems: Vec<GroupCount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at_ms: Option<u64>,
+    /// First incident received after the group was resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regressed_at_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct GroupTrendPoint {
+    pub day_start_ms: u64,
+    pub incidents: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct IncidentGroupDetail {
+    pub group: IncidentGroupSummary,
+    pub trend: Vec<GroupTrendPoint>,
+    pub incidents: Vec<IncidentSummary>,
+}
+
+/// Events worth grouping: anything at error severity or above, plus the event
+/// types that always describe a failure regardless of the reported severity.
+pub fn is_groupable_event(event: &IncidentEvent) -> bool {
+    event.severity >= DiagnosticSeverity::Error
+        || matches!(
+            event.event_type,
+            DiagnosticEventType::UncleanShutdown
+                | DiagnosticEventType::JavascriptError
+                | DiagnosticEventType::UnhandledRejection
+                | DiagnosticEventType::ConsoleError
+                | DiagnosticEventType::TauriIpcError
+                | DiagnosticEventType::NativeError
+        )
+}
+
+pub fn normalize_code(code: &str) -> String {
+    code.trim()
+        .chars()
+        .map(|character| match character {
+            '-' => '_',
+            other => other.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// Path-safe, stable group ID: FNV-1a 64 over the event type and normalized
+/// code. It is a lookup key, not a secret.
+pub fn group_id(event_type: DiagnosticEventType, normalized_code: &str) -> String {
+    let mut hash = 0xcbf29ce484222325u64;
+    let key = format!("{}:{normalized_code}", wire_name(&event_type));
+    for byte in key.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{GROUP_ID_PREFIX}{hash:016x}")
+}
+
+pub fn valid_group_id(value: &str) -> bool {
+    value.strip_prefix(GROUP_ID_PREFIX).is_some_and(|hex| {
+        hex.len() == 16
+            && hex
+                .bytes()
+                .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+    })
+}
+
+fn wire_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+#[derive(Debug)]
+struct GroupContribution {
+    event_type: DiagnosticEventType,
+    code: String,
+    events: u64,
+    highest_severity: DiagnosticSeverity,
+}
+
+fn contributions(record: &StoredIncident) -> BTreeMap<String, GroupContribution> {
+    let mut contributions = BTreeMap::new();
+    for event in record
+        .envelope
+        .events
+        .iter()
+        .filter(|event| is_groupable_event(event))
+    {
+        let code = normalize_code(&event.code);
+        let contribution = contributions
+            .entry(group_id(event.event_type, &code))
+            .or_insert(GroupContribution {
+                event_type: event.event_type,
+                code,
+                events: 0,
+                highest_severity: event.severity,
+            });
+        contribution.events += 1;
+        contribution.highest_severity = contribution.highest_severity.max(event.severity);
+    }
+    contributions
+}
+
+#[derive(Debug)]
+struct GroupAggregate {
+    event_type: DiagnosticEventType,
+    code: String,
+    /// `(received_at_ms, incident_id)`, so first/last seen and newest-first
+    /// paging come straight from the ordering.
+    incidents: BTreeSet<(u64, String)>,
+    event_count: u64,
+    severities: BTreeMap<DiagnosticSeverity, u64>,
+    app_versions: HashMap<String, u64>,
+    operating_systems: HashMap<OperatingSystem, u64>,
+    identities: HashMap<String, u64>,
+    installs: HashMap<String, u64>,
+}
+
+impl GroupAggregate {
+    fn new(event_type: DiagnosticEventType, code: String) -> Self {
+        Self {
+            event_type,
+            code,
+            incidents: BTreeSet::new(),
+            event_count: 0,
+            severities: BTreeMap::new(),
+            app_versions: HashMap::new(),
+            operating_systems: HashMap::new(),
+            identities: HashMap::new(),
+            installs: HashMap::new(),
+        }
+    }
+
+    fn apply(&mut self, record: &StoredIncident, contribution: &GroupContribution, added: bool) {
+        let adjust = |count: &mut u64| {
+            *count = if added {
+                count.saturating_add(1)
+            } else {
+                count.saturating_sub(1)
+            }
+        };
+        if added {
+            self.event_count = self.event_count.saturating_add(contribution.events);
+        } else {
+            self.event_count = self.event_count.saturating_sub(contribution.events);
+        }
+        adjust(
+            self.severities
+                .entry(contribution.highest_severity)
+                .or_default(),
+        );
+        adjust(
+            self.app_versions
+                .entry(record.envelope.app.version.clone())
+                .or_default(),
+        );
+        adjust(
+            self.operating_systems
+                .entry(record.envelope.platform.operating_system)
+                .or_default(),
+        );
+        adjust(
+            self.identities
+                .entry(record.authenticated_identity_digest.clone())
+                .or_default(),
+        );
+        adjust(
+            self.installs
+                .entry(record.envelope.install_id.clone())
+                .or_default(),
+        );
+        self.severities.retain(|_, count| *count > 0);
+        self.app_versions.retain(|_, count| *count > 0);
+        self.operating_systems.retain(|_, count| *count > 0);
+        self.identities.retain(|_, count| *count > 0);
+        self.installs.retain(|_, count| *count > 0);
+    }
+
+    fn summary(
+        &self,
+        group_id: &str,
+        resolution: Option<&GroupResolution>,
+    ) -> IncidentGroupSummary {
+        let first_seen_ms = self.incidents.first().map(|(at, _)| *at).unwrap_or(0);
+        let last_seen_ms = self.incidents.last().map(|(at, _)| *at).unwrap_or(0);
+        let regressed_at_ms = resolution.and_then(|resolution| {
+            self.incidents
+                .range((resolution.resolved_at_ms.saturating_add(1), String::new())..)
+                .next()
+                .map(|(at, _)| *at)
+        });
+        let status = match (resolution, regressed_at_ms) {
+            (None, _) => GroupStatus::Open,
+            (Some(_), Some(_)) => GroupStatus::Regressed,
+            (Some(_), None) => GroupStatus::Resolved,
+        };
+        IncidentGroupSummary {
+            group_id: group_id.to_owned(),
+            event_type: self.event_type,
+            code: self.code.clone(),
+            status,
+            highest_severity: self
+                .severities
+                .keys()
+                .next_back()
+                .copied()
+                .unwrap_or(DiagnosticSeverity::Error),
+            first_seen_ms,
+            last_seen_ms,
+            incident_count: self.incidents.len(),
+            event_count: self.event_count,
+            affected_identities: self.identities.len(),
+            affected_installs: self.installs.len(),
+            app_versions: ranked(
+                self.app_versions
+                    .iter()
+                    .map(|(version, count)| (version.clone(), *count)),
+            ),
+            operating_systems: ranked(
+                self.operating_systems
+                    .iter()
+                    .map(|(os, count)| (wire_name(os), *count)),
+            ),
+            resolved_at_ms: resolution.map(|resolution| resolution.resolved_at_ms),
+            regressed_at_ms,
+        }
+    }
+}
+
+fn ranked(counts: impl Iterator<Item = (String, u64)>) -> Vec<GroupCount> {
+    let mut counts: Vec<_> = counts
+        .map(|(value, incidents)| GroupCount { value, incidents })
+        .collect();
+    counts.sort_by(|left, right| {
+        right
+            .incidents
+            .cmp(&left.incidents)
+            .then_with(|| left.value.cmp(&right.value))
+    });
+    counts
+}
+
+#[derive(Debug, Default)]
+pub struct IncidentGroups {
+    groups: HashMap<String, GroupAggregate>,
+    /// Highest grouped severity of every grouped incident, by arrival.
+    received: BTreeMap<(u64, String), DiagnosticSeverity>,
+}
+
+impl IncidentGroups {
+    pub fn add(&mut self, record: &StoredIncident) {
+        let contributions = contributions(record);
+        if let Some(severity) = contributions
+            .values()
+            .map(|contribution| contribution.highest_severity)
+            .max()
+        {
+            self.received.insert(
+                (record.received_at_ms, record.incident_id.clone()),
+                severity,
+            );
+        }
+        for (group_id, contribution) in contributions {
+            let group = self.groups.entry(group_id).or_insert_with(|| {
+                GroupAggregate::new(contribution.event_type, contribution.code.clone())
+            });
+            if group
+                .incidents
+                .insert((record.received_at_ms, record.incident_id.clone()))
+            {
+                group.apply(record, &contribution, true);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, record: &StoredIncident) {
+        self.received
+            .remove(&(record.received_at_ms, record.incident_id.clone()));
+        for (group_id, contribution) in contributions(record) {
+            let Some(group) = self.groups.get_mut(&group_id) else {
+                continue;
+            };
+            if group
+                .incidents
+                .remove(&(record.received_at_ms, record.incident_id.clone()))
+            {
+                group.apply(record, &contribution, false);
+            }
+            if group.incidents.is_empty() {
+                self.groups.remove(&group_id);
+            }
+        }
+    }
+
+    /// Arrival times of grouped incidents at or above `severity` received at
+    /// or after `since_ms`.
+    pub fn received_since(&self, since_ms: u64, severity: DiagnosticSeverity) -> Vec<u64> {
+        self.received
+            .range((since_ms, String::new())..)
+            .filter(|(_, highest)| **highest >= severity)
+            .map(|((received_at_ms, _), _)| *received_at_ms)
+            .collect()
+    }
+
+    pub fn contains(&self, group_id: &str) -> bool {
+        self.groups.contains_key(group_id)
+    }
+
+    /// Most recently seen first.
+    pub fn summaries(
+        &self,
+        resolutions: &BTreeMap<String, GroupResolution>,
+    ) -> Vec<IncidentGroupSummary> {
+        let mut summaries: Vec<_> = self
+            .groups
+            .iter()
+            .map(|(group_id, group)| group.summary(group_id, resolutions.get(group_id)))
+            .collect();
+        summaries.sort_by(|left, right| {
+            right
+                .last_seen_ms
+                .cmp(&left.last_seen_ms)
+                .then_with(|| left.group_id.cmp(&right.group_id))
+        });
+        summaries
+    }
+
+    pub fn summary(
+        &self,
+        group_id: &str,
+        resolution: Option<&GroupResolution>,
+    ) -> Option<IncidentGroupSummary> {
+        self.groups
+            .get(group_id)
+            .map(|group| group.summary(group_id, resolution))
+    }
+
+    /// Newest-first `(received_at_ms, incident_id)` keys strictly before the
+    /// optional cursor.
+    pub fn incident_page(
+        &self,
+        group_id: &str,
+        limit: usize,
+        before: Option<&(u64, String)>,
+    ) -> Vec<(u64, String)> {
+        let Some(group) = self.groups.get(group_id) else {
+            return Vec::new();
+        };
+        let keys: Box<dyn Iterator<Item = &(u64, String)>> = match before {
+            Some(before) => Box::new(group.incidents.range(..before.clone()).rev()),
+            None => Box::new(group.incidents.iter().rev()),
+        };
+        keys.take(limit).cloned().collect()
+    }
+
+    /// Daily incident counts for the `days` UTC days ending with `now_ms`,
+    /// oldest first, including empty days.
+    pub fn trend(&self, group_id: &str, now_ms: u64, days: u64) -> Vec<GroupTrendPoint> {
+        let last_day = now_ms / MILLIS_PER_DAY;
+        let first_day = last_day.saturating_sub(days.saturating_sub(1));
+        let mut counts: BTreeMap<u64, u64> = (first_day..=last_day).map(|day| (day, 0)).collect();
+        if let Some(group) = self.groups.get(group_id) {
+            for (received_at_ms, _) in group
+                .incidents
+                .range((first_day * MILLIS_PER_DAY, String::new())..)
+            {
+                if let Some(count) = counts.get_mut(&(received_at_ms / MILLIS_PER_DAY)) {
+                    *count += 1;
+                }
+            }
+        }
+        counts
+            .into_iter()
+            .map(|(day, incidents)| GroupTrendPoint {
+                day_start_ms: day * MILLIS_PER_DAY,
+                incidents,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResolutionsFile {
+    version: u16,
+    resolved: BTreeMap<String, GroupResolution>,
+}
+
+/// Resolutions are kept even after retention empties a group so that a later
+/// recurrence still reports as a regression.
+pub fn load_resolutions(root: &Path) -> io::Result<BTreeMap<String, GroupResolution>> {
+    let bytes = match fs::read(root.join(RESOLUTIONS_FILE)) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(error) => return Err(error),
+    };
+    let file: ResolutionsFile = serde_json::from_slice(&bytes).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "diagnostics group resolutions are unreadable",
+        )
+    })?;
+    if file.version != RESOLUTIONS_VERSION || !file.resolved.keys().all(|id| valid_group_id(id)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "diagnostics group resolutions are unreadable",
+        ));
+    }
+    Ok(file.resolved)
+}
+
+pub fn save_resolutions(
+    root: &Path,
+    resolved: &BTreeMap<String, GroupResolution>,
+) -> io::Result<()> {
+    let bytes = serde_json::to_vec_pretty(&ResolutionsFile {
+        version: RESOLUTIONS_VERSION,
+        resolved: resolved.clone(),
+    })
+    .map_err(io::Error::other)?;
+    let path = root.join(RESOLUTIONS_FILE);
+    let temp_path = root.join(format!("{RESOLUTIONS_FILE}.tmp"));
+    let mut output = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp_path)?;
+    output.write_all(&bytes)?;
+    output.sync_all()?;
+    drop(output);
+    fs::rename(&temp_path, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_ids_normalize_codes_and_stay_path_safe() {
+        let id = group_id(
+            DiagnosticEventType::JavascriptError,
+            &normalize_code("JS.Type-Error"),
+        );
+        assert_eq!(
+            id,
+            group_id(DiagnosticEventType::JavascriptError, "js.type_error")
+        );
+        assert_ne!(
+            id,
+            group_id(DiagnosticEventType::UnhandledRejection, "js.type_error")
+        );
+        assert!(valid_group_id(&id));
+        assert!(!valid_group_id("grp_../../etc"));
+        assert!(!valid_group_id("inc_0123456789abcdef"));
+    }
+}
@@ -0,0 +1,965 @@
+//! Owner-uploaded debug symbols and read-time stack symbolication.
+//!
+//! Source maps (JavaScript) and Breakpad `.sym` files (native) are stored per
+//! build under `symbols/{git_sha}/{version}/{module}.{map|sym}` inside the
+//! private diagnostics root. Every path component is validated before it is
+//! joined, and uploads are parsed before they are kept.
+//!
+//! Incidents are never rewritten. When an owner reads an incident, frames in
+//! `JavascriptError`, `UnhandledRejection`, and `NativeError` details gain a
+//! `symbol` object. Resolved files and functions pass through the same
+//! `sanitize_text` as stored text, and absolute build paths are reduced to their
+//! file name first, so symbol files cannot reintroduce paths or secrets.
+
+use crate::diagnostics::{sanitize_text, DiagnosticEventType, StoredIncident};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+pub const MAX_SYMBOL_FILE_BYTES: usize = 128 * 1024 * 1024;
+
+const SYMBOLS_DIR: &str = "symbols";
+const MAX_CACHED_SYMBOL_FILES: usize = 4;
+const MAX_MODULE_NAME_BYTES: usize = 128;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    SourceMap,
+    Breakpad,
+}
+
+impl SymbolKind {
+    fn extension(self) -> &'static str {
+        match self {
+            SymbolKind::SourceMap => "map",
+            SymbolKind::Breakpad => "sym",
+        }
+    }
+
+    fn from_file_name(file_name: &str) -> Option<(&str, Self)> {
+        if let Some(module) = file_name.strip_suffix(".map") {
+            Some((module, SymbolKind::SourceMap))
+        } else {
+            file_name
+                .strip_suffix(".sym")
+                .map(|module| (module, SymbolKind::Breakpad))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SymbolError {
+    InvalidName(&'static str),
+    TooLarge,
+    Unparseable(&'static str),
+    Io(io::Error),
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName(field) => write!(formatter, "invalid {field}"),
+            Self::TooLarge => formatter.write_str("symbol file exceeds the size limit"),
+            Self::Unparseable(reason) => write!(formatter, "unreadable symbol file: {reason}"),
+            Self::Io(error) => write!(formatter, "symbol storage failed: {error}"),
+        }
+    }
+}
+
+impl From<io::Error> for SymbolError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SymbolFileInfo {
+    pub module: String,
+    pub kind: SymbolKind,
+    pub bytes: u64,
+    pub uploaded_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SymbolBuild {
+    pub git_sha: String,
+    pub version: String,
+    pub files: Vec<SymbolFileInfo>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct ResolvedFrame {
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    function: Option<String>,
+}
+
+impl ResolvedFrame {
+    fn into_value(self) -> Value {
+        let mut symbol = Map::new();
+        if let Some(function) = self.function {
+            symbol.insert(
+                "function".to_owned(),
+                Value::from(sanitize_function_name(&function)),
+            );
+        }
+        if let Some(file) = self.file {
+            symbol.insert(
+                "file".to_owned(),
+                Value::from(sanitize_text(&display_source_path(&file))),
+            );
+        }
+        if let Some(line) = self.line {
+            symbol.insert("line".to_owned(), Value::from(line));
+        }
+        if let Some(column) = self.column {
+            symbol.insert("column".to_owned(), Value::from(column));
+        }
+        Value::Object(symbol)
+    }
+}
+
+/// Rust and C++ qualified names would otherwise read as IPv6 literals, so each
+/// `::` segment is sanitized on its own.
+fn sanitize_function_name(name: &str) -> String {
+    name.split("::")
+        .map(sanitize_text)
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Bundler prefixes and leading relative segments are dropped; absolute paths
+/// keep only the file name so build-machine directories never reach output.
+fn display_source_path(path: &str) -> String {
+    let mut path = path;
+    for prefix in ["webpack://", "vite://", "rollup://"] {
+        if let Some(rest) = path.strip_prefix(prefix) {
+            path = rest.split_once('/').map(|(_, rest)| rest).unwrap_or(rest);
+        }
+    }
+    let absolute = path.starts_with('/')
+        || path.starts_with('\\')
+        || path.starts_with('~')
+        || path.contains("://")
+        || (path.len() >= 2
+            && path.as_bytes()[0].is_ascii_alphabetic()
+            && path.as_bytes()[1] == b':');
+    if absolute {
+        return path.rsplit(['/', '\\']).next().unwrap_or(path).to_owned();
+    }
+    loop {
+        if let Some(rest) = path.strip_prefix("./").or_else(|| path.strip_prefix("../")) {
+            path = rest;
+        } else {
+            break path.to_owned();
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SourceMapping {
+    generated_column: u32,
+    original: Option<(u32, u32, u32, Option<u32>)>,
+}
+
+#[derive(Debug)]
+struct SourceMap {
+    sources: Vec<Option<String>>,
+    names: Vec<String>,
+    lines: Vec<Vec<SourceMapping>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    version: u32,
+    #[serde(default)]
+    source_root: Option<String>,
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    names: Vec<String>,
+    mappings: String,
+}
+
+impl SourceMap {
+    fn parse(bytes: &[u8]) -> Result<Self, SymbolError> {
+        let raw: RawSourceMap = serde_json::from_slice(bytes)
+            .map_err(|_| SymbolError::Unparseable("not a v3 source map with mappings"))?;
+        if raw.version != 3 {
+            return Err(SymbolError::Unparseable(
+                "only v3 source maps are supported",
+            ));
+        }
+        let root = raw
+            .source_root
+            .as_deref()
+            .map(|root| root.trim_end_matches('/'))
+            .filter(|root| !root.is_empty());
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|source| {
+                source.map(|source| match root {
+                    Some(root) => format!("{root}/{source}"),
+                    None => source,
+                })
+            })
+            .collect::<Vec<_>>();
+        let lines = decode_mappings(&raw.mappings, sources.len(), raw.names.len())
+            .ok_or(SymbolError::Unparseable("invalid mappings"))?;
+        Ok(Self {
+            sources,
+            names: raw.names,
+            lines,
+        })
+    }
+
+    /// `line` and `column` are the one-based positions browsers report.
+    fn resolve(&self, line: u64, column: u64) -> Option<ResolvedFrame> {
+        let segments = self
+            .lines
+            .get(usize::try_from(line.checked_sub(1)?).ok()?)?;
+        let column = u32::try_from(column.saturating_sub(1)).ok()?;
+        let index = segments.partition_point(|segment| segment.generated_column <= column);
+        let (source, original_line, original_column, name) =
+            segments.get(index.checked_sub(1)?)?.original?;
+        Some(ResolvedFrame {
+            file: self.sources.get(source as usize).cloned().flatten(),
+            line: Some(original_line.checked_add(1)?),
+            column: Some(original_column.checked_add(1)?),
+            function: name.and_then(|name| self.names.get(name as usize).cloned()),
+        })
+    }
+}
+
+fn decode_mappings(
+    mappings: &str,
+    source_count: usize,
+    name_count: usize,
+) -> Option<Vec<Vec<SourceMapping>>> {
+    let mut lines = Vec::new();
+    let (mut source, mut original_line, mut original_column, mut name) = (0i64, 0i64, 0i64, 0i64);
+    for line in mappings.split(';') {
+        let mut generated_column = 0i64;
+        let mut segments = Vec::new();
+        for segment in line.split(',').filter(|segment| !segment.is_empty()) {
+            let fields = decode_vlq(segment)?;
+            generated_column = generated_column.checked_add(*fields.first()?)?;
+            let original = match fields.len() {
+                1 => None,
+                4 | 5 => {
+                    source = source.checked_add(fields[1])?;
+                    original_line = original_line.checked_add(fields[2])?;
+                    original_column = original_column.checked_add(fields[3])?;
+                    let name = if fields.len() == 5 {
+                        name = name.checked_add(fields[4])?;
+                        Some(
+                            u32::try_from(name)
+                                .ok()
+                                .filter(|name| (*name as usize) < name_count)?,
+                        )
+                    } else {
+                        None
+                    };
+                    let source = u32::try_from(source)
+                        .ok()
+                        .filter(|source| (*source as usize) < source_count)?;
+                    Some((
+                        source,
+                        u32::try_from(original_line).ok()?,
+                        u32::try_from(original_column).ok()?,
+                        name,
+                    ))
+                }
+                _ => return None,
+            };
+            segments.push(SourceMapping {
+                generated_column: u32::try_from(generated_column).ok()?,
+                original,
+            });
+        }
+        segments.sort_by_key(|segment| segment.generated_column);
+        lines.push(segments);
+    }
+    Some(lines)
+}
+
+fn decode_vlq(segment: &str) -> Option<Vec<i64>> {
+    let mut values = Vec::with_capacity(5);
+    let (mut value, mut shift) = (0i64, 0u32);
+    for byte in segment.bytes() {
+        let digit = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        } as i64;
+        value += (digit & 31) << shift;
+        if digit & 32 != 0 {
+            shift += 5;
+            if shift > 55 {
+                return None;
+            }
+        } else {
+            let magnitude = value >> 1;
+            values.push(if value & 1 == 1 {
+                -magnitude
+            } else {
+                magnitude
+            });
+            value = 0;
+            shift = 0;
+        }
+    }
+    (shift == 0).then_some(values)
+}
+
+#[derive(Debug)]
+struct NativeFunction {
+    address: u64,
+    size: u64,
+    name: String,
+    /// `(address, size, line, file)`, sorted by address.
+    lines: Vec<(u64, u64, u32, u32)>,
+}
+
+#[derive(Debug, Default)]
+struct BreakpadSymbols {
+    files: HashMap<u32, String>,
+    functions: Vec<NativeFunction>,
+    publics: Vec<(u64, String)>,
+}
+
+impl BreakpadSymbols {
+    fn parse(bytes: &[u8]) -> Result<Self, SymbolError> {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|_| SymbolError::Unparseable("Breakpad symbols must be UTF-8"))?;
+        let mut lines = text.lines();
+        if !lines
+            .find(|line| !line.trim().is_empty())
+            .is_some_and(|line| line.starts_with("MODULE "))
+        {
+            return Err(SymbolError::Unparseable("missing Breakpad MODULE record"));
+        }
+        let mut symbols = Self::default();
+        for line in lines {
+            let line = line.trim_end();
+            if let Some(rest) = line.strip_prefix("FILE ") {
+                if let Some((number, name)) = rest.split_once(' ') {
+                    if let Ok(number) = number.parse() {
+                        symbols.files.insert(number, name.to_owned());
+                    }
+                }
+            } else if let Some(rest) = line.strip_prefix("FUNC ") {
+                let rest = rest.strip_prefix("m ").unwrap_or(rest);
+                let mut parts = rest.splitn(4, ' ');
+                let (Some(address), Some(size), Some(_), Some(name)) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                let (Ok(address), Ok(size)) = (
+                    u64::from_str_radix(address, 16),
+                    u64::from_str_radix(size, 16),
+                ) else {
+                    continue;
+                };
+                symbols.functions.push(NativeFunction {
+                    address,
+                    size,
+                    name: name.to_owned(),
+                    lines: Vec::new(),
+                });
+            } else if let Some(rest) = line.strip_prefix("PUBLIC ") {
+                let rest = rest.strip_prefix("m ").unwrap_or(rest);
+                let mut parts = rest.splitn(3, ' ');
+                if let (Some(Ok(address)), Some(_), Some(name)) = (
+                    parts.next().map(|address| u64::from_str_radix(address, 16)),
+                    parts.next(),
+                    parts.next(),
+                ) {
+                    symbols.publics.push((address, name.to_owned()));
+                }
+            } else if line.as_bytes().first().is_some_and(u8::is_ascii_hexdigit) {
+                let mut parts = line.splitn(4, ' ');
+                let (Some(address), Some(size), Some(number), Some(file)) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                if let (Some(function), Ok(address), Ok(size), Ok(number), Ok(file)) = (
+                    symbols.functions.last_mut(),
+                    u64::from_str_radix(address, 16),
+                    u64::from_str_radix(size, 16),
+                    number.parse(),
+                    file.parse(),
+                ) {
+                    function.lines.push((address, size, number, file));
+                }
+            }
+        }
+        if symbols.functions.is_empty() && symbols.publics.is_empty() {
+            return Err(SymbolError::Unparseable("no FUNC or PUBLIC records"));
+        }
+        symbols.functions.sort_by_key(|function| function.address);
+        for function in &mut symbols.functions {
+            function.lines.sort_by_key(|line| line.0);
+        }
+        symbols.publics.sort_by_key(|public| public.0);
+        Ok(symbols)
+    }
+
+    /// `offset` is the module-relative address the client reports.
+    fn resolve(&self, offset: u64) -> Option<ResolvedFrame> {
+        let index = self
+            .functions
+            .partition_point(|function| function.address <= offset);
+        if let Some(function) = index
+            .checked_sub(1)
+            .and_then(|index| self.functions.get(index))
+            .filter(|function| offset < function.address.saturating_add(function.size.max(1)))
+        {
+            let line = function
+                .lines
+                .partition_point(|line| line.0 <= offset)
+                .checked_sub(1)
+                .and_then(|index| function.lines.get(index))
+                .filter(|line| offset < line.0.saturating_add(line.1.max(1)));
+            return Some(ResolvedFrame {
+                file: line.and_then(|line| self.files.get(&line.3).cloned()),
+                line: line.map(|line| line.2),
+                column: None,
+                function: Some(function.name.clone()),
+            });
+        }
+        let index = self.publics.partition_point(|public| public.0 <= offset);
+        let (_, name) = self.publics.get(index.checked_sub(1)?)?;
+        Some(ResolvedFrame {
+            function: Some(name.clone()),
+            ..ResolvedFrame::default()
+        })
+    }
+}
+
+#[derive(Debug)]
+enum SymbolFile {
+    SourceMap(SourceMap),
+    Breakpad(BreakpadSymbols),
+}
+
+impl SymbolFile {
+    fn parse(kind: SymbolKind, bytes: &[u8]) -> Result<Self, SymbolError> {
+        match kind {
+            SymbolKind::SourceMap => SourceMap::parse(bytes).map(Self::SourceMap),
+            SymbolKind::Breakpad => BreakpadSymbols::parse(bytes).map(Self::Breakpad),
+        }
+    }
+}
+
+fn validate_git_sha(value: &str) -> Result<(), SymbolError> {
+    if (7..=12).contains(&value.len())
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || matches!(byte, b'a'..=b'f'))
+    {
+        Ok(())
+    } else {
+        Err(SymbolError::InvalidName("git_sha"))
+    }
+}
+
+fn validate_name(value: &str, max_bytes: usize, field: &'static str) -> Result<(), SymbolError> {
+    if !value.is_empty()
+        && value.len() <= max_bytes
+        && value.as_bytes()[0].is_ascii_alphanumeric()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_' | b'-' | b'+'))
+    {
+        Ok(())
+    } else {
+        Err(SymbolError::InvalidName(field))
+    }
+}
+
+pub struct SymbolStore {
+    root: PathBuf,
+    write_lock: Mutex<()>,
+    cache: Mutex<HashMap<PathBuf, Arc<SymbolFile>>>,
+}
+
+impl SymbolStore {
+    pub fn new(diagnostics_root: &Path) -> Self {
+        Self {
+            root: diagnostics_root.join(SYMBOLS_DIR),
+            write_lock: Mutex::new(()),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn build_dir(&self, git_sha: &str, version: &str) -> Result<PathBuf, SymbolError> {
+        validate_git_sha(git_sha)?;
+        validate_name(version, 32, "version")?;
+        Ok(self.root.join(git_sha).join(version))
+    }
+
+    /// Parses and stores one symbol file, replacing any earlier upload for
+    /// the same build and module.
+    pub fn upload(
+        &self,
+        git_sha: &str,
+        version: &str,
+        module: &str,
+        kind: SymbolKind,
+        bytes: &[u8],
+    ) -> Result<SymbolFileInfo, SymbolError> {
+        let build = self.build_dir(git_sha, version)?;
+        validate_name(module, MAX_MODULE_NAME_BYTES, "module")?;
+        if bytes.len() > MAX_SYMBOL_FILE_BYTES {
+            return Err(SymbolError::TooLarge);
+        }
+        let parsed = SymbolFile::parse(kind, bytes)?;
+
+        let _guard = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        fs::create_dir_all(&build)?;
+        let file_name = format!("{module}.{}", kind.extension());
+        let path = build.join(&file_name);
+        let temp_path = build.join(format!("{file_name}.tmp"));
+        let mut output = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)?;
+        output.write_all(bytes)?;
+        output.sync_all()?;
+        drop(output);
+        fs::rename(&temp_path, &path)?;
+        // Same module uploaded as the other kind is superseded.
+        let other = match kind {
+            SymbolKind::SourceMap => SymbolKind::Breakpad,
+            SymbolKind::Breakpad => SymbolKind::SourceMap,
+        };
+        let _ = fs::remove_file(build.join(format!("{module}.{}", other.extension())));
+
+        let mut cache = self
+            .cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        cache.retain(|cached, _| !cached.starts_with(&build));
+        if cache.len() >= MAX_CACHED_SYMBOL_FILES {
+            cache.clear();
+        }
+        cache.insert(path.clone(), Arc::new(parsed));
+        file_info(&path).ok_or_else(|| SymbolError::Io(io::Error::other("symbol file vanished")))
+    }
+
+    pub fn list(&self) -> Result<Vec<SymbolBuild>, SymbolError> {
+        let mut builds = Vec::new();
+        for (git_sha, sha_dir) in child_dirs(&self.root)? {
+            if validate_git_sha(&git_sha).is_err() {
+                continue;
+            }
+            for (version, build) in child_dirs(&sha_dir)? {
+                let mut files = Vec::new();
+                for entry in fs::read_dir(&build)? {
+                    if let Some(info) = file_info(&entry?.path()) {
+                        files.push(info);
+                    }
+                }
+                files.sort_by(|left, right| left.module.cmp(&right.module));
+                builds.push(SymbolBuild {
+                    git_sha: git_sha.clone(),
+                    version,
+                    files,
+                });
+            }
+        }
+        builds.sort_by(|left, right| {
+            let newest =
+                |build: &SymbolBuild| build.files.iter().map(|file| file.uploaded_at_ms).max();
+            newest(right)
+                .cmp(&newest(left))
+                .then_with(|| left.git_sha.cmp(&right.git_sha))
+        });
+        Ok(builds)
+    }
+
+    /// Removes every symbol file for one build. Returns false when none existed.
+    pub fn delete_build(&self, git_sha: &str, version: &str) -> Result<bool, SymbolError> {
+        let build = self.build_dir(git_sha, version)?;
+        let _guard = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match fs::remove_dir_all(&build) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(error.into()),
+        }
+        let _ = fs::remove_dir(build.parent().unwrap_or(&self.root));
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|cached, _| !cached.starts_with(&build));
+        Ok(true)
+    }
+
+    /// Annotates resolvable frames in a record that is about to be returned
+    /// to an owner. Missing or unreadable symbols leave frames untouched.
+    pub fn symbolicate(&self, record: &mut StoredIncident) {
+        let Ok(build) = self.build_dir(&record.envelope.app.git_sha, &record.envelope.app.version)
+        else {
+            return;
+        };
+        if !build.is_dir() {
+            return;
+        }
+        for event in &mut record.envelope.events {
+            let javascript = match event.event_type {
+                DiagnosticEventType::JavascriptError | DiagnosticEventType::UnhandledRejection => {
+                    true
+                }
+                DiagnosticEventType::NativeError => false,
+                _ => continue,
+            };
+            if javascript {
+                if let Some(symbol) = self.resolve_frame(&build, &event.details, true) {
+                    event.details.insert("symbol".to_owned(), symbol);
+                }
+            }
+            if let Some(Value::Array(frames)) = event.details.get_mut("stack") {
+                for frame in frames {
+                    let Value::Object(frame) = frame else {
+                        continue;
+                    };
+                    if let Some(symbol) = self.resolve_frame(&build, frame, javascript) {
+                        frame.insert("symbol".to_owned(), symbol);
+                    }
+                }
+            }
+        }
+    }
+
+    fn resolve_frame<'a, M>(&self, build: &Path, frame: &'a M, javascript: bool) -> Option<Value>
+    where
+        &'a M: IntoIterator<Item = (&'a String, &'a Value)>,
+    {
+        let mut fields: HashMap<&str, &Value> = HashMap::new();
+        for (key, value) in frame {
+            fields.insert(key.as_str(), value);
+        }
+        let module = fields.get("module").and_then(|value| value.as_str());
+        if let (Some(line), Some(column)) = (
+            fields.get("line").and_then(|value| value.as_u64()),
+            fields.get("column").and_then(|value| value.as_u64()),
+        ) {
+            if !javascript {
+                return None;
+            }
+            let path = match module {
+                Some(module) => self.module_path(build, module, SymbolKind::SourceMap)?,
+                // Browser error events carry no script name; a build with a
+                // single source map can still be resolved unambiguously.
+                None => self.only_source_map(build)?,
+            };
+            return match self.load(&path, SymbolKind::SourceMap)?.as_ref() {
+                SymbolFile::SourceMap(map) => {
+                    map.resolve(line, column).map(ResolvedFrame::into_value)
+                }
+                SymbolFile::Breakpad(_) => None,
+            };
+        }
+        let offset = match fields.get("offset")? {
+            Value::Number(number) => number.as_u64()?,
+            Value::String(text) => u64::from_str_radix(text.strip_prefix("0x")?, 16).ok()?,
+            _ => return None,
+        };
+        let path = self.module_path(build, module?, SymbolKind::Breakpad)?;
+        match self.load(&path, SymbolKind::Breakpad)?.as_ref() {
+            SymbolFile::Breakpad(symbols) => symbols.resolve(offset).map(ResolvedFrame::into_value),
+            SymbolFile::SourceMap(_) => None,
+        }
+    }
+
+    fn module_path(&self, build: &Path, module: &str, kind: SymbolKind) -> Option<PathBuf> {
+        validate_name(module, MAX_MODULE_NAME_BYTES, "module").ok()?;
+        let path = build.join(format!("{module}.{}", kind.extension()));
+        path.is_file().then_some(path)
+    }
+
+    fn only_source_map(&self, build: &Path) -> Option<PathBuf> {
+        let mut maps = fs::read_dir(build)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "map"));
+        let only = maps.next()?;
+        maps.next().is_none().then_some(only)
+    }
+
+    fn load(&self, path: &Path, kind: SymbolKind) -> Option<Arc<SymbolFile>> {
+        if let Some(cached) = self
+            .cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(path)
+        {
+            return Some(cached.clone());
+        }
+        let parsed = Arc::new(SymbolFile::parse(kind, &fs::read(path).ok()?).ok()?);
+        let mut cache = self
+            .cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if cache.len() >= MAX_CACHED_SYMBOL_FILES {
+            cache.clear();
+        }
+        cache.insert(path.to_path_buf(), parsed.clone());
+        Some(parsed)
+    }
+}
+
+fn child_dirs(path: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    let mut dirs = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if let Some(name) = entry.file_name().to_str() {
+            dirs.push((name.to_owned(), entry.path()));
+        }
+    }
+    Ok(dirs)
+}
+
+fn file_info(path: &Path) -> Option<SymbolFileInfo> {
+    let file_name = path.file_name()?.to_str()?;
+    let (module, kind) = SymbolKind::from_file_name(file_name)?;
+    let metadata = fs::metadata(path).ok()?;
+    Some(SymbolFileInfo {
+        module: module.to_owned(),
+        kind,
+        bytes: metadata.len(),
+        uploaded_at_ms: metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::{
+        DiagnosticSeverity, IncidentEnvelope, IncidentEvent, STORED_RECORD_VERSION,
+    };
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    // Line 1: column 0 -> app.ts 1:1 `boot`; column 10 -> app.ts 5:3.
+    const SOURCE_MAP: &str =
+        r#"{"version":3,"sources":["../src/app.ts"],"names":["boot"],"mappings":"AAAAA,UAIE"}"#;
+    const BREAKPAD: &str = "MODULE windows x86_64 0123ABCD echo-desktop.pdb\n\
+        FILE 0 C:\\Users\\builder\\echo\\src\\main.rs\n\
+        FUNC 1000 40 0 echo_desktop::main\n\
+        1000 20 10 0\n\
+        1020 20 12 0\n\
+        PUBLIC 2000 0 echo_desktop::helper\n";
+
+    fn temp_root() -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("echo-symbols-test-{:016x}", rand::random::<u64>()));
+        fs::create_dir_all(&path).expect("create test directory");
+        path
+    }
+
+    fn incident(events: Vec<IncidentEvent>) -> StoredIncident {
+        let mut envelope: IncidentEnvelope = serde_json::from_slice(include_bytes!(
+            "../testdata/browser-diagnostics-envelope-v1.json"
+        ))
+        .expect("fixture envelope");
+        envelope.events = events;
+        StoredIncident {
+            record_version: STORED_RECORD_VERSION,
+            incident_id: format!("inc_{}", "0".repeat(32)),
+            received_at_ms: envelope.sent_at_ms,
+            authenticated_identity: "alice".to_owned(),
+            authenticated_identity_digest: "0".repeat(64),
+            payload_digest: String::new(),
+            envelope,
+        }
+    }
+
+    fn event(event_type: DiagnosticEventType, details: Value) -> IncidentEvent {
+        IncidentEvent {
+            sequence: 1,
+            timestamp_ms: 0,
+            event_type,
+            severity: DiagnosticSeverity::Error,
+            code: "javascript.window_error".to_owned(),
+            fingerprint: None,
+            message: None,
+            details: serde_json::from_value::<BTreeMap<String, Value>>(details)
+                .expect("details object"),
+        }
+    }
+
+    #[test]
+    fn decodes_source_maps_and_breakpad_symbols() {
+        let map = SourceMap::parse(SOURCE_MAP.as_bytes()).expect("parse source map");
+        assert_eq!(
+            map.resolve(1, 1),
+            Some(ResolvedFrame {
+                file: Some("../src/app.ts".to_owned()),
+                line: Some(1),
+                column: Some(1),
+                function: Some("boot".to_owned()),
+            })
+        );
+        assert_eq!(map.resolve(1, 40).and_then(|frame| frame.line), Some(5));
+        assert_eq!(map.resolve(2, 1), None);
+        assert!(SourceMap::parse(br#"{"version":3,"sections":[]}"#).is_err());
+        assert!(SourceMap::parse(br#"{"version":3,"sources":[],"mappings":"AAAA"}"#).is_err());
+
+        // Hostile maps: running sums past i64 and lines at u32::MAX.
+        let vlq = |value: i64| {
+            let mut rest = if value < 0 {
+                ((-value) << 1) | 1
+            } else {
+                value << 1
+            };
+            let mut encoded = String::new();
+            loop {
+                let mut digit = rest & 31;
+                rest >>= 5;
+                if rest > 0 {
+                    digit |= 32;
+                }
+                encoded.push(
+                    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/"
+                        [digit as usize] as char,
+                );
+                if rest == 0 {
+                    return encoded;
+                }
+            }
+        };
+        let overflowing = vec![vlq((1 << 59) - 1); 20].join(",");
+        let map = format!(r#"{{"version":3,"sources":["a.js"],"mappings":"{overflowing}"}}"#);
+        assert!(SourceMap::parse(map.as_bytes()).is_err());
+        let last_line = format!("AA{}A", vlq(i64::from(u32::MAX)));
+        let map = format!(r#"{{"version":3,"sources":["a.js"],"mappings":"{last_line}"}}"#);
+        let map = SourceMap::parse(map.as_bytes()).expect("parse edge map");
+        assert_eq!(map.resolve(1, 1), None);
+
+        let symbols = BreakpadSymbols::parse(BREAKPAD.as_bytes()).expect("parse symbols");
+        let frame = symbols.resolve(0x1025).expect("resolve in function");
+        assert_eq!(frame.function.as_deref(), Some("echo_desktop::main"));
+        assert_eq!(frame.line, Some(12));
+        assert_eq!(
+            symbols.resolve(0x2100).and_then(|frame| frame.function),
+            Some("echo_desktop::helper".to_owned())
+        );
+        assert!(symbols.resolve(0x10).is_none());
+        assert!(BreakpadSymbols::parse(b"FUNC 0 1 0 main\n").is_err());
+    }
+
+    #[test]
+    fn symbolicates_frames_without_leaking_build_paths() {
+        let root = temp_root();
+        let store = SymbolStore::new(&root);
+        let mut record = incident(Vec::new());
+        let (sha, version) = (
+            record.envelope.app.git_sha.clone(),
+            record.envelope.app.version.clone(),
+        );
+        store
+            .upload(
+                &sha,
+                &version,
+                "index-abc123.js",
+                SymbolKind::SourceMap,
+                SOURCE_MAP.as_bytes(),
+            )
+            .expect("upload source map");
+        store
+            .upload(
+                &sha,
+                &version,
+                "echo-desktop.exe",
+                SymbolKind::Breakpad,
+                BREAKPAD.as_bytes(),
+            )
+            .expect("upload symbols");
+        assert!(matches!(
+            store.upload(
+                &sha,
+                &version,
+                "../escape",
+                SymbolKind::SourceMap,
+                SOURCE_MAP.as_bytes()
+            ),
+            Err(SymbolError::InvalidName("module"))
+        ));
+        assert!(store
+            .upload(&sha, &version, "bad.js", SymbolKind::SourceMap, b"{}")
+            .is_err());
+
+        record.envelope.events = vec![
+            event(
+                DiagnosticEventType::JavascriptError,
+                json!({"line": 1, "column": 12, "error_code": "type_error"}),
+            ),
+            event(
+                DiagnosticEventType::NativeError,
+                json!({"stack": [
+                    {"module": "echo-desktop.exe", "offset": 4133},
+                    {"module": "echo-desktop.exe", "offset": "0x2100"},
+                    {"module": "unknown.dll", "offset": 16}
+                ]}),
+            ),
+        ];
+        store.symbolicate(&mut record);
+        assert_eq!(
+            record.envelope.events[0].details["symbol"],
+            json!({"file": "src/app.ts", "line": 5, "column": 3})
+        );
+        let frames = &record.envelope.events[1].details["stack"];
+        assert_eq!(
+            frames[0]["symbol"],
+            json!({"function": "echo_desktop::main", "file": "main.rs", "line": 12})
+        );
+        assert_eq!(frames[1]["symbol"]["function"], "echo_desktop::helper");
+        assert!(frames[2].get("symbol").is_none());
+
+        let builds = store.list().expect("list builds");
+        assert_eq!(builds.len(), 1);
+        assert_eq!(builds[0].files.len(), 2);
+        assert!(store.delete_build(&sha, &version).expect("delete build"));
+        assert!(!store.delete_build(&sha, &version).expect("repeat delete"));
+        let _ = fs::remove_dir_all(root);
+    }
+}
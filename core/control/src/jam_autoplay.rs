@@ -0,0 +1,404 @@
+//! Autoplay radio for Spotify Jams.
+//!
+//! Once a Jam with autoplay on has no pending entries left, Echo queues one
+//! track at a time chosen from the group's track favorites and the room's
+//! recent history. Picks are weighted by how many people contributed a track
+//! and how recently, and anything played in the last few tracks is skipped.
+//! Autoplay entries carry their own provenance and are withdrawn the moment a
+//! person queues something.
+
+use crate::config::{now_ts_ms, random_secret};
+use crate::jam_history::JamHistoryEntry;
+use crate::jam_library::{FavoriteItem, FavoriteKind, FavoriteSummary};
+use crate::jam_session::{
+    pending_queue_entry, JamPlaybackSource, JamQueueEntry, JamState, QueueDeliveryState,
+    QueuedTrack,
+};
+use crate::AppState;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+pub(crate) const AUTOPLAY_ACTOR_ID: &str = "echo-autoplay";
+const AUTOPLAY_NAME: &str = "Autoplay";
+// Tracks among the room's most recent plays are never picked again.
+const RECENT_REPEAT_WINDOW: usize = 25;
+const HISTORY_LOOKBACK_MS: u64 = 14 * DAY_MS;
+const DAY_MS: u64 = 24 * 60 * 60 * 1_000;
+
+/// Where an autoplay pick came from. Stored on queue and history entries.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AutoplaySource {
+    Favorites,
+    History,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct AutoplayCandidate {
+    pub(crate) summary: FavoriteSummary,
+    pub(crate) source: AutoplaySource,
+    pub(crate) weight: f64,
+}
+
+#[derive(Default)]
+struct CandidateStats {
+    summary: FavoriteSummary,
+    favorite: bool,
+    contributors: HashSet<String>,
+    last_activity_ms: u64,
+}
+
+fn spotify_track_uri(uri: &str) -> bool {
+    uri.starts_with("spotify:track:")
+}
+
+fn in_room(entry: &JamHistoryEntry, room: &str) -> bool {
+    entry
+        .room
+        .as_deref()
+        .is_none_or(|entry_room| entry_room == room)
+}
+
+/// Score every eligible track. `excluded_uris` holds what is already queued or
+/// playing; the room's recent plays are excluded here.
+pub(crate) fn autoplay_candidates(
+    favorites: &[FavoriteItem],
+    history: &[JamHistoryEntry],
+    room: &str,
+    excluded_uris: &HashSet<String>,
+    now_ms: u64,
+) -> Vec<AutoplayCandidate> {
+    let mut room_history = history
+        .iter()
+        .filter(|entry| in_room(entry, room))
+        .collect::<Vec<_>>();
+    room_history.sort_by_key(|entry| std::cmp::Reverse(entry.played_at_ms));
+    let recent = room_history
+        .iter()
+        .take(RECENT_REPEAT_WINDOW)
+        .map(|entry| entry.spotify_uri.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut stats: HashMap<String, CandidateStats> = HashMap::new();
+    for item in favorites
+        .iter()
+        .filter(|item| item.kind == FavoriteKind::Track)
+    {
+        let stat = stats.entry(item.summary.spotify_uri.clone()).or_default();
+        stat.summary = item.summary.clone();
+        stat.favorite = true;
+        for attribution in &item.attributions {
+            stat.contributors.insert(attribution.actor_id.clone());
+            stat.last_activity_ms = stat.last_activity_ms.max(attribution.added_at_ms);
+        }
+    }
+    let cutoff = now_ms.saturating_sub(HISTORY_LOOKBACK_MS);
+    for entry in room_history.iter().filter(|entry| {
+        entry.played_at_ms >= cutoff && entry.added_by_actor_id != AUTOPLAY_ACTOR_ID
+    }) {
+        let stat = stats
+            .entry(entry.spotify_uri.clone())
+            .or_insert_with(|| CandidateStats {
+                summary: FavoriteSummary {
+                    spotify_id: entry.spotify_id.clone(),
+                    spotify_uri: entry.spotify_uri.clone(),
+                    spotify_url: entry.spotify_url.clone(),
+                    name: entry.name.clone(),
+                    artist: Some(entry.artist.clone()),
+                    artwork_url: Some(entry.album_art_url.clone()),
+                    duration_ms: Some(entry.duration_ms),
+                    ..FavoriteSummary::default()
+                },
+                ..CandidateStats::default()
+            });
+        stat.contributors.insert(entry.added_by_actor_id.clone());
+        stat.last_activity_ms = stat.last_activity_ms.max(entry.played_at_ms);
+    }
+
+    let mut candidates = stats
+        .into_iter()
+        .filter(|(uri, stat)| {
+            spotify_track_uri(uri)
+                && !stat.contributors.is_empty()
+                && !recent.contains(uri.as_str())
+                && !excluded_uris.contains(uri)
+        })
+        .map(|(_, stat)| {
+            let age_days = now_ms.saturating_sub(stat.last_activity_ms) as f64 / DAY_MS as f64;
+            AutoplayCandidate {
+                summary: stat.summary,
+                source: if stat.favorite {
+                    AutoplaySource::Favorites
+                } else {
+                    AutoplaySource::History
+                },
+                weight: stat.contributors.len() as f64 / (1.0 + age_days),
+            }
+        })
+        .collect::<Vec<_>>();
+    // Stable order so a given roll always maps to the same pick.
+    candidates.sort_by(|a, b| a.summary.spotify_uri.cmp(&b.summary.spotify_uri));
+    candidates
+}
+
+/// Weighted choice; `roll` is uniform in `[0, 1)`.
+pub(crate) fn pick_autoplay_candidate(
+    candidates: &[AutoplayCandidate],
+    roll: f64,
+) -> Option<&AutoplayCandidate> {
+    let total = candidates
+        .iter()
+        .map(|candidate| candidate.weight)
+        .sum::<f64>();
+    let mut remaining = roll * total;
+    for candidate in candidates {
+        if remaining < candidate.weight {
+            return Some(candidate);
+        }
+        remaining -= candidate.weight;
+    }
+    candidates.last()
+}
+
+fn autoplay_track(candidate: &AutoplayCandidate, added_at_ms: u64) -> QueuedTrack {
+    let summary = &candidate.summary;
+    QueuedTrack {
+        queue_entry_id: format!("qe1_{}", random_secret()),
+        queue_batch_id: None,
+        spotify_id: summary.spotify_id.clone(),
+        spotify_uri: summary.spotify_uri.clone(),
+        spotify_url: summary.spotify_url.clone(),
+        name: summary.name.clone(),
+        artist: summary.artist.clone().unwrap_or_default(),
+        album_art_url: summary.artwork_url.clone().unwrap_or_default(),
+        duration_ms: summary.duration_ms.unwrap_or_default(),
+        added_at_ms,
+        added_by_actor_id: AUTOPLAY_ACTOR_ID.to_string(),
+        added_by_name: AUTOPLAY_NAME.to_string(),
+        playlist: None,
+        playlist_position: None,
+        autoplay: Some(candidate.source),
+        added_by: AUTOPLAY_NAME.to_string(),
+    }
+}
+
+pub(crate) fn is_pending_autoplay(entry: &JamQueueEntry) -> bool {
+    entry.delivery_state == QueueDeliveryState::Pending && entry.track.autoplay.is_some()
+}
+
+fn autoplay_wanted(jam: &JamState, generation: u64) -> bool {
+    jam.active
+        && jam.generation == generation
+        && jam.autoplay
+        && jam.playback_source == JamPlaybackSource::Spotify
+        && !jam.queue_control_stopped
+        && !jam
+            .queue
+            .iter()
+            .any(|entry| entry.delivery_state == QueueDeliveryState::Pending)
+}
+
+/// Queue one autoplay pick if the Jam wants one. Returns whether an entry was
+/// added, so the frontier pump knows to keep delivering.
+pub(crate) async fn refill_autoplay_queue(state: &AppState, generation: u64) -> bool {
+    let excluded_uris = {
+        let jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+        if !autoplay_wanted(&jam, generation) {
+            return false;
+        }
+        jam.queue
+            .iter()
+            .map(|entry| entry.track.spotify_uri.clone())
+            .chain(
+                jam.now_playing
+                    .as_ref()
+                    .map(|playing| playing.spotify_uri.clone()),
+            )
+            .collect::<HashSet<_>>()
+    };
+    let now_ms = now_ts_ms();
+    // History lives on disk; read it off the runtime and without the Jam lock.
+    let history_store = std::sync::Arc::clone(&state.jam_history);
+    let history = match tokio::task::spawn_blocking(move || history_store.list(now_ms)).await {
+        Ok(Ok(history)) => history,
+        Ok(Err(error)) => {
+            warn!("Jam autoplay is picking without history: {}", error);
+            Vec::new()
+        }
+        Err(error) => {
+            warn!("Jam autoplay history read task failed: {}", error);
+            Vec::new()
+        }
+    };
+    let mut candidates = autoplay_candidates(
+        &state.jam_favorites.snapshot(),
+        &history,
+        &state.jam_room,
+        &excluded_uris,
+        now_ms,
+    );
+    let content_filter = state.jam_content_filters.for_room(&state.jam_room);
+    candidates.retain(|candidate| content_filter.check_summary(&candidate.summary).is_none());
+    let Some(candidate) = pick_autoplay_candidate(&candidates, rand::thread_rng().gen()) else {
+        return false;
+    };
+    let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+    if !autoplay_wanted(&jam, generation) {
+        return false;
+    }
+    jam.queue
+        .push(pending_queue_entry(autoplay_track(candidate, now_ms)));
+    jam.queue_revision = jam.queue_revision.wrapping_add(1);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jam_library::FavoriteAttribution;
+
+    const NOW: u64 = 100 * DAY_MS;
+
+    fn favorite(id: &str, actors: &[&str], added_at_ms: u64) -> FavoriteItem {
+        FavoriteItem {
+            kind: FavoriteKind::Track,
+            summary: FavoriteSummary {
+                spotify_id: id.to_string(),
+                spotify_uri: format!("spotify:track:{id}"),
+                name: id.to_string(),
+                ..FavoriteSummary::default()
+            },
+            attributions: actors
+                .iter()
+                .map(|actor| FavoriteAttribution {
+                    actor_id: actor.to_string(),
+                    display_name: actor.to_string(),
+                    added_at_ms,
+                    source: "echo".to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn played(id: &str, actor: &str, room: Option<&str>, played_at_ms: u64) -> JamHistoryEntry {
+        JamHistoryEntry {
+            schema_version: 1,
+            history_entry_id: format!("jh-{id}-{played_at_ms}"),
+            played_at_ms,
+            queue_entry_id: String::new(),
+            queue_batch_id: None,
+            spotify_id: id.to_string(),
+            spotify_uri: format!("spotify:track:{id}"),
+            spotify_url: String::new(),
+            name: id.to_string(),
+            artist: String::new(),
+            album_art_url: String::new(),
+            duration_ms: 0,
+            added_at_ms: 0,
+            added_by_actor_id: actor.to_string(),
+            added_by_name: actor.to_string(),
+            added_by: actor.to_string(),
+            playlist: None,
+            room: room.map(str::to_string),
+            autoplay: None,
+        }
+    }
+
+    fn weights(candidates: &[AutoplayCandidate]) -> Vec<(&str, f64)> {
+        candidates
+            .iter()
+            .map(|candidate| (candidate.summary.name.as_str(), candidate.weight))
+            .collect()
+    }
+
+    #[test]
+    fn candidates_weigh_contributors_and_recency_and_skip_recent_plays() {
+        let favorites = vec![
+            favorite("shared", &["alex", "sam"], NOW),
+            favorite("old", &["alex"], NOW - 3 * DAY_MS),
+            favorite("recent", &["jo"], NOW),
+            favorite("queued", &["jo"], NOW),
+        ];
+        let history = vec![
+            played("recent", "jo", Some("main"), NOW - 1_000),
+            played("earlier", "sam", Some("main"), NOW - DAY_MS),
+            played("earlier", "jo", None, NOW - DAY_MS),
+            played("elsewhere", "sam", Some("side"), NOW - DAY_MS),
+            played("radio", AUTOPLAY_ACTOR_ID, Some("main"), NOW - DAY_MS),
+        ];
+        let mut history = history;
+        // Push "earlier" out of the recent-repeat window.
+        for index in 0..RECENT_REPEAT_WINDOW {
+            history.push(played(
+                &format!("filler{index}"),
+                AUTOPLAY_ACTOR_ID,
+                Some("main"),
+                NOW - 2_000 - index as u64,
+            ));
+        }
+        let excluded = HashSet::from(["spotify:track:queued".to_string()]);
+
+        let candidates = autoplay_candidates(&favorites, &history, "main", &excluded, NOW);
+
+        assert_eq!(
+            weights(&candidates),
+            [("earlier", 1.0), ("old", 0.25), ("shared", 2.0)]
+        );
+        assert_eq!(candidates[0].source, AutoplaySource::History);
+        assert_eq!(candidates[2].source, AutoplaySource::Favorites);
+    }
+
+    #[test]
+    fn weighted_pick_follows_the_roll() {
+        let candidates = autoplay_candidates(
+            &[
+                favorite("a", &["alex"], NOW),
+                favorite("b", &["alex", "sam", "jo"], NOW),
+            ],
+            &[],
+            "main",
+            &HashSet::new(),
+            NOW,
+        );
+        let pick =
+            |roll| pick_autoplay_candidate(&candidates, roll).map(|c| c.summary.name.as_str());
+        assert_eq!(pick(0.0), Some("a"));
+        assert_eq!(pick(0.24), Some("a"));
+        assert_eq!(pick(0.26), Some("b"));
+        assert_eq!(pick(0.999), Some("b"));
+        assert_eq!(pick_autoplay_candidate(&[], 0.5), None);
+    }
+
+    #[test]
+    fn autoplay_waits_for_an_empty_pending_queue() {
+        let candidate = AutoplayCandidate {
+            summary: favorite("a", &["alex"], NOW).summary,
+            source: AutoplaySource::Favorites,
+            weight: 1.0,
+        };
+        let mut jam = JamState {
+            active: true,
+            generation: 4,
+            autoplay: true,
+            ..JamState::default()
+        };
+        assert!(autoplay_wanted(&jam, 4));
+        assert!(!autoplay_wanted(&jam, 5));
+
+        jam.queue
+            .push(pending_queue_entry(autoplay_track(&candidate, NOW)));
+        assert!(is_pending_autoplay(&jam.queue[0]));
+        assert_eq!(jam.queue[0].track.added_by_actor_id, AUTOPLAY_ACTOR_ID);
+        assert!(!autoplay_wanted(&jam, 4));
+
+        jam.queue[0].delivery_state = QueueDeliveryState::SpotifyCommitted;
+        assert!(!is_pending_autoplay(&jam.queue[0]));
+        assert!(autoplay_wanted(&jam, 4));
+
+        jam.queue_control_stopped = true;
+        assert!(!autoplay_wanted(&jam, 4));
+    }
+}
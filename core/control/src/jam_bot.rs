@@ -1,16 +1,17 @@
-//! Relay for audio uploaded by the configured interactive-session Jam source.
+//! Relay for audio uploaded by the configured interactive-session Jam source,
+//! or decoded on the server by the local music library player.
 
 use crate::jam_source::{JamSourceRegistry, SourceEvent};
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     Arc,
 };
 use std::time::Duration;
 use tokio::sync::broadcast;
 use tracing::{info, warn};
 
-const TARGET_RATE: u32 = 48_000;
-const TARGET_CHANNELS: u32 = 2;
+pub(crate) const TARGET_RATE: u32 = 48_000;
+pub(crate) const TARGET_CHANNELS: u32 = 2;
 const FRAME_DURATION_MS: u32 = 20;
 const SAMPLES_PER_CHANNEL: u32 = TARGET_RATE * FRAME_DURATION_MS / 1000;
 const FRAME_SAMPLES: usize = (SAMPLES_PER_CHANNEL * TARGET_CHANNELS) as usize;
@@ -26,6 +27,8 @@ pub struct JamBot {
     publish_task: Option<tokio::task::JoinHandle<()>>,
     audio_tx: broadcast::Sender<AudioFrame>,
     healthy: Arc<AtomicBool>,
+    relayed_frames: Arc<AtomicU64>,
+    local: bool,
 }
 
 impl JamBot {
@@ -116,11 +119,14 @@ impl JamBot {
 
         let (audio_tx, _) = broadcast::channel::<AudioFrame>(64);
         let healthy = Arc::new(AtomicBool::new(true));
+        let relayed_frames = Arc::new(AtomicU64::new(0));
         let publish_task = tokio::spawn(broadcast_loop(
             generation,
             audio_tx.clone(),
             source_rx,
             healthy.clone(),
+            relayed_frames.clone(),
+            true,
         ));
 
         Ok(Self {
@@ -129,9 +135,41 @@ impl JamBot {
             publish_task: Some(publish_task),
             audio_tx,
             healthy,
+            relayed_frames,
+            local: false,
         })
     }
 
+    /// Relay audio published by the in-process local library player. There is
+    /// no agent handshake, and agent lifecycle events never affect its health.
+    pub fn start_local(generation: u64, source: JamSourceRegistry) -> Self {
+        let source_rx = source.subscribe();
+        let (audio_tx, _) = broadcast::channel::<AudioFrame>(64);
+        let healthy = Arc::new(AtomicBool::new(true));
+        let relayed_frames = Arc::new(AtomicU64::new(0));
+        let publish_task = tokio::spawn(broadcast_loop(
+            generation,
+            audio_tx.clone(),
+            source_rx,
+            healthy.clone(),
+            relayed_frames.clone(),
+            false,
+        ));
+        info!(
+            "[jam-bot] local library relay ready generation={}",
+            generation
+        );
+        Self {
+            generation,
+            source,
+            publish_task: Some(publish_task),
+            audio_tx,
+            healthy,
+            relayed_frames,
+            local: true,
+        }
+    }
+
     pub fn subscribe(&self) -> broadcast::Receiver<AudioFrame> {
         self.audio_tx.subscribe()
     }
@@ -144,10 +182,18 @@ impl JamBot {
         self.generation
     }
 
+    /// Milliseconds of audio relayed to listeners in this generation. It
+    /// stands still while the source is stalled or restarting.
+    pub fn relayed_audio_ms(&self) -> u64 {
+        self.relayed_frames.load(Ordering::Acquire) * u64::from(FRAME_DURATION_MS)
+    }
+
     pub async fn stop(mut self) {
         info!("[jam-bot] stopping generation={}", self.generation);
         self.healthy.store(false, Ordering::Release);
-        self.source.stop(self.generation).await;
+        if !self.local {
+            self.source.stop(self.generation).await;
+        }
         if let Some(task) = self.publish_task.take() {
             task.abort();
             let _ = task.await;
@@ -161,6 +207,8 @@ async fn broadcast_loop(
     tx: broadcast::Sender<AudioFrame>,
     mut source_rx: broadcast::Receiver<SourceEvent>,
     healthy: Arc<AtomicBool>,
+    relayed_frames: Arc<AtomicU64>,
+    follow_source_lifecycle: bool,
 ) {
     let mut accum: Vec<f32> = Vec::with_capacity(FRAME_SAMPLES * 4);
     let mut frame_count = 0_u64;
@@ -183,12 +231,15 @@ async fn broadcast_loop(
                 while accum.len() >= FRAME_SAMPLES {
                     frame_count += 1;
                     let data = accum.drain(..FRAME_SAMPLES).collect();
+                    relayed_frames.fetch_add(1, Ordering::Release);
                     let _ = tx.send(AudioFrame { data });
                     if frame_count == 1 {
                         info!("[jam-bot] first listener frame generation={}", generation);
                     }
                 }
             }
+            Ok(SourceEvent::Audio { .. }) => {}
+            Ok(_) if !follow_source_lifecycle => {}
             Ok(SourceEvent::Error {
                 generation: event_generation,
                 message,
@@ -271,7 +322,7 @@ async fn broadcast_loop(
     }
 }
 
-fn convert_samples(
+pub(crate) fn convert_samples(
     samples: &[f32],
     source_rate: u32,
     source_channels: u32,
@@ -333,7 +384,14 @@ mod tests {
         let (source_tx, source_rx) = broadcast::channel(16);
         let (audio_tx, mut audio_rx) = broadcast::channel(16);
         let healthy = Arc::new(AtomicBool::new(true));
-        let task = tokio::spawn(broadcast_loop(7, audio_tx, source_rx, healthy.clone()));
+        let task = tokio::spawn(broadcast_loop(
+            7,
+            audio_tx,
+            source_rx,
+            healthy.clone(),
+            Arc::new(AtomicU64::new(0)),
+            true,
+        ));
 
         source_tx.send(SourceEvent::Connected).unwrap();
         tokio::task::yield_now().await;
@@ -366,7 +424,15 @@ mod tests {
         let (source_tx, source_rx) = broadcast::channel(16);
         let (audio_tx, mut existing_listener) = broadcast::channel(16);
         let healthy = Arc::new(AtomicBool::new(true));
-        let task = tokio::spawn(broadcast_loop(8, audio_tx, source_rx, healthy.clone()));
+        let relayed_frames = Arc::new(AtomicU64::new(0));
+        let task = tokio::spawn(broadcast_loop(
+            8,
+            audio_tx,
+            source_rx,
+            healthy.clone(),
+            relayed_frames.clone(),
+            true,
+        ));
 
         source_tx
             .send(SourceEvent::Restarting { generation: 8 })
@@ -394,6 +460,7 @@ mod tests {
             .unwrap();
         assert_eq!(frame.data, vec![0.5; FRAME_SAMPLES]);
         assert!(healthy.load(Ordering::Acquire));
+        assert_eq!(relayed_frames.load(Ordering::Acquire), 1);
         task.abort();
     }
 
@@ -402,7 +469,14 @@ mod tests {
         let (source_tx, source_rx) = broadcast::channel(16);
         let (audio_tx, _audio_rx) = broadcast::channel(16);
         let healthy = Arc::new(AtomicBool::new(true));
-        let task = tokio::spawn(broadcast_loop(9, audio_tx, source_rx, healthy.clone()));
+        let task = tokio::spawn(broadcast_loop(
+            9,
+            audio_tx,
+            source_rx,
+            healthy.clone(),
+            Arc::new(AtomicU64::new(0)),
+            true,
+        ));
 
         source_tx
             .send(SourceEvent::AvailabilityChanged {
@@ -466,4 +540,24 @@ mod tests {
         };
         assert_eq!(error, "Jam source disconnected during startup");
     }
+
+    #[tokio::test]
+    async fn local_relay_ignores_agent_lifecycle_events() {
+        let source = JamSourceRegistry::new(false);
+        let bot = JamBot::start_local(41, source.clone());
+        let mut listener = bot.subscribe();
+        let (first_tx, _first_rx) = tokio::sync::mpsc::unbounded_channel();
+        let connection = source.test_register(first_tx).await;
+        source.test_unregister(connection).await;
+        tokio::task::yield_now().await;
+        assert!(bot.is_healthy());
+
+        source.publish_local_audio(41, 48_000, 2, vec![0.125; FRAME_SAMPLES]);
+        let frame = tokio::time::timeout(Duration::from_secs(1), listener.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.data, vec![0.125; FRAME_SAMPLES]);
+        bot.stop().await;
+    }
 }
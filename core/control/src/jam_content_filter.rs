@@ -0,0 +1,505 @@
+//! Per-room content filters for the Jam queue: explicit tracks, a maximum
+//! duration, and blocked artists or tracks.
+//!
+//! Filters are checked when a track is queued. A single add that fails is
+//! refused with `content_filtered`; playlist adds skip the failing positions
+//! and report them in `skipped`. Autoplay never picks a filtered track.
+//! Tracks already in the queue are left alone when a filter changes.
+
+use crate::auth::ensure_admin;
+use crate::jam_library::{favorite_backup_path, write_atomic, FavoriteSummary};
+use crate::jam_local_library::LocalTrack;
+use crate::AppState;
+
+use axum::extract::{Json, Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+pub(crate) const CONTENT_FILTERS_SCHEMA_VERSION: u16 = 1;
+const MIN_MAX_DURATION_MS: u64 = 30_000;
+const MAX_BLOCKLIST_ENTRIES: usize = 500;
+const MAX_BLOCKED_ARTIST_CHARS: usize = 200;
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct JamContentFilter {
+    #[serde(default)]
+    pub(crate) reject_explicit: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_duration_ms: Option<u64>,
+    /// Artist names, matched case-insensitively against whole credited names.
+    #[serde(default)]
+    pub(crate) blocked_artists: Vec<String>,
+    /// Track URIs (`spotify:track:…` or `echo-local:track:…`).
+    #[serde(default)]
+    pub(crate) blocked_tracks: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ContentFilterReason {
+    Explicit,
+    TooLong,
+    BlockedArtist,
+    BlockedTrack,
+}
+
+impl ContentFilterReason {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Explicit => "explicit",
+            Self::TooLong => "too_long",
+            Self::BlockedArtist => "blocked_artist",
+            Self::BlockedTrack => "blocked_track",
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            Self::Explicit => "This room does not allow explicit tracks",
+            Self::TooLong => "This track is longer than this room allows",
+            Self::BlockedArtist => "This artist is blocked in this room",
+            Self::BlockedTrack => "This track is blocked in this room",
+        }
+    }
+}
+
+impl JamContentFilter {
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `artist` is the comma-joined credit line Echo stores for a track.
+    fn check(
+        &self,
+        uri: &str,
+        artist: &str,
+        duration_ms: Option<u64>,
+        explicit: Option<bool>,
+    ) -> Option<ContentFilterReason> {
+        if self.blocked_tracks.iter().any(|blocked| blocked == uri) {
+            return Some(ContentFilterReason::BlockedTrack);
+        }
+        if self.reject_explicit && explicit == Some(true) {
+            return Some(ContentFilterReason::Explicit);
+        }
+        if self
+            .max_duration_ms
+            .zip(duration_ms)
+            .is_some_and(|(limit, duration)| duration > limit)
+        {
+            return Some(ContentFilterReason::TooLong);
+        }
+        // Match whole credits between ", " separators so a name that itself
+        // contains a comma still matches, but a longer name does not.
+        let credits = format!(", {}, ", artist.trim().to_lowercase());
+        let blocked_artist = self
+            .blocked_artists
+            .iter()
+            .any(|blocked| credits.contains(&format!(", {}, ", blocked.to_lowercase())));
+        blocked_artist.then_some(ContentFilterReason::BlockedArtist)
+    }
+
+    pub(crate) fn check_summary(&self, summary: &FavoriteSummary) -> Option<ContentFilterReason> {
+        self.check(
+            &summary.spotify_uri,
+            summary.artist.as_deref().unwrap_or_default(),
+            summary.duration_ms,
+            summary.explicit,
+        )
+    }
+
+    /// Local files carry no explicit flag, so only the other rules apply.
+    pub(crate) fn check_local(&self, track: &LocalTrack) -> Option<ContentFilterReason> {
+        self.check(&track.uri, &track.artist, Some(track.duration_ms), None)
+    }
+}
+
+pub(crate) fn content_filtered_response(reason: ContentFilterReason) -> axum::response::Response {
+    use axum::response::IntoResponse;
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        axum::Json(serde_json::json!({
+            "error": "content_filtered",
+            "reason": reason.as_str(),
+            "message": reason.message(),
+        })),
+    )
+        .into_response()
+}
+
+/// Canonical track URI for a blocklist entry: a Spotify track ID, URI, or
+/// `open.spotify.com` link, or a local library URI.
+fn blocked_track_uri(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.starts_with("echo-local:track:") && value.len() > "echo-local:track:".len() {
+        return Some(value.to_string());
+    }
+    let id = value
+        .strip_prefix("spotify:track:")
+        .or_else(|| {
+            value
+                .strip_prefix("https://open.spotify.com/track/")
+                .map(|rest| rest.split(['?', '/']).next().unwrap_or_default())
+        })
+        .unwrap_or(value);
+    crate::jam_library::valid_spotify_id(id).then(|| format!("spotify:track:{id}"))
+}
+
+/// Trim, de-duplicate, and bound a filter before it is stored.
+pub(crate) fn normalize_content_filter(
+    filter: JamContentFilter,
+) -> Result<JamContentFilter, String> {
+    if filter
+        .max_duration_ms
+        .is_some_and(|limit| limit < MIN_MAX_DURATION_MS)
+    {
+        return Err(format!(
+            "max_duration_ms must be at least {MIN_MAX_DURATION_MS}"
+        ));
+    }
+    if filter.blocked_artists.len() > MAX_BLOCKLIST_ENTRIES
+        || filter.blocked_tracks.len() > MAX_BLOCKLIST_ENTRIES
+    {
+        return Err(format!(
+            "blocklists hold at most {MAX_BLOCKLIST_ENTRIES} entries each"
+        ));
+    }
+    let mut blocked_artists = Vec::new();
+    for artist in &filter.blocked_artists {
+        let artist = artist.trim();
+        if artist.is_empty() || artist.chars().count() > MAX_BLOCKED_ARTIST_CHARS {
+            return Err(format!(
+                "blocked artist names must be 1-{MAX_BLOCKED_ARTIST_CHARS} characters"
+            ));
+        }
+        if !blocked_artists
+            .iter()
+            .any(|known: &String| known.eq_ignore_ascii_case(artist))
+        {
+            blocked_artists.push(artist.to_string());
+        }
+    }
+    let mut blocked_tracks = Vec::new();
+    for track in &filter.blocked_tracks {
+        let uri = blocked_track_uri(track)
+            .ok_or_else(|| format!("{track:?} is not a Spotify or local track"))?;
+        if !blocked_tracks.contains(&uri) {
+            blocked_tracks.push(uri);
+        }
+    }
+    Ok(JamContentFilter {
+        reject_explicit: filter.reject_explicit,
+        max_duration_ms: filter.max_duration_ms,
+        blocked_artists,
+        blocked_tracks,
+    })
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct ContentFilterFile {
+    schema_version: u16,
+    rooms: BTreeMap<String, JamContentFilter>,
+}
+
+impl Default for ContentFilterFile {
+    fn default() -> Self {
+        Self {
+            schema_version: CONTENT_FILTERS_SCHEMA_VERSION,
+            rooms: BTreeMap::new(),
+        }
+    }
+}
+
+pub(crate) struct ContentFilterStore {
+    path: PathBuf,
+    writable: bool,
+    inner: Mutex<ContentFilterFile>,
+}
+
+impl ContentFilterStore {
+    pub(crate) fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let backup = favorite_backup_path(&path);
+        let data = if path.exists() {
+            load_content_filter_file(&path)?
+        } else if backup.exists() {
+            let recovered = load_content_filter_file(&backup)?;
+            fs::rename(&backup, &path)?;
+            warn!(
+                "Recovered Jam content filters from {:?} after an interrupted atomic write",
+                backup
+            );
+            recovered
+        } else {
+            ContentFilterFile::default()
+        };
+        Ok(Self {
+            path,
+            writable: true,
+            inner: Mutex::new(data),
+        })
+    }
+
+    /// Keep enforcing the last valid filters, if the backup has them, without
+    /// letting a later change overwrite the unreadable primary.
+    pub(crate) fn recover_read_only(path: PathBuf) -> Self {
+        let backup = favorite_backup_path(&path);
+        let data = load_content_filter_file(&backup).unwrap_or_default();
+        Self {
+            path,
+            writable: false,
+            inner: Mutex::new(data),
+        }
+    }
+
+    pub(crate) fn disabled(path: PathBuf) -> Self {
+        Self {
+            path,
+            writable: false,
+            inner: Mutex::new(ContentFilterFile::default()),
+        }
+    }
+
+    pub(crate) fn for_room(&self, room: &str) -> JamContentFilter {
+        self.inner
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .rooms
+            .get(room)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn snapshot(&self) -> BTreeMap<String, JamContentFilter> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .rooms
+            .clone()
+    }
+
+    /// Replace a room's filter. An empty filter removes the room's entry.
+    pub(crate) fn set(&self, room: &str, filter: JamContentFilter) -> io::Result<()> {
+        if !self.writable {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "content filter store is read-only because its primary file could not be loaded",
+            ));
+        }
+        let mut data = self.inner.lock().unwrap_or_else(|error| error.into_inner());
+        let mut candidate = data.clone();
+        if filter.is_empty() {
+            candidate.rooms.remove(room);
+        } else {
+            candidate.rooms.insert(room.to_string(), filter);
+        }
+        let bytes = serde_json::to_vec_pretty(&candidate)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        write_atomic(&self.path, &bytes)?;
+        *data = candidate;
+        Ok(())
+    }
+}
+
+fn load_content_filter_file(path: &std::path::Path) -> io::Result<ContentFilterFile> {
+    let bytes = fs::read(path)?;
+    let parsed: ContentFilterFile = serde_json::from_slice(&bytes)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    if parsed.schema_version != CONTENT_FILTERS_SCHEMA_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "unsupported content filter schema {}",
+                parsed.schema_version
+            ),
+        ));
+    }
+    let mut rooms = BTreeMap::new();
+    for (room, filter) in parsed.rooms {
+        let filter = normalize_content_filter(filter)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        rooms.insert(room, filter);
+    }
+    Ok(ContentFilterFile {
+        schema_version: parsed.schema_version,
+        rooms,
+    })
+}
+
+pub(crate) async fn admin_jam_content_filters(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    Ok(Json(serde_json::json!({
+        "schema_version": CONTENT_FILTERS_SCHEMA_VERSION,
+        "rooms": state.jam_content_filters.snapshot(),
+    })))
+}
+
+pub(crate) async fn admin_jam_content_filter_put(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(room): Path<String>,
+    Json(filter): Json<JamContentFilter>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    ensure_admin(&state, &headers)
+        .map_err(|status| (status, Json(serde_json::json!({"error": "unauthorized"}))))?;
+    let room = room.trim().to_string();
+    let filter = if room.is_empty() || room.len() > 128 {
+        Err("room must be 1-128 characters".to_string())
+    } else {
+        normalize_content_filter(filter)
+    }
+    .map_err(|message| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "bad_request", "message": message})),
+        )
+    })?;
+    let store = std::sync::Arc::clone(&state.jam_content_filters);
+    let stored = filter.clone();
+    let target_room = room.clone();
+    tokio::task::spawn_blocking(move || store.set(&target_room, stored))
+        .await
+        .map_err(|error| error.to_string())
+        .and_then(|result| result.map_err(|error| error.to_string()))
+        .map_err(|message| {
+            warn!("Could not persist Jam content filter: {}", message);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "storage_error",
+                    "message": "Could not persist the content filter",
+                })),
+            )
+        })?;
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "room": room,
+        "content_filter": filter,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID_A: &str = "0VjIjW4GlUZAMYd2vXMi3b";
+    const ID_B: &str = "3n3Ppam7vgaVa1iaRUc9Lp";
+
+    fn summary(id: &str, artist: &str, duration_ms: u64, explicit: bool) -> FavoriteSummary {
+        FavoriteSummary {
+            spotify_id: id.to_string(),
+            spotify_uri: format!("spotify:track:{id}"),
+            name: id.to_string(),
+            artist: Some(artist.to_string()),
+            duration_ms: Some(duration_ms),
+            explicit: Some(explicit),
+            ..FavoriteSummary::default()
+        }
+    }
+
+    #[test]
+    fn filters_report_the_first_matching_rule() {
+        let filter = normalize_content_filter(JamContentFilter {
+            reject_explicit: true,
+            max_duration_ms: Some(600_000),
+            blocked_artists: vec![" The Band ".to_string(), "the band".to_string()],
+            blocked_tracks: vec![format!("https://open.spotify.com/track/{ID_B}?si=x")],
+        })
+        .unwrap();
+        assert_eq!(filter.blocked_artists, ["The Band"]);
+        assert_eq!(filter.blocked_tracks, [format!("spotify:track:{ID_B}")]);
+
+        let allowed = summary(ID_A, "Someone, Other", 200_000, false);
+        assert_eq!(filter.check_summary(&allowed), None);
+        assert_eq!(
+            filter.check_summary(&summary(ID_A, "Someone", 200_000, true)),
+            Some(ContentFilterReason::Explicit)
+        );
+        assert_eq!(
+            filter.check_summary(&summary(ID_A, "Someone", 900_000, false)),
+            Some(ContentFilterReason::TooLong)
+        );
+        assert_eq!(
+            filter.check_summary(&summary(ID_A, "Guest, THE BAND", 200_000, false)),
+            Some(ContentFilterReason::BlockedArtist)
+        );
+        // Credits that merely contain a blocked name are not the same artist.
+        assert_eq!(
+            filter.check_summary(&summary(ID_A, "The Band Two", 200_000, false)),
+            None
+        );
+        let commas = JamContentFilter {
+            blocked_artists: vec!["Tyler, The Creator".to_string()],
+            ..JamContentFilter::default()
+        };
+        assert_eq!(
+            commas.check_summary(&summary(ID_A, "Kali, Tyler, The Creator", 200_000, false)),
+            Some(ContentFilterReason::BlockedArtist)
+        );
+        assert_eq!(
+            filter.check_summary(&summary(ID_B, "Someone", 200_000, false)),
+            Some(ContentFilterReason::BlockedTrack)
+        );
+        assert!(JamContentFilter::default()
+            .check_summary(&summary(ID_A, "The Band", 9_000_000, true))
+            .is_none());
+    }
+
+    #[test]
+    fn normalization_rejects_bad_limits_and_entries() {
+        let short = JamContentFilter {
+            max_duration_ms: Some(1_000),
+            ..JamContentFilter::default()
+        };
+        assert!(normalize_content_filter(short).is_err());
+        let bad_track = JamContentFilter {
+            blocked_tracks: vec!["not a track".to_string()],
+            ..JamContentFilter::default()
+        };
+        assert!(normalize_content_filter(bad_track).is_err());
+        let local = JamContentFilter {
+            blocked_tracks: vec!["echo-local:track:abc123".to_string()],
+            ..JamContentFilter::default()
+        };
+        assert_eq!(
+            normalize_content_filter(local).unwrap().blocked_tracks,
+            ["echo-local:track:abc123"]
+        );
+    }
+
+    #[test]
+    fn store_persists_rooms_and_drops_empty_filters() {
+        let dir = std::env::temp_dir().join(format!(
+            "echo-content-filters-{}",
+            crate::config::random_secret()
+        ));
+        let path = dir.join("content-filters-v1.json");
+        let store = ContentFilterStore::open(path.clone()).unwrap();
+        let filter = JamContentFilter {
+            reject_explicit: true,
+            ..JamContentFilter::default()
+        };
+        store.set("main", filter.clone()).unwrap();
+        assert_eq!(store.for_room("other"), JamContentFilter::default());
+
+        let reopened = ContentFilterStore::open(path.clone()).unwrap();
+        assert_eq!(reopened.for_room("main"), filter);
+        reopened.set("main", JamContentFilter::default()).unwrap();
+        assert!(ContentFilterStore::open(path)
+            .unwrap()
+            .snapshot()
+            .is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
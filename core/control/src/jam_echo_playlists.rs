@@ -0,0 +1,1095 @@
+//! Echo playlists: named, collaborative playlists stored on the control
+//! server instead of in a Spotify account.
+//!
+//! Any participant may add, reorder, or remove tracks; every track keeps a
+//! `FavoriteAttribution` for whoever added it. Only the creator may rename or
+//! delete a playlist. Every change bumps `revision`, which the playlist exposes
+//! as its `snapshot_id`, so catalog search, item pages, and the playlist queue
+//! endpoints treat an Echo playlist like a Spotify one.
+
+use crate::auth::{bounded_jam_actor_display_name, ensure_admin, ensure_jam_actor, JamActor};
+use crate::config::{now_ts_ms, random_secret};
+use crate::jam_library::{
+    favorite_backup_path, favorite_track, fetch_favorite_summary, valid_spotify_id, write_atomic,
+    CatalogPlaylist, FavoriteAttribution, FavoriteKind, FavoriteSummary, JamApiError,
+    PlaylistExpansion, PlaylistItemsPage, CATALOG_SCHEMA_VERSION, MAX_PLAYLIST_QUEUE_TRACKS,
+};
+use crate::AppState;
+
+use axum::extract::{Json, Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+pub(crate) const ECHO_PLAYLISTS_SCHEMA_VERSION: u16 = 1;
+const MAX_ECHO_PLAYLISTS: usize = 500;
+const MAX_ECHO_PLAYLIST_NAME_CHARS: usize = 100;
+const MAX_ECHO_PLAYLIST_DESCRIPTION_CHARS: usize = 300;
+const MAX_ECHO_PLAYLIST_ITEM_BATCH: usize = 100;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct EchoPlaylistItem {
+    pub(crate) item_id: String,
+    #[serde(flatten)]
+    pub(crate) summary: FavoriteSummary,
+    pub(crate) added_by: FavoriteAttribution,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct EchoPlaylist {
+    pub(crate) playlist_id: String,
+    pub(crate) name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    pub(crate) created_by: FavoriteAttribution,
+    pub(crate) updated_at_ms: u64,
+    pub(crate) revision: u64,
+    pub(crate) items: Vec<EchoPlaylistItem>,
+}
+
+impl EchoPlaylist {
+    pub(crate) fn snapshot_id(&self) -> String {
+        format!("echo-r{}", self.revision)
+    }
+
+    /// The playlist in the shape catalog search and the queue flow use for a
+    /// Spotify playlist.
+    pub(crate) fn summary(&self) -> FavoriteSummary {
+        FavoriteSummary {
+            spotify_id: self.playlist_id.clone(),
+            spotify_uri: format!("echo:playlist:{}", self.playlist_id),
+            spotify_url: String::new(),
+            name: self.name.clone(),
+            owner: Some(self.created_by.display_name.clone()),
+            description: self.description.clone(),
+            artwork_url: self
+                .items
+                .iter()
+                .find_map(|item| item.summary.artwork_url.clone()),
+            track_count: Some(self.items.len() as u64),
+            snapshot_id: Some(self.snapshot_id()),
+            ..FavoriteSummary::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct EchoPlaylistFile {
+    schema_version: u16,
+    playlists: Vec<EchoPlaylist>,
+}
+
+impl Default for EchoPlaylistFile {
+    fn default() -> Self {
+        Self {
+            schema_version: ECHO_PLAYLISTS_SCHEMA_VERSION,
+            playlists: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum EchoPlaylistError {
+    NotFound,
+    ItemNotFound,
+    Changed,
+    OwnerRequired,
+    Full(&'static str),
+    Storage(io::Error),
+}
+
+impl From<EchoPlaylistError> for JamApiError {
+    fn from(error: EchoPlaylistError) -> Self {
+        let (status, code, message) = match error {
+            EchoPlaylistError::NotFound => (
+                StatusCode::NOT_FOUND,
+                "echo_playlist_not_found",
+                "Echo playlist not found".to_string(),
+            ),
+            EchoPlaylistError::ItemNotFound => (
+                StatusCode::CONFLICT,
+                "echo_playlist_item_not_found",
+                "One or more playlist items are no longer in the playlist".to_string(),
+            ),
+            EchoPlaylistError::Changed => (
+                StatusCode::CONFLICT,
+                "playlist_changed",
+                "The Echo playlist changed after it was opened; reload it and try again"
+                    .to_string(),
+            ),
+            EchoPlaylistError::OwnerRequired => (
+                StatusCode::FORBIDDEN,
+                "echo_playlist_owner_required",
+                "Only the playlist's creator can rename or delete it".to_string(),
+            ),
+            EchoPlaylistError::Full(message) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "echo_playlist_full",
+                message.to_string(),
+            ),
+            EchoPlaylistError::Storage(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "storage_error",
+                format!("Could not persist Echo playlist: {error}"),
+            ),
+        };
+        JamApiError {
+            status,
+            code,
+            message,
+            retry_after: None,
+        }
+    }
+}
+
+pub(crate) fn valid_echo_playlist_id(value: &str) -> bool {
+    value.strip_prefix("ep1_").is_some_and(|suffix| {
+        suffix.len() == 64
+            && suffix
+                .bytes()
+                .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+    })
+}
+
+pub(crate) struct EchoPlaylistStore {
+    path: PathBuf,
+    writable: bool,
+    inner: Mutex<EchoPlaylistFile>,
+}
+
+impl EchoPlaylistStore {
+    pub(crate) fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let backup = favorite_backup_path(&path);
+        let data = if path.exists() {
+            load_echo_playlist_file(&path)?
+        } else if backup.exists() {
+            let recovered = load_echo_playlist_file(&backup)?;
+            fs::rename(&backup, &path)?;
+            warn!(
+                "Recovered Echo playlists from {:?} after an interrupted atomic write",
+                backup
+            );
+            recovered
+        } else {
+            EchoPlaylistFile::default()
+        };
+        Ok(Self {
+            path,
+            writable: true,
+            inner: Mutex::new(data),
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn empty(path: PathBuf) -> Self {
+        Self {
+            path,
+            writable: true,
+            inner: Mutex::new(EchoPlaylistFile::default()),
+        }
+    }
+
+    /// Serve the backup, if it is valid, without ever overwriting the
+    /// unreadable primary.
+    pub(crate) fn recover_read_only(path: PathBuf) -> Self {
+        let backup = favorite_backup_path(&path);
+        let data = load_echo_playlist_file(&backup).unwrap_or_default();
+        Self {
+            path,
+            writable: false,
+            inner: Mutex::new(data),
+        }
+    }
+
+    pub(crate) fn disabled(path: PathBuf) -> Self {
+        Self {
+            path,
+            writable: false,
+            inner: Mutex::new(EchoPlaylistFile::default()),
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<EchoPlaylist> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .playlists
+            .clone()
+    }
+
+    pub(crate) fn get(&self, playlist_id: &str) -> Option<EchoPlaylist> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .playlists
+            .iter()
+            .find(|playlist| playlist.playlist_id == playlist_id)
+            .cloned()
+    }
+
+    /// Playlists whose name, description, or creator contains `query`
+    /// (case-insensitive), most recently changed first.
+    pub(crate) fn search(
+        &self,
+        query: &str,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<EchoPlaylist>, usize) {
+        let needle = query.to_lowercase();
+        let mut matches = self
+            .snapshot()
+            .into_iter()
+            .filter(|playlist| {
+                playlist.name.to_lowercase().contains(&needle)
+                    || playlist
+                        .description
+                        .as_deref()
+                        .is_some_and(|description| description.to_lowercase().contains(&needle))
+                    || playlist
+                        .created_by
+                        .display_name
+                        .to_lowercase()
+                        .contains(&needle)
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|left, right| {
+            right
+                .updated_at_ms
+                .cmp(&left.updated_at_ms)
+                .then_with(|| left.playlist_id.cmp(&right.playlist_id))
+        });
+        let total = matches.len();
+        let page = matches.into_iter().skip(offset).take(limit).collect();
+        (page, total)
+    }
+
+    pub(crate) fn create(
+        &self,
+        name: String,
+        description: Option<String>,
+        actor: &JamActor,
+        now_ms: u64,
+    ) -> Result<EchoPlaylist, EchoPlaylistError> {
+        let mut data = self.inner.lock().unwrap_or_else(|error| error.into_inner());
+        if data.playlists.len() >= MAX_ECHO_PLAYLISTS {
+            return Err(EchoPlaylistError::Full(
+                "Echo already stores the maximum number of playlists",
+            ));
+        }
+        let mut candidate = data.clone();
+        let playlist = EchoPlaylist {
+            playlist_id: format!("ep1_{}", random_secret()),
+            name,
+            description,
+            created_by: attribution(actor, now_ms),
+            updated_at_ms: now_ms,
+            revision: 1,
+            items: Vec::new(),
+        };
+        candidate.playlists.push(playlist.clone());
+        self.persist_locked(&candidate)
+            .map_err(EchoPlaylistError::Storage)?;
+        *data = candidate;
+        Ok(playlist)
+    }
+
+    pub(crate) fn delete(
+        &self,
+        playlist_id: &str,
+        actor_id: &str,
+    ) -> Result<(), EchoPlaylistError> {
+        let mut data = self.inner.lock().unwrap_or_else(|error| error.into_inner());
+        let mut candidate = data.clone();
+        let index = candidate
+            .playlists
+            .iter()
+            .position(|playlist| playlist.playlist_id == playlist_id)
+            .ok_or(EchoPlaylistError::NotFound)?;
+        if candidate.playlists[index].created_by.actor_id != actor_id {
+            return Err(EchoPlaylistError::OwnerRequired);
+        }
+        candidate.playlists.remove(index);
+        self.persist_locked(&candidate)
+            .map_err(EchoPlaylistError::Storage)?;
+        *data = candidate;
+        Ok(())
+    }
+
+    /// Apply `change` to a copy of one playlist. When `expected_revision` is
+    /// set it must match. Memory only changes once the new file is on disk.
+    pub(crate) fn update(
+        &self,
+        playlist_id: &str,
+        expected_revision: Option<u64>,
+        now_ms: u64,
+        change: impl FnOnce(&mut EchoPlaylist) -> Result<(), EchoPlaylistError>,
+    ) -> Result<EchoPlaylist, EchoPlaylistError> {
+        let mut data = self.inner.lock().unwrap_or_else(|error| error.into_inner());
+        let mut candidate = data.clone();
+        let playlist = candidate
+            .playlists
+            .iter_mut()
+            .find(|playlist| playlist.playlist_id == playlist_id)
+            .ok_or(EchoPlaylistError::NotFound)?;
+        if expected_revision.is_some_and(|revision| revision != playlist.revision) {
+            return Err(EchoPlaylistError::Changed);
+        }
+        change(playlist)?;
+        playlist.revision += 1;
+        playlist.updated_at_ms = now_ms;
+        let updated = playlist.clone();
+        self.persist_locked(&candidate)
+            .map_err(EchoPlaylistError::Storage)?;
+        *data = candidate;
+        Ok(updated)
+    }
+
+    fn persist_locked(&self, data: &EchoPlaylistFile) -> io::Result<()> {
+        if !self.writable {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Echo playlist store is read-only because its primary file could not be loaded",
+            ));
+        }
+        let bytes = serde_json::to_vec_pretty(data)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        write_atomic(&self.path, &bytes)
+    }
+}
+
+fn attribution(actor: &JamActor, now_ms: u64) -> FavoriteAttribution {
+    FavoriteAttribution {
+        actor_id: actor.actor_id.clone(),
+        display_name: actor.display_name.clone(),
+        added_at_ms: now_ms,
+        source: "echo".to_string(),
+    }
+}
+
+fn load_echo_playlist_file(path: &std::path::Path) -> io::Result<EchoPlaylistFile> {
+    let bytes = fs::read(path)?;
+    let parsed: EchoPlaylistFile = serde_json::from_slice(&bytes)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    if parsed.schema_version != ECHO_PLAYLISTS_SCHEMA_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported Echo playlist schema {}", parsed.schema_version),
+        ));
+    }
+    validate_echo_playlist_file(parsed)
+}
+
+fn validate_echo_playlist_file(mut data: EchoPlaylistFile) -> io::Result<EchoPlaylistFile> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut playlist_ids = HashSet::new();
+    for playlist in &mut data.playlists {
+        if !valid_echo_playlist_id(&playlist.playlist_id)
+            || !playlist_ids.insert(playlist.playlist_id.clone())
+            || playlist.name.trim().is_empty()
+            || playlist.created_by.actor_id.is_empty()
+            || playlist.items.len() > MAX_PLAYLIST_QUEUE_TRACKS
+        {
+            return Err(invalid(
+                "Echo playlists contain an invalid or duplicate playlist",
+            ));
+        }
+        playlist.created_by.display_name = bounded_jam_actor_display_name(
+            Some(&playlist.created_by.display_name),
+            &playlist.created_by.actor_id,
+        );
+        let mut item_ids = HashSet::new();
+        for item in &mut playlist.items {
+            if item.item_id.is_empty()
+                || !item_ids.insert(item.item_id.clone())
+                || !valid_spotify_id(&item.summary.spotify_id)
+                || item.added_by.actor_id.is_empty()
+            {
+                return Err(invalid(
+                    "Echo playlist contains an invalid or duplicate item",
+                ));
+            }
+            item.added_by.display_name = bounded_jam_actor_display_name(
+                Some(&item.added_by.display_name),
+                &item.added_by.actor_id,
+            );
+        }
+    }
+    Ok(data)
+}
+
+/// Insert `items` before `before_item_id`, or at the end without one.
+fn insert_items(
+    playlist: &mut EchoPlaylist,
+    items: Vec<EchoPlaylistItem>,
+    before_item_id: Option<&str>,
+) -> Result<(), EchoPlaylistError> {
+    let index = match before_item_id {
+        Some(anchor) => playlist
+            .items
+            .iter()
+            .position(|item| item.item_id == anchor)
+            .ok_or(EchoPlaylistError::ItemNotFound)?,
+        None => playlist.items.len(),
+    };
+    playlist.items.splice(index..index, items);
+    Ok(())
+}
+
+/// Take the listed items out of the playlist, in the listed order.
+fn take_items(
+    playlist: &mut EchoPlaylist,
+    item_ids: &[String],
+) -> Result<Vec<EchoPlaylistItem>, EchoPlaylistError> {
+    let mut taken = Vec::with_capacity(item_ids.len());
+    for item_id in item_ids {
+        let index = playlist
+            .items
+            .iter()
+            .position(|item| &item.item_id == item_id)
+            .ok_or(EchoPlaylistError::ItemNotFound)?;
+        taken.push(playlist.items.remove(index));
+    }
+    Ok(taken)
+}
+
+pub(crate) fn move_items(
+    playlist: &mut EchoPlaylist,
+    item_ids: &[String],
+    before_item_id: Option<&str>,
+) -> Result<(), EchoPlaylistError> {
+    if before_item_id.is_some_and(|anchor| item_ids.iter().any(|item_id| item_id == anchor)) {
+        return Err(EchoPlaylistError::ItemNotFound);
+    }
+    let moved = take_items(playlist, item_ids)?;
+    insert_items(playlist, moved, before_item_id)
+}
+
+/// Expand an Echo playlist for the playlist queue endpoints. Positions were
+/// already validated and sorted by the caller.
+pub(crate) fn echo_playlist_expansion(
+    state: &AppState,
+    playlist_id: &str,
+    selected_positions: Option<&[usize]>,
+    expected_snapshot_id: Option<&str>,
+) -> Result<PlaylistExpansion, JamApiError> {
+    let playlist = state
+        .jam_echo_playlists
+        .get(playlist_id)
+        .ok_or(EchoPlaylistError::NotFound)?;
+    if expected_snapshot_id.is_some_and(|snapshot_id| snapshot_id != playlist.snapshot_id()) {
+        return Err(EchoPlaylistError::Changed.into());
+    }
+    let tracks = match selected_positions {
+        Some(positions) => {
+            if let Some(position) = positions
+                .iter()
+                .find(|position| **position >= playlist.items.len())
+            {
+                return Err(JamApiError {
+                    status: StatusCode::UNPROCESSABLE_ENTITY,
+                    code: "playlist_position_out_of_range",
+                    message: format!(
+                        "Position {position} is past the end of this {}-track playlist",
+                        playlist.items.len()
+                    ),
+                    retry_after: None,
+                });
+            }
+            positions
+                .iter()
+                .map(|position| (*position, playlist.items[*position].summary.clone()))
+                .collect()
+        }
+        None => playlist
+            .items
+            .iter()
+            .enumerate()
+            .map(|(position, item)| (position, item.summary.clone()))
+            .collect(),
+    };
+    Ok(PlaylistExpansion {
+        playlist: playlist.summary(),
+        tracks,
+        skipped: Vec::new(),
+    })
+}
+
+/// One page of an Echo playlist in the `/api/jam/playlists/:id/items` shape.
+pub(crate) fn echo_playlist_items_page(
+    state: &AppState,
+    actor_id: &str,
+    playlist_id: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<PlaylistItemsPage, JamApiError> {
+    let playlist = state
+        .jam_echo_playlists
+        .get(playlist_id)
+        .ok_or(EchoPlaylistError::NotFound)?;
+    let items = playlist
+        .items
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(limit)
+        .map(|(position, item)| {
+            favorite_track(state, actor_id, item.summary.clone(), Some(position))
+        })
+        .collect();
+    let consumed = offset.saturating_add(limit);
+    Ok(PlaylistItemsPage {
+        schema_version: CATALOG_SCHEMA_VERSION,
+        playlist: echo_catalog_playlist(&playlist),
+        items,
+        skipped: Vec::new(),
+        offset,
+        limit,
+        total: playlist.items.len() as u64,
+        next_offset: (consumed < playlist.items.len()).then_some(consumed),
+        items_source: "echo",
+        local_cache: None,
+    })
+}
+
+/// Echo playlists cannot be favorited; they are already shared.
+pub(crate) fn echo_catalog_playlist(playlist: &EchoPlaylist) -> CatalogPlaylist {
+    CatalogPlaylist {
+        kind: FavoriteKind::Playlist,
+        summary: playlist.summary(),
+        favorited_by_me: false,
+        favorite_contributor_count: 0,
+    }
+}
+
+// ── HTTP ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub(crate) struct EchoPlaylistView {
+    #[serde(flatten)]
+    playlist: EchoPlaylist,
+    snapshot_id: String,
+    track_count: usize,
+    owned_by_me: bool,
+}
+
+impl EchoPlaylistView {
+    fn new(playlist: EchoPlaylist, actor_id: &str) -> Self {
+        Self {
+            snapshot_id: playlist.snapshot_id(),
+            track_count: playlist.items.len(),
+            owned_by_me: playlist.created_by.actor_id == actor_id,
+            playlist,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct EchoPlaylistListing {
+    playlist_id: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    created_by: FavoriteAttribution,
+    updated_at_ms: u64,
+    revision: u64,
+    snapshot_id: String,
+    track_count: usize,
+    contributor_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    artwork_url: Option<String>,
+    owned_by_me: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct EchoPlaylistListResponse {
+    schema_version: u16,
+    playlists: Vec<EchoPlaylistListing>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EchoPlaylistCreateRequest {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EchoPlaylistUpdateRequest {
+    expected_revision: u64,
+    #[serde(default)]
+    name: Option<String>,
+    /// An empty string clears the description.
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EchoPlaylistAddRequest {
+    spotify_id: String,
+    #[serde(default)]
+    before_item_id: Option<String>,
+    #[serde(default)]
+    expected_revision: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EchoPlaylistItemsRequest {
+    expected_revision: u64,
+    item_ids: Vec<String>,
+    #[serde(default)]
+    before_item_id: Option<String>,
+}
+
+fn ensure_echo_playlist_actor(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<JamActor, JamApiError> {
+    ensure_admin(state, headers).map_err(|status| JamApiError {
+        status,
+        code: "unauthorized",
+        message: "Authentication required".to_string(),
+        retry_after: None,
+    })?;
+    ensure_jam_actor(state, headers).map_err(|status| JamApiError {
+        status,
+        code: "actor_required",
+        message: "A current Echo participant token is required".to_string(),
+        retry_after: None,
+    })
+}
+
+fn validate_playlist_id(playlist_id: &str) -> Result<(), JamApiError> {
+    if valid_echo_playlist_id(playlist_id) {
+        Ok(())
+    } else {
+        Err(JamApiError::bad_request("invalid Echo playlist ID"))
+    }
+}
+
+fn playlist_name(name: &str) -> Result<String, JamApiError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_ECHO_PLAYLIST_NAME_CHARS {
+        return Err(JamApiError::bad_request(format!(
+            "name must contain between 1 and {MAX_ECHO_PLAYLIST_NAME_CHARS} characters"
+        )));
+    }
+    Ok(name.to_string())
+}
+
+fn playlist_description(description: &str) -> Result<Option<String>, JamApiError> {
+    let description = description.trim();
+    if description.chars().count() > MAX_ECHO_PLAYLIST_DESCRIPTION_CHARS {
+        return Err(JamApiError::bad_request(format!(
+            "description must be at most {MAX_ECHO_PLAYLIST_DESCRIPTION_CHARS} characters"
+        )));
+    }
+    Ok((!description.is_empty()).then(|| description.to_string()))
+}
+
+fn validate_item_ids(item_ids: &[String]) -> Result<(), JamApiError> {
+    if item_ids.is_empty() || item_ids.len() > MAX_ECHO_PLAYLIST_ITEM_BATCH {
+        return Err(JamApiError::bad_request(format!(
+            "item_ids must contain between 1 and {MAX_ECHO_PLAYLIST_ITEM_BATCH} items"
+        )));
+    }
+    let mut unique = HashSet::new();
+    if !item_ids.iter().all(|item_id| unique.insert(item_id)) {
+        return Err(JamApiError::bad_request(
+            "item_ids must not contain duplicates",
+        ));
+    }
+    Ok(())
+}
+
+/// Run a store mutation off the async runtime, like favorite writes.
+async fn blocking_store<T: Send + 'static>(
+    state: &AppState,
+    mutation: impl FnOnce(&EchoPlaylistStore) -> Result<T, EchoPlaylistError> + Send + 'static,
+) -> Result<T, JamApiError> {
+    let store = std::sync::Arc::clone(&state.jam_echo_playlists);
+    tokio::task::spawn_blocking(move || mutation(&store))
+        .await
+        .map_err(|error| {
+            JamApiError::internal(format!("Echo playlist storage task failed: {error}"))
+        })?
+        .map_err(JamApiError::from)
+}
+
+pub(crate) async fn jam_echo_playlists_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<EchoPlaylistListResponse>, JamApiError> {
+    let actor = ensure_echo_playlist_actor(&state, &headers)?;
+    let mut playlists = state.jam_echo_playlists.snapshot();
+    playlists.sort_by_key(|playlist| std::cmp::Reverse(playlist.updated_at_ms));
+    let playlists = playlists
+        .into_iter()
+        .map(|playlist| {
+            let summary = playlist.summary();
+            let contributor_count = playlist
+                .items
+                .iter()
+                .map(|item| item.added_by.actor_id.as_str())
+                .collect::<HashSet<_>>()
+                .len();
+            EchoPlaylistListing {
+                snapshot_id: playlist.snapshot_id(),
+                track_count: playlist.items.len(),
+                owned_by_me: playlist.created_by.actor_id == actor.actor_id,
+                contributor_count,
+                artwork_url: summary.artwork_url,
+                playlist_id: playlist.playlist_id,
+                name: playlist.name,
+                description: playlist.description,
+                created_by: playlist.created_by,
+                updated_at_ms: playlist.updated_at_ms,
+                revision: playlist.revision,
+            }
+        })
+        .collect();
+    Ok(Json(EchoPlaylistListResponse {
+        schema_version: ECHO_PLAYLISTS_SCHEMA_VERSION,
+        playlists,
+    }))
+}
+
+pub(crate) async fn jam_echo_playlist_create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<EchoPlaylistCreateRequest>,
+) -> Result<Json<EchoPlaylistView>, JamApiError> {
+    let actor = ensure_echo_playlist_actor(&state, &headers)?;
+    let name = playlist_name(&payload.name)?;
+    let description = playlist_description(payload.description.as_deref().unwrap_or_default())?;
+    let actor_id = actor.actor_id.clone();
+    let playlist = blocking_store(&state, move |store| {
+        store.create(name, description, &actor, now_ts_ms())
+    })
+    .await?;
+    Ok(Json(EchoPlaylistView::new(playlist, &actor_id)))
+}
+
+pub(crate) async fn jam_echo_playlist_get(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(playlist_id): Path<String>,
+) -> Result<Json<EchoPlaylistView>, JamApiError> {
+    let actor = ensure_echo_playlist_actor(&state, &headers)?;
+    validate_playlist_id(&playlist_id)?;
+    let playlist = state
+        .jam_echo_playlists
+        .get(&playlist_id)
+        .ok_or(EchoPlaylistError::NotFound)?;
+    Ok(Json(EchoPlaylistView::new(playlist, &actor.actor_id)))
+}
+
+pub(crate) async fn jam_echo_playlist_update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(playlist_id): Path<String>,
+    Json(payload): Json<EchoPlaylistUpdateRequest>,
+) -> Result<Json<EchoPlaylistView>, JamApiError> {
+    let actor = ensure_echo_playlist_actor(&state, &headers)?;
+    validate_playlist_id(&playlist_id)?;
+    let name = payload.name.as_deref().map(playlist_name).transpose()?;
+    let description = payload
+        .description
+        .as_deref()
+        .map(playlist_description)
+        .transpose()?;
+    let actor_id = actor.actor_id.clone();
+    let playlist = blocking_store(&state, move |store| {
+        store.update(
+            &playlist_id,
+            Some(payload.expected_revision),
+            now_ts_ms(),
+            |playlist| {
+                if playlist.created_by.actor_id != actor.actor_id {
+                    return Err(EchoPlaylistError::OwnerRequired);
+                }
+                if let Some(name) = name {
+                    playlist.name = name;
+                }
+                if let Some(description) = description {
+                    playlist.description = description;
+                }
+                Ok(())
+            },
+        )
+    })
+    .await?;
+    Ok(Json(EchoPlaylistView::new(playlist, &actor_id)))
+}
+
+pub(crate) async fn jam_echo_playlist_delete(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(playlist_id): Path<String>,
+) -> Result<Json<serde_json::Value>, JamApiError> {
+    let actor = ensure_echo_playlist_actor(&state, &headers)?;
+    validate_playlist_id(&playlist_id)?;
+    blocking_store(&state, move |store| {
+        store.delete(&playlist_id, &actor.actor_id)
+    })
+    .await?;
+    Ok(Json(serde_json::json!({"ok": true})))
+}
+
+pub(crate) async fn jam_echo_playlist_add(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(playlist_id): Path<String>,
+    Json(payload): Json<EchoPlaylistAddRequest>,
+) -> Result<Json<EchoPlaylistView>, JamApiError> {
+    let actor = ensure_echo_playlist_actor(&state, &headers)?;
+    validate_playlist_id(&playlist_id)?;
+    if state.jam_echo_playlists.get(&playlist_id).is_none() {
+        return Err(EchoPlaylistError::NotFound.into());
+    }
+    let summary = fetch_favorite_summary(&state, FavoriteKind::Track, &payload.spotify_id).await?;
+    let actor_id = actor.actor_id.clone();
+    let playlist = blocking_store(&state, move |store| {
+        let now_ms = now_ts_ms();
+        store.update(
+            &playlist_id,
+            payload.expected_revision,
+            now_ms,
+            |playlist| {
+                if playlist.items.len() >= MAX_PLAYLIST_QUEUE_TRACKS {
+                    return Err(EchoPlaylistError::Full(
+                        "Echo playlists hold at most 1000 tracks",
+                    ));
+                }
+                let item = EchoPlaylistItem {
+                    item_id: format!("epi1_{}", random_secret()),
+                    summary,
+                    added_by: attribution(&actor, now_ms),
+                };
+                insert_items(playlist, vec![item], payload.before_item_id.as_deref())
+            },
+        )
+    })
+    .await?;
+    Ok(Json(EchoPlaylistView::new(playlist, &actor_id)))
+}
+
+pub(crate) async fn jam_echo_playlist_remove(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(playlist_id): Path<String>,
+    Json(payload): Json<EchoPlaylistItemsRequest>,
+) -> Result<Json<EchoPlaylistView>, JamApiError> {
+    let actor = ensure_echo_playlist_actor(&state, &headers)?;
+    validate_playlist_id(&playlist_id)?;
+    validate_item_ids(&payload.item_ids)?;
+    let playlist = blocking_store(&state, move |store| {
+        store.update(
+            &playlist_id,
+            Some(payload.expected_revision),
+            now_ts_ms(),
+            |playlist| take_items(playlist, &payload.item_ids).map(drop),
+        )
+    })
+    .await?;
+    Ok(Json(EchoPlaylistView::new(playlist, &actor.actor_id)))
+}
+
+pub(crate) async fn jam_echo_playlist_move(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(playlist_id): Path<String>,
+    Json(payload): Json<EchoPlaylistItemsRequest>,
+) -> Result<Json<EchoPlaylistView>, JamApiError> {
+    let actor = ensure_echo_playlist_actor(&state, &headers)?;
+    validate_playlist_id(&playlist_id)?;
+    validate_item_ids(&payload.item_ids)?;
+    let playlist = blocking_store(&state, move |store| {
+        store.update(
+            &playlist_id,
+            Some(payload.expected_revision),
+            now_ts_ms(),
+            |playlist| {
+                move_items(
+                    playlist,
+                    &payload.item_ids,
+                    payload.before_item_id.as_deref(),
+                )
+            },
+        )
+    })
+    .await?;
+    Ok(Json(EchoPlaylistView::new(playlist, &actor.actor_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID_A: &str = "0VjIjW4GlUZAMYd2vXMi3b";
+    const ID_B: &str = "3n3Ppam7vgaVa1iaRUc9Lp";
+    const ID_C: &str = "7ouMYWpwJ422jRcDASZB7P";
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("echo-{label}-{}", random_secret()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn actor(id: &str, name: &str) -> JamActor {
+        JamActor {
+            actor_id: id.to_string(),
+            display_name: name.to_string(),
+            room: "main".to_string(),
+        }
+    }
+
+    fn add(store: &EchoPlaylistStore, playlist_id: &str, spotify_id: &str, who: &JamActor) {
+        store
+            .update(playlist_id, None, 20, |playlist| {
+                let item = EchoPlaylistItem {
+                    item_id: format!("epi1_{spotify_id}"),
+                    summary: FavoriteSummary {
+                        spotify_id: spotify_id.to_string(),
+                        spotify_uri: format!("spotify:track:{spotify_id}"),
+                        name: spotify_id.to_string(),
+                        ..FavoriteSummary::default()
+                    },
+                    added_by: attribution(who, 20),
+                };
+                insert_items(playlist, vec![item], None)
+            })
+            .unwrap();
+    }
+
+    fn order(playlist: &EchoPlaylist) -> Vec<&str> {
+        playlist
+            .items
+            .iter()
+            .map(|item| item.summary.spotify_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn collaborators_add_move_and_remove_with_revision_checks() {
+        let dir = temp_dir("echo-playlists-edit");
+        let store = EchoPlaylistStore::empty(dir.join("echo-playlists-v1.json"));
+        let sam = actor("ea1_sam", "Sam");
+        let alex = actor("ea1_alex", "Alex");
+        let created = store.create("Friday".to_string(), None, &sam, 10).unwrap();
+        let id = created.playlist_id.clone();
+        assert!(valid_echo_playlist_id(&id));
+
+        add(&store, &id, ID_A, &sam);
+        add(&store, &id, ID_B, &alex);
+        add(&store, &id, ID_C, &alex);
+        let playlist = store.get(&id).unwrap();
+        assert_eq!(playlist.revision, 4);
+        assert_eq!(playlist.items[1].added_by.display_name, "Alex");
+
+        let moved = store
+            .update(&id, Some(4), 30, |playlist| {
+                move_items(
+                    playlist,
+                    &[format!("epi1_{ID_C}")],
+                    Some(&format!("epi1_{ID_A}")),
+                )
+            })
+            .unwrap();
+        assert_eq!(order(&moved), [ID_C, ID_A, ID_B]);
+
+        // A client working from revision 4 must reload first.
+        let stale = store.update(&id, Some(4), 40, |playlist| {
+            take_items(playlist, &[format!("epi1_{ID_A}")]).map(drop)
+        });
+        assert!(matches!(stale, Err(EchoPlaylistError::Changed)));
+        let removed = store
+            .update(&id, Some(5), 40, |playlist| {
+                take_items(playlist, &[format!("epi1_{ID_A}")]).map(drop)
+            })
+            .unwrap();
+        assert_eq!(order(&removed), [ID_C, ID_B]);
+        assert_eq!(removed.snapshot_id(), "echo-r6");
+
+        assert!(matches!(
+            store.delete(&id, &alex.actor_id),
+            Err(EchoPlaylistError::OwnerRequired)
+        ));
+        store.delete(&id, &sam.actor_id).unwrap();
+        assert!(store.get(&id).is_none());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn playlists_survive_reopen_and_recover_from_the_backup() {
+        let dir = temp_dir("echo-playlists-recovery");
+        let path = dir.join("echo-playlists-v1.json");
+        let store = EchoPlaylistStore::open(path.clone()).unwrap();
+        let sam = actor("ea1_sam", "Sam");
+        let id = store
+            .create(
+                "Road trip".to_string(),
+                Some("Long drives".to_string()),
+                &sam,
+                10,
+            )
+            .unwrap()
+            .playlist_id;
+        add(&store, &id, ID_A, &sam);
+
+        let reopened = EchoPlaylistStore::open(path.clone()).unwrap();
+        assert_eq!(order(&reopened.get(&id).unwrap()), [ID_A]);
+        let (found, total) = reopened.search("road", 0, 10);
+        assert_eq!((found.len(), total), (1, 1));
+        assert_eq!(reopened.search("LONG", 0, 10).1, 1);
+        assert_eq!(reopened.search("nothing", 0, 10).1, 0);
+
+        // The backup holds the state before the last write.
+        fs::remove_file(&path).unwrap();
+        let recovered = EchoPlaylistStore::open(path.clone()).unwrap();
+        assert!(recovered.get(&id).unwrap().items.is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn moves_reject_unknown_items_and_self_anchors() {
+        let sam = actor("ea1_sam", "Sam");
+        let mut playlist = EchoPlaylist {
+            playlist_id: format!("ep1_{}", random_secret()),
+            name: "Mix".to_string(),
+            description: None,
+            created_by: attribution(&sam, 0),
+            updated_at_ms: 0,
+            revision: 1,
+            items: Vec::new(),
+        };
+        for spotify_id in [ID_A, ID_B] {
+            playlist.items.push(EchoPlaylistItem {
+                item_id: format!("epi1_{spotify_id}"),
+                summary: FavoriteSummary {
+                    spotify_id: spotify_id.to_string(),
+                    ..FavoriteSummary::default()
+                },
+                added_by: attribution(&sam, 0),
+            });
+        }
+        let anchor = format!("epi1_{ID_A}");
+        assert!(matches!(
+            move_items(&mut playlist, std::slice::from_ref(&anchor), Some(&anchor)),
+            Err(EchoPlaylistError::ItemNotFound)
+        ));
+        assert!(matches!(
+            move_items(&mut playlist, &["epi1_missing".to_string()], None),
+            Err(EchoPlaylistError::ItemNotFound)
+        ));
+        move_items(&mut playlist, &[anchor], None).unwrap();
+        assert_eq!(order(&playlist), [ID_B, ID_A]);
+    }
+}
@@ -0,0 +1,608 @@
+//! Export Jam history or favorites as M3U, XSPF, or CSV downloads, or as a
+//! new playlist on the connected Spotify account.
+//!
+//! History exports cover a `played_at_ms` range of the caller's room. Favorite
+//! exports include every favorited track; favorited playlists are skipped.
+
+use crate::auth::{ensure_admin, ensure_jam_actor, JamActor};
+use crate::config::now_ts_ms;
+use crate::jam_history::{
+    history_storage_error_response, read_room_history, JamHistoryEntry, DAY_MS,
+};
+use crate::jam_library::{spotify_json_request, FavoriteItem, FavoriteKind, JamApiError};
+use crate::jam_session::{
+    current_spotify_token, spotify_playlist_scope_required_error, spotify_playlist_write_authorized,
+};
+use crate::AppState;
+
+use axum::{
+    extract::{Query, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// Spotify accepts at most 100 URIs per add request and 10,000 per playlist.
+const SPOTIFY_ADD_ITEMS_CHUNK: usize = 100;
+const SPOTIFY_PLAYLIST_MAX_ITEMS: usize = 10_000;
+const MAX_PLAYLIST_NAME_CHARS: usize = 100;
+const MAX_PLAYLIST_DESCRIPTION_CHARS: usize = 300;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ExportSource {
+    History,
+    Favorites,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ExportFormat {
+    #[default]
+    M3u,
+    Xspf,
+    Csv,
+}
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::M3u => "audio/x-mpegurl; charset=utf-8",
+            Self::Xspf => "application/xspf+xml; charset=utf-8",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::M3u => "m3u",
+            Self::Xspf => "xspf",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct JamExportQuery {
+    source: ExportSource,
+    #[serde(default)]
+    format: ExportFormat,
+    #[serde(default)]
+    from_ms: Option<u64>,
+    #[serde(default)]
+    to_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct JamSpotifyPlaylistExportRequest {
+    source: ExportSource,
+    #[serde(default)]
+    from_ms: Option<u64>,
+    #[serde(default)]
+    to_ms: Option<u64>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    public: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct JamSpotifyPlaylistExportResponse {
+    ok: bool,
+    spotify_id: String,
+    spotify_uri: String,
+    spotify_url: String,
+    name: String,
+    track_count: usize,
+    skipped: usize,
+}
+
+/// One exported row, common to history and favorites.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ExportTrack {
+    pub(crate) played_at_ms: Option<u64>,
+    pub(crate) name: String,
+    pub(crate) artist: String,
+    pub(crate) duration_ms: u64,
+    pub(crate) spotify_uri: String,
+    pub(crate) spotify_url: String,
+    pub(crate) album_art_url: String,
+    pub(crate) added_by: String,
+    pub(crate) playlist: String,
+}
+
+impl ExportTrack {
+    /// Where a player should look for the track: the Spotify link when there
+    /// is one, otherwise the raw URI.
+    fn location(&self) -> &str {
+        if self.spotify_url.is_empty() {
+            &self.spotify_uri
+        } else {
+            &self.spotify_url
+        }
+    }
+}
+
+fn history_export_tracks(
+    entries: &[JamHistoryEntry],
+    from_ms: u64,
+    to_ms: u64,
+) -> Vec<ExportTrack> {
+    let mut entries = entries
+        .iter()
+        .filter(|entry| (from_ms..=to_ms).contains(&entry.played_at_ms))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.played_at_ms);
+    entries
+        .into_iter()
+        .map(|entry| ExportTrack {
+            played_at_ms: Some(entry.played_at_ms),
+            name: entry.name.clone(),
+            artist: entry.artist.clone(),
+            duration_ms: entry.duration_ms,
+            spotify_uri: entry.spotify_uri.clone(),
+            spotify_url: entry.spotify_url.clone(),
+            album_art_url: entry.album_art_url.clone(),
+            added_by: entry.added_by_name.clone(),
+            playlist: entry
+                .playlist
+                .as_ref()
+                .map(|playlist| playlist.name.clone())
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn favorite_export_tracks(items: &[FavoriteItem]) -> Vec<ExportTrack> {
+    items
+        .iter()
+        .filter(|item| item.kind == FavoriteKind::Track)
+        .map(|item| ExportTrack {
+            played_at_ms: None,
+            name: item.summary.name.clone(),
+            artist: item.summary.artist.clone().unwrap_or_default(),
+            duration_ms: item.summary.duration_ms.unwrap_or(0),
+            spotify_uri: item.summary.spotify_uri.clone(),
+            spotify_url: item.summary.spotify_url.clone(),
+            album_art_url: item.summary.artwork_url.clone().unwrap_or_default(),
+            added_by: item
+                .attributions
+                .iter()
+                .map(|attribution| attribution.display_name.as_str())
+                .collect::<Vec<_>>()
+                .join("; "),
+            playlist: String::new(),
+        })
+        .collect()
+}
+
+fn track_title(track: &ExportTrack) -> String {
+    if track.artist.is_empty() {
+        track.name.clone()
+    } else {
+        format!("{} - {}", track.artist, track.name)
+    }
+}
+
+/// Extended M3U. Line breaks inside titles would start a new entry, so they
+/// are flattened to spaces.
+pub(crate) fn render_m3u(tracks: &[ExportTrack]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    for track in tracks {
+        let seconds = if track.duration_ms == 0 {
+            -1
+        } else {
+            (track.duration_ms / 1000) as i64
+        };
+        let title = track_title(track).replace(['\r', '\n'], " ");
+        out.push_str(&format!("#EXTINF:{seconds},{title}\n"));
+        out.push_str(&track.location().replace(['\r', '\n'], ""));
+        out.push('\n');
+    }
+    out
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // XML 1.0 forbids most control characters outright.
+            ch if ch.is_control() && !matches!(ch, '\t' | '\n' | '\r') => {}
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+pub(crate) fn render_xspf(title: &str, tracks: &[ExportTrack]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+    );
+    out.push_str(&format!(
+        "  <title>{}</title>\n  <trackList>\n",
+        xml_escape(title)
+    ));
+    for track in tracks {
+        out.push_str("    <track>\n");
+        if !track.location().is_empty() {
+            out.push_str(&format!(
+                "      <location>{}</location>\n",
+                xml_escape(track.location())
+            ));
+        }
+        out.push_str(&format!(
+            "      <title>{}</title>\n",
+            xml_escape(&track.name)
+        ));
+        if !track.artist.is_empty() {
+            out.push_str(&format!(
+                "      <creator>{}</creator>\n",
+                xml_escape(&track.artist)
+            ));
+        }
+        if track.duration_ms > 0 {
+            out.push_str(&format!(
+                "      <duration>{}</duration>\n",
+                track.duration_ms
+            ));
+        }
+        if !track.album_art_url.is_empty() {
+            out.push_str(&format!(
+                "      <image>{}</image>\n",
+                xml_escape(&track.album_art_url)
+            ));
+        }
+        out.push_str("    </track>\n");
+    }
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+/// RFC 4180 quoting. Cells that a spreadsheet would evaluate as a formula get
+/// a leading apostrophe so an exported track name cannot run as one.
+fn csv_cell(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+pub(crate) fn render_csv(tracks: &[ExportTrack]) -> String {
+    let mut out = String::from(
+        "played_at_ms,name,artist,duration_ms,spotify_uri,spotify_url,album_art_url,added_by,playlist\r\n",
+    );
+    for track in tracks {
+        let played_at = track
+            .played_at_ms
+            .map(|played_at_ms| played_at_ms.to_string())
+            .unwrap_or_default();
+        let row = [
+            played_at,
+            csv_cell(&track.name),
+            csv_cell(&track.artist),
+            track.duration_ms.to_string(),
+            csv_cell(&track.spotify_uri),
+            csv_cell(&track.spotify_url),
+            csv_cell(&track.album_art_url),
+            csv_cell(&track.added_by),
+            csv_cell(&track.playlist),
+        ];
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Spotify track URIs in first-played order, each once. Local files and
+/// episodes cannot be added to a Spotify playlist.
+pub(crate) fn spotify_playlist_uris(tracks: &[ExportTrack]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tracks
+        .iter()
+        .map(|track| track.spotify_uri.as_str())
+        .filter(|uri| uri.starts_with("spotify:track:") && seen.insert(*uri))
+        .take(SPOTIFY_PLAYLIST_MAX_ITEMS)
+        .map(str::to_string)
+        .collect()
+}
+
+fn export_auth_error(status: StatusCode, code: &'static str, message: &str) -> JamApiError {
+    JamApiError {
+        status,
+        code,
+        message: message.to_string(),
+        retry_after: None,
+    }
+}
+
+fn ensure_export_actor(state: &AppState, headers: &HeaderMap) -> Result<JamActor, JamApiError> {
+    ensure_admin(state, headers)
+        .map_err(|status| export_auth_error(status, "unauthorized", "Authentication required"))?;
+    ensure_jam_actor(state, headers).map_err(|status| {
+        export_auth_error(
+            status,
+            "actor_required",
+            "A current Echo participant token is required",
+        )
+    })
+}
+
+fn export_range(
+    state: &AppState,
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+) -> Result<(u64, u64), JamApiError> {
+    let to_ms = to_ms.unwrap_or_else(now_ts_ms);
+    let from_ms = from_ms
+        .unwrap_or_else(|| to_ms.saturating_sub(state.jam_history.retention_days() * DAY_MS));
+    if from_ms > to_ms {
+        return Err(JamApiError::bad_request("Invalid export range"));
+    }
+    Ok((from_ms, to_ms))
+}
+
+/// Collect the rows to export. Errors are history storage failures.
+async fn export_tracks(
+    state: &AppState,
+    actor: &JamActor,
+    source: ExportSource,
+    from_ms: u64,
+    to_ms: u64,
+) -> Result<Vec<ExportTrack>, StatusCode> {
+    match source {
+        ExportSource::History => {
+            let entries = read_room_history(state, &actor.room).await?;
+            Ok(history_export_tracks(&entries, from_ms, to_ms))
+        }
+        ExportSource::Favorites => Ok(favorite_export_tracks(&state.jam_favorites.snapshot())),
+    }
+}
+
+fn export_title(source: ExportSource) -> &'static str {
+    match source {
+        ExportSource::History => "Echo Jam history",
+        ExportSource::Favorites => "Echo Jam favorites",
+    }
+}
+
+pub(crate) async fn jam_export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<JamExportQuery>,
+) -> Response {
+    let actor = match ensure_export_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    let (from_ms, to_ms) = match export_range(&state, query.from_ms, query.to_ms) {
+        Ok(range) => range,
+        Err(error) => return error.into_response(),
+    };
+    let tracks = match export_tracks(&state, &actor, query.source, from_ms, to_ms).await {
+        Ok(tracks) => tracks,
+        Err(status) => return history_storage_error_response(status),
+    };
+    let body = match query.format {
+        ExportFormat::M3u => render_m3u(&tracks),
+        ExportFormat::Xspf => render_xspf(export_title(query.source), &tracks),
+        ExportFormat::Csv => render_csv(&tracks),
+    };
+
+    let mut response = (StatusCode::OK, body).into_response();
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(query.format.content_type()),
+    );
+    response
+        .headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    let source = match query.source {
+        ExportSource::History => "history",
+        ExportSource::Favorites => "favorites",
+    };
+    let disposition = format!(
+        "attachment; filename=\"echo-jam-{source}.{}\"",
+        query.format.extension()
+    );
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        response.headers_mut().insert(CONTENT_DISPOSITION, value);
+    }
+    response
+}
+
+fn bounded_text(value: Option<&str>, max_chars: usize) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.chars().take(max_chars).collect())
+}
+
+pub(crate) async fn jam_export_spotify_playlist(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<JamSpotifyPlaylistExportRequest>,
+) -> Result<Json<JamSpotifyPlaylistExportResponse>, JamApiError> {
+    let actor = ensure_export_actor(&state, &headers)?;
+    {
+        let token = current_spotify_token(&state);
+        if token.is_none() {
+            return Err(JamApiError {
+                status: StatusCode::BAD_REQUEST,
+                code: "spotify_not_connected",
+                message: "Connect Spotify before exporting a playlist".to_string(),
+                retry_after: None,
+            });
+        }
+        if !spotify_playlist_write_authorized(token.as_ref()) {
+            return Err(spotify_playlist_scope_required_error());
+        }
+    }
+    let (from_ms, to_ms) = export_range(&state, request.from_ms, request.to_ms)?;
+    let tracks = export_tracks(&state, &actor, request.source, from_ms, to_ms)
+        .await
+        .map_err(|status| {
+            JamApiError::internal(format!("Jam history is unavailable ({status})"))
+        })?;
+    let uris = spotify_playlist_uris(&tracks);
+    if uris.is_empty() {
+        return Err(JamApiError {
+            status: StatusCode::BAD_REQUEST,
+            code: "export_empty",
+            message: "There are no Spotify tracks to export".to_string(),
+            retry_after: None,
+        });
+    }
+
+    let name = bounded_text(request.name.as_deref(), MAX_PLAYLIST_NAME_CHARS)
+        .unwrap_or_else(|| export_title(request.source).to_string());
+    let description = bounded_text(
+        request.description.as_deref(),
+        MAX_PLAYLIST_DESCRIPTION_CHARS,
+    )
+    .unwrap_or_else(|| format!("Exported from Echo Chamber by {}", actor.display_name));
+    let created = spotify_json_request(
+        &state,
+        reqwest::Method::POST,
+        "https://api.spotify.com/v1/me/playlists",
+        Some(serde_json::json!({
+            "name": name,
+            "description": description,
+            "public": request.public,
+        })),
+    )
+    .await?;
+    let spotify_id = created["id"].as_str().unwrap_or_default().to_string();
+    if spotify_id.is_empty() {
+        return Err(JamApiError {
+            status: StatusCode::BAD_GATEWAY,
+            code: "spotify_invalid_response",
+            message: "Spotify did not return the new playlist".to_string(),
+            retry_after: None,
+        });
+    }
+    let spotify_url = created["external_urls"]["spotify"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+
+    let items_url = format!("https://api.spotify.com/v1/playlists/{spotify_id}/items");
+    for (index, chunk) in uris.chunks(SPOTIFY_ADD_ITEMS_CHUNK).enumerate() {
+        if let Err(mut error) = spotify_json_request(
+            &state,
+            reqwest::Method::POST,
+            &items_url,
+            Some(serde_json::json!({ "uris": chunk })),
+        )
+        .await
+        {
+            // The playlist already exists; say how far it got so the caller
+            // can find it instead of retrying into a second copy.
+            error.message = format!(
+                "{} (playlist {spotify_id} was created with {} of {} tracks)",
+                error.message,
+                index * SPOTIFY_ADD_ITEMS_CHUNK,
+                uris.len()
+            );
+            return Err(error);
+        }
+    }
+
+    Ok(Json(JamSpotifyPlaylistExportResponse {
+        ok: true,
+        spotify_uri: created["uri"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("spotify:playlist:{spotify_id}")),
+        spotify_id,
+        spotify_url,
+        name,
+        track_count: uris.len(),
+        skipped: tracks.len() - uris.len(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(name: &str, artist: &str, uri: &str) -> ExportTrack {
+        ExportTrack {
+            played_at_ms: Some(1_000),
+            name: name.to_string(),
+            artist: artist.to_string(),
+            duration_ms: 215_500,
+            spotify_uri: uri.to_string(),
+            spotify_url: String::new(),
+            album_art_url: String::new(),
+            added_by: "Sam".to_string(),
+            playlist: String::new(),
+        }
+    }
+
+    #[test]
+    fn m3u_and_xspf_render_each_track_safely() {
+        let tracks = vec![
+            track("Song\nTwo", "A & B", "spotify:track:one"),
+            ExportTrack {
+                duration_ms: 0,
+                ..track("Local", "", "echo-local:abc")
+            },
+        ];
+
+        assert_eq!(
+            render_m3u(&tracks),
+            "#EXTM3U\n#EXTINF:215,A & B - Song Two\nspotify:track:one\n#EXTINF:-1,Local\necho-local:abc\n"
+        );
+        let xspf = render_xspf("Night <1>", &tracks);
+        assert!(xspf.contains("<title>Night &lt;1&gt;</title>"));
+        assert!(xspf.contains("<creator>A &amp; B</creator>"));
+        assert!(xspf.contains("<duration>215500</duration>"));
+        assert_eq!(xspf.matches("<track>").count(), 2);
+    }
+
+    #[test]
+    fn csv_quotes_fields_and_neutralizes_formulas() {
+        let mut row = track("Hello, \"World\"", "=HYPERLINK(\"x\")", "spotify:track:one");
+        row.added_by = "Sam; Alex".to_string();
+        let csv = render_csv(&[row]);
+        let mut lines = csv.split("\r\n");
+        assert!(lines.next().unwrap().starts_with("played_at_ms,name,"));
+        assert_eq!(
+            lines.next().unwrap(),
+            "1000,\"Hello, \"\"World\"\"\",\"'=HYPERLINK(\"\"x\"\")\",215500,spotify:track:one,,,Sam; Alex,"
+        );
+    }
+
+    #[test]
+    fn spotify_playlist_keeps_first_play_of_each_spotify_track() {
+        let tracks = vec![
+            track("One", "", "spotify:track:one"),
+            track("Local", "", "echo-local:abc"),
+            track("Two", "", "spotify:track:two"),
+            track("One again", "", "spotify:track:one"),
+            track("Episode", "", "spotify:episode:three"),
+        ];
+        assert_eq!(
+            spotify_playlist_uris(&tracks),
+            ["spotify:track:one", "spotify:track:two"]
+        );
+    }
+}
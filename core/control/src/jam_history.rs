@@ -1,5 +1,6 @@
 use crate::auth::{ensure_admin, ensure_jam_actor};
 use crate::config::{now_ts_ms, random_secret};
+use crate::jam_autoplay::AutoplaySource;
 use crate::jam_session::{QueuedPlaylistProvenance, QueuedTrack};
 use crate::AppState;
 
@@ -23,9 +24,11 @@ use std::{
 use tracing::warn;
 
 pub(crate) const HISTORY_SCHEMA_VERSION: u16 = 1;
+// Default retention; `CORE_JAM_HISTORY_RETENTION_DAYS` can raise it for
+// monthly and yearly recaps.
 pub(crate) const HISTORY_RETENTION_DAYS: u64 = 30;
-const DAY_MS: u64 = 24 * 60 * 60 * 1_000;
-const RETENTION_MS: u64 = HISTORY_RETENTION_DAYS * DAY_MS;
+pub(crate) const MAX_HISTORY_RETENTION_DAYS: u64 = 3_650;
+pub(crate) const DAY_MS: u64 = 24 * 60 * 60 * 1_000;
 const MAX_HISTORY_LIMIT: usize = 200;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -50,10 +53,16 @@ pub(crate) struct JamHistoryEntry {
     pub(crate) added_by: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) playlist: Option<QueuedPlaylistProvenance>,
+    // The room whose Jam played this track. Rows written before Jams were
+    // per room have none and are shown to every room.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) room: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) autoplay: Option<AutoplaySource>,
 }
 
 impl JamHistoryEntry {
-    fn from_track(track: &QueuedTrack, played_at_ms: u64) -> Self {
+    fn from_track(track: &QueuedTrack, room: &str, played_at_ms: u64) -> Self {
         Self {
             schema_version: HISTORY_SCHEMA_VERSION,
             history_entry_id: format!("jh1_{}", random_secret()),
@@ -72,6 +81,8 @@ impl JamHistoryEntry {
             added_by_name: track.added_by_name.clone(),
             added_by: track.added_by.clone(),
             playlist: track.playlist.clone(),
+            room: Some(room.to_string()),
+            autoplay: track.autoplay,
         }
     }
 }
@@ -79,17 +90,19 @@ impl JamHistoryEntry {
 pub(crate) struct JamHistoryStore {
     dir: PathBuf,
     enabled: bool,
+    retention_days: u64,
     lock: Mutex<()>,
     revision: AtomicU64,
 }
 
 impl JamHistoryStore {
-    pub(crate) fn open(dir: PathBuf, now_ms: u64) -> io::Result<Self> {
+    pub(crate) fn open(dir: PathBuf, retention_days: u64, now_ms: u64) -> io::Result<Self> {
         fs::create_dir_all(&dir)?;
         recover_prune_backups(&dir)?;
         let store = Self {
             dir,
             enabled: true,
+            retention_days: retention_days.clamp(1, MAX_HISTORY_RETENTION_DAYS),
             lock: Mutex::new(()),
             revision: AtomicU64::new(0),
         };
@@ -101,11 +114,20 @@ impl JamHistoryStore {
         Self {
             dir,
             enabled: false,
+            retention_days: HISTORY_RETENTION_DAYS,
             lock: Mutex::new(()),
             revision: AtomicU64::new(0),
         }
     }
 
+    pub(crate) fn retention_days(&self) -> u64 {
+        self.retention_days
+    }
+
+    fn retention_cutoff_ms(&self, now_ms: u64) -> u64 {
+        now_ms.saturating_sub(self.retention_days * DAY_MS)
+    }
+
     pub(crate) fn revision(&self) -> u64 {
         self.revision.load(AtomicOrdering::Relaxed)
     }
@@ -113,9 +135,10 @@ impl JamHistoryStore {
     pub(crate) fn append_observation(
         &self,
         track: &QueuedTrack,
+        room: &str,
         played_at_ms: u64,
     ) -> io::Result<JamHistoryEntry> {
-        let entry = JamHistoryEntry::from_track(track, played_at_ms);
+        let entry = JamHistoryEntry::from_track(track, room, played_at_ms);
         if !self.enabled {
             return Err(io::Error::new(
                 io::ErrorKind::NotConnected,
@@ -157,7 +180,7 @@ impl JamHistoryStore {
             ));
         }
         let _guard = self.lock.lock().unwrap_or_else(|error| error.into_inner());
-        let cutoff = now_ms.saturating_sub(RETENTION_MS);
+        let cutoff = self.retention_cutoff_ms(now_ms);
         let mut entries = Vec::new();
         for path in history_files(&self.dir)? {
             for line in BufReader::new(fs::File::open(&path)?).lines() {
@@ -195,7 +218,7 @@ impl JamHistoryStore {
     }
 
     fn prune_locked(&self, now_ms: u64) -> io::Result<()> {
-        let cutoff = now_ms.saturating_sub(RETENTION_MS);
+        let cutoff = self.retention_cutoff_ms(now_ms);
         for path in history_files(&self.dir)? {
             let mut retained = Vec::new();
             let mut changed = false;
@@ -400,6 +423,48 @@ fn history_order(left: &JamHistoryEntry, right: &JamHistoryEntry, sort: &str) ->
     primary.then_with(|| left.history_entry_id.cmp(&right.history_entry_id))
 }
 
+/// Every retained row visible to `room`: its own rows plus legacy rows
+/// written before Jams were per room.
+pub(crate) async fn read_room_history(
+    state: &AppState,
+    room: &str,
+) -> Result<Vec<JamHistoryEntry>, StatusCode> {
+    let history = std::sync::Arc::clone(&state.jam_history);
+    let mut entries = match tokio::task::spawn_blocking(move || history.list(now_ts_ms())).await {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(error)) => {
+            warn!("Could not read Jam history: {}", error);
+            return Err(if error.kind() == io::ErrorKind::NotConnected {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            });
+        }
+        Err(error) => {
+            warn!("Jam history read task failed: {}", error);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    entries.retain(|entry| {
+        entry
+            .room
+            .as_deref()
+            .is_none_or(|entry_room| entry_room == room)
+    });
+    Ok(entries)
+}
+
+pub(crate) fn history_storage_error_response(status: StatusCode) -> axum::response::Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error":"history_storage_error",
+            "message":"Could not read Jam history",
+        })),
+    )
+        .into_response()
+}
+
 pub(crate) async fn jam_history_list(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -408,9 +473,12 @@ pub(crate) async fn jam_history_list(
     if let Err(status) = ensure_admin(&state, &headers) {
         return (status, Json(serde_json::json!({"error":"unauthorized"}))).into_response();
     }
-    if let Err(status) = ensure_jam_actor(&state, &headers) {
-        return (status, Json(serde_json::json!({"error":"actor_required"}))).into_response();
-    }
+    let actor = match ensure_jam_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(status) => {
+            return (status, Json(serde_json::json!({"error":"actor_required"}))).into_response();
+        }
+    };
     if !matches!(
         query.sort.as_str(),
         "played_at" | "added_at" | "track" | "artist" | "added_by" | "playlist"
@@ -427,36 +495,9 @@ pub(crate) async fn jam_history_list(
         )
             .into_response();
     }
-    let history = std::sync::Arc::clone(&state.jam_history);
-    let mut entries = match tokio::task::spawn_blocking(move || history.list(now_ts_ms())).await {
-        Ok(Ok(entries)) => entries,
-        Ok(Err(error)) => {
-            warn!("Could not read Jam history: {}", error);
-            let status = if error.kind() == io::ErrorKind::NotConnected {
-                StatusCode::SERVICE_UNAVAILABLE
-            } else {
-                StatusCode::INTERNAL_SERVER_ERROR
-            };
-            return (
-                status,
-                Json(serde_json::json!({
-                    "error":"history_storage_error",
-                    "message":"Could not read Jam history",
-                })),
-            )
-                .into_response();
-        }
-        Err(error) => {
-            warn!("Jam history read task failed: {}", error);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error":"history_storage_error",
-                    "message":"Could not read Jam history",
-                })),
-            )
-                .into_response();
-        }
+    let mut entries = match read_room_history(&state, &actor.room).await {
+        Ok(entries) => entries,
+        Err(status) => return history_storage_error_response(status),
     };
     entries.retain(|entry| {
         query
@@ -492,7 +533,7 @@ pub(crate) async fn jam_history_list(
         .collect();
     Json(HistoryResponse {
         schema_version: HISTORY_SCHEMA_VERSION,
-        retention_days: HISTORY_RETENTION_DAYS,
+        retention_days: state.jam_history.retention_days(),
         items,
         offset: query.offset,
         limit: query.limit,
@@ -521,6 +562,7 @@ mod tests {
             added_by_name: "Sam".to_string(),
             playlist: None,
             playlist_position: None,
+            autoplay: None,
             added_by: "Sam".to_string(),
         }
     }
@@ -667,12 +709,20 @@ mod tests {
     fn store_applies_exact_thirty_day_retention() {
         let dir = std::env::temp_dir().join(format!("echo-jam-history-{}", random_secret()));
         let now = 50 * DAY_MS + 123;
-        let store = JamHistoryStore::open(dir.clone(), now).unwrap();
+        let store = JamHistoryStore::open(dir.clone(), HISTORY_RETENTION_DAYS, now).unwrap();
         store
-            .append_observation(&track("AAAAAAAAAAAAAAAAAAAAAA"), now - RETENTION_MS)
+            .append_observation(
+                &track("AAAAAAAAAAAAAAAAAAAAAA"),
+                "main",
+                now - HISTORY_RETENTION_DAYS * DAY_MS,
+            )
             .unwrap();
         store
-            .append_observation(&track("BBBBBBBBBBBBBBBBBBBBBB"), now - RETENTION_MS - 1)
+            .append_observation(
+                &track("BBBBBBBBBBBBBBBBBBBBBB"),
+                "main",
+                now - HISTORY_RETENTION_DAYS * DAY_MS - 1,
+            )
             .unwrap();
         let entries = store.list(now).unwrap();
         assert_eq!(entries.len(), 1);
@@ -680,15 +730,34 @@ mod tests {
         let _ = fs::remove_dir_all(dir);
     }
 
+    #[test]
+    fn configured_retention_keeps_rows_for_yearly_recaps() {
+        let dir = std::env::temp_dir().join(format!("echo-jam-history-{}", random_secret()));
+        let now = 500 * DAY_MS;
+        let store = JamHistoryStore::open(dir.clone(), 365, now).unwrap();
+        assert_eq!(store.retention_days(), 365);
+        store
+            .append_observation(&track("AAAAAAAAAAAAAAAAAAAAAA"), "main", now - 364 * DAY_MS)
+            .unwrap();
+        store
+            .append_observation(&track("BBBBBBBBBBBBBBBBBBBBBB"), "main", now - 366 * DAY_MS)
+            .unwrap();
+        store.prune(now).unwrap();
+        let entries = store.list(now).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].spotify_id, "AAAAAAAAAAAAAAAAAAAAAA");
+        let _ = fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn store_revision_advances_only_after_successful_appends() {
         let dir = std::env::temp_dir().join(format!("echo-jam-history-{}", random_secret()));
         let now = 50 * DAY_MS;
-        let store = JamHistoryStore::open(dir.clone(), now).unwrap();
+        let store = JamHistoryStore::open(dir.clone(), HISTORY_RETENTION_DAYS, now).unwrap();
         assert_eq!(store.revision(), 0);
 
         store
-            .append_observation(&track("AAAAAAAAAAAAAAAAAAAAAA"), now)
+            .append_observation(&track("AAAAAAAAAAAAAAAAAAAAAA"), "main", now)
             .unwrap();
         assert_eq!(store.revision(), 1);
         assert_eq!(store.list(now).unwrap().len(), 1);
@@ -699,7 +768,7 @@ mod tests {
         );
 
         store
-            .append_observation(&track("BBBBBBBBBBBBBBBBBBBBBB"), now + 1)
+            .append_observation(&track("BBBBBBBBBBBBBBBBBBBBBB"), "main", now + 1)
             .unwrap();
         assert_eq!(store.revision(), 2);
         let _ = fs::remove_dir_all(dir);
@@ -712,9 +781,9 @@ mod tests {
         let now = 50 * DAY_MS;
         let path = dir.join(format!("history-v1-{}.jsonl", now / DAY_MS));
         fs::write(&path, b"{torn").unwrap();
-        let store = JamHistoryStore::open(dir.clone(), now).unwrap();
+        let store = JamHistoryStore::open(dir.clone(), HISTORY_RETENTION_DAYS, now).unwrap();
         store
-            .append_observation(&track("AAAAAAAAAAAAAAAAAAAAAA"), now)
+            .append_observation(&track("AAAAAAAAAAAAAAAAAAAAAA"), "main", now)
             .unwrap();
         let lines = fs::read_to_string(path).unwrap();
         let rows = lines.lines().collect::<Vec<_>>();
@@ -731,25 +800,42 @@ mod tests {
         let now = 50 * DAY_MS;
         let original = dir.join(format!("history-v1-{}.jsonl", now / DAY_MS));
         let backup = PathBuf::from(format!("{}.prune.bak", original.display()));
-        let entry = JamHistoryEntry::from_track(&track("AAAAAAAAAAAAAAAAAAAAAA"), now);
+        let entry = JamHistoryEntry::from_track(&track("AAAAAAAAAAAAAAAAAAAAAA"), "main", now);
         fs::write(
             &backup,
             format!("{}\n", serde_json::to_string(&entry).unwrap()),
         )
         .unwrap();
-        let store = JamHistoryStore::open(dir.clone(), now).unwrap();
+        let store = JamHistoryStore::open(dir.clone(), HISTORY_RETENTION_DAYS, now).unwrap();
         assert!(original.exists());
         assert!(!backup.exists());
         assert_eq!(store.list(now).unwrap(), vec![entry]);
         let _ = fs::remove_dir_all(dir);
     }
 
+    #[test]
+    fn history_rows_record_their_room_and_legacy_rows_have_none() {
+        let dir = std::env::temp_dir().join(format!("echo-jam-history-{}", random_secret()));
+        let now = 50 * DAY_MS;
+        let store = JamHistoryStore::open(dir.clone(), HISTORY_RETENTION_DAYS, now).unwrap();
+        let entry = store
+            .append_observation(&track("AAAAAAAAAAAAAAAAAAAAAA"), "lounge", now)
+            .unwrap();
+        assert_eq!(entry.room.as_deref(), Some("lounge"));
+
+        let mut legacy = serde_json::to_value(&entry).unwrap();
+        legacy.as_object_mut().unwrap().remove("room");
+        let legacy: JamHistoryEntry = serde_json::from_value(legacy).unwrap();
+        assert_eq!(legacy.room, None);
+        let _ = fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn disabled_store_reports_unavailable_instead_of_dropping_history() {
         let store = JamHistoryStore::disabled(PathBuf::from("disabled"));
         assert_eq!(
             store
-                .append_observation(&track("AAAAAAAAAAAAAAAAAAAAAA"), DAY_MS)
+                .append_observation(&track("AAAAAAAAAAAAAAAAAAAAAA"), "main", DAY_MS)
                 .unwrap_err()
                 .kind(),
             io::ErrorKind::NotConnected
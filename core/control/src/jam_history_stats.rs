@@ -0,0 +1,419 @@
+//! Aggregate views over the Jam history store: top tracks, artists,
+//! contributors and playlists, plus listening time per day or week.
+//!
+//! Listening time is the sum of the played tracks' durations. History records
+//! when a track started, not how long it ran, so skipped tracks count in full.
+
+use crate::auth::{ensure_admin, ensure_jam_actor};
+use crate::config::now_ts_ms;
+use crate::jam_autoplay::AUTOPLAY_ACTOR_ID;
+use crate::jam_history::{
+    history_storage_error_response, read_room_history, JamHistoryEntry, DAY_MS,
+    HISTORY_SCHEMA_VERSION,
+};
+use crate::AppState;
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const DEFAULT_STATS_LIMIT: usize = 10;
+const MAX_STATS_LIMIT: usize = 100;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StatsBucket {
+    #[default]
+    Day,
+    /// Weeks start on Monday, UTC.
+    Week,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct HistoryStatsQuery {
+    #[serde(default)]
+    from_ms: Option<u64>,
+    #[serde(default)]
+    to_ms: Option<u64>,
+    #[serde(default)]
+    bucket: StatsBucket,
+    #[serde(default = "default_stats_limit")]
+    limit: usize,
+}
+
+fn default_stats_limit() -> usize {
+    DEFAULT_STATS_LIMIT
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct TrackStat {
+    spotify_id: String,
+    spotify_uri: String,
+    name: String,
+    artist: String,
+    album_art_url: String,
+    play_count: usize,
+    contributor_count: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ArtistStat {
+    artist: String,
+    play_count: usize,
+    listening_ms: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ContributorStat {
+    actor_id: String,
+    display_name: String,
+    play_count: usize,
+    listening_ms: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct PlaylistStat {
+    spotify_id: String,
+    name: String,
+    play_count: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ListeningPeriod {
+    period_start_ms: u64,
+    play_count: usize,
+    listening_ms: u64,
+    listening_hours: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct HistoryStats {
+    schema_version: u16,
+    from_ms: u64,
+    to_ms: u64,
+    bucket: StatsBucket,
+    play_count: usize,
+    listening_ms: u64,
+    top_tracks: Vec<TrackStat>,
+    top_artists: Vec<ArtistStat>,
+    top_contributors: Vec<ContributorStat>,
+    top_playlists: Vec<PlaylistStat>,
+    listening: Vec<ListeningPeriod>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct HistoryStatsResponse {
+    retention_days: u64,
+    #[serde(flatten)]
+    stats: HistoryStats,
+}
+
+fn period_start_ms(played_at_ms: u64, bucket: StatsBucket) -> u64 {
+    let day = played_at_ms / DAY_MS;
+    let start_day = match bucket {
+        StatsBucket::Day => day,
+        // 1970-01-01 was a Thursday, three days after a Monday.
+        StatsBucket::Week => day.saturating_sub((day + 3) % 7),
+    };
+    start_day * DAY_MS
+}
+
+/// Sort by count, then a stable key, and keep the first `limit`.
+fn top<T>(mut rows: Vec<T>, limit: usize, key: impl Fn(&T) -> (usize, String)) -> Vec<T> {
+    rows.sort_by(|left, right| {
+        let (left_count, left_key) = key(left);
+        let (right_count, right_key) = key(right);
+        right_count
+            .cmp(&left_count)
+            .then_with(|| left_key.cmp(&right_key))
+    });
+    rows.truncate(limit);
+    rows
+}
+
+/// Aggregate rows played in `[from_ms, to_ms]`.
+pub(crate) fn summarize_history(
+    entries: &[JamHistoryEntry],
+    from_ms: u64,
+    to_ms: u64,
+    bucket: StatsBucket,
+    limit: usize,
+) -> HistoryStats {
+    let entries = entries
+        .iter()
+        .filter(|entry| (from_ms..=to_ms).contains(&entry.played_at_ms))
+        .collect::<Vec<_>>();
+
+    let mut tracks: HashMap<&str, (TrackStat, HashSet<&str>)> = HashMap::new();
+    let mut artists: HashMap<&str, ArtistStat> = HashMap::new();
+    let mut contributors: HashMap<&str, ContributorStat> = HashMap::new();
+    let mut playlists: HashMap<&str, PlaylistStat> = HashMap::new();
+    let mut periods: BTreeMap<u64, (usize, u64)> = BTreeMap::new();
+    for entry in &entries {
+        let (track, track_contributors) =
+            tracks.entry(entry.spotify_uri.as_str()).or_insert_with(|| {
+                (
+                    TrackStat {
+                        spotify_id: entry.spotify_id.clone(),
+                        spotify_uri: entry.spotify_uri.clone(),
+                        name: entry.name.clone(),
+                        artist: entry.artist.clone(),
+                        album_art_url: entry.album_art_url.clone(),
+                        play_count: 0,
+                        contributor_count: 0,
+                    },
+                    HashSet::new(),
+                )
+            });
+        track.play_count += 1;
+        track_contributors.insert(entry.added_by_actor_id.as_str());
+
+        if !entry.artist.is_empty() {
+            let artist = artists
+                .entry(entry.artist.as_str())
+                .or_insert_with(|| ArtistStat {
+                    artist: entry.artist.clone(),
+                    play_count: 0,
+                    listening_ms: 0,
+                });
+            artist.play_count += 1;
+            artist.listening_ms += entry.duration_ms;
+        }
+
+        // Autoplay picks are nobody's contribution.
+        if entry.added_by_actor_id != AUTOPLAY_ACTOR_ID && !entry.added_by_actor_id.is_empty() {
+            let contributor = contributors
+                .entry(entry.added_by_actor_id.as_str())
+                .or_insert_with(|| ContributorStat {
+                    actor_id: entry.added_by_actor_id.clone(),
+                    display_name: entry.added_by_name.clone(),
+                    play_count: 0,
+                    listening_ms: 0,
+                });
+            contributor.play_count += 1;
+            contributor.listening_ms += entry.duration_ms;
+        }
+
+        if let Some(playlist) = entry.playlist.as_ref() {
+            playlists
+                .entry(playlist.spotify_id.as_str())
+                .or_insert_with(|| PlaylistStat {
+                    spotify_id: playlist.spotify_id.clone(),
+                    name: playlist.name.clone(),
+                    play_count: 0,
+                })
+                .play_count += 1;
+        }
+
+        let period = periods
+            .entry(period_start_ms(entry.played_at_ms, bucket))
+            .or_default();
+        period.0 += 1;
+        period.1 += entry.duration_ms;
+    }
+
+    let tracks = tracks
+        .into_values()
+        .map(|(mut track, track_contributors)| {
+            track.contributor_count = track_contributors.len();
+            track
+        })
+        .collect();
+    HistoryStats {
+        schema_version: HISTORY_SCHEMA_VERSION,
+        from_ms,
+        to_ms,
+        bucket,
+        play_count: entries.len(),
+        listening_ms: entries.iter().map(|entry| entry.duration_ms).sum(),
+        top_tracks: top(tracks, limit, |track| {
+            (track.play_count, track.spotify_uri.clone())
+        }),
+        top_artists: top(artists.into_values().collect(), limit, |artist| {
+            (artist.play_count, artist.artist.to_lowercase())
+        }),
+        top_contributors: top(contributors.into_values().collect(), limit, |contributor| {
+            (contributor.play_count, contributor.actor_id.clone())
+        }),
+        top_playlists: top(playlists.into_values().collect(), limit, |playlist| {
+            (playlist.play_count, playlist.spotify_id.clone())
+        }),
+        listening: periods
+            .into_iter()
+            .map(
+                |(period_start_ms, (play_count, listening_ms))| ListeningPeriod {
+                    period_start_ms,
+                    play_count,
+                    listening_ms,
+                    listening_hours: listening_ms as f64 / 3_600_000.0,
+                },
+            )
+            .collect(),
+    }
+}
+
+pub(crate) async fn jam_history_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<HistoryStatsQuery>,
+) -> impl IntoResponse {
+    if let Err(status) = ensure_admin(&state, &headers) {
+        return (status, Json(serde_json::json!({"error":"unauthorized"}))).into_response();
+    }
+    let actor = match ensure_jam_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(status) => {
+            return (status, Json(serde_json::json!({"error":"actor_required"}))).into_response();
+        }
+    };
+    let now_ms = now_ts_ms();
+    let to_ms = query.to_ms.unwrap_or(now_ms);
+    let from_ms = query
+        .from_ms
+        .unwrap_or_else(|| to_ms.saturating_sub(state.jam_history.retention_days() * DAY_MS));
+    if from_ms > to_ms || !(1..=MAX_STATS_LIMIT).contains(&query.limit) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error":"bad_request",
+                "message":"Invalid history stats range or limit",
+            })),
+        )
+            .into_response();
+    }
+    let entries = match read_room_history(&state, &actor.room).await {
+        Ok(entries) => entries,
+        Err(status) => return history_storage_error_response(status),
+    };
+    Json(HistoryStatsResponse {
+        retention_days: state.jam_history.retention_days(),
+        stats: summarize_history(&entries, from_ms, to_ms, query.bucket, query.limit),
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jam_session::QueuedPlaylistProvenance;
+
+    const HOUR_MS: u64 = 60 * 60 * 1_000;
+    // A Wednesday: 2024-01-03T00:00:00Z.
+    const WEDNESDAY: u64 = 19_725 * DAY_MS;
+
+    fn played(id: &str, artist: &str, actor: &str, played_at_ms: u64) -> JamHistoryEntry {
+        JamHistoryEntry {
+            schema_version: HISTORY_SCHEMA_VERSION,
+            history_entry_id: format!("jh-{id}-{played_at_ms}"),
+            played_at_ms,
+            queue_entry_id: String::new(),
+            queue_batch_id: None,
+            spotify_id: id.to_string(),
+            spotify_uri: format!("spotify:track:{id}"),
+            spotify_url: String::new(),
+            name: id.to_string(),
+            artist: artist.to_string(),
+            album_art_url: String::new(),
+            duration_ms: HOUR_MS / 2,
+            added_at_ms: 0,
+            added_by_actor_id: actor.to_string(),
+            added_by_name: actor.to_uppercase(),
+            added_by: actor.to_uppercase(),
+            playlist: None,
+            room: Some("main".to_string()),
+            autoplay: None,
+        }
+    }
+
+    #[test]
+    fn weeks_start_on_monday_utc() {
+        let monday = WEDNESDAY - 2 * DAY_MS;
+        assert_eq!(
+            period_start_ms(WEDNESDAY + HOUR_MS, StatsBucket::Day),
+            WEDNESDAY
+        );
+        assert_eq!(
+            period_start_ms(WEDNESDAY + HOUR_MS, StatsBucket::Week),
+            monday
+        );
+        assert_eq!(period_start_ms(monday, StatsBucket::Week), monday);
+        assert_eq!(
+            period_start_ms(monday - 1, StatsBucket::Week),
+            monday - 7 * DAY_MS
+        );
+    }
+
+    #[test]
+    fn summary_ranks_tracks_artists_contributors_and_playlists_in_range() {
+        let mut from_playlist = played("b", "Band", "sam", WEDNESDAY + 2 * HOUR_MS);
+        from_playlist.playlist = Some(QueuedPlaylistProvenance {
+            spotify_id: "mix".to_string(),
+            spotify_uri: "spotify:playlist:mix".to_string(),
+            spotify_url: String::new(),
+            name: "Mix".to_string(),
+        });
+        let entries = vec![
+            played("a", "Artist", "alex", WEDNESDAY),
+            played("a", "Artist", "sam", WEDNESDAY + HOUR_MS),
+            from_playlist,
+            played("c", "Band", AUTOPLAY_ACTOR_ID, WEDNESDAY + DAY_MS),
+            played("old", "Artist", "alex", WEDNESDAY - 30 * DAY_MS),
+        ];
+
+        let stats = summarize_history(
+            &entries,
+            WEDNESDAY,
+            WEDNESDAY + 2 * DAY_MS,
+            StatsBucket::Day,
+            2,
+        );
+
+        assert_eq!(stats.play_count, 4);
+        assert_eq!(stats.listening_ms, 2 * HOUR_MS);
+        let top_tracks = stats
+            .top_tracks
+            .iter()
+            .map(|track| {
+                (
+                    track.name.as_str(),
+                    track.play_count,
+                    track.contributor_count,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(top_tracks, [("a", 2, 2), ("b", 1, 1)]);
+        let top_artists = stats
+            .top_artists
+            .iter()
+            .map(|artist| (artist.artist.as_str(), artist.play_count))
+            .collect::<Vec<_>>();
+        assert_eq!(top_artists, [("Artist", 2), ("Band", 2)]);
+        let contributors = stats
+            .top_contributors
+            .iter()
+            .map(|contributor| (contributor.actor_id.as_str(), contributor.play_count))
+            .collect::<Vec<_>>();
+        assert_eq!(contributors, [("sam", 2), ("alex", 1)]);
+        assert_eq!(stats.top_playlists[0].name, "Mix");
+        let listening = stats
+            .listening
+            .iter()
+            .map(|period| {
+                (
+                    period.period_start_ms,
+                    period.play_count,
+                    period.listening_hours,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listening,
+            [(WEDNESDAY, 3, 1.5), (WEDNESDAY + DAY_MS, 1, 0.5)]
+        );
+    }
+}
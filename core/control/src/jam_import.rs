@@ -0,0 +1,997 @@
+//! Jam queue import: turn pasted chat text into a queueable preview.
+//!
+//! `POST /api/jam/import` extracts `open.spotify.com` links and `spotify:`
+//! URIs (the same 22-character IDs and canonical URLs the desktop client's
+//! `spotify_link` module accepts, plus albums), resolves albums and playlists
+//! into tracks, and fuzzy-matches "Artist - Title" lines through Spotify
+//! catalog search. The resolved tracks are kept for a short while under a
+//! `ji1_` import ID that the playlist queue endpoints accept like a playlist
+//! ID, so a preview is committed through the normal selection flow.
+
+use crate::auth::{ensure_admin, ensure_jam_actor};
+use crate::config::{now_ts_ms, random_secret};
+use crate::jam_library::{
+    favorite_track, fetch_favorite_summary, fetch_playlist_expansion, normalize_track,
+    spotify_catalog_search, spotify_json_request, valid_spotify_id, CatalogTrack, FavoriteKind,
+    FavoriteSummary, JamApiError, PlaylistExpansion, MAX_PLAYLIST_QUEUE_TRACKS,
+};
+use crate::AppState;
+
+use axum::extract::{Json, State};
+use axum::http::HeaderMap;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+pub(crate) const JAM_IMPORT_SCHEMA_VERSION: u16 = 1;
+const MAX_IMPORT_TEXT_BYTES: usize = 20_000;
+const MAX_IMPORT_LINKS: usize = 50;
+const MAX_IMPORT_TEXT_QUERIES: usize = 25;
+const MAX_IMPORT_LINE_CHARS: usize = 200;
+const IMPORT_SEARCH_LIMIT: usize = 5;
+const SPOTIFY_ALBUM_TRACKS_LIMIT: usize = 50;
+/// Minimum blended title/artist similarity for a text line to count as a
+/// match.
+const MIN_MATCH_SCORE: f64 = 0.6;
+const IMPORT_TTL_MS: u64 = 30 * 60 * 1000;
+const MAX_STORED_IMPORTS: usize = 64;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ImportSourceKind {
+    Track,
+    Album,
+    Playlist,
+    Text,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ImportTarget {
+    Spotify(ImportSourceKind, String),
+    Text { artist: String, title: String },
+}
+
+/// One thing to resolve, in the order it appeared in the pasted text.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ImportRequestItem {
+    pub(crate) line: usize,
+    pub(crate) input: String,
+    pub(crate) target: ImportTarget,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct ImportIssue {
+    pub(crate) line: usize,
+    pub(crate) input: String,
+    pub(crate) reason: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ParsedImport {
+    pub(crate) items: Vec<ImportRequestItem>,
+    pub(crate) issues: Vec<ImportIssue>,
+    pub(crate) ignored_lines: usize,
+}
+
+pub(crate) fn valid_jam_import_id(value: &str) -> bool {
+    value.strip_prefix("ji1_").is_some_and(|suffix| {
+        suffix.len() == 64
+            && suffix
+                .bytes()
+                .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+    })
+}
+
+fn link_kind(value: &str) -> Option<ImportSourceKind> {
+    match value {
+        "track" => Some(ImportSourceKind::Track),
+        "album" => Some(ImportSourceKind::Album),
+        "playlist" => Some(ImportSourceKind::Playlist),
+        _ => None,
+    }
+}
+
+/// Parse one whitespace-delimited token as a Spotify link or URI.
+///
+/// `Some(Err(..))` means the token is clearly meant as a Spotify link but is
+/// not one Echo can queue (an artist page, a malformed ID, a plain-HTTP URL).
+pub(crate) fn parse_spotify_reference(
+    token: &str,
+) -> Option<Result<(ImportSourceKind, String), &'static str>> {
+    let token = token.trim_matches(|c: char| {
+        matches!(
+            c,
+            '<' | '>' | '(' | ')' | '[' | ']' | '"' | '\'' | ',' | '.' | ';' | '!'
+        )
+    });
+    if let Some(rest) = token.strip_prefix("spotify:") {
+        let Some((kind, id)) = rest.split_once(':') else {
+            return Some(Err("invalid_link"));
+        };
+        let Some(kind) = link_kind(kind) else {
+            return Some(Err("unsupported_link"));
+        };
+        return Some(if valid_spotify_id(id) {
+            Ok((kind, id.to_string()))
+        } else {
+            Err("invalid_link")
+        });
+    }
+    let path = if let Some(path) = token.strip_prefix("https://open.spotify.com/") {
+        path
+    } else if token.starts_with("http://open.spotify.com/") {
+        return Some(Err("invalid_link"));
+    } else {
+        return None;
+    };
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    let mut kind = segments.next()?;
+    if kind.starts_with("intl-") {
+        kind = segments.next()?;
+    }
+    let Some(kind) = link_kind(kind) else {
+        return Some(Err("unsupported_link"));
+    };
+    Some(match segments.next() {
+        Some(id) if valid_spotify_id(id) && segments.next().is_none() => Ok((kind, id.to_string())),
+        _ => Err("invalid_link"),
+    })
+}
+
+/// Strip a leading list marker such as `-`, `*`, `•`, `3.` or `12)`.
+fn strip_list_marker(line: &str) -> &str {
+    let line = line.trim();
+    for marker in ["- ", "* ", "• "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            return rest.trim_start();
+        }
+    }
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    if digits > 0 && digits <= 4 {
+        let rest = &line[digits..];
+        if let Some(rest) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return rest.trim_start();
+        }
+    }
+    line
+}
+
+fn parse_text_line(line: &str) -> Option<(String, String)> {
+    let line = strip_list_marker(line);
+    if line.chars().count() > MAX_IMPORT_LINE_CHARS {
+        return None;
+    }
+    let (artist, title) = [" - ", " – ", " — "]
+        .iter()
+        .find_map(|separator| line.split_once(separator))?;
+    let (artist, title) = (artist.trim(), title.trim());
+    (!artist.is_empty() && !title.is_empty()).then(|| (artist.to_string(), title.to_string()))
+}
+
+/// Split pasted text into links and "Artist - Title" lines. Duplicated
+/// links are reported once; later copies are skipped.
+pub(crate) fn parse_import_text(text: &str) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+    let mut seen_links = HashSet::new();
+    let mut link_count = 0usize;
+    let mut text_count = 0usize;
+    for (index, raw_line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        if raw_line.trim().is_empty() {
+            continue;
+        }
+        let mut found_link = false;
+        for token in raw_line.split_whitespace() {
+            let Some(reference) = parse_spotify_reference(token) else {
+                continue;
+            };
+            found_link = true;
+            let input = token.to_string();
+            let (kind, id) = match reference {
+                Ok(reference) => reference,
+                Err(reason) => {
+                    parsed.issues.push(ImportIssue {
+                        line: line_number,
+                        input,
+                        reason: reason.to_string(),
+                    });
+                    continue;
+                }
+            };
+            if !seen_links.insert((kind, id.clone())) {
+                parsed.issues.push(ImportIssue {
+                    line: line_number,
+                    input,
+                    reason: "duplicate".to_string(),
+                });
+                continue;
+            }
+            link_count += 1;
+            if link_count > MAX_IMPORT_LINKS {
+                parsed.issues.push(ImportIssue {
+                    line: line_number,
+                    input,
+                    reason: "too_many_links".to_string(),
+                });
+                continue;
+            }
+            parsed.items.push(ImportRequestItem {
+                line: line_number,
+                input,
+                target: ImportTarget::Spotify(kind, id),
+            });
+        }
+        if found_link {
+            continue;
+        }
+        let Some((artist, title)) = parse_text_line(raw_line) else {
+            parsed.ignored_lines += 1;
+            continue;
+        };
+        text_count += 1;
+        let input = strip_list_marker(raw_line).to_string();
+        if text_count > MAX_IMPORT_TEXT_QUERIES {
+            parsed.issues.push(ImportIssue {
+                line: line_number,
+                input,
+                reason: "too_many_searches".to_string(),
+            });
+            continue;
+        }
+        parsed.items.push(ImportRequestItem {
+            line: line_number,
+            input,
+            target: ImportTarget::Text { artist, title },
+        });
+    }
+    parsed
+}
+
+// ── Fuzzy matching ────────────────────────────────────────────────────────
+
+/// Lowercase alphanumeric words separated by single spaces.
+fn match_key(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Drop "(feat. ...)", "[Live]" and " - 2011 Remaster" style decorations
+/// from a catalog title so they do not count against a plain pasted title.
+fn undecorated_title(title: &str) -> String {
+    let mut result = String::with_capacity(title.len());
+    let mut depth = 0usize;
+    for c in title.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => result.push(c),
+            _ => {}
+        }
+    }
+    match result.split_once(" - ") {
+        Some((head, _)) if !head.trim().is_empty() => head.to_string(),
+        _ => result,
+    }
+}
+
+fn bigrams(value: &str) -> Vec<(char, char)> {
+    let chars = value.chars().collect::<Vec<_>>();
+    chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+/// Sørensen–Dice similarity over character bigrams, in `0.0..=1.0`.
+fn similarity(left: &str, right: &str) -> f64 {
+    let (left, right) = (match_key(left), match_key(right));
+    if left.is_empty() || right.is_empty() {
+        return 0.0;
+    }
+    if left == right {
+        return 1.0;
+    }
+    let left = bigrams(&left);
+    let mut right = bigrams(&right);
+    if left.is_empty() || right.is_empty() {
+        return 0.0;
+    }
+    let total = left.len() + right.len();
+    let mut shared = 0usize;
+    for pair in &left {
+        if let Some(index) = right.iter().position(|candidate| candidate == pair) {
+            right.swap_remove(index);
+            shared += 1;
+        }
+    }
+    (2 * shared) as f64 / total as f64
+}
+
+/// Score a catalog track against a pasted "Artist - Title" line. Lines are
+/// also tried the other way round, since "Title - Artist" is common too.
+pub(crate) fn match_score(artist: &str, title: &str, candidate: &FavoriteSummary) -> f64 {
+    let candidate_title = undecorated_title(&candidate.name);
+    let candidate_artists = candidate.artist.as_deref().unwrap_or_default();
+    let artist_similarity = |query: &str| {
+        candidate_artists
+            .split(", ")
+            .map(|name| similarity(query, name))
+            .fold(similarity(query, candidate_artists), f64::max)
+    };
+    let title_similarity =
+        |query: &str| similarity(query, &candidate_title).max(similarity(query, &candidate.name));
+    let forward = 0.6 * title_similarity(title) + 0.4 * artist_similarity(artist);
+    let reversed = 0.6 * title_similarity(artist) + 0.4 * artist_similarity(title);
+    forward.max(reversed)
+}
+
+fn best_match(
+    artist: &str,
+    title: &str,
+    candidates: Vec<FavoriteSummary>,
+) -> Option<(FavoriteSummary, f64)> {
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let score = match_score(artist, title, &candidate);
+            (candidate, score)
+        })
+        .filter(|(_, score)| *score >= MIN_MATCH_SCORE)
+        .fold(
+            None,
+            |best: Option<(FavoriteSummary, f64)>, next| match best {
+                Some(best) if best.1 >= next.1 => Some(best),
+                _ => Some(next),
+            },
+        )
+}
+
+// ── Preview store ─────────────────────────────────────────────────────────
+
+#[derive(Clone, Debug)]
+struct StoredImport {
+    actor_id: String,
+    created_at_ms: u64,
+    tracks: Vec<FavoriteSummary>,
+}
+
+impl StoredImport {
+    fn snapshot_id(&self) -> String {
+        format!("import-{}", self.created_at_ms)
+    }
+
+    fn summary(&self, import_id: &str) -> FavoriteSummary {
+        FavoriteSummary {
+            spotify_id: import_id.to_string(),
+            spotify_uri: format!("echo:import:{import_id}"),
+            name: "Pasted tracks".to_string(),
+            artwork_url: self
+                .tracks
+                .iter()
+                .find_map(|track| track.artwork_url.clone()),
+            track_count: Some(self.tracks.len() as u64),
+            snapshot_id: Some(self.snapshot_id()),
+            ..FavoriteSummary::default()
+        }
+    }
+}
+
+/// Resolved previews, held in memory until they expire. A preview is only
+/// visible to the participant who pasted it.
+#[derive(Default)]
+pub(crate) struct JamImportStore {
+    imports: Mutex<HashMap<String, StoredImport>>,
+}
+
+impl JamImportStore {
+    /// Store a preview and return its import ID and snapshot ID.
+    pub(crate) fn insert(
+        &self,
+        actor_id: &str,
+        tracks: Vec<FavoriteSummary>,
+        now_ms: u64,
+    ) -> (String, String) {
+        let mut imports = self.imports.lock().unwrap_or_else(|e| e.into_inner());
+        imports.retain(|_, stored| now_ms.saturating_sub(stored.created_at_ms) < IMPORT_TTL_MS);
+        while imports.len() >= MAX_STORED_IMPORTS {
+            let Some(oldest) = imports
+                .iter()
+                .min_by_key(|(_, stored)| stored.created_at_ms)
+                .map(|(import_id, _)| import_id.clone())
+            else {
+                break;
+            };
+            imports.remove(&oldest);
+        }
+        let import_id = format!("ji1_{}", random_secret());
+        let stored = StoredImport {
+            actor_id: actor_id.to_string(),
+            created_at_ms: now_ms,
+            tracks,
+        };
+        let snapshot_id = stored.snapshot_id();
+        imports.insert(import_id.clone(), stored);
+        (import_id, snapshot_id)
+    }
+
+    /// Expand a preview for the playlist queue endpoints. Positions were
+    /// already validated and sorted by the caller.
+    pub(crate) fn expansion(
+        &self,
+        actor_id: &str,
+        import_id: &str,
+        selected_positions: Option<&[usize]>,
+        expected_snapshot_id: Option<&str>,
+        now_ms: u64,
+    ) -> Result<PlaylistExpansion, JamApiError> {
+        let stored = {
+            let imports = self.imports.lock().unwrap_or_else(|e| e.into_inner());
+            imports
+                .get(import_id)
+                .filter(|stored| {
+                    stored.actor_id == actor_id
+                        && now_ms.saturating_sub(stored.created_at_ms) < IMPORT_TTL_MS
+                })
+                .cloned()
+        }
+        .ok_or_else(|| JamApiError {
+            status: StatusCode::NOT_FOUND,
+            code: "jam_import_not_found",
+            message: "This import preview expired; paste the list again".to_string(),
+            retry_after: None,
+        })?;
+        if expected_snapshot_id.is_some_and(|snapshot_id| snapshot_id != stored.snapshot_id()) {
+            return Err(JamApiError {
+                status: StatusCode::CONFLICT,
+                code: "playlist_changed",
+                message: "The import preview changed; paste the list again".to_string(),
+                retry_after: None,
+            });
+        }
+        let tracks = match selected_positions {
+            Some(positions) => {
+                if let Some(position) = positions
+                    .iter()
+                    .find(|position| **position >= stored.tracks.len())
+                {
+                    return Err(JamApiError {
+                        status: StatusCode::UNPROCESSABLE_ENTITY,
+                        code: "playlist_position_out_of_range",
+                        message: format!(
+                            "Position {position} is past the end of this {}-track import",
+                            stored.tracks.len()
+                        ),
+                        retry_after: None,
+                    });
+                }
+                positions
+                    .iter()
+                    .map(|position| (*position, stored.tracks[*position].clone()))
+                    .collect()
+            }
+            None => stored.tracks.iter().cloned().enumerate().collect(),
+        };
+        Ok(PlaylistExpansion {
+            playlist: stored.summary(import_id),
+            tracks,
+            skipped: Vec::new(),
+        })
+    }
+}
+
+/// Expand a stored import for `jam_queue_playlist_impl`.
+pub(crate) fn jam_import_expansion(
+    state: &AppState,
+    actor_id: &str,
+    import_id: &str,
+    selected_positions: Option<&[usize]>,
+    expected_snapshot_id: Option<&str>,
+) -> Result<PlaylistExpansion, JamApiError> {
+    state.jam_imports.expansion(
+        actor_id,
+        import_id,
+        selected_positions,
+        expected_snapshot_id,
+        now_ts_ms(),
+    )
+}
+
+// ── Resolution ────────────────────────────────────────────────────────────
+
+/// Errors that make the rest of the import pointless (Spotify not linked,
+/// rate limited, or down), as opposed to one bad link.
+fn aborts_import(error: &JamApiError) -> bool {
+    matches!(
+        error.status,
+        StatusCode::UNAUTHORIZED
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::INTERNAL_SERVER_ERROR
+    ) || matches!(
+        error.code,
+        "spotify_upstream_error" | "spotify_request_failed"
+    )
+}
+
+/// Every playable track on an album, in album order, plus the reasons any
+/// others were skipped.
+async fn fetch_album_tracks(
+    state: &AppState,
+    album_id: &str,
+) -> Result<(Vec<FavoriteSummary>, Vec<&'static str>), JamApiError> {
+    let url = format!("https://api.spotify.com/v1/albums/{album_id}");
+    let album = spotify_json_request(state, reqwest::Method::GET, &url, None).await?;
+    let artwork_url = album["images"][0]["url"].as_str().map(str::to_string);
+    let total = album["tracks"]["total"].as_u64().unwrap_or(0) as usize;
+    if total > MAX_PLAYLIST_QUEUE_TRACKS {
+        return Err(JamApiError {
+            status: StatusCode::PAYLOAD_TOO_LARGE,
+            code: "album_too_large",
+            message: format!("This album has {total} tracks"),
+            retry_after: None,
+        });
+    }
+    let mut items = album["tracks"]["items"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    while items.len() < total {
+        let url = format!(
+            "https://api.spotify.com/v1/albums/{album_id}/tracks?offset={}&limit={SPOTIFY_ALBUM_TRACKS_LIMIT}",
+            items.len()
+        );
+        let page = spotify_json_request(state, reqwest::Method::GET, &url, None).await?;
+        let page_items = page["items"].as_array().cloned().unwrap_or_default();
+        if page_items.is_empty() {
+            break;
+        }
+        items.extend(page_items);
+    }
+    let mut tracks = Vec::with_capacity(items.len());
+    let mut skipped = Vec::new();
+    for item in &items {
+        match normalize_track(item) {
+            Ok(mut summary) => {
+                if summary.artwork_url.is_none() {
+                    summary.artwork_url = artwork_url.clone();
+                }
+                tracks.push(summary);
+            }
+            Err(reason) => skipped.push(reason),
+        }
+    }
+    Ok((tracks, skipped))
+}
+
+async fn search_text_line(
+    state: &AppState,
+    artist: &str,
+    title: &str,
+) -> Result<Option<(FavoriteSummary, f64)>, JamApiError> {
+    let query = format!("{artist} {title}");
+    let container =
+        spotify_catalog_search(state, FavoriteKind::Track, &query, 0, IMPORT_SEARCH_LIMIT).await?;
+    let candidates = container["items"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|value| normalize_track(value).ok())
+        .collect();
+    Ok(best_match(artist, title, candidates))
+}
+
+/// Resolved tracks for one parsed source, before de-duplication.
+struct ResolvedSource {
+    tracks: Vec<FavoriteSummary>,
+    skipped: Vec<String>,
+    match_score: Option<f64>,
+}
+
+async fn resolve_item(
+    state: &AppState,
+    target: &ImportTarget,
+) -> Result<ResolvedSource, JamApiError> {
+    let resolved = match target {
+        ImportTarget::Spotify(ImportSourceKind::Track, id) => ResolvedSource {
+            tracks: vec![fetch_favorite_summary(state, FavoriteKind::Track, id).await?],
+            skipped: Vec::new(),
+            match_score: None,
+        },
+        ImportTarget::Spotify(ImportSourceKind::Album, id) => {
+            let (tracks, skipped) = fetch_album_tracks(state, id).await?;
+            ResolvedSource {
+                tracks,
+                skipped: skipped.into_iter().map(str::to_string).collect(),
+                match_score: None,
+            }
+        }
+        ImportTarget::Spotify(ImportSourceKind::Playlist | ImportSourceKind::Text, id) => {
+            let expansion = fetch_playlist_expansion(state, id, None).await?;
+            ResolvedSource {
+                tracks: expansion
+                    .tracks
+                    .into_iter()
+                    .map(|(_, summary)| summary)
+                    .collect(),
+                skipped: expansion
+                    .skipped
+                    .into_iter()
+                    .map(|item| item.reason)
+                    .collect(),
+                match_score: None,
+            }
+        }
+        ImportTarget::Text { artist, title } => match search_text_line(state, artist, title).await?
+        {
+            Some((summary, score)) => ResolvedSource {
+                tracks: vec![summary],
+                skipped: Vec::new(),
+                match_score: Some(score),
+            },
+            None => ResolvedSource {
+                tracks: Vec::new(),
+                skipped: vec!["no_match".to_string()],
+                match_score: None,
+            },
+        },
+    };
+    Ok(resolved)
+}
+
+// ── HTTP ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct JamImportRequest {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct JamImportItem {
+    #[serde(flatten)]
+    track: CatalogTrack,
+    source: ImportSourceKind,
+    line: usize,
+    input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_score: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct JamImportPreview {
+    schema_version: u16,
+    /// Pass as `playlist_id` to the playlist queue endpoints; absent when
+    /// nothing resolved.
+    import_id: Option<String>,
+    snapshot_id: Option<String>,
+    expires_at_ms: Option<u64>,
+    items: Vec<JamImportItem>,
+    issues: Vec<ImportIssue>,
+    duplicates_removed: usize,
+    ignored_lines: usize,
+}
+
+/// Group per-track skip reasons so a 40-track album with three local files
+/// produces one issue, not three.
+fn push_skipped(issues: &mut Vec<ImportIssue>, item: &ImportRequestItem, skipped: Vec<String>) {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for reason in skipped {
+        match counts.iter_mut().find(|(known, _)| *known == reason) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((reason, 1)),
+        }
+    }
+    for (reason, count) in counts {
+        issues.push(ImportIssue {
+            line: item.line,
+            input: item.input.clone(),
+            reason: if count == 1 {
+                reason
+            } else {
+                format!("{reason} ({count} tracks)")
+            },
+        });
+    }
+}
+
+pub(crate) async fn jam_import_preview(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<JamImportRequest>,
+) -> Result<Json<JamImportPreview>, JamApiError> {
+    ensure_admin(&state, &headers).map_err(|status| JamApiError {
+        status,
+        code: "unauthorized",
+        message: "Authentication required".to_string(),
+        retry_after: None,
+    })?;
+    let actor = ensure_jam_actor(&state, &headers).map_err(|status| JamApiError {
+        status,
+        code: "actor_required",
+        message: "A current Echo participant token is required".to_string(),
+        retry_after: None,
+    })?;
+    if payload.text.trim().is_empty() || payload.text.len() > MAX_IMPORT_TEXT_BYTES {
+        return Err(JamApiError::bad_request(format!(
+            "text must contain between 1 and {MAX_IMPORT_TEXT_BYTES} bytes"
+        )));
+    }
+    let parsed = parse_import_text(&payload.text);
+    if parsed.items.is_empty() && parsed.issues.is_empty() {
+        return Err(JamApiError {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            code: "import_empty",
+            message: "No Spotify links or \"Artist - Title\" lines were found".to_string(),
+            retry_after: None,
+        });
+    }
+
+    let mut issues = parsed.issues;
+    let mut tracks: Vec<(FavoriteSummary, &ImportRequestItem, Option<f64>)> = Vec::new();
+    let mut seen_tracks = HashSet::new();
+    let mut duplicates_removed = 0usize;
+    for item in &parsed.items {
+        if tracks.len() >= MAX_PLAYLIST_QUEUE_TRACKS {
+            issues.push(ImportIssue {
+                line: item.line,
+                input: item.input.clone(),
+                reason: "import_too_large".to_string(),
+            });
+            continue;
+        }
+        let resolved = match resolve_item(&state, &item.target).await {
+            Ok(resolved) => resolved,
+            Err(error) if aborts_import(&error) => return Err(error),
+            Err(error) => {
+                issues.push(ImportIssue {
+                    line: item.line,
+                    input: item.input.clone(),
+                    reason: error.code.to_string(),
+                });
+                continue;
+            }
+        };
+        push_skipped(&mut issues, item, resolved.skipped);
+        for summary in resolved.tracks {
+            if !seen_tracks.insert(summary.spotify_id.clone()) {
+                duplicates_removed += 1;
+                continue;
+            }
+            if tracks.len() >= MAX_PLAYLIST_QUEUE_TRACKS {
+                issues.push(ImportIssue {
+                    line: item.line,
+                    input: item.input.clone(),
+                    reason: "import_too_large".to_string(),
+                });
+                break;
+            }
+            tracks.push((summary, item, resolved.match_score));
+        }
+    }
+    issues.sort_by_key(|issue| issue.line);
+
+    let now_ms = now_ts_ms();
+    let (import_id, snapshot_id) = if tracks.is_empty() {
+        (None, None)
+    } else {
+        let summaries = tracks
+            .iter()
+            .map(|(summary, _, _)| summary.clone())
+            .collect();
+        let (import_id, snapshot_id) = state.jam_imports.insert(&actor.actor_id, summaries, now_ms);
+        (Some(import_id), Some(snapshot_id))
+    };
+    let items = tracks
+        .into_iter()
+        .enumerate()
+        .map(|(position, (summary, item, match_score))| JamImportItem {
+            track: favorite_track(&state, &actor.actor_id, summary, Some(position)),
+            source: match item.target {
+                ImportTarget::Spotify(kind, _) => kind,
+                ImportTarget::Text { .. } => ImportSourceKind::Text,
+            },
+            line: item.line,
+            input: item.input.clone(),
+            match_score: match_score.map(|score| (score * 100.0).round() / 100.0),
+        })
+        .collect();
+    Ok(Json(JamImportPreview {
+        schema_version: JAM_IMPORT_SCHEMA_VERSION,
+        expires_at_ms: import_id.as_ref().map(|_| now_ms + IMPORT_TTL_MS),
+        import_id,
+        snapshot_id,
+        items,
+        issues,
+        duplicates_removed,
+        ignored_lines: parsed.ignored_lines,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID_A: &str = "0VjIjW4GlUZAMYd2vXMi3b";
+    const ID_B: &str = "3n3Ppam7vgaVa1iaRUc9Lp";
+
+    fn track(id: &str, name: &str, artist: &str) -> FavoriteSummary {
+        FavoriteSummary {
+            spotify_id: id.to_string(),
+            spotify_uri: format!("spotify:track:{id}"),
+            name: name.to_string(),
+            artist: Some(artist.to_string()),
+            ..FavoriteSummary::default()
+        }
+    }
+
+    fn targets(parsed: &ParsedImport) -> Vec<&ImportTarget> {
+        parsed.items.iter().map(|item| &item.target).collect()
+    }
+
+    #[test]
+    fn extracts_links_uris_and_artist_title_lines_in_order() {
+        let text = format!(
+            "check these out\n\
+             https://open.spotify.com/track/{ID_A}?si=abc123, and\n\
+             spotify:album:{ID_B}\n\
+             <https://open.spotify.com/intl-de/playlist/{ID_B}>\n\
+             1. Daft Punk - One More Time\n\
+             • Massive Attack – Teardrop\n\
+             lol\n"
+        );
+        let parsed = parse_import_text(&text);
+        assert_eq!(
+            targets(&parsed),
+            vec![
+                &ImportTarget::Spotify(ImportSourceKind::Track, ID_A.to_string()),
+                &ImportTarget::Spotify(ImportSourceKind::Album, ID_B.to_string()),
+                &ImportTarget::Spotify(ImportSourceKind::Playlist, ID_B.to_string()),
+                &ImportTarget::Text {
+                    artist: "Daft Punk".to_string(),
+                    title: "One More Time".to_string(),
+                },
+                &ImportTarget::Text {
+                    artist: "Massive Attack".to_string(),
+                    title: "Teardrop".to_string(),
+                },
+            ]
+        );
+        assert_eq!(parsed.items[0].line, 2);
+        assert_eq!(parsed.items[3].input, "Daft Punk - One More Time");
+        assert!(parsed.issues.is_empty());
+        assert_eq!(parsed.ignored_lines, 2);
+    }
+
+    #[test]
+    fn reports_unsupported_malformed_and_duplicate_links() {
+        let text = format!(
+            "https://open.spotify.com/artist/{ID_A}\n\
+             spotify:track:not-an-id\n\
+             http://open.spotify.com/track/{ID_A}\n\
+             https://open.spotify.com/track/{ID_A}/extra\n\
+             spotify:track:{ID_A} https://open.spotify.com/track/{ID_A}\n"
+        );
+        let parsed = parse_import_text(&text);
+        assert_eq!(parsed.items.len(), 1);
+        let reasons = parsed
+            .issues
+            .iter()
+            .map(|issue| (issue.line, issue.reason.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![
+                (1, "unsupported_link"),
+                (2, "invalid_link"),
+                (3, "invalid_link"),
+                (4, "invalid_link"),
+                (5, "duplicate"),
+            ]
+        );
+    }
+
+    #[test]
+    fn caps_links_and_text_searches_per_import() {
+        let mut text = String::new();
+        for index in 0..MAX_IMPORT_TEXT_QUERIES + 2 {
+            text.push_str(&format!("Artist {index} - Song {index}\n"));
+        }
+        let parsed = parse_import_text(&text);
+        assert_eq!(parsed.items.len(), MAX_IMPORT_TEXT_QUERIES);
+        assert_eq!(parsed.issues.len(), 2);
+        assert!(parsed
+            .issues
+            .iter()
+            .all(|issue| issue.reason == "too_many_searches"));
+    }
+
+    #[test]
+    fn fuzzy_match_tolerates_decorations_typos_and_swapped_order() {
+        let exact = track(ID_A, "One More Time", "Daft Punk");
+        assert!(match_score("Daft Punk", "One More Time", &exact) > 0.99);
+
+        let decorated = track(ID_A, "Teardrop - 2006 Remaster", "Massive Attack");
+        assert!(match_score("massive attack", "teardrop", &decorated) > 0.95);
+
+        let featured = track(
+            ID_A,
+            "Get Lucky (feat. Pharrell Williams)",
+            "Daft Punk, Pharrell Williams",
+        );
+        assert!(match_score("Pharrell Williams", "Get Lucky", &featured) > 0.95);
+        assert!(match_score("Daft Pnuk", "Get Lucky", &featured) >= MIN_MATCH_SCORE);
+        assert!(match_score("Get Lucky", "Daft Punk", &featured) > 0.95);
+
+        let unrelated = track(ID_B, "Bohemian Rhapsody", "Queen");
+        assert!(match_score("Daft Punk", "One More Time", &unrelated) < MIN_MATCH_SCORE);
+    }
+
+    #[test]
+    fn best_match_picks_the_highest_score_above_the_threshold() {
+        let candidates = vec![
+            track(ID_B, "One More Time - Live", "Tribute Band"),
+            track(ID_A, "One More Time", "Daft Punk"),
+        ];
+        let (summary, _) = best_match("Daft Punk", "One More Time", candidates).unwrap();
+        assert_eq!(summary.spotify_id, ID_A);
+        assert!(best_match(
+            "Daft Punk",
+            "One More Time",
+            vec![track(ID_B, "Yellow", "Coldplay")]
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn stored_imports_expand_like_a_playlist_for_their_owner_only() {
+        let store = JamImportStore::default();
+        let (import_id, snapshot_id) = store.insert(
+            "actor-a",
+            vec![track(ID_A, "One", "A"), track(ID_B, "Two", "B")],
+            1_000,
+        );
+        assert!(valid_jam_import_id(&import_id));
+
+        let all = store
+            .expansion("actor-a", &import_id, None, None, 2_000)
+            .unwrap();
+        assert_eq!(all.tracks.len(), 2);
+        assert_eq!(all.playlist.spotify_id, import_id);
+        assert_eq!(
+            all.playlist.snapshot_id.as_deref(),
+            Some(snapshot_id.as_str())
+        );
+
+        let selected = store
+            .expansion("actor-a", &import_id, Some(&[1]), Some(&snapshot_id), 2_000)
+            .unwrap();
+        assert_eq!(selected.tracks, vec![(1, track(ID_B, "Two", "B"))]);
+
+        let error = |result: Result<PlaylistExpansion, JamApiError>| result.unwrap_err().code;
+        assert_eq!(
+            error(store.expansion("actor-b", &import_id, None, None, 2_000)),
+            "jam_import_not_found"
+        );
+        assert_eq!(
+            error(store.expansion("actor-a", &import_id, Some(&[2]), Some(&snapshot_id), 2_000)),
+            "playlist_position_out_of_range"
+        );
+        assert_eq!(
+            error(store.expansion("actor-a", &import_id, Some(&[0]), Some("import-1"), 2_000)),
+            "playlist_changed"
+        );
+        assert_eq!(
+            error(store.expansion("actor-a", &import_id, None, None, 1_000 + IMPORT_TTL_MS)),
+            "jam_import_not_found"
+        );
+    }
+}
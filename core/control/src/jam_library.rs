@@ -1,9 +1,14 @@
 use crate::auth::{bounded_jam_actor_display_name, ensure_admin, ensure_jam_actor, JamActor};
 use crate::config::{now_ts_ms, urlencoded};
+use crate::jam_echo_playlists::{
+    echo_catalog_playlist, echo_playlist_items_page, valid_echo_playlist_id,
+};
+use crate::jam_local_library::LocalTrack;
 use crate::jam_playlist_cache::PLAYLIST_ITEMS_CACHE_CHUNK_SIZE;
 use crate::jam_session::{
-    remember_spotify_rate_limit_seconds, spotify_api_request, spotify_library_scope_required_error,
-    spotify_library_scopes_authorized, spotify_rate_limit_error, spotify_retry_after_seconds,
+    current_spotify_token, remember_spotify_rate_limit_seconds, spotify_api_request,
+    spotify_library_scope_required_error, spotify_library_scopes_authorized,
+    spotify_rate_limit_error, spotify_retry_after_seconds, JamPlaybackSource,
 };
 use crate::spotify_public_catalog::{
     fetch_public_playlist_chunk, PublicCatalogError, PublicPlaylistPositionOutcome,
@@ -38,6 +43,8 @@ const SPOTIFY_PLAYLIST_ITEMS_LIMIT: usize = 50;
 const MAX_PLAYLIST_ITEMS_OFFSET: usize = 100_000;
 pub(crate) const MAX_PLAYLIST_QUEUE_TRACKS: usize = 1_000;
 const MAX_CATALOG_OFFSET: usize = 1_000;
+// Library matches mixed into the first page of a Spotify track search.
+const MIXED_LOCAL_TRACKS_LIMIT: usize = 5;
 const MAX_FAVORITES_LIMIT: usize = 200;
 const PLAYLIST_ARTWORK_CACHE_MAX_ENTRIES: usize = 512;
 const PLAYLIST_ARTWORK_CACHE_TTL_MS: u64 = 60 * 60 * 1_000;
@@ -577,7 +584,7 @@ fn load_favorite_file(path: &FsPath) -> io::Result<FavoriteFile> {
     validate_favorite_file(parsed)
 }
 
-fn favorite_backup_path(path: &FsPath) -> PathBuf {
+pub(crate) fn favorite_backup_path(path: &FsPath) -> PathBuf {
     let parent = path.parent().unwrap_or_else(|| FsPath::new("."));
     let file_name = path
         .file_name()
@@ -618,7 +625,7 @@ fn validate_favorite_file(mut data: FavoriteFile) -> io::Result<FavoriteFile> {
     Ok(data)
 }
 
-fn write_atomic(path: &FsPath, bytes: &[u8]) -> io::Result<()> {
+pub(crate) fn write_atomic(path: &FsPath, bytes: &[u8]) -> io::Result<()> {
     let parent = path.parent().ok_or_else(|| {
         io::Error::new(io::ErrorKind::InvalidInput, "favorite path has no parent")
     })?;
@@ -923,11 +930,30 @@ pub(crate) struct CatalogPlaylist {
     pub(crate) favorite_contributor_count: usize,
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct CatalogLocalTrack {
+    pub(crate) kind: FavoriteKind,
+    pub(crate) source: CatalogSource,
+    #[serde(flatten)]
+    pub(crate) track: LocalTrack,
+}
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(untagged)]
 pub(crate) enum CatalogItem {
     Track(CatalogTrack),
     Playlist(CatalogPlaylist),
+    LocalTrack(CatalogLocalTrack),
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CatalogSource {
+    #[default]
+    Spotify,
+    Local,
+    /// Echo playlists stored on this server.
+    Echo,
 }
 
 #[derive(Debug, Deserialize)]
@@ -936,6 +962,8 @@ pub(crate) struct CatalogSearchRequest {
     query: String,
     offset: usize,
     limit: usize,
+    #[serde(default)]
+    source: CatalogSource,
 }
 
 #[derive(Debug, Serialize)]
@@ -1004,7 +1032,7 @@ pub(crate) fn validate_selected_playlist_positions(
     Ok(canonical)
 }
 
-fn favorite_track(
+pub(crate) fn favorite_track(
     state: &AppState,
     actor_id: &str,
     summary: FavoriteSummary,
@@ -1045,6 +1073,33 @@ fn favorite_playlist(
     }
 }
 
+/// One Spotify search page, returned as the raw `tracks` or `playlists`
+/// container (`items`, `total`, ...).
+pub(crate) async fn spotify_catalog_search(
+    state: &AppState,
+    kind: FavoriteKind,
+    query: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<serde_json::Value, JamApiError> {
+    let url = format!(
+        "https://api.spotify.com/v1/search?q={}&type={}&offset={}&limit={}",
+        urlencoded(query),
+        kind.as_str(),
+        offset,
+        limit,
+    );
+    let mut data = spotify_json_request(state, reqwest::Method::GET, &url, None).await?;
+    let container_name = match kind {
+        FavoriteKind::Track => "tracks",
+        FavoriteKind::Playlist => "playlists",
+    };
+    Ok(data
+        .get_mut(container_name)
+        .map(serde_json::Value::take)
+        .unwrap_or(serde_json::Value::Null))
+}
+
 pub(crate) async fn jam_catalog_search(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -1069,19 +1124,31 @@ pub(crate) async fn jam_catalog_search(
             "query must contain between 1 and 250 characters",
         ));
     }
-    let url = format!(
-        "https://api.spotify.com/v1/search?q={}&type={}&offset={}&limit={}",
-        urlencoded(query),
-        payload.kind.as_str(),
-        payload.offset,
-        payload.limit,
-    );
-    let data = spotify_json_request(&state, reqwest::Method::GET, &url, None).await?;
-    let container_name = match payload.kind {
-        FavoriteKind::Track => "tracks",
-        FavoriteKind::Playlist => "playlists",
+    match payload.source {
+        CatalogSource::Local => return local_catalog_search(&state, &payload, query).map(Json),
+        CatalogSource::Echo => return echo_catalog_search(&state, &payload, query).map(Json),
+        CatalogSource::Spotify => {}
+    }
+    // Library tracks sit next to Spotify results on the first page, but only
+    // where they can be queued: in a Jam that plays from the local library.
+    let local_slots = if payload.kind == FavoriteKind::Track && state.jam_local_library.enabled() {
+        mixed_local_track_slots(
+            active_playback_source(&state, &actor.room),
+            payload.offset,
+            payload.limit,
+        )
+    } else {
+        0
+    };
+    let local = if local_slots > 0 {
+        state.jam_local_library.search(query, 0, local_slots).0
+    } else {
+        Vec::new()
     };
-    let container = data.get(container_name).unwrap_or(&serde_json::Value::Null);
+    // Mixed tracks count against `limit`; `total` and paging follow Spotify.
+    let spotify_limit = payload.limit - local.len();
+    let container =
+        spotify_catalog_search(&state, payload.kind, query, payload.offset, spotify_limit).await?;
     let total = container
         .get("total")
         .and_then(serde_json::Value::as_u64)
@@ -1100,7 +1167,8 @@ pub(crate) async fn jam_catalog_search(
             }),
         })
         .collect::<Vec<_>>();
-    let consumed = payload.offset.saturating_add(payload.limit);
+    let items = local_catalog_items(local).chain(items).collect();
+    let consumed = payload.offset.saturating_add(spotify_limit);
     let next_offset =
         (consumed <= MAX_CATALOG_OFFSET && consumed < total as usize).then_some(consumed);
     Ok(Json(CatalogPage {
@@ -1114,6 +1182,101 @@ pub(crate) async fn jam_catalog_search(
     }))
 }
 
+/// Playback source of the Jam running in `room`, if one is active.
+fn active_playback_source(state: &AppState, room: &str) -> Option<JamPlaybackSource> {
+    let scoped = state.existing_jam_room(room)?;
+    let jam = scoped.jam.lock().unwrap_or_else(|error| error.into_inner());
+    jam.active.then_some(jam.playback_source)
+}
+
+/// How many of the first page's `limit` slots go to library tracks. At least
+/// one slot stays with Spotify so its paging keeps advancing.
+fn mixed_local_track_slots(
+    playback_source: Option<JamPlaybackSource>,
+    offset: usize,
+    limit: usize,
+) -> usize {
+    if offset != 0 || playback_source != Some(JamPlaybackSource::Local) {
+        return 0;
+    }
+    MIXED_LOCAL_TRACKS_LIMIT.min(limit.saturating_sub(1))
+}
+
+fn local_catalog_items(tracks: Vec<LocalTrack>) -> impl Iterator<Item = CatalogItem> {
+    tracks.into_iter().map(|track| {
+        CatalogItem::LocalTrack(CatalogLocalTrack {
+            kind: FavoriteKind::Track,
+            source: CatalogSource::Local,
+            track,
+        })
+    })
+}
+
+fn local_catalog_search(
+    state: &AppState,
+    payload: &CatalogSearchRequest,
+    query: &str,
+) -> Result<CatalogPage, JamApiError> {
+    if !state.jam_local_library.enabled() {
+        return Err(JamApiError {
+            status: StatusCode::NOT_FOUND,
+            code: "local_library_unavailable",
+            message: "The local music library is not configured".to_string(),
+            retry_after: None,
+        });
+    }
+    if payload.kind != FavoriteKind::Track {
+        return Err(JamApiError::bad_request(
+            "the local music library only contains tracks",
+        ));
+    }
+    let (tracks, total) = state
+        .jam_local_library
+        .search(query, payload.offset, payload.limit);
+    let items = local_catalog_items(tracks).collect();
+    let consumed = payload.offset.saturating_add(payload.limit);
+    let next_offset = (consumed <= MAX_CATALOG_OFFSET && consumed < total).then_some(consumed);
+    Ok(CatalogPage {
+        schema_version: CATALOG_SCHEMA_VERSION,
+        kind: payload.kind,
+        items,
+        offset: payload.offset,
+        limit: payload.limit,
+        total: total as u64,
+        next_offset,
+    })
+}
+
+fn echo_catalog_search(
+    state: &AppState,
+    payload: &CatalogSearchRequest,
+    query: &str,
+) -> Result<CatalogPage, JamApiError> {
+    if payload.kind != FavoriteKind::Playlist {
+        return Err(JamApiError::bad_request(
+            "Echo catalog search only returns playlists",
+        ));
+    }
+    let (playlists, total) = state
+        .jam_echo_playlists
+        .search(query, payload.offset, payload.limit);
+    let items = playlists
+        .iter()
+        .map(|playlist| CatalogItem::Playlist(echo_catalog_playlist(playlist)))
+        .collect();
+    let consumed = payload.offset.saturating_add(payload.limit);
+    let next_offset = (consumed <= MAX_CATALOG_OFFSET && consumed < total).then_some(consumed);
+    Ok(CatalogPage {
+        schema_version: CATALOG_SCHEMA_VERSION,
+        kind: payload.kind,
+        items,
+        offset: payload.offset,
+        limit: payload.limit,
+        total: total as u64,
+        next_offset,
+    })
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct PlaylistItemsQuery {
     #[serde(default)]
@@ -1573,6 +1736,17 @@ pub(crate) async fn jam_playlist_items(
         message: "A current Echo participant token is required".to_string(),
         retry_after: None,
     })?;
+    if valid_echo_playlist_id(&playlist_id) {
+        validate_playlist_items_page(query.offset, query.limit)?;
+        return echo_playlist_items_page(
+            &state,
+            &actor.actor_id,
+            &playlist_id,
+            query.offset,
+            query.limit,
+        )
+        .map(Json);
+    }
     let summary = fetch_playlist_summary(&state, &playlist_id).await?;
     let (tracks, skipped, total, items_source) =
         fetch_playlist_items_page_with_source(&state, &summary, query.offset, query.limit).await?;
@@ -1978,12 +2152,7 @@ async fn hydrate_favorite_playlist_artwork(state: &AppState, views: &mut [Favori
     if missing.is_empty() {
         return;
     }
-    let spotify_connected = state
-        .jam
-        .lock()
-        .unwrap_or_else(|error| error.into_inner())
-        .spotify_token
-        .is_some();
+    let spotify_connected = current_spotify_token(state).is_some();
     if !spotify_connected {
         return;
     }
@@ -2321,8 +2490,8 @@ pub(crate) async fn jam_favorites_import_spotify(
         retry_after: None,
     })?;
     {
-        let jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
-        if jam.spotify_token.is_none() {
+        let token = current_spotify_token(&state);
+        if token.is_none() {
             return Err(JamApiError {
                 status: StatusCode::BAD_REQUEST,
                 code: "spotify_not_connected",
@@ -2330,7 +2499,7 @@ pub(crate) async fn jam_favorites_import_spotify(
                 retry_after: None,
             });
         }
-        if !spotify_library_scopes_authorized(jam.spotify_token.as_ref()) {
+        if !spotify_library_scopes_authorized(token.as_ref()) {
             return Err(spotify_library_scope_required_error());
         }
     }
@@ -2445,6 +2614,7 @@ mod tests {
         JamActor {
             actor_id: id.to_string(),
             display_name: name.to_string(),
+            room: "main".to_string(),
         }
     }
 
@@ -2485,6 +2655,26 @@ mod tests {
         assert!(!valid_spotify_id("0VjIjW4GlUZAMYd2vXMi3bb"));
     }
 
+    #[test]
+    fn library_tracks_are_mixed_in_only_where_they_can_be_queued() {
+        // A Spotify Jam refuses library tracks, and no Jam queues nothing.
+        for source in [None, Some(JamPlaybackSource::Spotify)] {
+            assert_eq!(mixed_local_track_slots(source, 0, MAX_SEARCH_LIMIT), 0);
+        }
+        let local = Some(JamPlaybackSource::Local);
+        assert_eq!(mixed_local_track_slots(local, 10, MAX_SEARCH_LIMIT), 0);
+        for limit in 1..=MAX_SEARCH_LIMIT {
+            let slots = mixed_local_track_slots(local, 0, limit);
+            assert!(slots <= MIXED_LOCAL_TRACKS_LIMIT);
+            // The page never outgrows `limit` and Spotify keeps a slot.
+            assert!(slots < limit, "limit {limit}");
+        }
+        assert_eq!(
+            mixed_local_track_slots(local, 0, MAX_SEARCH_LIMIT),
+            MIXED_LOCAL_TRACKS_LIMIT
+        );
+    }
+
     #[test]
     fn playlist_and_track_normalization_use_canonical_fields() {
         let track = normalize_track(&track_json(ID_A, "Song")).unwrap();
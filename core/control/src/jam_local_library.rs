@@ -0,0 +1,837 @@
+//! Server-side music library indexed from `CORE_JAM_LOCAL_LIBRARY_DIR`.
+//!
+//! Library tracks are decoded in-process and published to the Jam relay as
+//! source audio for the active generation, so a library Jam needs neither
+//! Spotify nor the interactive-session source agent.
+
+use crate::auth::ensure_admin;
+use crate::config::now_ts_ms;
+use crate::jam_session::{
+    begin_local_queue_track, finish_local_queue_track, update_local_queue_progress, LocalQueueStep,
+};
+use crate::AppState;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path as FsPath, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+    time::Duration,
+};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader},
+    io::MediaSourceStream,
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey},
+    probe::Hint,
+};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+pub(crate) const LOCAL_TRACK_URI_PREFIX: &str = "echo-local:track:";
+const LOCAL_TRACK_ID_LEN: usize = 24;
+const LOCAL_LIBRARY_EXTENSIONS: [&str; 7] = ["mp3", "flac", "ogg", "oga", "wav", "m4a", "aac"];
+const MAX_LOCAL_LIBRARY_TRACKS: usize = 20_000;
+const MAX_LOCAL_LIBRARY_DEPTH: usize = 16;
+const MAX_LOCAL_ARTWORK_BYTES: usize = 4 * 1024 * 1024;
+const MAX_LOCAL_TAG_CHARS: usize = 200;
+// Decoded audio is published slightly ahead of real time so the relay's 20 ms
+// framing never starves, but never so far ahead that Skip feels delayed.
+const LOCAL_PLAYBACK_LEAD: Duration = Duration::from_millis(250);
+const LOCAL_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+const LOCAL_IDLE_RECHECK: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct LocalTrack {
+    pub(crate) local_id: String,
+    pub(crate) uri: String,
+    pub(crate) relative_path: String,
+    pub(crate) name: String,
+    pub(crate) artist: String,
+    pub(crate) album: String,
+    pub(crate) duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) artwork_url: Option<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+#[derive(Default)]
+struct LocalIndex {
+    tracks: Vec<LocalTrack>,
+    by_id: HashMap<String, usize>,
+    scanned_at_ms: Option<u64>,
+}
+
+pub(crate) struct LocalLibrary {
+    root: Option<PathBuf>,
+    index: RwLock<LocalIndex>,
+    revision: AtomicU64,
+}
+
+impl LocalLibrary {
+    pub(crate) fn new(root: Option<PathBuf>) -> Self {
+        Self {
+            root,
+            index: RwLock::new(LocalIndex::default()),
+            revision: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.root.is_some()
+    }
+
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Acquire)
+    }
+
+    /// Rebuild the index from disk. The previous index stays readable until
+    /// the complete replacement is swapped in.
+    pub(crate) fn rescan(&self) -> io::Result<usize> {
+        let Some(root) = self.root.as_deref() else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "local music library is not configured",
+            ));
+        };
+        let mut files = Vec::new();
+        collect_audio_files(root, 0, &mut files)?;
+        files.sort();
+        let mut tracks = files
+            .iter()
+            .filter_map(|path| match probe_local_track(root, path) {
+                Ok(track) => Some(track),
+                Err(error) => {
+                    warn!(
+                        "Skipping unreadable local library file {:?}: {}",
+                        path, error
+                    );
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        tracks.sort_by(|left, right| {
+            left.artist
+                .to_lowercase()
+                .cmp(&right.artist.to_lowercase())
+                .then_with(|| left.album.to_lowercase().cmp(&right.album.to_lowercase()))
+                .then_with(|| left.relative_path.cmp(&right.relative_path))
+        });
+        let by_id = tracks
+            .iter()
+            .enumerate()
+            .map(|(position, track)| (track.local_id.clone(), position))
+            .collect();
+        let count = tracks.len();
+        let mut index = self
+            .index
+            .write()
+            .unwrap_or_else(|error| error.into_inner());
+        *index = LocalIndex {
+            tracks,
+            by_id,
+            scanned_at_ms: Some(now_ts_ms()),
+        };
+        self.revision.fetch_add(1, Ordering::AcqRel);
+        Ok(count)
+    }
+
+    pub(crate) fn get(&self, local_id: &str) -> Option<LocalTrack> {
+        let index = self.index.read().unwrap_or_else(|error| error.into_inner());
+        index
+            .by_id
+            .get(local_id)
+            .and_then(|position| index.tracks.get(*position))
+            .cloned()
+    }
+
+    /// Case-insensitive search where every whitespace-separated term must
+    /// appear in the title, artist, album, or library-relative path.
+    pub(crate) fn search(
+        &self,
+        query: &str,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<LocalTrack>, usize) {
+        let terms = query
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        let index = self.index.read().unwrap_or_else(|error| error.into_inner());
+        let matches = index
+            .tracks
+            .iter()
+            .filter(|track| {
+                let haystack = format!(
+                    "{}\n{}\n{}\n{}",
+                    track.name, track.artist, track.album, track.relative_path
+                )
+                .to_lowercase();
+                terms.iter().all(|term| haystack.contains(term.as_str()))
+            })
+            .collect::<Vec<_>>();
+        let total = matches.len();
+        let page = matches
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect();
+        (page, total)
+    }
+
+    fn artwork(&self, local_id: &str) -> io::Result<Option<(String, Vec<u8>)>> {
+        let Some(track) = self.get(local_id) else {
+            return Ok(None);
+        };
+        let mut probed = probe_file(&track.path).map_err(symphonia_io_error)?;
+        let mut artwork = probed
+            .metadata
+            .get()
+            .and_then(|metadata| metadata.current().and_then(revision_artwork));
+        if artwork.is_none() {
+            artwork = probed
+                .format
+                .metadata()
+                .current()
+                .and_then(revision_artwork);
+        }
+        Ok(artwork)
+    }
+}
+
+pub(crate) fn local_track_uri(local_id: &str) -> String {
+    format!("{LOCAL_TRACK_URI_PREFIX}{local_id}")
+}
+
+pub(crate) fn valid_local_track_id(local_id: &str) -> bool {
+    local_id.len() == LOCAL_TRACK_ID_LEN
+        && local_id
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
+
+pub(crate) fn local_track_id_from_uri(uri: &str) -> Option<&str> {
+    uri.strip_prefix(LOCAL_TRACK_URI_PREFIX)
+        .filter(|local_id| valid_local_track_id(local_id))
+}
+
+fn local_track_id(relative_path: &str) -> String {
+    let digest = Sha256::digest(relative_path.as_bytes());
+    digest[..LOCAL_TRACK_ID_LEN / 2]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn collect_audio_files(dir: &FsPath, depth: usize, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if depth > MAX_LOCAL_LIBRARY_DEPTH {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        if files.len() >= MAX_LOCAL_LIBRARY_TRACKS {
+            warn!(
+                "Local music library index is capped at {} tracks",
+                MAX_LOCAL_LIBRARY_TRACKS
+            );
+            return Ok(());
+        }
+        let entry = entry?;
+        // Symlinks are not followed, so the index cannot escape the library
+        // root or loop through a cyclic link.
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_audio_files(&path, depth + 1, files)?;
+        } else if file_type.is_file() && has_audio_extension(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn has_audio_extension(path: &FsPath) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| {
+            LOCAL_LIBRARY_EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+        .unwrap_or(false)
+}
+
+fn relative_library_path(root: &FsPath, path: &FsPath) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts = relative
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("/"))
+}
+
+fn probe_file(path: &FsPath) -> Result<symphonia::core::probe::ProbeResult, SymphoniaError> {
+    let file = fs::File::open(path)?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    symphonia::default::get_probe().format(
+        &hint,
+        stream,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )
+}
+
+fn symphonia_io_error(error: SymphoniaError) -> io::Error {
+    match error {
+        SymphoniaError::IoError(error) => error,
+        other => io::Error::new(io::ErrorKind::InvalidData, other.to_string()),
+    }
+}
+
+#[derive(Default)]
+struct LocalTags {
+    title: Option<String>,
+    artist: Option<String>,
+    album_artist: Option<String>,
+    album: Option<String>,
+    has_artwork: bool,
+}
+
+impl LocalTags {
+    fn absorb(&mut self, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            let value = bounded_tag(&tag.value.to_string());
+            if value.is_empty() {
+                continue;
+            }
+            let slot = match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => &mut self.title,
+                Some(StandardTagKey::Artist) => &mut self.artist,
+                Some(StandardTagKey::AlbumArtist) => &mut self.album_artist,
+                Some(StandardTagKey::Album) => &mut self.album,
+                _ => continue,
+            };
+            slot.get_or_insert(value);
+        }
+        self.has_artwork |= revision_artwork(revision).is_some();
+    }
+}
+
+fn bounded_tag(value: &str) -> String {
+    value
+        .chars()
+        .filter(|character| !character.is_control())
+        .take(MAX_LOCAL_TAG_CHARS)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn revision_artwork(revision: &MetadataRevision) -> Option<(String, Vec<u8>)> {
+    revision
+        .visuals()
+        .iter()
+        .find(|visual| {
+            visual.media_type.starts_with("image/")
+                && !visual.data.is_empty()
+                && visual.data.len() <= MAX_LOCAL_ARTWORK_BYTES
+        })
+        .map(|visual| (visual.media_type.clone(), visual.data.to_vec()))
+}
+
+fn probe_local_track(root: &FsPath, path: &FsPath) -> io::Result<LocalTrack> {
+    let relative_path = relative_library_path(root, path)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "path is not valid UTF-8"))?;
+    let mut probed = probe_file(path).map_err(symphonia_io_error)?;
+    let mut tags = LocalTags::default();
+    if let Some(metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.current() {
+            tags.absorb(revision);
+        }
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        tags.absorb(revision);
+    }
+    let duration_ms = probed
+        .format
+        .default_track()
+        .and_then(|track| {
+            let params = &track.codec_params;
+            let sample_rate = u64::from(params.sample_rate?);
+            (sample_rate > 0)
+                .then(|| params.n_frames.unwrap_or(0).saturating_mul(1_000) / sample_rate)
+        })
+        .unwrap_or(0);
+    let local_id = local_track_id(&relative_path);
+    let fallback_name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(bounded_tag)
+        .unwrap_or_default();
+    Ok(LocalTrack {
+        uri: local_track_uri(&local_id),
+        artwork_url: tags
+            .has_artwork
+            .then(|| format!("/api/jam/local/artwork/{local_id}")),
+        local_id,
+        relative_path,
+        name: tags.title.unwrap_or(fallback_name),
+        artist: tags.artist.or(tags.album_artist).unwrap_or_default(),
+        album: tags.album.unwrap_or_default(),
+        duration_ms,
+        path: path.to_path_buf(),
+    })
+}
+
+struct DecodedChunk {
+    sample_rate: u32,
+    channels: u32,
+    samples: Vec<f32>,
+}
+
+/// Decode one file into interleaved f32 chunks. Returns when the file ends or
+/// the receiver is dropped by a skip, stop, or Jam teardown.
+fn decode_local_track(path: &FsPath, chunks: mpsc::Sender<DecodedChunk>) -> Result<(), String> {
+    let probed = probe_file(path).map_err(|error| error.to_string())?;
+    let mut format: Box<dyn FormatReader> = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| "file has no decodable audio track".to_string())?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|error| error.to_string())?;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(());
+            }
+            Err(SymphoniaError::ResetRequired) => return Ok(()),
+            Err(error) => return Err(error.to_string()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet is dropped; the rest of the file still plays.
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(error) => return Err(error.to_string()),
+        };
+        let spec = *decoded.spec();
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        if buffer.samples().is_empty() {
+            continue;
+        }
+        let chunk = DecodedChunk {
+            sample_rate: spec.rate,
+            channels: spec.channels.count() as u32,
+            samples: buffer.samples().to_vec(),
+        };
+        if chunks.blocking_send(chunk).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+#[derive(Debug)]
+enum LocalPlayerCommand {
+    Wake,
+    Interrupt { queue_entry_id: String },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum LocalPlaybackOutcome {
+    Finished,
+    Interrupted,
+    Failed(String),
+}
+
+/// Per-generation player that drains library entries from the Jam queue.
+pub(crate) struct LocalPlayer {
+    generation: u64,
+    commands: mpsc::UnboundedSender<LocalPlayerCommand>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl LocalPlayer {
+    pub(crate) fn start(state: AppState, generation: u64) -> Self {
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(local_player_loop(state, generation, command_rx));
+        Self {
+            generation,
+            commands,
+            task: Some(task),
+        }
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub(crate) fn wake(&self) {
+        let _ = self.commands.send(LocalPlayerCommand::Wake);
+    }
+
+    /// Stop the exact queue occurrence if it is still the one playing.
+    pub(crate) fn interrupt(&self, queue_entry_id: String) {
+        let _ = self
+            .commands
+            .send(LocalPlayerCommand::Interrupt { queue_entry_id });
+    }
+
+    pub(crate) async fn stop(mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+            let _ = task.await;
+        }
+        info!("[jam-local] player stopped generation={}", self.generation);
+    }
+}
+
+async fn local_player_loop(
+    state: AppState,
+    generation: u64,
+    mut commands: mpsc::UnboundedReceiver<LocalPlayerCommand>,
+) {
+    info!("[jam-local] player started generation={}", generation);
+    loop {
+        let step = {
+            let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+            begin_local_queue_track(&mut jam, generation)
+        };
+        let track = match step {
+            LocalQueueStep::Ended => return,
+            LocalQueueStep::Idle => {
+                tokio::select! {
+                    command = commands.recv() => if command.is_none() { return },
+                    _ = tokio::time::sleep(LOCAL_IDLE_RECHECK) => {}
+                }
+                continue;
+            }
+            LocalQueueStep::Play(track) => *track,
+        };
+
+        let library_track = local_track_id_from_uri(&track.spotify_uri)
+            .and_then(|local_id| state.jam_local_library.get(local_id));
+        let outcome = match library_track {
+            Some(library_track) => {
+                let history = std::sync::Arc::clone(&state.jam_history);
+                let history_track = track.clone();
+                let room = std::sync::Arc::clone(&state.jam_room);
+                let played_at_ms = now_ts_ms();
+                tokio::task::spawn_blocking(move || {
+                    if let Err(error) =
+                        history.append_observation(&history_track, &room, played_at_ms)
+                    {
+                        warn!("Jam history observation could not be persisted: {}", error);
+                    }
+                });
+                play_local_track(
+                    &state,
+                    generation,
+                    &track.queue_entry_id,
+                    &library_track,
+                    &mut commands,
+                )
+                .await
+            }
+            None => LocalPlaybackOutcome::Failed(format!(
+                "'{}' is no longer in the local music library",
+                track.name
+            )),
+        };
+        if let LocalPlaybackOutcome::Failed(error) = &outcome {
+            warn!(
+                "[jam-local] playback failed generation={}: {}",
+                generation, error
+            );
+        }
+        let error = match outcome {
+            LocalPlaybackOutcome::Failed(error) => Some(error),
+            LocalPlaybackOutcome::Finished | LocalPlaybackOutcome::Interrupted => None,
+        };
+        let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+        if !finish_local_queue_track(&mut jam, generation, &track.queue_entry_id, error) {
+            return;
+        }
+    }
+}
+
+async fn play_local_track(
+    state: &AppState,
+    generation: u64,
+    queue_entry_id: &str,
+    track: &LocalTrack,
+    commands: &mut mpsc::UnboundedReceiver<LocalPlayerCommand>,
+) -> LocalPlaybackOutcome {
+    let (chunk_tx, mut chunk_rx) = mpsc::channel::<DecodedChunk>(8);
+    let path = track.path.clone();
+    let decoder = tokio::task::spawn_blocking(move || decode_local_track(&path, chunk_tx));
+    let started = tokio::time::Instant::now();
+    let mut published = Duration::ZERO;
+    let mut last_progress = Duration::ZERO;
+    let outcome = loop {
+        let chunk = tokio::select! {
+            chunk = chunk_rx.recv() => chunk,
+            command = commands.recv() => match command {
+                Some(LocalPlayerCommand::Interrupt { queue_entry_id: target })
+                    if target == queue_entry_id => break LocalPlaybackOutcome::Interrupted,
+                Some(_) => continue,
+                None => break LocalPlaybackOutcome::Interrupted,
+            },
+        };
+        let Some(chunk) = chunk else {
+            break LocalPlaybackOutcome::Finished;
+        };
+        let channels = chunk.channels.max(1);
+        let frames = chunk.samples.len() as u64 / u64::from(channels);
+        if chunk.sample_rate > 0 {
+            published += Duration::from_micros(frames * 1_000_000 / u64::from(chunk.sample_rate));
+        }
+        state.jam_source.publish_local_audio(
+            generation,
+            chunk.sample_rate,
+            channels,
+            chunk.samples,
+        );
+        if published.saturating_sub(last_progress) >= LOCAL_PROGRESS_INTERVAL {
+            last_progress = published;
+            let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+            update_local_queue_progress(
+                &mut jam,
+                generation,
+                queue_entry_id,
+                published.as_millis().min(u64::MAX as u128) as u64,
+            );
+        }
+        let deadline = started + published.saturating_sub(LOCAL_PLAYBACK_LEAD);
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => {}
+            command = commands.recv() => match command {
+                Some(LocalPlayerCommand::Interrupt { queue_entry_id: target })
+                    if target == queue_entry_id => break LocalPlaybackOutcome::Interrupted,
+                Some(_) => {}
+                None => break LocalPlaybackOutcome::Interrupted,
+            },
+        }
+    };
+    drop(chunk_rx);
+    match decoder.await {
+        Ok(Err(error)) if outcome == LocalPlaybackOutcome::Finished => {
+            LocalPlaybackOutcome::Failed(error)
+        }
+        Err(error) if outcome == LocalPlaybackOutcome::Finished => {
+            LocalPlaybackOutcome::Failed(format!("local decoder task failed: {error}"))
+        }
+        _ => outcome,
+    }
+}
+
+pub(crate) async fn jam_local_artwork(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(local_id): Path<String>,
+) -> Result<Response, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    if !valid_local_track_id(&local_id) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let library = std::sync::Arc::clone(&state.jam_local_library);
+    let artwork = tokio::task::spawn_blocking(move || library.artwork(&local_id))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|_| StatusCode::NOT_FOUND)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let (mime, bytes) = artwork;
+    let mut response = bytes.into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(&mime)
+            .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream")),
+    );
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, max-age=3600"),
+    );
+    Ok(response)
+}
+
+pub(crate) async fn jam_local_rescan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    if !state.jam_local_library.enabled() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let library = std::sync::Arc::clone(&state.jam_local_library);
+    let count = tokio::task::spawn_blocking(move || library.rescan())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|error| {
+            warn!("Local music library rescan failed: {}", error);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let scanned_at_ms = state
+        .jam_local_library
+        .index
+        .read()
+        .unwrap_or_else(|error| error.into_inner())
+        .scanned_at_ms;
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "track_count": count,
+        "revision": state.jam_local_library.revision(),
+        "scanned_at_ms": scanned_at_ms,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::random_secret;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("echo-jam-local-{label}-{}", random_secret()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_wav(path: &FsPath, sample_rate: u32, channels: u16, frames: u32) {
+        let data_len = frames * u32::from(channels) * 2;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16_u32.to_le_bytes());
+        bytes.extend_from_slice(&1_u16.to_le_bytes());
+        bytes.extend_from_slice(&channels.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * u32::from(channels) * 2).to_le_bytes());
+        bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+        bytes.extend_from_slice(&16_u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for frame in 0..frames * u32::from(channels) {
+            let sample = ((frame % 64) as i16 - 32) * 256;
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn local_track_uris_only_accept_indexed_id_shape() {
+        let local_id = local_track_id("Artist/Album/01 Song.flac");
+        assert!(valid_local_track_id(&local_id));
+        assert_eq!(
+            local_track_id_from_uri(&local_track_uri(&local_id)),
+            Some(local_id.as_str())
+        );
+        assert_eq!(
+            local_track_id_from_uri("spotify:track:4uLU6hMCjMI75M1A2tKUQC"),
+            None
+        );
+        assert_eq!(
+            local_track_id_from_uri("echo-local:track:../../etc/passwd"),
+            None
+        );
+        assert_eq!(
+            local_track_id_from_uri(&format!(
+                "{LOCAL_TRACK_URI_PREFIX}{}",
+                local_id.to_uppercase()
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn scan_indexes_nested_audio_with_stable_ids_and_file_stem_titles() {
+        let root = temp_dir("scan");
+        fs::create_dir_all(root.join("Mixes")).unwrap();
+        write_wav(&root.join("Mixes").join("Late Night.wav"), 8_000, 2, 8_000);
+        fs::write(root.join("notes.txt"), b"not audio").unwrap();
+        fs::write(root.join("broken.mp3"), b"not really an mp3").unwrap();
+
+        let library = LocalLibrary::new(Some(root.clone()));
+        assert_eq!(library.rescan().unwrap(), 1);
+        let (tracks, total) = library.search("late NIGHT", 0, 10);
+        assert_eq!(total, 1);
+        let track = &tracks[0];
+        assert_eq!(track.name, "Late Night");
+        assert_eq!(track.relative_path, "Mixes/Late Night.wav");
+        assert_eq!(track.duration_ms, 1_000);
+        assert_eq!(track.artwork_url, None);
+        assert_eq!(track.local_id, local_track_id("Mixes/Late Night.wav"));
+        assert_eq!(library.get(&track.local_id).unwrap().uri, track.uri);
+        assert_eq!(library.search("mixes night", 0, 10).1, 1);
+        assert_eq!(library.search("daytime", 0, 10).1, 0);
+
+        let first_revision = library.revision();
+        library.rescan().unwrap();
+        assert!(library.revision() > first_revision);
+        assert!(library.get(&track.local_id).is_some());
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn unconfigured_library_refuses_to_scan() {
+        let library = LocalLibrary::new(None);
+        assert!(!library.enabled());
+        assert_eq!(
+            library.rescan().unwrap_err().kind(),
+            io::ErrorKind::NotConnected
+        );
+        assert_eq!(library.search("anything", 0, 10).1, 0);
+    }
+
+    #[test]
+    fn decoder_emits_interleaved_chunks_for_the_whole_file() {
+        let root = temp_dir("decode");
+        let path = root.join("tone.wav");
+        write_wav(&path, 48_000, 2, 4_800);
+        let (chunk_tx, mut chunk_rx) = mpsc::channel(1_024);
+        decode_local_track(&path, chunk_tx).unwrap();
+        let mut samples = 0;
+        while let Ok(chunk) = chunk_rx.try_recv() {
+            assert_eq!(chunk.sample_rate, 48_000);
+            assert_eq!(chunk.channels, 2);
+            samples += chunk.samples.len();
+        }
+        assert_eq!(samples, 9_600);
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn decoder_stops_quietly_when_playback_is_abandoned() {
+        let root = temp_dir("abandon");
+        let path = root.join("tone.wav");
+        write_wav(&path, 48_000, 2, 48_000);
+        let (chunk_tx, chunk_rx) = mpsc::channel(1);
+        drop(chunk_rx);
+        assert_eq!(decode_local_track(&path, chunk_tx), Ok(()));
+        fs::remove_dir_all(root).ok();
+    }
+}
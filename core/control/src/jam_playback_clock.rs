@@ -0,0 +1,330 @@
+//! Server clock to track position model for the Jam relay.
+//!
+//! Spotify reports `progress_ms` only when it is polled, and those reports
+//! carry request latency. Listeners instead render against one published
+//! anchor: a track position at a server timestamp. Between observations the
+//! position advances with the audio the relay has actually sent, so a stalled
+//! source stops the clock instead of running ahead of what listeners hear.
+//! Each new Spotify or local-player observation corrects it; small drift is
+//! blended in, and a jump past `SEEK_THRESHOLD_MS` re-anchors and bumps
+//! `revision` so clients snap instead of easing.
+//!
+//! Like skip votes, the clock observes `now_playing` lazily when state is
+//! published rather than hooking every place playback advances.
+
+use crate::config::now_ts_ms;
+use crate::jam_session::{JamState, NowPlayingInfo};
+
+use serde::Serialize;
+
+/// A difference this large between the model and an observation is a seek or
+/// a missed track change, not drift.
+const SEEK_THRESHOLD_MS: i64 = 2_000;
+/// Observation jitter from Spotify request latency is ignored below this.
+const DRIFT_DEADBAND_MS: i64 = 150;
+/// Fraction of the remaining drift applied per observation.
+const DRIFT_CORRECTION_DIVISOR: i64 = 4;
+/// The relay may briefly run ahead of the wall clock when a source flushes
+/// buffered audio; anything beyond this is clamped.
+const RELAY_LEAD_ALLOWANCE_MS: u64 = 1_000;
+
+#[derive(Clone, Debug)]
+struct ClockAnchor {
+    track_key: String,
+    spotify_id: String,
+    duration_ms: u64,
+    playing: bool,
+    server_ms: u64,
+    position_ms: u64,
+    relay_ms: Option<u64>,
+    observed_at_ms: Option<u64>,
+    last_correction_ms: i64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PlaybackClock {
+    anchor: Option<ClockAnchor>,
+    revision: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct PlaybackClockSnapshot {
+    pub(crate) spotify_id: String,
+    pub(crate) position_ms: u64,
+    pub(crate) server_time_ms: u64,
+    pub(crate) duration_ms: u64,
+    pub(crate) playing: bool,
+    /// Track milliseconds per server millisecond: 1 while playing, else 0.
+    pub(crate) rate: f64,
+    pub(crate) revision: u64,
+    /// `relay` while relayed audio drives the clock, `wall` otherwise.
+    pub(crate) timing_source: &'static str,
+    pub(crate) last_correction_ms: i64,
+}
+
+fn track_key(now_playing: &NowPlayingInfo) -> String {
+    if now_playing.spotify_uri.is_empty() {
+        format!("{}\u{1f}{}", now_playing.name, now_playing.artist)
+    } else {
+        now_playing.spotify_uri.clone()
+    }
+}
+
+impl ClockAnchor {
+    /// Milliseconds of playback since the anchor, measured in relayed audio
+    /// when both ends of the interval have a relay reading.
+    fn elapsed_ms(&self, now_ms: u64, relay_ms: Option<u64>) -> (u64, bool) {
+        let wall = now_ms.saturating_sub(self.server_ms);
+        match (self.relay_ms, relay_ms) {
+            (Some(anchor), Some(relay)) if relay >= anchor => {
+                ((relay - anchor).min(wall + RELAY_LEAD_ALLOWANCE_MS), true)
+            }
+            _ => (wall, false),
+        }
+    }
+
+    fn position_at(&self, now_ms: u64, relay_ms: Option<u64>) -> (u64, bool) {
+        if !self.playing {
+            return (self.position_ms, false);
+        }
+        let (elapsed, relay) = self.elapsed_ms(now_ms, relay_ms);
+        let position = self.position_ms.saturating_add(elapsed);
+        let position = if self.duration_ms > 0 {
+            position.min(self.duration_ms)
+        } else {
+            position
+        };
+        (position, relay)
+    }
+}
+
+impl PlaybackClock {
+    /// Fold the current now-playing observation into the model. `now_ms` is
+    /// the server wall clock and `relay_ms` the audio time the relay has sent
+    /// for this generation, when a relay is running.
+    pub(crate) fn observe(
+        &mut self,
+        now_playing: Option<&NowPlayingInfo>,
+        now_ms: u64,
+        relay_ms: Option<u64>,
+    ) {
+        let Some(now_playing) = now_playing else {
+            if self.anchor.take().is_some() {
+                self.revision += 1;
+            }
+            return;
+        };
+        let observed_at_ms = now_playing
+            .fetched_at
+            .map(|fetched_at| now_ms.saturating_sub(fetched_at.elapsed().as_millis() as u64));
+        self.observe_at(now_playing, observed_at_ms, now_ms, relay_ms);
+    }
+
+    fn observe_at(
+        &mut self,
+        now_playing: &NowPlayingInfo,
+        observed_at_ms: Option<u64>,
+        now_ms: u64,
+        relay_ms: Option<u64>,
+    ) {
+        let key = track_key(now_playing);
+        // The observed position, carried forward from when it was fetched.
+        let observed_position = if now_playing.is_playing {
+            now_playing
+                .progress_ms
+                .saturating_add(now_ms.saturating_sub(observed_at_ms.unwrap_or(now_ms)))
+        } else {
+            now_playing.progress_ms
+        };
+        let observed_position = if now_playing.duration_ms > 0 {
+            observed_position.min(now_playing.duration_ms)
+        } else {
+            observed_position
+        };
+
+        let (position_ms, correction, discontinuity) = match &self.anchor {
+            Some(anchor) if anchor.track_key == key => {
+                if observed_at_ms.is_some() && anchor.observed_at_ms == observed_at_ms {
+                    return;
+                }
+                let (predicted, _) = anchor.position_at(now_ms, relay_ms);
+                let drift = observed_position as i64 - predicted as i64;
+                if drift.abs() >= SEEK_THRESHOLD_MS {
+                    (observed_position, drift, true)
+                } else if anchor.playing != now_playing.is_playing {
+                    (observed_position, drift, false)
+                } else if drift.abs() < DRIFT_DEADBAND_MS {
+                    (predicted, 0, false)
+                } else {
+                    let correction = drift / DRIFT_CORRECTION_DIVISOR;
+                    (
+                        predicted.saturating_add_signed(correction),
+                        correction,
+                        false,
+                    )
+                }
+            }
+            _ => (observed_position, 0, true),
+        };
+        if discontinuity {
+            self.revision += 1;
+        }
+        self.anchor = Some(ClockAnchor {
+            track_key: key,
+            spotify_id: now_playing.spotify_id.clone(),
+            duration_ms: now_playing.duration_ms,
+            playing: now_playing.is_playing,
+            server_ms: now_ms,
+            position_ms,
+            relay_ms,
+            observed_at_ms,
+            last_correction_ms: correction,
+        });
+    }
+
+    pub(crate) fn snapshot(
+        &self,
+        now_ms: u64,
+        relay_ms: Option<u64>,
+    ) -> Option<PlaybackClockSnapshot> {
+        let anchor = self.anchor.as_ref()?;
+        let (position_ms, relay) = anchor.position_at(now_ms, relay_ms);
+        Some(PlaybackClockSnapshot {
+            spotify_id: anchor.spotify_id.clone(),
+            position_ms,
+            server_time_ms: now_ms,
+            duration_ms: anchor.duration_ms,
+            playing: anchor.playing,
+            rate: if anchor.playing { 1.0 } else { 0.0 },
+            revision: self.revision,
+            timing_source: if relay { "relay" } else { "wall" },
+            last_correction_ms: anchor.last_correction_ms,
+        })
+    }
+}
+
+/// Observe the room's current track and return the model to publish, or
+/// `None` when `generation` is not the running Jam or nothing is playing.
+pub(crate) fn publish_playback_clock(
+    jam: &mut JamState,
+    generation: u64,
+    relay_ms: Option<u64>,
+) -> Option<PlaybackClockSnapshot> {
+    if !jam.active || jam.generation != generation {
+        return None;
+    }
+    let now_ms = now_ts_ms();
+    jam.playback_clock
+        .observe(jam.now_playing.as_ref(), now_ms, relay_ms);
+    jam.playback_clock.snapshot(now_ms, relay_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playing(uri: &str, progress_ms: u64) -> NowPlayingInfo {
+        NowPlayingInfo {
+            spotify_id: uri.trim_start_matches("spotify:track:").to_string(),
+            spotify_uri: uri.to_string(),
+            spotify_url: String::new(),
+            name: "Late Night".to_string(),
+            artist: "The Echoes".to_string(),
+            album_art_url: String::new(),
+            duration_ms: 200_000,
+            progress_ms,
+            is_playing: true,
+            fetched_at: None,
+        }
+    }
+
+    const TRACK_A: &str = "spotify:track:0VjIjW4GlUZAMYd2vXMi3b";
+    const TRACK_B: &str = "spotify:track:3n3Ppam7vgaVa1iaRUc9Lp";
+
+    #[test]
+    fn clock_advances_with_relayed_audio_and_falls_back_to_the_wall_clock() {
+        let mut clock = PlaybackClock::default();
+        clock.observe_at(&playing(TRACK_A, 10_000), Some(1_000), 1_000, Some(50_000));
+        let snapshot = clock.snapshot(4_000, Some(52_000)).unwrap();
+        // The relay only sent two seconds of the three wall-clock seconds.
+        assert_eq!(snapshot.position_ms, 12_000);
+        assert_eq!(snapshot.timing_source, "relay");
+        assert_eq!(snapshot.server_time_ms, 4_000);
+        assert_eq!(snapshot.rate, 1.0);
+
+        // A relay flush cannot run more than the allowance past the wall clock.
+        assert_eq!(
+            clock.snapshot(2_000, Some(60_000)).unwrap().position_ms,
+            12_000
+        );
+
+        let wall = clock.snapshot(4_000, None).unwrap();
+        assert_eq!(wall.position_ms, 13_000);
+        assert_eq!(wall.timing_source, "wall");
+        assert_eq!(clock.snapshot(900_000, None).unwrap().position_ms, 200_000);
+    }
+
+    #[test]
+    fn observations_blend_small_drift_and_snap_on_seeks_and_track_changes() {
+        let mut clock = PlaybackClock::default();
+        clock.observe_at(&playing(TRACK_A, 10_000), Some(0), 0, None);
+        let revision = clock.snapshot(0, None).unwrap().revision;
+
+        // Jitter inside the deadband leaves the model alone.
+        clock.observe_at(&playing(TRACK_A, 15_100), Some(5_000), 5_000, None);
+        let snapshot = clock.snapshot(5_000, None).unwrap();
+        assert_eq!(snapshot.position_ms, 15_000);
+        assert_eq!(snapshot.last_correction_ms, 0);
+
+        // Real drift is corrected a quarter at a time.
+        clock.observe_at(&playing(TRACK_A, 20_800), Some(10_000), 10_000, None);
+        let snapshot = clock.snapshot(10_000, None).unwrap();
+        assert_eq!(snapshot.position_ms, 20_200);
+        assert_eq!(snapshot.last_correction_ms, 200);
+        assert_eq!(snapshot.revision, revision);
+
+        // Re-reading the same observation is not a new correction.
+        clock.observe_at(&playing(TRACK_A, 20_800), Some(10_000), 11_000, None);
+        assert_eq!(clock.snapshot(11_000, None).unwrap().position_ms, 21_200);
+
+        // A seek re-anchors on the observation.
+        clock.observe_at(&playing(TRACK_A, 90_000), Some(12_000), 12_500, None);
+        let snapshot = clock.snapshot(12_500, None).unwrap();
+        assert_eq!(snapshot.position_ms, 90_500);
+        assert_eq!(snapshot.revision, revision + 1);
+
+        clock.observe_at(&playing(TRACK_B, 0), Some(13_000), 13_000, None);
+        let snapshot = clock.snapshot(13_000, None).unwrap();
+        assert_eq!(snapshot.spotify_id, "3n3Ppam7vgaVa1iaRUc9Lp");
+        assert_eq!(snapshot.position_ms, 0);
+        assert_eq!(snapshot.revision, revision + 2);
+    }
+
+    #[test]
+    fn paused_tracks_hold_their_position_and_a_cleared_track_clears_the_clock() {
+        let mut clock = PlaybackClock::default();
+        let mut paused = playing(TRACK_A, 42_000);
+        paused.is_playing = false;
+        clock.observe_at(&paused, Some(0), 0, Some(0));
+        let snapshot = clock.snapshot(30_000, Some(30_000)).unwrap();
+        assert_eq!(snapshot.position_ms, 42_000);
+        assert!(!snapshot.playing);
+        assert_eq!(snapshot.rate, 0.0);
+
+        clock.observe_at(
+            &playing(TRACK_A, 42_000),
+            Some(31_000),
+            31_000,
+            Some(30_000),
+        );
+        assert_eq!(
+            clock.snapshot(32_000, Some(31_000)).unwrap().position_ms,
+            43_000
+        );
+
+        let revision = clock.revision;
+        clock.observe(None, 33_000, None);
+        assert!(clock.snapshot(33_000, None).is_none());
+        assert_eq!(clock.revision, revision + 1);
+    }
+}
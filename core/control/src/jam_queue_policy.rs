@@ -0,0 +1,273 @@
+//! Admission and ordering policy for a Jam's pending queue.
+//!
+//! Entries that Spotify or the local player already hold keep their exact
+//! position, and so do pending entries someone moved by hand. Only the
+//! remaining `Pending` slots are reordered, so receipts, removals, moves, and
+//! frontier delivery see the same entries they always did.
+
+use crate::config::Config;
+use crate::jam_autoplay::is_pending_autoplay;
+use crate::jam_session::{JamQueueEntry, QueueDeliveryState};
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum QueueOrdering {
+    #[default]
+    Arrival,
+    /// Round-robin pending tracks by `added_by_actor_id`.
+    FairShare,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub(crate) struct JamQueuePolicy {
+    pub(crate) ordering: QueueOrdering,
+    pub(crate) max_pending_per_actor: Option<usize>,
+    pub(crate) reject_duplicates: bool,
+}
+
+impl JamQueuePolicy {
+    pub(crate) fn from_config(config: &Config) -> Self {
+        Self {
+            ordering: if config.jam_queue_fair_share {
+                QueueOrdering::FairShare
+            } else {
+                QueueOrdering::Arrival
+            },
+            max_pending_per_actor: config.jam_queue_max_pending_per_actor,
+            reject_duplicates: config.jam_queue_reject_duplicates,
+        }
+    }
+}
+
+/// The outcome of offering new entries from one actor. Candidates keep their
+/// offered order; rejected ones are never placed in the queue.
+#[derive(Debug, Default)]
+pub(crate) struct QueueAdmission {
+    pub(crate) admitted: Vec<JamQueueEntry>,
+    pub(crate) duplicates: Vec<JamQueueEntry>,
+    pub(crate) over_limit: Vec<JamQueueEntry>,
+}
+
+pub(crate) fn admit_queue_entries(
+    queue: &[JamQueueEntry],
+    policy: &JamQueuePolicy,
+    actor_id: &str,
+    candidates: Vec<JamQueueEntry>,
+) -> QueueAdmission {
+    let queued_uris: HashSet<&str> = if policy.reject_duplicates {
+        // Pending autoplay picks are withdrawn on placement, so they never
+        // make a person's track a duplicate.
+        queue
+            .iter()
+            .filter(|entry| !is_pending_autoplay(entry))
+            .map(|entry| entry.track.spotify_uri.as_str())
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    let mut capacity = policy.max_pending_per_actor.map(|limit| {
+        let pending = queue
+            .iter()
+            .filter(|entry| {
+                entry.delivery_state == QueueDeliveryState::Pending
+                    && entry.track.added_by_actor_id == actor_id
+            })
+            .count();
+        limit.saturating_sub(pending)
+    });
+    let mut admission = QueueAdmission::default();
+    let mut batch_uris = HashSet::new();
+    for entry in candidates {
+        if policy.reject_duplicates
+            && (queued_uris.contains(entry.track.spotify_uri.as_str())
+                || !batch_uris.insert(entry.track.spotify_uri.clone()))
+        {
+            admission.duplicates.push(entry);
+            continue;
+        }
+        if capacity == Some(0) {
+            admission.over_limit.push(entry);
+            continue;
+        }
+        if let Some(remaining) = capacity.as_mut() {
+            *remaining -= 1;
+        }
+        admission.admitted.push(entry);
+    }
+    admission
+}
+
+/// Append admitted entries, then apply the ordering policy. Autoplay picks
+/// that are still pending step aside for anything a person queues.
+pub(crate) fn place_queue_entries(
+    queue: &mut Vec<JamQueueEntry>,
+    policy: &JamQueuePolicy,
+    entries: Vec<JamQueueEntry>,
+) {
+    queue.retain(|entry| !is_pending_autoplay(entry));
+    queue.extend(entries);
+    if policy.ordering == QueueOrdering::FairShare {
+        fair_share_pending(queue);
+    }
+}
+
+/// Interleave pending entries one per contributor per round. Contributors
+/// take turns in the order of their earliest pending entry, and each
+/// contributor's own tracks keep their relative order. Pinned entries are
+/// skipped entirely and the interleave flows around them.
+pub(crate) fn fair_share_pending(queue: &mut [JamQueueEntry]) {
+    let slots: Vec<usize> = queue
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.delivery_state == QueueDeliveryState::Pending && !entry.pinned)
+        .map(|(index, _)| index)
+        .collect();
+    if slots.len() < 2 {
+        return;
+    }
+
+    let mut contributors: Vec<&str> = Vec::new();
+    let mut lanes: HashMap<&str, Vec<usize>> = HashMap::new();
+    for &slot in &slots {
+        let actor_id = queue[slot].track.added_by_actor_id.as_str();
+        lanes
+            .entry(actor_id)
+            .or_insert_with(|| {
+                contributors.push(actor_id);
+                Vec::new()
+            })
+            .push(slot);
+    }
+    let mut order = Vec::with_capacity(slots.len());
+    for round in 0.. {
+        let before = order.len();
+        for actor_id in &contributors {
+            if let Some(&slot) = lanes[actor_id].get(round) {
+                order.push(slot);
+            }
+        }
+        if order.len() == before {
+            break;
+        }
+    }
+
+    let reordered: Vec<JamQueueEntry> = order.iter().map(|&slot| queue[slot].clone()).collect();
+    for (slot, entry) in slots.into_iter().zip(reordered) {
+        queue[slot] = entry;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jam_session::{pending_queue_entry, QueuedTrack};
+
+    fn entry(uri: &str, actor_id: &str) -> JamQueueEntry {
+        pending_queue_entry(QueuedTrack {
+            queue_entry_id: format!("{actor_id}-{uri}"),
+            queue_batch_id: None,
+            spotify_id: uri.to_string(),
+            spotify_uri: format!("spotify:track:{uri}"),
+            spotify_url: String::new(),
+            name: uri.to_string(),
+            artist: String::new(),
+            album_art_url: String::new(),
+            duration_ms: 0,
+            added_at_ms: 0,
+            added_by_actor_id: actor_id.to_string(),
+            added_by_name: actor_id.to_string(),
+            playlist: None,
+            playlist_position: None,
+            autoplay: None,
+            added_by: actor_id.to_string(),
+        })
+    }
+
+    fn names(queue: &[JamQueueEntry]) -> Vec<&str> {
+        queue
+            .iter()
+            .map(|entry| entry.track.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn fair_share_interleaves_pending_entries_by_contributor() {
+        let mut queue = vec![
+            entry("a1", "alex"),
+            entry("a2", "alex"),
+            entry("a3", "alex"),
+            entry("s1", "sam"),
+            entry("j1", "jo"),
+            entry("s2", "sam"),
+        ];
+        queue[0].delivery_state = QueueDeliveryState::SpotifyCommitted;
+
+        fair_share_pending(&mut queue);
+
+        // The committed head stays put; Alex's next pending track still leads
+        // because Alex has waited longest.
+        assert_eq!(names(&queue), ["a1", "a2", "s1", "j1", "a3", "s2"]);
+        assert_eq!(
+            queue[0].delivery_state,
+            QueueDeliveryState::SpotifyCommitted
+        );
+    }
+
+    #[test]
+    fn arrival_ordering_only_appends() {
+        let mut queue = vec![entry("a1", "alex"), entry("a2", "alex")];
+        place_queue_entries(
+            &mut queue,
+            &JamQueuePolicy::default(),
+            vec![entry("s1", "sam")],
+        );
+        assert_eq!(names(&queue), ["a1", "a2", "s1"]);
+
+        let fair = JamQueuePolicy {
+            ordering: QueueOrdering::FairShare,
+            ..JamQueuePolicy::default()
+        };
+        place_queue_entries(&mut queue, &fair, vec![entry("j1", "jo")]);
+        assert_eq!(names(&queue), ["a1", "s1", "j1", "a2"]);
+    }
+
+    #[test]
+    fn admission_applies_per_actor_caps_and_duplicate_guard() {
+        let mut queue = vec![entry("a1", "alex"), entry("s1", "sam")];
+        queue[0].delivery_state = QueueDeliveryState::SpotifyCommitted;
+        let policy = JamQueuePolicy {
+            ordering: QueueOrdering::Arrival,
+            max_pending_per_actor: Some(2),
+            reject_duplicates: true,
+        };
+
+        let admission = admit_queue_entries(
+            &queue,
+            &policy,
+            "alex",
+            vec![
+                entry("s1", "alex"),
+                entry("a2", "alex"),
+                entry("a2", "alex"),
+                entry("a3", "alex"),
+                entry("a4", "alex"),
+            ],
+        );
+
+        // Committed tracks do not count toward the pending cap.
+        assert_eq!(names(&admission.admitted), ["a2", "a3"]);
+        assert_eq!(names(&admission.duplicates), ["s1", "a2"]);
+        assert_eq!(names(&admission.over_limit), ["a4"]);
+
+        let unlimited = admit_queue_entries(
+            &queue,
+            &JamQueuePolicy::default(),
+            "alex",
+            vec![entry("s1", "alex"), entry("s1", "alex")],
+        );
+        assert_eq!(unlimited.admitted.len(), 2);
+    }
+}
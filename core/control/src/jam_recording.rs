@@ -0,0 +1,1558 @@
+//! Server-side recordings of the relayed Jam audio. An admin starts one for a
+//! room's running generation; the relay is encoded to 16-bit FLAC in the
+//! control-plane data directory and split into one tagged file per track
+//! whenever the room's now-playing track changes.
+
+use crate::auth::ensure_admin;
+use crate::config::{now_ts_ms, random_secret};
+use crate::jam_bot::{AudioFrame, TARGET_CHANNELS, TARGET_RATE};
+use crate::jam_history::DAY_MS;
+use crate::jam_session::NowPlayingInfo;
+use crate::AppState;
+
+use axum::{
+    extract::{Path, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc as std_mpsc, Arc, Mutex,
+    },
+};
+use tokio::sync::{broadcast, oneshot};
+use tracing::{info, warn};
+
+pub(crate) const RECORDING_SCHEMA_VERSION: u16 = 1;
+pub(crate) const RECORDING_RETENTION_DAYS: u64 = 14;
+pub(crate) const MAX_RECORDING_RETENTION_DAYS: u64 = 365;
+pub(crate) const RECORDING_MAX_TOTAL_MB: u64 = 4_096;
+const RECORDING_ID_PREFIX: &str = "jr1_";
+const RECORDING_ID_HEX_LEN: usize = 24;
+// Track changes are observed with a few seconds of lag; shorter segments are
+// the tail of a skipped track rather than anything worth keeping.
+const MIN_SEGMENT_MS: u64 = 2_000;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct JamRecordingEntry {
+    pub(crate) schema_version: u16,
+    pub(crate) recording_id: String,
+    pub(crate) room: String,
+    pub(crate) generation: u64,
+    pub(crate) track_number: u32,
+    pub(crate) started_at_ms: u64,
+    pub(crate) ended_at_ms: u64,
+    pub(crate) duration_ms: u64,
+    pub(crate) bytes: u64,
+    pub(crate) spotify_id: String,
+    pub(crate) spotify_url: String,
+    pub(crate) name: String,
+    pub(crate) artist: String,
+    pub(crate) album_art_url: String,
+}
+
+impl JamRecordingEntry {
+    fn download_filename(&self) -> String {
+        let stem = format!(
+            "{} {:02} - {} - {}",
+            self.room, self.track_number, self.artist, self.name
+        );
+        let stem: String = stem
+            .chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() || " -_.()".contains(ch) {
+                    ch
+                } else {
+                    '_'
+                }
+            })
+            .take(120)
+            .collect();
+        let stem = stem.trim();
+        if stem.is_empty() {
+            format!("{}.flac", self.recording_id)
+        } else {
+            format!("{stem}.flac")
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct JamRecordingActive {
+    pub(crate) room: String,
+    pub(crate) generation: u64,
+    pub(crate) started_at_ms: u64,
+}
+
+struct ActiveRecording {
+    session: u64,
+    generation: u64,
+    started_at_ms: u64,
+    stop: oneshot::Sender<()>,
+}
+
+pub(crate) struct JamRecordingStore {
+    dir: PathBuf,
+    enabled: bool,
+    retention_days: u64,
+    max_total_bytes: u64,
+    lock: Mutex<()>,
+    active: Mutex<BTreeMap<String, ActiveRecording>>,
+    next_session: AtomicU64,
+}
+
+impl JamRecordingStore {
+    pub(crate) fn open(
+        dir: PathBuf,
+        retention_days: u64,
+        max_total_mb: u64,
+        now_ms: u64,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let store = Self {
+            enabled: true,
+            retention_days: retention_days.clamp(1, MAX_RECORDING_RETENTION_DAYS),
+            max_total_bytes: max_total_mb.max(1).saturating_mul(1024 * 1024),
+            ..Self::disabled(dir)
+        };
+        store.remove_incomplete()?;
+        store.prune(now_ms)?;
+        Ok(store)
+    }
+
+    pub(crate) fn disabled(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            enabled: false,
+            retention_days: RECORDING_RETENTION_DAYS,
+            max_total_bytes: RECORDING_MAX_TOTAL_MB * 1024 * 1024,
+            lock: Mutex::new(()),
+            active: Mutex::new(BTreeMap::new()),
+            next_session: AtomicU64::new(1),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn audio_path(&self, recording_id: &str) -> PathBuf {
+        self.dir.join(format!("{recording_id}.flac"))
+    }
+
+    fn entry_path(&self, recording_id: &str) -> PathBuf {
+        self.dir.join(format!("{recording_id}.json"))
+    }
+
+    /// Drop anything a crash left behind: unfinished `.part` audio, temporary
+    /// sidecars, and audio or sidecars missing their other half.
+    fn remove_incomplete(&self) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|error| error.into_inner());
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let orphaned = if name.ends_with(".part") || name.ends_with(".tmp") {
+                true
+            } else if let Some(id) = name.strip_suffix(".flac") {
+                !self.entry_path(id).exists()
+            } else if let Some(id) = name.strip_suffix(".json") {
+                !self.audio_path(id).exists()
+            } else {
+                false
+            };
+            if orphaned {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn list(&self) -> io::Result<Vec<JamRecordingEntry>> {
+        if !self.enabled {
+            return Err(recordings_unavailable());
+        }
+        let _guard = self.lock.lock().unwrap_or_else(|error| error.into_inner());
+        self.list_locked()
+    }
+
+    fn list_locked(&self) -> io::Result<Vec<JamRecordingEntry>> {
+        let mut entries = Vec::new();
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            match serde_json::from_slice::<JamRecordingEntry>(&fs::read(&path)?) {
+                Ok(entry)
+                    if entry.schema_version == RECORDING_SCHEMA_VERSION
+                        && valid_recording_id(&entry.recording_id)
+                        && self.audio_path(&entry.recording_id).exists() =>
+                {
+                    entries.push(entry);
+                }
+                Ok(_) => {}
+                Err(error) => warn!("Skipping malformed Jam recording {:?}: {}", path, error),
+            }
+        }
+        entries.sort_by(|a, b| {
+            b.started_at_ms
+                .cmp(&a.started_at_ms)
+                .then(b.track_number.cmp(&a.track_number))
+        });
+        Ok(entries)
+    }
+
+    pub(crate) fn get(
+        &self,
+        recording_id: &str,
+    ) -> io::Result<Option<(JamRecordingEntry, PathBuf)>> {
+        if !self.enabled {
+            return Err(recordings_unavailable());
+        }
+        if !valid_recording_id(recording_id) {
+            return Ok(None);
+        }
+        let _guard = self.lock.lock().unwrap_or_else(|error| error.into_inner());
+        let audio = self.audio_path(recording_id);
+        let entry = match fs::read(self.entry_path(recording_id)) {
+            Ok(bytes) => serde_json::from_slice::<JamRecordingEntry>(&bytes)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        Ok(audio.exists().then_some((entry, audio)))
+    }
+
+    pub(crate) fn delete(&self, recording_id: &str) -> io::Result<bool> {
+        if !self.enabled {
+            return Err(recordings_unavailable());
+        }
+        if !valid_recording_id(recording_id) {
+            return Ok(false);
+        }
+        let _guard = self.lock.lock().unwrap_or_else(|error| error.into_inner());
+        let existed = self.entry_path(recording_id).exists();
+        self.remove_recording(recording_id)?;
+        Ok(existed)
+    }
+
+    fn remove_recording(&self, recording_id: &str) -> io::Result<()> {
+        for path in [self.entry_path(recording_id), self.audio_path(recording_id)] {
+            match fs::remove_file(path) {
+                Ok(()) => {}
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove recordings past the retention window, then the oldest ones
+    /// until the rest fit the total size budget.
+    pub(crate) fn prune(&self, now_ms: u64) -> io::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let _guard = self.lock.lock().unwrap_or_else(|error| error.into_inner());
+        self.prune_locked(now_ms)
+    }
+
+    fn prune_locked(&self, now_ms: u64) -> io::Result<()> {
+        let cutoff = now_ms.saturating_sub(self.retention_days * DAY_MS);
+        let mut kept_bytes = 0_u64;
+        for entry in self.list_locked()? {
+            if entry.ended_at_ms < cutoff || kept_bytes + entry.bytes > self.max_total_bytes {
+                self.remove_recording(&entry.recording_id)?;
+            } else {
+                kept_bytes += entry.bytes;
+            }
+        }
+        Ok(())
+    }
+
+    fn create_segment(
+        &self,
+        room: &str,
+        generation: u64,
+        track_number: u32,
+        track: SegmentTrack,
+    ) -> io::Result<SegmentWriter> {
+        let recording_id = format!(
+            "{RECORDING_ID_PREFIX}{}",
+            &random_secret()[..RECORDING_ID_HEX_LEN]
+        );
+        let part_path = self.dir.join(format!("{recording_id}.flac.part"));
+        let album = format!("Echo Jam in {room}");
+        let track_tag = track_number.to_string();
+        let mut comments = vec![
+            ("TITLE", track.name.as_str()),
+            ("ARTIST", track.artist.as_str()),
+            ("ALBUM", album.as_str()),
+            ("TRACKNUMBER", track_tag.as_str()),
+        ];
+        if !track.spotify_url.is_empty() {
+            comments.push(("COMMENT", track.spotify_url.as_str()));
+        }
+        let flac = FlacWriter::new(
+            BufWriter::new(File::create(&part_path)?),
+            TARGET_RATE,
+            TARGET_CHANNELS,
+            &comments,
+        )?;
+        Ok(SegmentWriter {
+            part_path,
+            flac,
+            entry: JamRecordingEntry {
+                schema_version: RECORDING_SCHEMA_VERSION,
+                recording_id,
+                room: room.to_string(),
+                generation,
+                track_number,
+                started_at_ms: track.started_at_ms,
+                ended_at_ms: track.started_at_ms,
+                duration_ms: 0,
+                bytes: 0,
+                spotify_id: track.spotify_id,
+                spotify_url: track.spotify_url,
+                name: track.name,
+                artist: track.artist,
+                album_art_url: track.album_art_url,
+            },
+        })
+    }
+
+    /// Close a segment and publish it, or discard it when it is too short to
+    /// be a track.
+    fn finish_segment(
+        &self,
+        segment: SegmentWriter,
+        now_ms: u64,
+    ) -> io::Result<Option<JamRecordingEntry>> {
+        let SegmentWriter {
+            part_path,
+            flac,
+            mut entry,
+        } = segment;
+        let (writer, frames) = flac.finish()?;
+        writer
+            .into_inner()
+            .map_err(|error| error.into_error())?
+            .sync_all()?;
+        entry.duration_ms = frames * 1_000 / u64::from(TARGET_RATE);
+        if entry.duration_ms < MIN_SEGMENT_MS {
+            fs::remove_file(&part_path)?;
+            return Ok(None);
+        }
+        entry.ended_at_ms = now_ms;
+        entry.bytes = fs::metadata(&part_path)?.len();
+
+        let _guard = self.lock.lock().unwrap_or_else(|error| error.into_inner());
+        fs::rename(&part_path, self.audio_path(&entry.recording_id))?;
+        let entry_path = self.entry_path(&entry.recording_id);
+        let temp_path = entry_path.with_extension("json.tmp");
+        let mut file = File::create(&temp_path)?;
+        serde_json::to_writer_pretty(&mut file, &entry)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        file.sync_all()?;
+        fs::rename(&temp_path, entry_path)?;
+        self.prune_locked(now_ms)?;
+        Ok(Some(entry))
+    }
+
+    /// Register a recording of `room`'s `generation`. Returns `None` when that
+    /// generation is already being recorded; a leftover recording of an older
+    /// generation is told to stop.
+    fn begin_active(
+        &self,
+        room: &str,
+        generation: u64,
+        now_ms: u64,
+    ) -> Option<(u64, oneshot::Receiver<()>)> {
+        let mut active = self
+            .active
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        if active
+            .get(room)
+            .is_some_and(|recording| recording.generation == generation)
+        {
+            return None;
+        }
+        let session = self.next_session.fetch_add(1, Ordering::Relaxed);
+        let (stop, stop_rx) = oneshot::channel();
+        if let Some(previous) = active.insert(
+            room.to_string(),
+            ActiveRecording {
+                session,
+                generation,
+                started_at_ms: now_ms,
+                stop,
+            },
+        ) {
+            let _ = previous.stop.send(());
+        }
+        Some((session, stop_rx))
+    }
+
+    fn end_active(&self, room: &str, session: u64) {
+        let mut active = self
+            .active
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        if active
+            .get(room)
+            .is_some_and(|recording| recording.session == session)
+        {
+            active.remove(room);
+        }
+    }
+
+    pub(crate) fn stop(&self, room: &str) -> bool {
+        let recording = self
+            .active
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .remove(room);
+        match recording {
+            Some(recording) => {
+                let _ = recording.stop.send(());
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn is_recording(&self, room: &str) -> bool {
+        self.active
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .contains_key(room)
+    }
+
+    pub(crate) fn active(&self) -> Vec<JamRecordingActive> {
+        self.active
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .iter()
+            .map(|(room, recording)| JamRecordingActive {
+                room: room.clone(),
+                generation: recording.generation,
+                started_at_ms: recording.started_at_ms,
+            })
+            .collect()
+    }
+}
+
+fn recordings_unavailable() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotConnected,
+        "Jam recording storage is unavailable",
+    )
+}
+
+fn valid_recording_id(recording_id: &str) -> bool {
+    recording_id
+        .strip_prefix(RECORDING_ID_PREFIX)
+        .is_some_and(|hex| {
+            hex.len() == RECORDING_ID_HEX_LEN
+                && hex
+                    .bytes()
+                    .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+        })
+}
+
+// ── Recorder ─────────────────────────────────────────────────────────────
+
+struct SegmentTrack {
+    started_at_ms: u64,
+    spotify_id: String,
+    spotify_url: String,
+    name: String,
+    artist: String,
+    album_art_url: String,
+}
+
+impl SegmentTrack {
+    fn from_now_playing(now_playing: &NowPlayingInfo, started_at_ms: u64) -> Self {
+        Self {
+            started_at_ms,
+            spotify_id: now_playing.spotify_id.clone(),
+            spotify_url: now_playing.spotify_url.clone(),
+            name: now_playing.name.clone(),
+            artist: now_playing.artist.clone(),
+            album_art_url: now_playing.album_art_url.clone(),
+        }
+    }
+}
+
+struct SegmentWriter {
+    part_path: PathBuf,
+    flac: FlacWriter<BufWriter<File>>,
+    entry: JamRecordingEntry,
+}
+
+enum RecorderCommand {
+    Begin(SegmentTrack),
+    Samples(Vec<f32>),
+    End,
+}
+
+/// Identifies the track a now-playing observation refers to. Progress and
+/// play/pause updates of the same track keep the current segment.
+fn track_key(now_playing: &NowPlayingInfo) -> String {
+    if now_playing.spotify_id.is_empty() {
+        format!("{}\u{1f}{}", now_playing.name, now_playing.artist)
+    } else {
+        now_playing.spotify_id.clone()
+    }
+}
+
+/// Forward the relay of `generation` to the segment writer until an admin
+/// stops the recording, the Jam moves on, or the relay closes.
+async fn record_generation(
+    state: AppState,
+    session: u64,
+    generation: u64,
+    mut audio_rx: broadcast::Receiver<AudioFrame>,
+    mut stop_rx: oneshot::Receiver<()>,
+) {
+    let room = state.jam_room.to_string();
+    let store = Arc::clone(&state.jam_recordings);
+    let (command_tx, command_rx) = std_mpsc::channel();
+    let writer = {
+        let store = Arc::clone(&store);
+        let room = room.clone();
+        tokio::task::spawn_blocking(move || write_segments(&store, &room, generation, command_rx))
+    };
+    info!(
+        "Jam recording started room={} generation={}",
+        room, generation
+    );
+
+    let mut current_track: Option<String> = None;
+    loop {
+        let frame = tokio::select! {
+            _ = &mut stop_rx => break,
+            frame = audio_rx.recv() => match frame {
+                Ok(frame) => frame,
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    warn!(
+                        "Jam recording lagged room={} generation={} dropped={}",
+                        room, generation, count
+                    );
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+        let now_playing = {
+            let jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
+            if !jam.active || jam.generation != generation {
+                break;
+            }
+            jam.now_playing.clone()
+        };
+        let track = now_playing.as_ref().map(track_key);
+        if track != current_track {
+            current_track = track;
+            let command = match &now_playing {
+                Some(now_playing) => {
+                    RecorderCommand::Begin(SegmentTrack::from_now_playing(now_playing, now_ts_ms()))
+                }
+                None => RecorderCommand::End,
+            };
+            if command_tx.send(command).is_err() {
+                break;
+            }
+        }
+        if current_track.is_some()
+            && command_tx
+                .send(RecorderCommand::Samples(frame.data))
+                .is_err()
+        {
+            break;
+        }
+    }
+    drop(command_tx);
+    if let Err(error) = writer.await {
+        warn!("Jam recording writer task failed: {}", error);
+    }
+    store.end_active(&room, session);
+    info!(
+        "Jam recording stopped room={} generation={}",
+        room, generation
+    );
+}
+
+fn write_segments(
+    store: &JamRecordingStore,
+    room: &str,
+    generation: u64,
+    commands: std_mpsc::Receiver<RecorderCommand>,
+) {
+    let finish = |segment: Option<SegmentWriter>| {
+        if let Some(segment) = segment {
+            let part_path = segment.part_path.clone();
+            if let Err(error) = store.finish_segment(segment, now_ts_ms()) {
+                warn!("Could not save Jam recording segment: {}", error);
+                let _ = fs::remove_file(part_path);
+            }
+        }
+    };
+    let mut current: Option<SegmentWriter> = None;
+    let mut track_number = 0_u32;
+    for command in commands {
+        match command {
+            RecorderCommand::Begin(track) => {
+                finish(current.take());
+                track_number += 1;
+                current = store
+                    .create_segment(room, generation, track_number, track)
+                    .map_err(|error| warn!("Could not start Jam recording segment: {}", error))
+                    .ok();
+            }
+            RecorderCommand::Samples(samples) => {
+                if let Some(segment) = current.as_mut() {
+                    if let Err(error) = segment.flac.write_samples(&samples) {
+                        warn!("Jam recording segment write failed: {}", error);
+                        let _ = fs::remove_file(&segment.part_path);
+                        current = None;
+                    }
+                }
+            }
+            RecorderCommand::End => finish(current.take()),
+        }
+    }
+    finish(current.take());
+}
+
+// ── Handlers ─────────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub(crate) struct JamRecordingRequest {
+    enabled: bool,
+}
+
+fn recording_error(
+    status: StatusCode,
+    error: &str,
+    message: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        status,
+        Json(serde_json::json!({"error": error, "message": message})),
+    )
+}
+
+fn storage_status(error: &io::Error) -> StatusCode {
+    if error.kind() == io::ErrorKind::NotConnected {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        warn!("Jam recording storage failed: {}", error);
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+async fn run_store<T: Send + 'static>(
+    state: &AppState,
+    operation: impl FnOnce(&JamRecordingStore) -> io::Result<T> + Send + 'static,
+) -> Result<T, StatusCode> {
+    let store = Arc::clone(&state.jam_recordings);
+    tokio::task::spawn_blocking(move || operation(&store))
+        .await
+        .map_err(|error| {
+            warn!("Jam recording storage task failed: {}", error);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map_err(|error| storage_status(&error))
+}
+
+pub(crate) async fn admin_jam_recordings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    let store = &state.jam_recordings;
+    let recordings = if store.enabled() {
+        run_store(&state, |store| store.list()).await?
+    } else {
+        Vec::new()
+    };
+    Ok(Json(serde_json::json!({
+        "enabled": store.enabled(),
+        "retention_days": store.retention_days,
+        "max_total_bytes": store.max_total_bytes,
+        "total_bytes": recordings.iter().map(|entry| entry.bytes).sum::<u64>(),
+        "active": store.active(),
+        "recordings": recordings,
+    })))
+}
+
+/// Start or stop recording the room's running Jam generation. Recording also
+/// stops on its own when that generation ends.
+pub(crate) async fn admin_jam_recording_set(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(room): Path<String>,
+    Json(payload): Json<JamRecordingRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    ensure_admin(&state, &headers)
+        .map_err(|status| (status, Json(serde_json::json!({"error": "unauthorized"}))))?;
+    let room = room.trim().to_string();
+    if room.is_empty() || room.len() > 128 {
+        return Err(recording_error(
+            StatusCode::BAD_REQUEST,
+            "bad_request",
+            "room must be 1-128 characters",
+        ));
+    }
+    if !payload.enabled {
+        let stopped = state.jam_recordings.stop(&room);
+        return Ok(Json(serde_json::json!({
+            "ok": true,
+            "room": room,
+            "recording": false,
+            "stopped": stopped,
+        })));
+    }
+    if !state.jam_recordings.enabled() {
+        return Err(recording_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "recording_unavailable",
+            "Jam recording storage is unavailable",
+        ));
+    }
+    let no_active_jam = || {
+        recording_error(
+            StatusCode::CONFLICT,
+            "no_active_jam",
+            "This room has no running Jam",
+        )
+    };
+    let state = state.existing_jam_room(&room).ok_or_else(no_active_jam)?;
+    let generation = {
+        let jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
+        jam.active.then_some(jam.generation)
+    }
+    .ok_or_else(no_active_jam)?;
+    let audio_rx = match state.jam_bot.lock().await.as_ref() {
+        Some(bot) if bot.generation() == generation => bot.subscribe(),
+        _ => return Err(no_active_jam()),
+    };
+    let Some((session, stop_rx)) =
+        state
+            .jam_recordings
+            .begin_active(&room, generation, now_ts_ms())
+    else {
+        return Ok(Json(serde_json::json!({
+            "ok": true,
+            "room": room,
+            "recording": true,
+            "generation": generation,
+            "already_recording": true,
+        })));
+    };
+    tokio::spawn(record_generation(
+        state, session, generation, audio_rx, stop_rx,
+    ));
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "room": room,
+        "recording": true,
+        "generation": generation,
+    })))
+}
+
+pub(crate) async fn admin_jam_recording_download(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(recording_id): Path<String>,
+) -> Response {
+    if let Err(status) = ensure_admin(&state, &headers) {
+        return status.into_response();
+    }
+    let (entry, path) = match run_store(&state, move |store| store.get(&recording_id)).await {
+        Ok(Some(found)) => found,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(status) => return status.into_response(),
+    };
+    let body = match tokio::fs::read(&path).await {
+        Ok(body) => body,
+        // Pruned between the lookup and the read.
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+        Err(error) => return storage_status(&error).into_response(),
+    };
+    let mut response = (StatusCode::OK, body).into_response();
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("audio/flac"));
+    response
+        .headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    let disposition = format!("attachment; filename=\"{}\"", entry.download_filename());
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        response.headers_mut().insert(CONTENT_DISPOSITION, value);
+    }
+    response
+}
+
+pub(crate) async fn admin_jam_recording_delete(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(recording_id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    if run_store(&state, move |store| store.delete(&recording_id)).await? {
+        Ok(Json(serde_json::json!({"ok": true})))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+// ── FLAC encoder ─────────────────────────────────────────────────────────
+//
+// A small fixed-predictor FLAC encoder: 16-bit samples, 4096-sample blocks,
+// fixed predictors of order 0-4 with partitioned Rice residuals, and the
+// cheapest stereo decorrelation per block. That roughly halves PCM without
+// pulling in an encoder dependency.
+
+const FLAC_BLOCK_SIZE: usize = 4_096;
+const FLAC_BITS_PER_SAMPLE: u32 = 16;
+const FLAC_MAX_FIXED_ORDER: usize = 4;
+const FLAC_MAX_PARTITION_ORDER: u32 = 4;
+const FLAC_MAX_RICE_PARAMETER: u32 = 14;
+// `fLaC` plus the STREAMINFO block header.
+const FLAC_STREAMINFO_OFFSET: u64 = 8;
+
+pub(crate) struct FlacWriter<W: Write + Seek> {
+    out: W,
+    sample_rate: u32,
+    channels: u32,
+    pending: Vec<i64>,
+    frame_number: u32,
+    total_frames: u64,
+    min_frame_bytes: u32,
+    max_frame_bytes: u32,
+}
+
+impl<W: Write + Seek> FlacWriter<W> {
+    pub(crate) fn new(
+        mut out: W,
+        sample_rate: u32,
+        channels: u32,
+        comments: &[(&str, &str)],
+    ) -> io::Result<Self> {
+        out.write_all(b"fLaC")?;
+        let mut writer = Self {
+            out,
+            sample_rate,
+            channels,
+            pending: Vec::with_capacity(FLAC_BLOCK_SIZE * channels as usize),
+            frame_number: 0,
+            total_frames: 0,
+            min_frame_bytes: 0,
+            max_frame_bytes: 0,
+        };
+        // STREAMINFO is rewritten with the final totals by `finish`.
+        let streaminfo = writer.streaminfo();
+        writer.write_metadata_block(0, false, &streaminfo)?;
+        let vorbis_comment = vorbis_comment(comments);
+        writer.write_metadata_block(4, true, &vorbis_comment)?;
+        Ok(writer)
+    }
+
+    fn write_metadata_block(&mut self, kind: u8, last: bool, body: &[u8]) -> io::Result<()> {
+        let length = body.len() as u32;
+        self.out.write_all(&[
+            (u8::from(last) << 7) | kind,
+            (length >> 16) as u8,
+            (length >> 8) as u8,
+            length as u8,
+        ])?;
+        self.out.write_all(body)
+    }
+
+    fn streaminfo(&self) -> Vec<u8> {
+        let mut bits = BitWriter::default();
+        bits.write(FLAC_BLOCK_SIZE as u64, 16);
+        bits.write(FLAC_BLOCK_SIZE as u64, 16);
+        bits.write(u64::from(self.min_frame_bytes), 24);
+        bits.write(u64::from(self.max_frame_bytes), 24);
+        bits.write(u64::from(self.sample_rate), 20);
+        bits.write(u64::from(self.channels - 1), 3);
+        bits.write(u64::from(FLAC_BITS_PER_SAMPLE - 1), 5);
+        bits.write(self.total_frames >> 32, 4);
+        bits.write(self.total_frames & 0xffff_ffff, 32);
+        // An all-zero MD5 signature means "not computed".
+        for _ in 0..4 {
+            bits.write(0, 32);
+        }
+        bits.bytes
+    }
+
+    /// Append interleaved samples in `[-1.0, 1.0]`.
+    pub(crate) fn write_samples(&mut self, samples: &[f32]) -> io::Result<()> {
+        self.pending.extend(
+            samples
+                .iter()
+                .map(|sample| (sample.clamp(-1.0, 1.0) * 32_767.0).round() as i64),
+        );
+        let block_samples = FLAC_BLOCK_SIZE * self.channels as usize;
+        while self.pending.len() >= block_samples {
+            let block: Vec<i64> = self.pending.drain(..block_samples).collect();
+            self.write_frame(&block)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the final partial block and fill in STREAMINFO. Returns the
+    /// writer and the number of samples per channel written.
+    pub(crate) fn finish(mut self) -> io::Result<(W, u64)> {
+        let channels = self.channels as usize;
+        let whole = self.pending.len() / channels * channels;
+        if whole > 0 {
+            let block: Vec<i64> = self.pending.drain(..whole).collect();
+            self.write_frame(&block)?;
+        }
+        let streaminfo = self.streaminfo();
+        self.out.seek(SeekFrom::Start(FLAC_STREAMINFO_OFFSET))?;
+        self.out.write_all(&streaminfo)?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()?;
+        Ok((self.out, self.total_frames))
+    }
+
+    fn write_frame(&mut self, interleaved: &[i64]) -> io::Result<()> {
+        let channels = self.channels as usize;
+        let block_size = interleaved.len() / channels;
+        let deinterleaved: Vec<Vec<i64>> = (0..channels)
+            .map(|channel| {
+                interleaved
+                    .iter()
+                    .skip(channel)
+                    .step_by(channels)
+                    .copied()
+                    .collect()
+            })
+            .collect();
+        let (assignment, subframes) = if channels == 2 {
+            choose_stereo_subframes(&deinterleaved[0], &deinterleaved[1])
+        } else {
+            let subframes = deinterleaved
+                .iter()
+                .map(|samples| plan_subframe(samples, FLAC_BITS_PER_SAMPLE))
+                .collect();
+            (self.channels - 1, subframes)
+        };
+
+        let mut bits = BitWriter::default();
+        bits.write(0x3ffe, 14);
+        bits.write(0, 1);
+        // Fixed-blocksize stream.
+        bits.write(0, 1);
+        // Block size as a 16-bit value after the frame number.
+        bits.write(0b0111, 4);
+        bits.write(u64::from(sample_rate_code(self.sample_rate)), 4);
+        bits.write(u64::from(assignment), 4);
+        // 16 bits per sample.
+        bits.write(0b100, 3);
+        bits.write(0, 1);
+        for byte in utf8_frame_number(self.frame_number) {
+            bits.write(u64::from(byte), 8);
+        }
+        bits.write(block_size as u64 - 1, 16);
+        let header_crc = crc8(&bits.bytes);
+        bits.write(u64::from(header_crc), 8);
+        for subframe in &subframes {
+            subframe.write(&mut bits);
+        }
+        bits.align();
+        let frame_crc = crc16(&bits.bytes);
+        bits.write(u64::from(frame_crc), 16);
+
+        self.out.write_all(&bits.bytes)?;
+        let frame_bytes = bits.bytes.len() as u32;
+        self.min_frame_bytes = if self.frame_number == 0 {
+            frame_bytes
+        } else {
+            self.min_frame_bytes.min(frame_bytes)
+        };
+        self.max_frame_bytes = self.max_frame_bytes.max(frame_bytes);
+        self.frame_number += 1;
+        self.total_frames += block_size as u64;
+        Ok(())
+    }
+}
+
+fn vorbis_comment(comments: &[(&str, &str)]) -> Vec<u8> {
+    let vendor = concat!("echo-core-control ", env!("CARGO_PKG_VERSION"));
+    let mut body = Vec::new();
+    body.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    body.extend_from_slice(vendor.as_bytes());
+    let comments: Vec<String> = comments
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect();
+    body.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        body.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        body.extend_from_slice(comment.as_bytes());
+    }
+    body
+}
+
+fn sample_rate_code(sample_rate: u32) -> u8 {
+    match sample_rate {
+        44_100 => 0b1001,
+        48_000 => 0b1010,
+        96_000 => 0b1011,
+        // Taken from STREAMINFO.
+        _ => 0b0000,
+    }
+}
+
+fn utf8_frame_number(value: u32) -> Vec<u8> {
+    if value < 0x80 {
+        return vec![value as u8];
+    }
+    let length = match value {
+        0..=0x7ff => 2,
+        0x800..=0xffff => 3,
+        0x1_0000..=0x1f_ffff => 4,
+        0x20_0000..=0x3ff_ffff => 5,
+        _ => 6,
+    };
+    let mut bytes = vec![0_u8; length];
+    let mut rest = value;
+    for byte in bytes.iter_mut().skip(1).rev() {
+        *byte = 0x80 | (rest & 0x3f) as u8;
+        rest >>= 6;
+    }
+    bytes[0] = (0xff_u16 << (8 - length)) as u8 | rest as u8;
+    bytes
+}
+
+/// Pick independent, left/side, right/side, or mid/side coding, whichever
+/// encodes the block smallest.
+fn choose_stereo_subframes(left: &[i64], right: &[i64]) -> (u32, Vec<Subframe>) {
+    let side: Vec<i64> = left.iter().zip(right).map(|(l, r)| l - r).collect();
+    let mid: Vec<i64> = left.iter().zip(right).map(|(l, r)| (l + r) >> 1).collect();
+    let left = plan_subframe(left, FLAC_BITS_PER_SAMPLE);
+    let right = plan_subframe(right, FLAC_BITS_PER_SAMPLE);
+    let side = plan_subframe(&side, FLAC_BITS_PER_SAMPLE + 1);
+    let mid = plan_subframe(&mid, FLAC_BITS_PER_SAMPLE);
+    let candidates = [
+        (0b0001, left.bits + right.bits),
+        (0b1000, left.bits + side.bits),
+        (0b1001, side.bits + right.bits),
+        (0b1010, mid.bits + side.bits),
+    ];
+    let (assignment, _) = candidates
+        .into_iter()
+        .min_by_key(|(_, bits)| *bits)
+        .unwrap_or((0b0001, 0));
+    let subframes = match assignment {
+        0b1000 => vec![left, side],
+        0b1001 => vec![side, right],
+        0b1010 => vec![mid, side],
+        _ => vec![left, right],
+    };
+    (assignment, subframes)
+}
+
+/// Rice parameter and residuals of each partition, in order.
+type RicePartitions = Vec<(u32, Vec<i64>)>;
+
+enum SubframeKind {
+    Constant(i64),
+    Verbatim(Vec<i64>),
+    Fixed {
+        warmup: Vec<i64>,
+        partition_order: u32,
+        partitions: RicePartitions,
+    },
+}
+
+struct Subframe {
+    bits_per_sample: u32,
+    kind: SubframeKind,
+    bits: u64,
+}
+
+impl Subframe {
+    fn write(&self, bits: &mut BitWriter) {
+        let bps = self.bits_per_sample;
+        match &self.kind {
+            SubframeKind::Constant(value) => {
+                bits.write(0, 8);
+                bits.write_signed(*value, bps);
+            }
+            SubframeKind::Verbatim(samples) => {
+                bits.write(0b0000_0010, 8);
+                for sample in samples {
+                    bits.write_signed(*sample, bps);
+                }
+            }
+            SubframeKind::Fixed {
+                warmup,
+                partition_order,
+                partitions,
+            } => {
+                bits.write(((0b001_000 | warmup.len() as u64) << 1) & 0x7e, 8);
+                for sample in warmup {
+                    bits.write_signed(*sample, bps);
+                }
+                // Rice coding with 4-bit parameters.
+                bits.write(0, 2);
+                bits.write(u64::from(*partition_order), 4);
+                for (parameter, residuals) in partitions {
+                    bits.write(u64::from(*parameter), 4);
+                    for residual in residuals {
+                        bits.write_rice(*residual, *parameter);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn plan_subframe(samples: &[i64], bits_per_sample: u32) -> Subframe {
+    let header_bits = 8_u64;
+    if samples.iter().all(|sample| *sample == samples[0]) {
+        return Subframe {
+            bits_per_sample,
+            kind: SubframeKind::Constant(samples[0]),
+            bits: header_bits + u64::from(bits_per_sample),
+        };
+    }
+    let verbatim_bits = header_bits + samples.len() as u64 * u64::from(bits_per_sample);
+    let mut best: Option<(u64, usize, u32, RicePartitions)> = None;
+    for order in 0..=FLAC_MAX_FIXED_ORDER.min(samples.len().saturating_sub(1)) {
+        let residuals = fixed_residuals(samples, order);
+        let (partition_order, partitions, residual_bits) =
+            plan_partitions(&residuals, samples.len(), order);
+        let bits = header_bits + order as u64 * u64::from(bits_per_sample) + residual_bits;
+        if best
+            .as_ref()
+            .is_none_or(|(best_bits, ..)| bits < *best_bits)
+        {
+            best = Some((bits, order, partition_order, partitions));
+        }
+    }
+    match best {
+        Some((bits, order, partition_order, partitions)) if bits < verbatim_bits => Subframe {
+            bits_per_sample,
+            kind: SubframeKind::Fixed {
+                warmup: samples[..order].to_vec(),
+                partition_order,
+                partitions,
+            },
+            bits,
+        },
+        _ => Subframe {
+            bits_per_sample,
+            kind: SubframeKind::Verbatim(samples.to_vec()),
+            bits: verbatim_bits,
+        },
+    }
+}
+
+fn fixed_residuals(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|i| match order {
+            0 => samples[i],
+            1 => samples[i] - samples[i - 1],
+            2 => samples[i] - 2 * samples[i - 1] + samples[i - 2],
+            3 => samples[i] - 3 * samples[i - 1] + 3 * samples[i - 2] - samples[i - 3],
+            _ => {
+                samples[i] - 4 * samples[i - 1] + 6 * samples[i - 2] - 4 * samples[i - 3]
+                    + samples[i - 4]
+            }
+        })
+        .collect()
+}
+
+/// Split the residual into the partition order and per-partition Rice
+/// parameters that encode it smallest. Returns the residual section's size
+/// in bits, including its coding-method and partition-order fields.
+fn plan_partitions(
+    residuals: &[i64],
+    block_size: usize,
+    order: usize,
+) -> (u32, RicePartitions, u64) {
+    let mut best: Option<(u32, RicePartitions, u64)> = None;
+    for partition_order in 0..=FLAC_MAX_PARTITION_ORDER {
+        let count = 1_usize << partition_order;
+        let length = block_size / count;
+        if !block_size.is_multiple_of(count) || length <= order {
+            break;
+        }
+        let mut bits = 2 + 4;
+        let mut partitions = Vec::with_capacity(count);
+        for index in 0..count {
+            let start = (index * length).max(order) - order;
+            let end = (index + 1) * length - order;
+            let partition = &residuals[start..end];
+            let (parameter, partition_bits) = best_rice_parameter(partition);
+            bits += 4 + partition_bits;
+            partitions.push((parameter, partition.to_vec()));
+        }
+        if best
+            .as_ref()
+            .is_none_or(|(.., best_bits)| bits < *best_bits)
+        {
+            best = Some((partition_order, partitions, bits));
+        }
+    }
+    best.unwrap_or_else(|| {
+        let (parameter, bits) = best_rice_parameter(residuals);
+        (0, vec![(parameter, residuals.to_vec())], 2 + 4 + 4 + bits)
+    })
+}
+
+fn best_rice_parameter(residuals: &[i64]) -> (u32, u64) {
+    let folded: Vec<u64> = residuals.iter().map(|residual| zigzag(*residual)).collect();
+    let cost = |parameter: u32| -> u64 {
+        folded
+            .iter()
+            .map(|value| (value >> parameter) + 1 + u64::from(parameter))
+            .sum()
+    };
+    // The optimum sits next to log2 of the mean folded residual, so only
+    // its neighbours are costed exactly.
+    let mean = folded.iter().sum::<u64>() / folded.len().max(1) as u64;
+    let estimate = (u64::BITS - mean.leading_zeros()).min(FLAC_MAX_RICE_PARAMETER);
+    (estimate.saturating_sub(1)..=(estimate + 1).min(FLAC_MAX_RICE_PARAMETER))
+        .map(|parameter| (parameter, cost(parameter)))
+        .min_by_key(|(_, bits)| *bits)
+        .unwrap_or((0, 0))
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    pending_bits: u32,
+}
+
+impl BitWriter {
+    /// Append the low `count` bits of `value`, most significant first.
+    /// `count` is at most 32.
+    fn write(&mut self, value: u64, count: u32) {
+        if count == 0 {
+            return;
+        }
+        self.accumulator = (self.accumulator << count) | (value & ((1_u64 << count) - 1));
+        self.pending_bits += count;
+        while self.pending_bits >= 8 {
+            self.pending_bits -= 8;
+            self.bytes
+                .push((self.accumulator >> self.pending_bits) as u8);
+        }
+        self.accumulator &= (1_u64 << self.pending_bits) - 1;
+    }
+
+    fn write_signed(&mut self, value: i64, count: u32) {
+        self.write(value as u64, count);
+    }
+
+    fn write_rice(&mut self, value: i64, parameter: u32) {
+        let folded = zigzag(value);
+        let mut quotient = folded >> parameter;
+        while quotient >= 32 {
+            self.write(0, 32);
+            quotient -= 32;
+        }
+        self.write(1, quotient as u32 + 1);
+        self.write(folded, parameter);
+    }
+
+    fn align(&mut self) {
+        if self.pending_bits > 0 {
+            self.write(0, 8 - self.pending_bits);
+        }
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0_u8, |mut crc, byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0_u16, |mut crc, byte| {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use symphonia::core::{
+        audio::SampleBuffer, codecs::DecoderOptions, errors::Error as SymphoniaError,
+        formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+    };
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("echo-jam-recording-{label}-{}", random_secret()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn test_signal(frames: usize) -> Vec<f32> {
+        let mut noise = 0x2545_f491_u32;
+        (0..frames)
+            .flat_map(|frame| {
+                noise ^= noise << 13;
+                noise ^= noise >> 17;
+                noise ^= noise << 5;
+                let jitter = (noise % 200) as f32 / 32_767.0;
+                let tone = (frame as f32 * 0.031).sin() * 0.4;
+                // A silent stretch exercises constant subframes.
+                if (5_000..9_200).contains(&frame) {
+                    [0.0, 0.0]
+                } else {
+                    [tone + jitter, tone * 0.5 - jitter]
+                }
+            })
+            .collect()
+    }
+
+    fn decode(bytes: Vec<u8>) -> (Vec<i16>, Vec<(String, String)>) {
+        let stream = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension("flac");
+        let mut probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .unwrap();
+        let tags = probed
+            .format
+            .metadata()
+            .current()
+            .map(|revision| {
+                revision
+                    .tags()
+                    .iter()
+                    .map(|tag| (tag.key.clone(), tag.value.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let track = probed.format.default_track().unwrap();
+        assert_eq!(track.codec_params.sample_rate, Some(TARGET_RATE));
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .unwrap();
+        let mut samples = Vec::new();
+        loop {
+            let packet = match probed.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(error))
+                    if error.kind() == io::ErrorKind::UnexpectedEof =>
+                {
+                    break;
+                }
+                Err(error) => panic!("{error}"),
+            };
+            let decoded = decoder.decode(&packet).unwrap();
+            let mut buffer = SampleBuffer::<i16>::new(decoded.capacity() as u64, *decoded.spec());
+            buffer.copy_interleaved_ref(decoded);
+            samples.extend_from_slice(buffer.samples());
+        }
+        (samples, tags)
+    }
+
+    #[test]
+    fn flac_encoder_round_trips_losslessly_with_tags() {
+        // Two full blocks and a short final block.
+        let signal = test_signal(FLAC_BLOCK_SIZE * 2 + 1_234);
+        let mut writer = FlacWriter::new(
+            Cursor::new(Vec::new()),
+            TARGET_RATE,
+            TARGET_CHANNELS,
+            &[
+                ("TITLE", "Late Night"),
+                ("ARTIST", "The Echoes"),
+                ("COMMENT", ""),
+            ],
+        )
+        .unwrap();
+        for chunk in signal.chunks(1_920) {
+            writer.write_samples(chunk).unwrap();
+        }
+        let (cursor, frames) = writer.finish().unwrap();
+        assert_eq!(frames, (FLAC_BLOCK_SIZE * 2 + 1_234) as u64);
+        let bytes = cursor.into_inner();
+        assert!(
+            bytes.len() < signal.len() * 2 * 3 / 4,
+            "expected compression, got {} bytes",
+            bytes.len()
+        );
+
+        let (decoded, tags) = decode(bytes);
+        let expected: Vec<i16> = signal
+            .iter()
+            .map(|sample| (sample.clamp(-1.0, 1.0) * 32_767.0).round() as i16)
+            .collect();
+        assert_eq!(decoded, expected);
+        assert!(tags.contains(&("TITLE".to_string(), "Late Night".to_string())));
+        assert!(tags.contains(&("ARTIST".to_string(), "The Echoes".to_string())));
+        assert!(!tags.iter().any(|(key, _)| key == "COMMENT"));
+    }
+
+    #[test]
+    fn utf8_frame_numbers_use_the_flac_variable_length_coding() {
+        assert_eq!(utf8_frame_number(0x7f), vec![0x7f]);
+        assert_eq!(utf8_frame_number(0x80), vec![0xc2, 0x80]);
+        assert_eq!(utf8_frame_number(0x1234), vec![0xe1, 0x88, 0xb4]);
+    }
+
+    fn segment_track(name: &str) -> SegmentTrack {
+        SegmentTrack {
+            started_at_ms: 1_000,
+            spotify_id: "0VjIjW4GlUZAMYd2vXMi3b".to_string(),
+            spotify_url: "https://open.spotify.com/track/0VjIjW4GlUZAMYd2vXMi3b".to_string(),
+            name: name.to_string(),
+            artist: "The Echoes".to_string(),
+            album_art_url: String::new(),
+        }
+    }
+
+    fn record(
+        store: &JamRecordingStore,
+        track_number: u32,
+        seconds: usize,
+        now_ms: u64,
+    ) -> Option<JamRecordingEntry> {
+        let mut segment = store
+            .create_segment("main", 7, track_number, segment_track("Late Night"))
+            .unwrap();
+        segment
+            .flac
+            .write_samples(&test_signal(TARGET_RATE as usize * seconds))
+            .unwrap();
+        store.finish_segment(segment, now_ms).unwrap()
+    }
+
+    #[test]
+    fn store_publishes_segments_and_drops_short_ones() {
+        let dir = temp_dir("publish");
+        let store = JamRecordingStore::open(dir.clone(), 14, 64, 10_000).unwrap();
+        let entry = record(&store, 1, 3, 10_000).unwrap();
+        assert_eq!(entry.duration_ms, 3_000);
+        assert_eq!(entry.room, "main");
+        assert!(entry.bytes > 0);
+        assert_eq!(
+            entry.download_filename(),
+            "main 01 - The Echoes - Late Night.flac"
+        );
+        assert!(record(&store, 2, 1, 10_000).is_none());
+
+        assert_eq!(store.list().unwrap(), vec![entry.clone()]);
+        let (found, path) = store.get(&entry.recording_id).unwrap().unwrap();
+        assert_eq!(found, entry);
+        assert_eq!(fs::metadata(path).unwrap().len(), entry.bytes);
+        assert!(store.get("jr1_../../etc/passwd").unwrap().is_none());
+        let leftovers: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".part"))
+            .collect();
+        assert!(leftovers.is_empty());
+
+        assert!(store.delete(&entry.recording_id).unwrap());
+        assert!(!store.delete(&entry.recording_id).unwrap());
+        assert!(store.list().unwrap().is_empty());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn store_prunes_by_age_and_total_size_and_cleans_up_after_a_crash() {
+        let dir = temp_dir("prune");
+        let store = JamRecordingStore::open(dir.clone(), 1, 64, 0).unwrap();
+        let old = record(&store, 1, 3, 1_000).unwrap();
+        let recent = record(&store, 2, 3, DAY_MS + 5_000).unwrap();
+        store.prune(DAY_MS + 5_000).unwrap();
+        assert_eq!(store.list().unwrap(), vec![recent.clone()]);
+        assert!(!store.audio_path(&old.recording_id).exists());
+
+        // A budget smaller than two recordings keeps only the newest.
+        let tight = JamRecordingStore {
+            max_total_bytes: recent.bytes + 1,
+            ..JamRecordingStore::open(dir.clone(), 1, 64, DAY_MS + 5_000).unwrap()
+        };
+        let newest = record(&tight, 3, 3, DAY_MS + 6_000).unwrap();
+        assert_eq!(tight.list().unwrap(), vec![newest]);
+
+        fs::write(
+            dir.join("jr1_000000000000000000000000.flac.part"),
+            b"partial",
+        )
+        .unwrap();
+        fs::write(dir.join("jr1_111111111111111111111111.flac"), b"no sidecar").unwrap();
+        JamRecordingStore::open(dir.clone(), 1, 64, DAY_MS + 6_000).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn only_one_recording_per_room_generation_is_active() {
+        let store = JamRecordingStore::disabled(temp_dir("active"));
+        let (_, mut first_stop) = store.begin_active("main", 4, 1).unwrap();
+        assert!(store.begin_active("main", 4, 2).is_none());
+        assert!(store.is_recording("main"));
+        assert!(!store.is_recording("other"));
+
+        let (second, _second_stop) = store.begin_active("main", 5, 3).unwrap();
+        assert!(
+            first_stop.try_recv().is_ok(),
+            "the stale recording is stopped"
+        );
+        assert_eq!(store.active()[0].generation, 5);
+        store.end_active("main", second);
+        assert!(!store.is_recording("main"));
+        assert!(!store.stop("main"));
+    }
+}
@@ -3,12 +3,22 @@ use crate::auth::{
     RevokedParticipantBinding,
 };
 use crate::config::*;
+use crate::jam_autoplay::{is_pending_autoplay, refill_autoplay_queue, AutoplaySource};
+use crate::jam_content_filter::content_filtered_response;
+use crate::jam_echo_playlists::{echo_playlist_expansion, valid_echo_playlist_id};
 use crate::jam_history::{new_history_observation, HistoryObservation};
+use crate::jam_import::{jam_import_expansion, valid_jam_import_id};
 use crate::jam_library::{
     fetch_favorite_summary, fetch_playlist_expansion, fetch_playlist_selection, valid_spotify_id,
     validate_selected_playlist_positions, FavoriteKind, FavoriteSummary, JamApiError,
     SkippedPlaylistItem,
 };
+use crate::jam_local_library::{local_track_id_from_uri, LocalTrack};
+use crate::jam_playback_clock::publish_playback_clock;
+use crate::jam_queue_policy::{
+    admit_queue_entries, place_queue_entries, JamQueuePolicy, QueueAdmission,
+};
+use crate::jam_skip_vote::{record_skip_vote, skip_vote_tally, SkipVoteOutcome, SkipVotes};
 use crate::rooms::schedule_jam_auto_end;
 use crate::AppState;
 
@@ -23,6 +33,7 @@ use std::{
     collections::{HashMap, HashSet},
     fs,
     future::Future,
+    sync::{Arc, Mutex},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tracing::{info, warn};
@@ -34,6 +45,7 @@ const SPOTIFY_CONNECT_REGISTRATION_POLL_INTERVAL: Duration = Duration::from_secs
 const SPOTIFY_CONNECT_REGISTRATION_POLL_ATTEMPTS: usize = 4;
 const SPOTIFY_CONNECT_REPAIR_DEADLINE: Duration = Duration::from_secs(15);
 const SOURCE_START_RECHECK_INTERVAL: Duration = Duration::from_millis(100);
+const SOURCE_FAILOVER_READY_TIMEOUT: Duration = Duration::from_secs(10);
 const SPOTIFY_COMMITTED_QUEUE_FRONTIER: usize = 2;
 const MAX_QUEUE_REMOVAL_ENTRIES: usize = 1_000;
 const SPOTIFY_LIBRARY_SCOPES: [&str; 3] = [
@@ -41,6 +53,10 @@ const SPOTIFY_LIBRARY_SCOPES: [&str; 3] = [
     "playlist-read-private",
     "playlist-read-collaborative",
 ];
+// Optional: only exporting Jam history or favorites as a Spotify playlist needs
+// them, so tokens granted before playlist export keep working for playback.
+const SPOTIFY_PLAYLIST_WRITE_SCOPES: [&str; 2] =
+    ["playlist-modify-private", "playlist-modify-public"];
 const SPOTIFY_CALLBACK_RECEIVED_HTML: &str = "<html><body><h1>Spotify authorization received</h1><p>Return to Echo Chamber while it verifies the connection. You can close this tab.</p></body></html>";
 
 // ── Structs ──────────────────────────────────────────────────────────────
@@ -61,9 +77,9 @@ fn spotify_scope_from_token_response(data: &serde_json::Value, fallback: Option<
         .to_string()
 }
 
-pub(crate) fn spotify_library_scopes_authorized(token: Option<&SpotifyToken>) -> bool {
+fn spotify_scopes_granted(token: Option<&SpotifyToken>, required_scopes: &[&str]) -> bool {
     token.is_some_and(|token| {
-        SPOTIFY_LIBRARY_SCOPES.iter().all(|required_scope| {
+        required_scopes.iter().all(|required_scope| {
             token
                 .scope
                 .split_ascii_whitespace()
@@ -72,6 +88,14 @@ pub(crate) fn spotify_library_scopes_authorized(token: Option<&SpotifyToken>) ->
     })
 }
 
+pub(crate) fn spotify_library_scopes_authorized(token: Option<&SpotifyToken>) -> bool {
+    spotify_scopes_granted(token, &SPOTIFY_LIBRARY_SCOPES)
+}
+
+pub(crate) fn spotify_playlist_write_authorized(token: Option<&SpotifyToken>) -> bool {
+    spotify_scopes_granted(token, &SPOTIFY_PLAYLIST_WRITE_SCOPES)
+}
+
 pub(crate) struct SpotifyPending {
     pub(crate) state: String,
     pub(crate) code: Option<String>,
@@ -84,12 +108,12 @@ pub(crate) struct JamState {
     pub(crate) generation: u64,
     pub(crate) host_identity: String,
     pub(crate) host_participant_auth_id: String,
-    pub(crate) spotify_token: Option<SpotifyToken>,
     pub(crate) queue: Vec<JamQueueEntry>,
     pub(crate) queue_revision: u64,
     pub(crate) track_queue_receipts: HashMap<String, TrackQueueReceipt>,
     pub(crate) playlist_queue_receipts: HashMap<String, PlaylistQueueReceipt>,
     pub(crate) queue_removal_receipts: HashMap<String, QueueRemovalReceipt>,
+    pub(crate) queue_move_receipts: HashMap<String, QueueMoveReceipt>,
     pub(crate) queue_control_epoch: u64,
     // Monotonic admission fence for any transition into a stopped queue. Unlike
     // `queue_control_stopped`, this is not cleared when a later explicit add
@@ -97,9 +121,14 @@ pub(crate) struct JamState {
     pub(crate) queue_stop_epoch: u64,
     pub(crate) queue_control_stopped: bool,
     pub(crate) uncertain_skip: Option<UncertainSkipBoundary>,
+    pub(crate) skip_votes: Option<SkipVotes>,
+    // Bumped whenever a track starts, including the same song again.
+    pub(crate) track_epoch: u64,
+    pub(crate) autoplay: bool,
     pub(crate) last_history_spotify_id: Option<String>,
     pub(crate) last_history_was_echo: bool,
     pub(crate) now_playing: Option<NowPlayingInfo>,
+    pub(crate) playback_clock: crate::jam_playback_clock::PlaybackClock,
     pub(crate) listeners: HashMap<String, String>,
     pub(crate) audio_connections: HashMap<String, JamAudioConnection>,
     pub(crate) next_audio_connection_id: u64,
@@ -108,6 +137,173 @@ pub(crate) struct JamState {
     pub(crate) last_error: Option<String>,
     pub(crate) spotify_is_playing: bool,
     pub(crate) audio_expected_since: Option<std::time::Instant>,
+    pub(crate) playback_source: JamPlaybackSource,
+    pub(crate) source_failover: Option<JamSourceFailover>,
+}
+
+/// The most recent handoff of this generation to a standby source agent.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct JamSourceFailover {
+    pub(crate) from_source_id: Option<String>,
+    pub(crate) to_source_id: String,
+    pub(crate) reason: String,
+    pub(crate) at_ms: u64,
+}
+
+/// What produces the audio for a Jam generation. Spotify Jams capture the
+/// bound Spotify device through the source agent; local Jams are decoded from
+/// the server's music library and never touch Spotify.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JamPlaybackSource {
+    #[default]
+    Spotify,
+    Local,
+}
+
+/// One room's Jam. Each room owns its state, listener relay, and lifecycle
+/// fences; the Spotify account, capture source, and library stores stay
+/// server-wide.
+#[derive(Clone, Default)]
+pub(crate) struct JamRoom {
+    pub(crate) jam: Arc<Mutex<JamState>>,
+    pub(crate) bot: Arc<tokio::sync::Mutex<Option<crate::jam_bot::JamBot>>>,
+    pub(crate) local_player: Arc<tokio::sync::Mutex<Option<crate::jam_local_library::LocalPlayer>>>,
+    pub(crate) lifecycle: Arc<tokio::sync::Mutex<()>>,
+    pub(crate) queue_lifecycle: Arc<tokio::sync::Mutex<()>>,
+    pub(crate) state_refresh: Arc<tokio::sync::Mutex<()>>,
+    last_used_ms: Arc<std::sync::atomic::AtomicU64>,
+}
+
+/// Rooms whose Jam has been idle and unreferenced this long are dropped.
+const JAM_ROOM_IDLE_EVICT_MS: u64 = 10 * 60 * 1_000;
+
+impl JamRoom {
+    /// Nothing but the room map holds this room, its Jam is over, and no view
+    /// has been taken for `JAM_ROOM_IDLE_EVICT_MS`.
+    fn evictable(&self, now_ms: u64) -> bool {
+        if Arc::strong_count(&self.jam) != 1
+            || Arc::strong_count(&self.bot) != 1
+            || Arc::strong_count(&self.local_player) != 1
+            || now_ms.saturating_sub(self.last_used_ms.load(std::sync::atomic::Ordering::Relaxed))
+                < JAM_ROOM_IDLE_EVICT_MS
+        {
+            return false;
+        }
+        let idle = self.jam.try_lock().is_ok_and(|jam| {
+            !jam.active
+                && !jam.starting
+                && jam.listeners.is_empty()
+                && jam.audio_connections.is_empty()
+        });
+        idle && self.bot.try_lock().is_ok_and(|bot| bot.is_none())
+            && self
+                .local_player
+                .try_lock()
+                .is_ok_and(|player| player.is_none())
+    }
+}
+
+impl AppState {
+    /// A view of this state whose Jam fields belong to `room`. Rooms are
+    /// created on first use and only evicted once nothing references them, so
+    /// every live view of one room shares the same lifecycle fences. Blank
+    /// names are refused: the unscoped state's own Jam fields are a detached
+    /// placeholder that no request acts on.
+    pub(crate) fn for_jam_room(&self, room: &str) -> Result<AppState, StatusCode> {
+        if room.trim().is_empty() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let jam_room = self
+            .jam_rooms
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .entry(room.to_string())
+            .or_default()
+            .clone();
+        jam_room
+            .last_used_ms
+            .store(now_ts_ms(), std::sync::atomic::Ordering::Relaxed);
+        Ok(self.with_jam_room(room, jam_room))
+    }
+
+    /// Like `for_jam_room`, but never creates a room that has not held Jam
+    /// state yet.
+    pub(crate) fn existing_jam_room(&self, room: &str) -> Option<AppState> {
+        let jam_room = self
+            .jam_rooms
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .get(room)
+            .cloned()?;
+        jam_room
+            .last_used_ms
+            .store(now_ts_ms(), std::sync::atomic::Ordering::Relaxed);
+        Some(self.with_jam_room(room, jam_room))
+    }
+
+    /// Drop rooms whose Jam is over and that no handler, task, or listener
+    /// still references. Views are only taken under the `jam_rooms` lock, so
+    /// an unreferenced room cannot gain one while it is being removed.
+    pub(crate) fn evict_idle_jam_rooms(&self, now_ms: u64) -> usize {
+        let mut rooms = self
+            .jam_rooms
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let before = rooms.len();
+        rooms.retain(|_, jam_room| !jam_room.evictable(now_ms));
+        before - rooms.len()
+    }
+
+    fn with_jam_room(&self, room: &str, jam_room: JamRoom) -> AppState {
+        let mut scoped = self.clone();
+        scoped.jam_room = Arc::from(room);
+        scoped.jam = jam_room.jam;
+        scoped.jam_bot = jam_room.bot;
+        scoped.jam_local_player = jam_room.local_player;
+        scoped.jam_lifecycle = jam_room.lifecycle;
+        scoped.jam_queue_lifecycle = jam_room.queue_lifecycle;
+        scoped.jam_state_refresh = jam_room.state_refresh;
+        scoped
+    }
+
+    /// Scoped views of every room that has held Jam state, in room order.
+    pub(crate) fn jam_room_states(&self) -> Vec<AppState> {
+        let mut rooms: Vec<(String, JamRoom)> = self
+            .jam_rooms
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .iter()
+            .map(|(room, jam_room)| (room.clone(), jam_room.clone()))
+            .collect();
+        rooms.sort_by(|a, b| a.0.cmp(&b.0));
+        rooms
+            .into_iter()
+            .map(|(room, jam_room)| self.with_jam_room(&room, jam_room))
+            .collect()
+    }
+
+    /// The room whose Jam last used `generation`. Generations are allocated
+    /// server-wide, so at most one room can match.
+    pub(crate) fn jam_room_for_generation(&self, generation: u64) -> Option<AppState> {
+        self.jam_room_states().into_iter().find(|scoped| {
+            scoped
+                .jam
+                .lock()
+                .unwrap_or_else(|error| error.into_inner())
+                .generation
+                == generation
+        })
+    }
+
+    /// Allocate a Jam generation that no room has used. Source audio, listener
+    /// sockets, and the local player are keyed by generation alone.
+    pub(crate) fn next_jam_generation(&self) -> u64 {
+        self.jam_generation
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+            .wrapping_add(1)
+            .max(1)
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
@@ -117,6 +313,8 @@ pub(crate) enum QueueDeliveryState {
     #[default]
     SpotifyCommitted,
     CommitUnknown,
+    // The server-side local library player is decoding this entry.
+    LocalPlaying,
 }
 
 impl QueueDeliveryState {
@@ -176,6 +374,9 @@ pub(crate) struct QueuedTrack {
     pub(crate) playlist: Option<QueuedPlaylistProvenance>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) playlist_position: Option<usize>,
+    // Set when autoplay picked this track rather than a person.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) autoplay: Option<AutoplaySource>,
     // Kept for compatibility with the existing viewer during rollout.
     pub(crate) added_by: String,
 }
@@ -188,6 +389,10 @@ pub(crate) struct JamQueueEntry {
     pub(crate) delivery_state: QueueDeliveryState,
     #[serde(default)]
     pub(crate) can_remove: bool,
+    // Someone placed this entry by hand, so fair-share ordering leaves it
+    // where they put it.
+    #[serde(default)]
+    pub(crate) pinned: bool,
     // A track placed through Spotify's Add to Queue endpoint is not the current
     // occurrence yet. Keep that distinction internal so an already-playing
     // track with the same URI cannot steal this queue entry's provenance.
@@ -195,11 +400,12 @@ pub(crate) struct JamQueueEntry {
     current_match_state: QueueCurrentMatchState,
 }
 
-fn pending_queue_entry(track: QueuedTrack) -> JamQueueEntry {
+pub(crate) fn pending_queue_entry(track: QueuedTrack) -> JamQueueEntry {
     JamQueueEntry {
         track,
         delivery_state: QueueDeliveryState::Pending,
         can_remove: true,
+        pinned: false,
         current_match_state: QueueCurrentMatchState::Eligible,
     }
 }
@@ -385,6 +591,117 @@ fn queue_removal_receipt_response(
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct QueueMoveFingerprint {
+    expected_queue_revision: u64,
+    // Order matters here: the moved entries land in exactly this order.
+    queue_entry_ids: Vec<String>,
+    before_queue_entry_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum QueueMoveMutationError {
+    QueueChanged,
+    NotMovable,
+}
+
+/// Move pending entries, in the given order, to just before
+/// `before_queue_entry_id`, or to the end of the queue when it is `None`.
+/// The anchor must itself be pending so moved entries can never jump ahead of
+/// anything already handed to Spotify or the local player.
+fn move_pending_queue_entries(
+    jam: &mut JamState,
+    queue_entry_ids: &[String],
+    before_queue_entry_id: Option<&str>,
+) -> Result<(), QueueMoveMutationError> {
+    let movable = |entry_id: &str| {
+        jam.queue
+            .iter()
+            .find(|entry| entry.track.queue_entry_id == entry_id)
+            .map(|entry| entry.delivery_state.is_removable() && entry.can_remove)
+    };
+    for entry_id in queue_entry_ids
+        .iter()
+        .map(String::as_str)
+        .chain(before_queue_entry_id)
+    {
+        match movable(entry_id) {
+            None => return Err(QueueMoveMutationError::QueueChanged),
+            Some(false) => return Err(QueueMoveMutationError::NotMovable),
+            Some(true) => {}
+        }
+    }
+    let selected = queue_entry_ids
+        .iter()
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+    let (mut moved, mut remaining): (Vec<_>, Vec<_>) = std::mem::take(&mut jam.queue)
+        .into_iter()
+        .partition(|entry| selected.contains(entry.track.queue_entry_id.as_str()));
+    for entry in &mut moved {
+        entry.pinned = true;
+    }
+    moved.sort_by_key(|entry| {
+        queue_entry_ids
+            .iter()
+            .position(|entry_id| *entry_id == entry.track.queue_entry_id)
+    });
+    let insert_at = before_queue_entry_id
+        .and_then(|anchor| {
+            remaining
+                .iter()
+                .position(|entry| entry.track.queue_entry_id == anchor)
+        })
+        .unwrap_or(remaining.len());
+    remaining.splice(insert_at..insert_at, moved);
+    jam.queue = remaining;
+    jam.queue_revision = jam.queue_revision.wrapping_add(1);
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct QueueMoveReceipt {
+    actor_id: String,
+    generation: u64,
+    fingerprint: QueueMoveFingerprint,
+    created_at_ms: u64,
+    response: JamQueueMoveResponse,
+}
+
+fn insert_queue_move_receipt(jam: &mut JamState, request_id: String, receipt: QueueMoveReceipt) {
+    if jam.queue_move_receipts.len() >= 128 {
+        let oldest = jam
+            .queue_move_receipts
+            .iter()
+            .min_by_key(|(_, receipt)| receipt.created_at_ms)
+            .map(|(request_id, _)| request_id.clone());
+        if let Some(oldest) = oldest {
+            jam.queue_move_receipts.remove(&oldest);
+        }
+    }
+    jam.queue_move_receipts.insert(request_id, receipt);
+}
+
+fn queue_move_receipt_response(
+    jam: &JamState,
+    request_id: &str,
+    actor_id: &str,
+    generation: u64,
+    fingerprint: &QueueMoveFingerprint,
+) -> Result<Option<JamQueueMoveResponse>, ()> {
+    let Some(receipt) = jam.queue_move_receipts.get(request_id) else {
+        return Ok(None);
+    };
+    if receipt.actor_id == actor_id
+        && receipt.generation == generation
+        && &receipt.fingerprint == fingerprint
+    {
+        Ok(Some(receipt.response.clone()))
+    } else {
+        Err(())
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct NowPlayingInfo {
     #[serde(default)]
@@ -431,6 +748,8 @@ pub(crate) struct SpotifyTokenRequest {
 #[derive(Deserialize)]
 pub(crate) struct JamStartRequest {
     identity: String,
+    #[serde(default)]
+    source: JamPlaybackSource,
 }
 
 #[derive(Deserialize)]
@@ -471,6 +790,25 @@ pub(crate) struct JamQueueRemoveResponse {
     removed_count: usize,
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct JamQueueMoveRequest {
+    generation: u64,
+    request_id: String,
+    expected_queue_revision: u64,
+    queue_entry_ids: Vec<String>,
+    #[serde(default)]
+    before_queue_entry_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct JamQueueMoveResponse {
+    ok: bool,
+    generation: u64,
+    queue_revision: u64,
+    moved_entry_ids: Vec<String>,
+    before_queue_entry_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct PlaylistQueueRequest {
     generation: u64,
@@ -525,6 +863,12 @@ pub(crate) struct JamGenerationRequest {
     generation: u64,
 }
 
+#[derive(Deserialize)]
+pub(crate) struct JamAutoplayRequest {
+    generation: u64,
+    enabled: bool,
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 struct JamAudioAuthMessage {
@@ -551,7 +895,7 @@ pub(crate) async fn jam_spotify_init(
         "https://127.0.0.1:{}/api/jam/spotify-callback",
         state.config.port
     );
-    let scopes = "user-read-private user-modify-playback-state user-read-currently-playing user-read-playback-state user-library-read playlist-read-private playlist-read-collaborative";
+    let scopes = "user-read-private user-modify-playback-state user-read-currently-playing user-read-playback-state user-library-read playlist-read-private playlist-read-collaborative playlist-modify-private playlist-modify-public";
     let auth_url = format!(
         "https://accounts.spotify.com/authorize?client_id={}&response_type=code&redirect_uri={}&scope={}&state={}&code_challenge={}&code_challenge_method=S256",
         urlencoded(&client_id),
@@ -629,6 +973,17 @@ fn spotify_upstream_message(body: &str) -> String {
         })
 }
 
+pub(crate) fn spotify_playlist_scope_required_error() -> JamApiError {
+    JamApiError {
+        status: StatusCode::FORBIDDEN,
+        code: "spotify_playlist_scope_required",
+        message:
+            "Spotify playlist creation is not authorized. Use Refresh Spotify Access, then try again."
+                .to_string(),
+        retry_after: None,
+    }
+}
+
 pub(crate) fn spotify_library_scope_required_error() -> JamApiError {
     JamApiError {
         status: StatusCode::FORBIDDEN,
@@ -876,10 +1231,10 @@ pub(crate) async fn jam_spotify_token(
 
     // Do not replace a previously working token until the candidate has passed
     // both scope inspection and a real Spotify Web API request.
-    {
-        let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
-        jam.spotify_token = Some(token.clone());
-    }
+    *state
+        .spotify_token
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = Some(token.clone());
     // A new authorization may belong to a different Spotify account. Playlist
     // covers are process-memory-only, but private artwork must not survive that
     // account boundary.
@@ -988,12 +1343,8 @@ pub(crate) async fn spotify_api_request(
     url: &str,
     body: Option<serde_json::Value>,
 ) -> Result<reqwest::Response, (StatusCode, String)> {
-    let token = {
-        let jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
-        jam.spotify_token
-            .clone()
-            .ok_or((StatusCode::BAD_REQUEST, "Spotify not connected".to_string()))?
-    };
+    let token = current_spotify_token(state)
+        .ok_or((StatusCode::BAD_REQUEST, "Spotify not connected".to_string()))?;
 
     let mut req = state
         .http_client
@@ -1059,17 +1410,19 @@ pub(crate) async fn spotify_api_request(
     Ok(resp)
 }
 
+/// The Spotify account is shared by every room's Jam, so its token lives on
+/// `AppState` rather than in any one room's `JamState`.
+pub(crate) fn current_spotify_token(state: &AppState) -> Option<SpotifyToken> {
+    state
+        .spotify_token
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .clone()
+}
+
 async fn refresh_spotify_token(state: &AppState, old: &SpotifyToken) -> Option<SpotifyToken> {
     let _refresh = state.spotify_refresh_lock.lock().await;
-    let current = {
-        state
-            .jam
-            .lock()
-            .unwrap_or_else(|error| error.into_inner())
-            .spotify_token
-            .clone()
-    };
-    if let Some(current) = current {
+    if let Some(current) = current_spotify_token(state) {
         if current.access_token != old.access_token {
             return Some(current);
         }
@@ -1106,10 +1459,10 @@ async fn refresh_spotify_token(state: &AppState, old: &SpotifyToken) -> Option<S
         scope: spotify_scope_from_token_response(&data, Some(&old.scope)),
     };
 
-    {
-        let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
-        jam.spotify_token = Some(new_token.clone());
-    }
+    *state
+        .spotify_token
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = Some(new_token.clone());
     let persisted = persist_spotify_token(
         &state.spotify_token_file,
         &new_token,
@@ -1206,7 +1559,7 @@ async fn resolve_spotify_device(state: &AppState) -> Result<SpotifyDevice, (Stat
             let source = state.jam_source.snapshot().await;
             jam_source_start_preflight(&source)
                 .map_err(|(status, message)| SpotifyDeviceResolveError::Other(status, message))?;
-            resolve_spotify_device_once(state).await
+            resolve_spotify_device_once(state, source.source_id.as_deref()).await
         },
         |action| state.jam_source.repair_spotify_connect(action, deadline),
         SPOTIFY_CONNECT_REGISTRATION_POLL_ATTEMPTS,
@@ -1219,8 +1572,12 @@ async fn resolve_spotify_device(state: &AppState) -> Result<SpotifyDevice, (Stat
 
 async fn resolve_spotify_device_once(
     state: &AppState,
+    source_id: Option<&str>,
 ) -> Result<SpotifyDevice, SpotifyDeviceResolveError> {
-    if state.config.spotify_device_id.is_none() && state.config.spotify_device_name.is_none() {
+    // Each source PC runs its own Spotify Connect device.
+    let (device_id, device_name) =
+        crate::jam_source::jam_source_device_preference(&state.config, source_id);
+    if device_id.is_none() && device_name.is_none() {
         return Err(SpotifyDeviceResolveError::Other(
             StatusCode::SERVICE_UNAVAILABLE,
             "Spotify Connect device is not configured (set SPOTIFY_DEVICE_ID or SPOTIFY_DEVICE_NAME)"
@@ -1247,11 +1604,7 @@ async fn resolve_spotify_device_once(
     })?;
     let candidates = parse_spotify_devices(&data)?;
 
-    select_spotify_device(
-        candidates,
-        state.config.spotify_device_id.as_deref(),
-        state.config.spotify_device_name.as_deref(),
-    )
+    select_spotify_device(candidates, device_id.as_deref(), device_name.as_deref())
 }
 
 fn parse_spotify_devices(
@@ -1468,20 +1821,29 @@ async fn bind_spotify_playback_to_device(
     } else {
         return Err(spotify_response_error(response, "Read Spotify playback").await);
     };
+    transfer_spotify_playback(state, device, playback.is_playing).await?;
+    Ok(playback)
+}
+
+async fn transfer_spotify_playback(
+    state: &AppState,
+    device: &SpotifyDevice,
+    play: bool,
+) -> Result<(), (StatusCode, String)> {
     let response = spotify_api_request(
         state,
         reqwest::Method::PUT,
         "https://api.spotify.com/v1/me/player",
         Some(serde_json::json!({
             "device_ids": [device.id],
-            "play": playback.is_playing,
+            "play": play,
         })),
     )
     .await?;
     if !response.status().is_success() {
         return Err(spotify_response_error(response, "Transfer Spotify playback").await);
     }
-    Ok(playback)
+    Ok(())
 }
 
 fn spotify_pause_url(device_id: &str) -> String {
@@ -1544,11 +1906,15 @@ pub(crate) fn clear_active_jam_state(jam: &mut JamState) {
     jam.track_queue_receipts.clear();
     jam.playlist_queue_receipts.clear();
     jam.queue_removal_receipts.clear();
+    jam.queue_move_receipts.clear();
+    jam.skip_votes = None;
+    jam.autoplay = false;
     jam.last_history_spotify_id = None;
     jam.last_history_was_echo = false;
     jam.listeners.clear();
     jam.audio_connections.clear();
     jam.now_playing = None;
+    jam.playback_clock = Default::default();
     jam.spotify_device_id = None;
     jam.spotify_device_name = None;
     jam.spotify_is_playing = false;
@@ -1556,6 +1922,8 @@ pub(crate) fn clear_active_jam_state(jam: &mut JamState) {
     jam.queue_control_stopped = false;
     jam.uncertain_skip = None;
     jam.audio_expected_since = None;
+    jam.playback_source = JamPlaybackSource::Spotify;
+    jam.source_failover = None;
 }
 
 #[derive(Clone, Copy)]
@@ -1662,6 +2030,17 @@ async fn end_jam_generation_locked(
             None
         }
     };
+    let local_player = {
+        let mut guard = state.jam_local_player.lock().await;
+        if guard.as_ref().map(|player| player.generation()) == Some(generation) {
+            guard.take()
+        } else {
+            None
+        }
+    };
+    if let Some(player) = local_player {
+        player.stop().await;
+    }
     if let Some(bot) = bot {
         bot.stop().await;
     } else {
@@ -1671,14 +2050,125 @@ async fn end_jam_generation_locked(
     true
 }
 
-pub(crate) async fn end_jam_for_source_unavailable(
+/// Move an active Spotify generation to the best healthy standby source and
+/// transfer playback to that PC's Spotify Connect device, keeping the queue,
+/// listeners, and relay. The caller holds `jam_lifecycle`. Returns false when
+/// no standby took over; a failed handoff leaves the new source active so
+/// ending the generation still stops it.
+async fn fail_over_jam_source_locked(state: &AppState, generation: u64, reason: &str) -> bool {
+    let (previous_device, was_playing) = {
+        let jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
+        if !jam.active
+            || jam.generation != generation
+            || jam.playback_source != JamPlaybackSource::Spotify
+        {
+            return false;
+        }
+        (
+            bound_spotify_device(&jam, generation),
+            jam.spotify_is_playing,
+        )
+    };
+    let Some(standby) = state.jam_source.failover_candidate().await else {
+        return false;
+    };
+    let previous_source = state.jam_source.snapshot().await.source_id;
+    warn!(
+        "Jam generation {} failing over from source {:?} to {}: {}",
+        generation, previous_source, standby, reason
+    );
+
+    // Pause first, as in teardown, so the old PC does not resume locally.
+    pause_bound_spotify_before_release(state, generation, previous_device.as_ref()).await;
+    if let Err(error) = state
+        .jam_source
+        .hand_off(generation, &standby, SOURCE_FAILOVER_READY_TIMEOUT)
+        .await
+    {
+        warn!(
+            "Jam generation {} source handoff failed: {}",
+            generation, error
+        );
+        return false;
+    }
+    let device = match resolve_spotify_device(state).await {
+        Ok(device) => device,
+        Err((_, error)) => {
+            warn!(
+                "Jam generation {} could not find Spotify on source {}: {}",
+                generation, standby, error
+            );
+            return false;
+        }
+    };
+    match tokio::time::timeout(
+        SPOTIFY_START_BIND_TIMEOUT,
+        transfer_spotify_playback(state, &device, was_playing),
+    )
+    .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err((_, error))) => {
+            warn!(
+                "Jam generation {} could not transfer Spotify to {}: {}",
+                generation, device.name, error
+            );
+            return false;
+        }
+        Err(_) => {
+            warn!(
+                "Jam generation {} Spotify transfer to {} timed out",
+                generation, device.name
+            );
+            return false;
+        }
+    }
+
+    let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
+    if !jam.active || jam.generation != generation {
+        return false;
+    }
+    jam.spotify_device_id = Some(device.id.clone());
+    jam.spotify_device_name = Some(device.name.clone());
+    jam.spotify_is_playing = was_playing;
+    jam.audio_expected_since = was_playing.then(std::time::Instant::now);
+    jam.source_failover = Some(JamSourceFailover {
+        from_source_id: previous_source,
+        to_source_id: standby.clone(),
+        reason: reason.to_string(),
+        at_ms: now_ts_ms(),
+    });
+    info!(
+        "Jam generation {} now captures from source {} on Spotify device {}",
+        generation, standby, device.name
+    );
+    true
+}
+
+/// React to the active source failing: hand the generation to a healthy
+/// standby when one exists, otherwise end it.
+pub(crate) async fn handle_jam_source_loss(
     state: &AppState,
     generation: u64,
     reason: String,
 ) -> bool {
+    // Source events carry only the generation; route them to its room.
+    let Some(state) = state.jam_room_for_generation(generation) else {
+        return false;
+    };
     let _lifecycle = state.jam_lifecycle.lock().await;
+    if local_generation(
+        &state.jam.lock().unwrap_or_else(|e| e.into_inner()),
+        generation,
+    ) {
+        // The source agent does not carry local library audio.
+        return false;
+    }
+    if fail_over_jam_source_locked(&state, generation, &reason).await {
+        return false;
+    }
     end_jam_generation_locked(
-        state,
+        &state,
         generation,
         JamEndCondition::ActiveOrStarting,
         "source unavailable",
@@ -1687,6 +2177,10 @@ pub(crate) async fn end_jam_for_source_unavailable(
     .await
 }
 
+fn local_generation(jam: &JamState, generation: u64) -> bool {
+    jam.generation == generation && jam.playback_source == JamPlaybackSource::Local
+}
+
 fn active_jam_source_watchdog_error(
     source: &crate::jam_source::JamSourceSnapshot,
     generation: u64,
@@ -1723,18 +2217,39 @@ fn active_jam_source_watchdog_error(
 /// snapshot to retain it. Starting Jams are intentionally excluded: startup has
 /// a normal pre-source binding window and its own source-loss guard.
 pub(crate) async fn end_active_jam_if_source_unhealthy(state: &AppState) -> bool {
+    let mut ended = false;
+    for room in state.jam_room_states() {
+        ended |= end_room_jam_if_source_unhealthy(&room).await;
+    }
+    ended
+}
+
+async fn end_room_jam_if_source_unhealthy(state: &AppState) -> bool {
     let _lifecycle = state.jam_lifecycle.lock().await;
     let generation = {
         let jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
-        if !jam.active {
+        if !jam.active || jam.playback_source == JamPlaybackSource::Local {
             return false;
         }
         jam.generation
     };
     let source = state.jam_source.snapshot().await;
     let Some(reason) = active_jam_source_watchdog_error(&source, generation) else {
+        if state.jam_source.capture_failover_due(generation).await {
+            // A capture restart did not help; a standby may do better. With
+            // none available the stall keeps retrying restarts as before.
+            fail_over_jam_source_locked(
+                state,
+                generation,
+                "Jam source capture stayed stalled after a restart",
+            )
+            .await;
+        }
         return false;
     };
+    if fail_over_jam_source_locked(state, generation, &reason).await {
+        return false;
+    }
 
     end_jam_generation_locked(
         state,
@@ -1802,7 +2317,16 @@ pub(crate) async fn reconcile_revoked_participant_bindings(
     if revoked.is_empty() {
         return;
     }
+    for room in state.jam_room_states() {
+        reconcile_room_revoked_participant_bindings(&room, revoked, reason).await;
+    }
+}
 
+async fn reconcile_room_revoked_participant_bindings(
+    state: &AppState,
+    revoked: &[RevokedParticipantBinding],
+    reason: &'static str,
+) {
     let _lifecycle = state.jam_lifecycle.lock().await;
     let (stop_generation, auto_end_generation) = {
         let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
@@ -1931,32 +2455,53 @@ pub(crate) async fn jam_start(
     let actor_auth_id = actor
         .echo_participant_auth_id
         .ok_or((StatusCode::UNAUTHORIZED, String::new()))?;
+    let state = state
+        .for_jam_room(&actor.video.room)
+        .map_err(|status| (status, "A room is required".to_string()))?;
     let actor_identity = actor.sub;
     let _lifecycle = state.jam_lifecycle.lock().await;
+    if payload.source == JamPlaybackSource::Local {
+        return jam_start_local(&state, actor_identity, actor_auth_id).await;
+    }
 
     let source_before_start = state.jam_source.snapshot().await;
     jam_source_start_preflight(&source_before_start)?;
 
+    if current_spotify_token(&state).is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Spotify is not connected".to_string(),
+        ));
+    }
     let generation = {
         let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
-        if jam.spotify_token.is_none() {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                "Spotify is not connected".to_string(),
-            ));
-        }
         if jam.active || jam.starting {
             return Err((StatusCode::CONFLICT, "A Jam is already running".to_string()));
         }
         jam.starting = true;
-        jam.generation = jam.generation.wrapping_add(1).max(1);
+        jam.generation = state.next_jam_generation();
         jam.last_error = None;
+        jam.playback_source = JamPlaybackSource::Spotify;
         jam.generation
     };
+    // One capture source serves the whole server, so only one room at a time
+    // can run a Spotify Jam.
+    if let Err(owner) = state
+        .jam_source
+        .claim(&state.jam_room, generation, |owner| {
+            jam_generation_live(&state, owner.generation)
+        })
+        .await
+    {
+        let error = format!("The Jam source is in use by room {}", owner.room);
+        fail_jam_start(&state, generation, &error);
+        return Err((StatusCode::CONFLICT, error));
+    }
 
     let device = match resolve_spotify_device(&state).await {
         Ok(device) => device,
         Err(error) => {
+            state.jam_source.release(generation).await;
             fail_jam_start(&state, generation, &error.1);
             return Err(error);
         }
@@ -1974,6 +2519,7 @@ pub(crate) async fn jam_start(
         Ok(bot) => bot,
         Err(error) => {
             warn!("Jam audio bot failed to start: {}", error);
+            state.jam_source.release(generation).await;
             fail_jam_start(&state, generation, &error);
             return Err((StatusCode::SERVICE_UNAVAILABLE, error));
         }
@@ -2082,14 +2628,99 @@ pub(crate) async fn jam_start(
     Ok(Json(jam_start_response(generation, &device)))
 }
 
-fn fail_jam_start(state: &AppState, generation: u64, error: &str) {
-    let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
-    if jam.generation == generation && jam.starting {
-        jam.starting = false;
-        jam.active = false;
-        jam.last_error = Some(error.to_string());
-        jam.spotify_is_playing = false;
-        jam.audio_expected_since = None;
+/// Start a local library Jam while the caller holds `jam_lifecycle`. Audio is
+/// decoded by the in-process player, so neither Spotify nor the source PC is
+/// consulted.
+async fn jam_start_local(
+    state: &AppState,
+    identity: String,
+    participant_auth_id: String,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    if !state.jam_local_library.enabled() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Local music library is not configured".to_string(),
+        ));
+    }
+    let generation = {
+        let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
+        if jam.active || jam.starting {
+            return Err((StatusCode::CONFLICT, "A Jam is already running".to_string()));
+        }
+        jam.starting = true;
+        jam.generation = state.next_jam_generation();
+        jam.last_error = None;
+        jam.playback_source = JamPlaybackSource::Local;
+        jam.generation
+    };
+    if let Some(stale) = state.jam_bot.lock().await.take() {
+        stale.stop().await;
+    }
+    if let Some(stale) = state.jam_local_player.lock().await.take() {
+        stale.stop().await;
+    }
+    let bot = crate::jam_bot::JamBot::start_local(generation, state.jam_source.clone());
+
+    let binding_still_current =
+        participant_binding_is_current(state, &identity, &participant_auth_id);
+    let activated = {
+        let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
+        if jam.generation != generation || !jam.starting || !binding_still_current {
+            if jam.generation == generation && jam.starting {
+                jam.starting = false;
+                jam.playback_source = JamPlaybackSource::Spotify;
+                jam.last_error = Some("Jam host authorization changed during startup".to_string());
+            }
+            false
+        } else {
+            apply_jam_start_result(&mut jam, identity, participant_auth_id, true);
+            jam.queue_control_epoch = jam.queue_control_epoch.wrapping_add(1);
+            jam.queue_control_stopped = false;
+            info!(
+                "Local library Jam started by {} (auto-joined as listener)",
+                jam.host_identity
+            );
+            true
+        }
+    };
+    if !activated {
+        bot.stop().await;
+        return Err((StatusCode::CONFLICT, "Jam start was superseded".to_string()));
+    }
+    *state.jam_bot.lock().await = Some(bot);
+    *state.jam_local_player.lock().await = Some(crate::jam_local_library::LocalPlayer::start(
+        state.clone(),
+        generation,
+    ));
+
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "generation": generation,
+        "listener_joined": true,
+        "source_status": "ready",
+        "playback_source": JamPlaybackSource::Local,
+    })))
+}
+
+/// Whether some room's Jam is still starting or running `generation`.
+fn jam_generation_live(state: &AppState, generation: u64) -> bool {
+    state
+        .jam_room_for_generation(generation)
+        .map(|room| {
+            let jam = room.jam.lock().unwrap_or_else(|e| e.into_inner());
+            jam.generation == generation && (jam.active || jam.starting)
+        })
+        .unwrap_or(false)
+}
+
+fn fail_jam_start(state: &AppState, generation: u64, error: &str) {
+    let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
+    if jam.generation == generation && jam.starting {
+        jam.starting = false;
+        jam.active = false;
+        jam.last_error = Some(error.to_string());
+        jam.spotify_is_playing = false;
+        jam.audio_expected_since = None;
     }
 }
 
@@ -2135,7 +2766,7 @@ fn should_restart_stalled_capture(
 }
 
 async fn ensure_jam_source_ready(state: &AppState) -> Result<u64, (StatusCode, String)> {
-    let (generation, spotify_is_playing, audio_expected_ms) = {
+    let (generation, spotify_is_playing, audio_expected_ms, playback_source) = {
         let jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
         if !jam.active {
             return Err((StatusCode::CONFLICT, "No active Jam".to_string()));
@@ -2145,9 +2776,9 @@ async fn ensure_jam_source_ready(state: &AppState) -> Result<u64, (StatusCode, S
             jam.spotify_is_playing,
             jam.audio_expected_since
                 .map(|at| at.elapsed().as_millis().min(u64::MAX as u128) as u64),
+            jam.playback_source,
         )
     };
-    let source = state.jam_source.snapshot().await;
     let bot_healthy = state
         .jam_bot
         .lock()
@@ -2155,6 +2786,17 @@ async fn ensure_jam_source_ready(state: &AppState) -> Result<u64, (StatusCode, S
         .as_ref()
         .map(|bot| bot.generation() == generation && bot.is_healthy())
         .unwrap_or(false);
+    if playback_source == JamPlaybackSource::Local {
+        return if bot_healthy {
+            Ok(generation)
+        } else {
+            Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Local library audio relay is not running".to_string(),
+            ))
+        };
+    }
+    let source = state.jam_source.snapshot().await;
     let (public_status, public_error) = public_source_health(
         true,
         spotify_is_playing,
@@ -2348,6 +2990,7 @@ pub(crate) async fn jam_stop(
     let actor_auth_id = actor
         .echo_participant_auth_id
         .ok_or(StatusCode::UNAUTHORIZED)?;
+    let state = state.for_jam_room(&actor.video.room)?;
     let actor_identity = actor.sub;
     let _lifecycle = state.jam_lifecycle.lock().await;
 
@@ -3100,11 +3743,12 @@ async fn persist_jam_history_observation(
     if let Some(track) = observation.queued_track {
         let history = std::sync::Arc::clone(&state.jam_history);
         let jam_state = std::sync::Arc::clone(&state.jam);
+        let room = std::sync::Arc::clone(&state.jam_room);
         let observed_spotify_id = observation.spotify_id;
         let observed_echo_run = observation.echo_run;
         let played_at_ms = now_ts_ms();
         match tokio::task::spawn_blocking(move || {
-            let entry = history.append_observation(&track, played_at_ms)?;
+            let entry = history.append_observation(&track, &room, played_at_ms)?;
             let mut jam = jam_state.lock().unwrap_or_else(|error| error.into_inner());
             if active_generation_matches(&jam, observation_generation) {
                 jam.last_history_spotify_id = Some(observed_spotify_id);
@@ -3138,6 +3782,8 @@ pub(crate) async fn jam_state(
     headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     ensure_admin(&state, &headers)?;
+    let participant = ensure_jam_participant(&state, &headers, None)?;
+    let state = state.for_jam_room(&participant.video.room)?;
 
     // Only one Spotify observation may be in flight. Without this fence, an
     // older network response can arrive last and overwrite fresher playback
@@ -3145,9 +3791,11 @@ pub(crate) async fn jam_state(
     let refresh_guard = state.jam_state_refresh.lock().await;
 
     // Check if we need to refresh now_playing from Spotify
+    let spotify_token = current_spotify_token(&state);
     let playback_fetch = {
         let jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
-        if !jam.active || jam.spotify_token.is_none() {
+        if !jam.active || spotify_token.is_none() || jam.playback_source == JamPlaybackSource::Local
+        {
             None
         } else {
             let due = jam.uncertain_skip.is_some()
@@ -3358,6 +4006,14 @@ pub(crate) async fn jam_state(
                             })
                             .flatten()
                             .map(|observation| (fetch_generation, observation));
+                        if jam
+                            .now_playing
+                            .as_ref()
+                            .is_none_or(|previous| previous.spotify_uri != np.spotify_uri)
+                            || same_track_occurrence_restarted(jam.now_playing.as_ref(), &np)
+                        {
+                            jam.track_epoch = jam.track_epoch.wrapping_add(1);
+                        }
                         jam.now_playing = Some(np);
                     }
                 }
@@ -3396,6 +4052,10 @@ pub(crate) async fn jam_state(
         skip_reconciliation_pending,
         spotify_is_playing,
         audio_expected_ms,
+        playback_source,
+        skip_votes,
+        autoplay,
+        source_failover,
     ) = {
         let jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
         (
@@ -3408,8 +4068,8 @@ pub(crate) async fn jam_state(
             state.jam_history.revision(),
             jam.now_playing.clone(),
             jam.listeners.keys().cloned().collect::<Vec<String>>(),
-            jam.spotify_token.is_some(),
-            spotify_library_scopes_authorized(jam.spotify_token.as_ref()),
+            spotify_token.is_some(),
+            spotify_library_scopes_authorized(spotify_token.as_ref()),
             jam.spotify_device_id.clone(),
             jam.spotify_device_name.clone(),
             jam.last_error.clone(),
@@ -3417,32 +4077,59 @@ pub(crate) async fn jam_state(
             jam.spotify_is_playing,
             jam.audio_expected_since
                 .map(|at| at.elapsed().as_millis().min(u64::MAX as u128) as u64),
+            jam.playback_source,
+            state
+                .config
+                .jam_skip_vote_percent
+                .map(|percent| skip_vote_tally(&jam, percent)),
+            jam.autoplay,
+            jam.source_failover.clone(),
         )
     };
     let listener_count = listeners.len();
-    let bot_healthy = state
+    let (bot_healthy, relay_audio_ms) = state
         .jam_bot
         .lock()
         .await
         .as_ref()
-        .map(|bot| bot.is_healthy() && bot.generation() == generation)
-        .unwrap_or(false);
+        .filter(|bot| bot.generation() == generation)
+        .map(|bot| (bot.is_healthy(), Some(bot.relayed_audio_ms())))
+        .unwrap_or((false, None));
     let source = state.jam_source.snapshot().await;
-    let bot_connected = bot_healthy && source.ready && source.generation == Some(generation);
-    let (source_status, source_error) = public_source_health(
-        active,
-        spotify_is_playing,
-        audio_expected_ms,
-        source.ready,
-        source.status.clone(),
-        source.error.clone(),
-    );
-    if should_restart_stalled_capture(
-        active,
-        spotify_is_playing,
-        audio_expected_ms,
-        &source.status,
-    ) && state.jam_source.restart_stalled_capture(generation).await
+    let local_playback = active && playback_source == JamPlaybackSource::Local;
+    let bot_connected = if local_playback {
+        bot_healthy
+    } else {
+        bot_healthy && source.ready && source.generation == Some(generation)
+    };
+    let (source_status, source_error) = if local_playback {
+        // The library player is in-process; only the relay can fail.
+        if bot_healthy {
+            ("ready".to_string(), None)
+        } else {
+            (
+                "error".to_string(),
+                Some("Local library audio relay is not running".to_string()),
+            )
+        }
+    } else {
+        public_source_health(
+            active,
+            spotify_is_playing,
+            audio_expected_ms,
+            source.ready,
+            source.status.clone(),
+            source.error.clone(),
+        )
+    };
+    if !local_playback
+        && should_restart_stalled_capture(
+            active,
+            spotify_is_playing,
+            audio_expected_ms,
+            &source.status,
+        )
+        && state.jam_source.restart_stalled_capture(generation).await
     {
         warn!(
             "Jam source capture stalled while Spotify was playing; requested generation {} rebind",
@@ -3450,7 +4137,18 @@ pub(crate) async fn jam_state(
         );
     }
 
-    Ok(Json(serde_json::json!({
+    let source_agents = serde_json::json!({
+        "active_source_id": source.source_id,
+        "health_score": source.health_score,
+        "protocol": source.protocol,
+        "resuming": source.resuming,
+        "frames_lost": source.frames_lost,
+        "sources": source.sources,
+        "last_failover": source_failover,
+    });
+
+    let mut response = serde_json::json!({
+        "room": state.jam_room.as_ref(),
         "active": active,
         "starting": starting,
         "generation": generation,
@@ -3459,7 +4157,12 @@ pub(crate) async fn jam_state(
         "queue_revision": queue_revision,
         "history_revision": history_revision,
         "queue_removal_supported": true,
+        "queue_move_supported": true,
+        "skip_votes": skip_votes,
+        "autoplay": autoplay,
         "track_queue_request_id_supported": true,
+        "queue_policy": JamQueuePolicy::from_config(&state.config),
+        "content_filter": state.jam_content_filters.for_room(&state.jam_room),
         "now_playing": now_playing,
         "listeners": listeners,
         "listener_count": listener_count,
@@ -3473,7 +4176,7 @@ pub(crate) async fn jam_state(
         "bot_connected": bot_connected,
         "last_error": last_error,
         "skip_reconciliation_pending": skip_reconciliation_pending,
-        "jam_protocol_version": crate::jam_source::JAM_SOURCE_PROTOCOL_VERSION,
+        "jam_protocol_version": crate::jam_source::JAM_AUDIO_PROTOCOL_VERSION,
         "source_status": source_status,
         "source_error": source_error,
         "source_availability_known": source.availability_known,
@@ -3482,7 +4185,21 @@ pub(crate) async fn jam_state(
         "source_peak": source.peak,
         "source_ready": source.ready,
         "spotify_connect_repair_supported": source.spotify_connect_repair_supported,
-    })))
+        "source_owner_room": source.owner_room,
+        "playback_source": playback_source,
+        "local_library_enabled": state.jam_local_library.enabled(),
+        "local_library_revision": state.jam_local_library.revision(),
+    });
+    // Added outside the literal, which is at serde_json's macro recursion limit.
+    response["source_agents"] = source_agents;
+    response["playback_clock"] = serde_json::json!(publish_playback_clock(
+        &mut state.jam.lock().unwrap_or_else(|e| e.into_inner()),
+        generation,
+        relay_audio_ms,
+    ));
+    // Lets clients tell listeners that the Jam is being recorded.
+    response["recording"] = serde_json::json!(state.jam_recordings.is_recording(&state.jam_room));
+    Ok(Json(response))
 }
 
 pub(crate) async fn jam_search(
@@ -3667,10 +4384,140 @@ fn queued_track_from_summary(
         added_by_name: actor.display_name.clone(),
         playlist: playlist.cloned(),
         playlist_position,
+        autoplay: None,
+        added_by: actor.display_name.clone(),
+    }
+}
+
+fn queued_track_from_local(track: &LocalTrack, actor: &JamActor, added_at_ms: u64) -> QueuedTrack {
+    QueuedTrack {
+        queue_entry_id: format!("qe1_{}", random_secret()),
+        queue_batch_id: None,
+        spotify_id: track.local_id.clone(),
+        spotify_uri: track.uri.clone(),
+        spotify_url: String::new(),
+        name: track.name.clone(),
+        artist: track.artist.clone(),
+        album_art_url: track.artwork_url.clone().unwrap_or_default(),
+        duration_ms: track.duration_ms,
+        added_at_ms,
+        added_by_actor_id: actor.actor_id.clone(),
+        added_by_name: actor.display_name.clone(),
+        playlist: None,
+        playlist_position: None,
+        autoplay: None,
         added_by: actor.display_name.clone(),
     }
 }
 
+pub(crate) enum LocalQueueStep {
+    Ended,
+    Idle,
+    Play(Box<QueuedTrack>),
+}
+
+/// Claim the head of a local Jam queue for the library player. The claimed
+/// entry stops being removable and becomes the public now-playing track.
+pub(crate) fn begin_local_queue_track(jam: &mut JamState, generation: u64) -> LocalQueueStep {
+    if !active_generation_matches(jam, generation)
+        || jam.playback_source != JamPlaybackSource::Local
+    {
+        return LocalQueueStep::Ended;
+    }
+    if jam.queue_control_stopped {
+        return LocalQueueStep::Idle;
+    }
+    let Some(entry) = jam.queue.first_mut() else {
+        return LocalQueueStep::Idle;
+    };
+    entry.delivery_state = QueueDeliveryState::LocalPlaying;
+    entry.can_remove = false;
+    let track = entry.track.clone();
+    jam.queue_revision = jam.queue_revision.wrapping_add(1);
+    jam.track_epoch = jam.track_epoch.wrapping_add(1);
+    jam.now_playing = Some(NowPlayingInfo {
+        spotify_id: track.spotify_id.clone(),
+        spotify_uri: track.spotify_uri.clone(),
+        spotify_url: String::new(),
+        name: track.name.clone(),
+        artist: track.artist.clone(),
+        album_art_url: track.album_art_url.clone(),
+        duration_ms: track.duration_ms,
+        progress_ms: 0,
+        is_playing: true,
+        fetched_at: Some(std::time::Instant::now()),
+    });
+    jam.last_history_spotify_id = Some(track.spotify_id.clone());
+    jam.last_history_was_echo = true;
+    jam.audio_expected_since = Some(std::time::Instant::now());
+    LocalQueueStep::Play(Box::new(track))
+}
+
+pub(crate) fn update_local_queue_progress(
+    jam: &mut JamState,
+    generation: u64,
+    queue_entry_id: &str,
+    progress_ms: u64,
+) {
+    let playing = jam.queue.first().is_some_and(|entry| {
+        entry.track.queue_entry_id == queue_entry_id
+            && entry.delivery_state == QueueDeliveryState::LocalPlaying
+    });
+    if !active_generation_matches(jam, generation) || !playing {
+        return;
+    }
+    if let Some(now_playing) = jam.now_playing.as_mut() {
+        now_playing.progress_ms = progress_ms;
+        now_playing.fetched_at = Some(std::time::Instant::now());
+    }
+}
+
+/// Retire a finished, skipped, or failed library entry. An entry that Stop
+/// Music returned to Pending stays queued so it replays on resume. Returns
+/// false once the generation is no longer a local Jam.
+pub(crate) fn finish_local_queue_track(
+    jam: &mut JamState,
+    generation: u64,
+    queue_entry_id: &str,
+    error: Option<String>,
+) -> bool {
+    if !active_generation_matches(jam, generation)
+        || jam.playback_source != JamPlaybackSource::Local
+    {
+        return false;
+    }
+    let position = jam.queue.iter().position(|entry| {
+        entry.track.queue_entry_id == queue_entry_id
+            && entry.delivery_state == QueueDeliveryState::LocalPlaying
+    });
+    if let Some(position) = position {
+        let removed = jam.queue.remove(position);
+        jam.queue_revision = jam.queue_revision.wrapping_add(1);
+        jam.now_playing = None;
+        jam.audio_expected_since = None;
+        info!("Jam: retired local library track '{}'", removed.track.name);
+    }
+    if error.is_some() {
+        jam.last_error = error;
+    }
+    true
+}
+
+fn local_playing_entry_id(jam: &JamState) -> Option<String> {
+    jam.queue
+        .first()
+        .filter(|entry| entry.delivery_state == QueueDeliveryState::LocalPlaying)
+        .map(|entry| entry.track.queue_entry_id.clone())
+}
+
+fn playback_source_mismatch_response() -> Response {
+    playlist_queue_error_response(
+        StatusCode::CONFLICT,
+        "playback_source_mismatch",
+        "This track cannot play in the active Jam's playback source",
+    )
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 struct SpotifyPlacementObservation {
     playback_present: bool,
@@ -4393,7 +5240,10 @@ async fn pump_queue_frontier_locked(
     loop {
         let (control_stopped, should_resume, track) = {
             let jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
-            if !active_generation_matches(&jam, generation) {
+            // Local library entries are drained by the in-process player.
+            if !active_generation_matches(&jam, generation)
+                || jam.playback_source == JamPlaybackSource::Local
+            {
                 return;
             }
             (
@@ -4430,6 +5280,9 @@ async fn pump_queue_frontier_locked(
             return;
         }
         let Some(track) = track else {
+            if refill_autoplay_queue(state, generation).await {
+                continue;
+            }
             return;
         };
         match commit_pending_queue_track_guarded(
@@ -4504,6 +5357,9 @@ pub(crate) async fn jam_queue_add(
             "A current Echo participant token is required",
         )
     })?;
+    let state = state
+        .for_jam_room(&actor.room)
+        .map_err(IntoResponse::into_response)?;
     if let Some(request_id) = payload.request_id.as_deref() {
         if !playlist_queue_request_id_valid(request_id) {
             return Err(playlist_queue_error_response(
@@ -4513,6 +5369,20 @@ pub(crate) async fn jam_queue_add(
             ));
         }
     }
+    if let Some(local_id) = local_track_id_from_uri(&payload.spotify_uri) {
+        let local_id = local_id.to_string();
+        return jam_queue_add_local(
+            &state,
+            &actor,
+            payload.generation,
+            payload.request_id,
+            local_id,
+        )
+        .await;
+    }
+    if active_local_generation(&state, payload.generation) {
+        return Err(playback_source_mismatch_response());
+    }
     let spotify_id = spotify_track_id_from_uri(&payload.spotify_uri)
         .ok_or_else(|| {
             playlist_queue_error_response(
@@ -4564,6 +5434,13 @@ pub(crate) async fn jam_queue_add(
     let summary = fetch_favorite_summary(&state, FavoriteKind::Track, &spotify_id)
         .await
         .map_err(JamApiError::into_response)?;
+    if let Some(reason) = state
+        .jam_content_filters
+        .for_room(&actor.room)
+        .check_summary(&summary)
+    {
+        return Err(content_filtered_response(reason));
+    }
     let _queue_lifecycle = state.jam_queue_lifecycle.lock().await;
     if let Some(request_id) = payload.request_id.as_deref() {
         let replay = {
@@ -4642,7 +5519,17 @@ pub(crate) async fn jam_queue_add(
                 "The active Jam has no bound Spotify device",
             ));
         }
-        jam.queue.push(pending_queue_entry(track.clone()));
+        let policy = JamQueuePolicy::from_config(&state.config);
+        let admission = admit_queue_entries(
+            &jam.queue,
+            &policy,
+            &actor.actor_id,
+            vec![pending_queue_entry(track.clone())],
+        );
+        if let Some(response) = single_queue_admission_refusal(&admission) {
+            return Err(response);
+        }
+        place_queue_entries(&mut jam.queue, &policy, admission.admitted);
         jam.queue_revision = jam.queue_revision.wrapping_add(1);
         generation
     };
@@ -4683,6 +5570,168 @@ pub(crate) async fn jam_queue_add(
     Ok(Json(response))
 }
 
+fn active_local_generation(state: &AppState, generation: u64) -> bool {
+    let jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+    active_generation_matches(&jam, generation) && jam.playback_source == JamPlaybackSource::Local
+}
+
+async fn wake_local_player(state: &AppState, generation: u64) {
+    if let Some(player) = state.jam_local_player.lock().await.as_ref() {
+        if player.generation() == generation {
+            player.wake();
+        }
+    }
+}
+
+/// Queue a local library track. Library entries never enter Spotify's queue;
+/// the in-process player drains them in order.
+async fn jam_queue_add_local(
+    state: &AppState,
+    actor: &JamActor,
+    generation: u64,
+    request_id: Option<String>,
+    local_id: String,
+) -> Result<Json<serde_json::Value>, Response> {
+    let library_track = state.jam_local_library.get(&local_id).ok_or_else(|| {
+        playlist_queue_error_response(
+            StatusCode::NOT_FOUND,
+            "local_track_not_found",
+            "The track is no longer in the local music library",
+        )
+    })?;
+    if let Some(reason) = state
+        .jam_content_filters
+        .for_room(&actor.room)
+        .check_local(&library_track)
+    {
+        return Err(content_filtered_response(reason));
+    }
+    let _queue_lifecycle = state.jam_queue_lifecycle.lock().await;
+    let _lifecycle = state.jam_lifecycle.lock().await;
+    let response_track = {
+        let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+        if let Some(request_id) = request_id.as_deref() {
+            match track_queue_receipt_response(
+                &jam,
+                request_id,
+                &actor.actor_id,
+                &local_id,
+                generation,
+            ) {
+                Ok(Some(track)) => {
+                    return Ok(Json(serde_json::json!({
+                        "ok": true,
+                        "request_id": request_id,
+                        "track": track,
+                    })));
+                }
+                Ok(None) => {}
+                Err(()) => {
+                    return Err(playlist_queue_error_response(
+                        StatusCode::CONFLICT,
+                        "request_id_conflict",
+                        "request_id was already used for a different track queue operation",
+                    ));
+                }
+            }
+        }
+        if !active_generation_matches(&jam, generation) {
+            return Err(playlist_queue_error_response(
+                StatusCode::CONFLICT,
+                "generation_changed",
+                "Jam generation changed",
+            ));
+        }
+        if jam.playback_source != JamPlaybackSource::Local {
+            return Err(playback_source_mismatch_response());
+        }
+        let entry =
+            pending_queue_entry(queued_track_from_local(&library_track, actor, now_ts_ms()));
+        let policy = JamQueuePolicy::from_config(&state.config);
+        let admission =
+            admit_queue_entries(&jam.queue, &policy, &actor.actor_id, vec![entry.clone()]);
+        if let Some(response) = single_queue_admission_refusal(&admission) {
+            return Err(response);
+        }
+        place_queue_entries(&mut jam.queue, &policy, admission.admitted);
+        jam.queue_revision = jam.queue_revision.wrapping_add(1);
+        // An explicit add resumes a stopped queue, matching Spotify Jams.
+        if jam.queue_control_stopped {
+            jam.queue_control_stopped = false;
+            jam.queue_control_epoch = jam.queue_control_epoch.wrapping_add(1);
+        }
+        if let Some(request_id) = request_id.clone() {
+            insert_track_queue_receipt(
+                &mut jam,
+                request_id,
+                TrackQueueReceipt {
+                    actor_id: actor.actor_id.clone(),
+                    spotify_id: local_id,
+                    generation,
+                    created_at_ms: now_ts_ms(),
+                    track: entry.clone(),
+                },
+            );
+        }
+        entry
+    };
+    wake_local_player(state, generation).await;
+    let mut response = serde_json::json!({ "ok": true, "track": response_track });
+    if let Some(request_id) = request_id {
+        response["request_id"] = serde_json::Value::String(request_id);
+    }
+    Ok(Json(response))
+}
+
+/// Skip the library track that is playing now, or stop the library queue.
+/// Stop returns the interrupted entry to Pending so it replays on resume.
+async fn jam_local_playback_control(
+    state: &AppState,
+    generation: u64,
+    stop: bool,
+) -> Result<Json<serde_json::Value>, Response> {
+    let _queue_lifecycle = state.jam_queue_lifecycle.lock().await;
+    let _lifecycle = state.jam_lifecycle.lock().await;
+    let interrupted = {
+        let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+        if !active_generation_matches(&jam, generation) {
+            return Err(playlist_queue_error_response(
+                StatusCode::CONFLICT,
+                "generation_changed",
+                "Jam generation changed",
+            ));
+        }
+        let interrupted = local_playing_entry_id(&jam);
+        if stop {
+            if let Some(entry) = jam.queue.first_mut() {
+                if entry.delivery_state == QueueDeliveryState::LocalPlaying {
+                    entry.delivery_state = QueueDeliveryState::Pending;
+                    entry.can_remove = true;
+                }
+            }
+            jam.queue_control_stopped = true;
+            jam.queue_control_epoch = jam.queue_control_epoch.wrapping_add(1);
+            jam.queue_stop_epoch = jam.queue_stop_epoch.wrapping_add(1);
+            jam.queue_revision = jam.queue_revision.wrapping_add(1);
+            jam.now_playing = None;
+            jam.audio_expected_since = None;
+        }
+        interrupted
+    };
+    if let Some(queue_entry_id) = interrupted {
+        if let Some(player) = state.jam_local_player.lock().await.as_ref() {
+            if player.generation() == generation {
+                player.interrupt(queue_entry_id);
+            }
+        }
+    }
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "generation": generation,
+        "playback_source": JamPlaybackSource::Local,
+    })))
+}
+
 fn queue_removal_fingerprint(
     payload: &JamQueueRemoveRequest,
 ) -> Result<QueueRemovalFingerprint, Response> {
@@ -4696,12 +5745,10 @@ fn queue_removal_fingerprint(
         ));
     }
     let mut canonical_ids = payload.queue_entry_ids.clone();
-    if canonical_ids.iter().any(|entry_id| {
-        !(8..=128).contains(&entry_id.len())
-            || !entry_id
-                .bytes()
-                .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_'))
-    }) {
+    if !canonical_ids
+        .iter()
+        .all(|entry_id| valid_queue_entry_id(entry_id))
+    {
         return Err(playlist_queue_error_response(
             StatusCode::BAD_REQUEST,
             "invalid_queue_entry_ids",
@@ -4722,6 +5769,49 @@ fn queue_removal_fingerprint(
     })
 }
 
+fn valid_queue_entry_id(entry_id: &str) -> bool {
+    (8..=128).contains(&entry_id.len())
+        && entry_id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_'))
+}
+
+fn queue_move_fingerprint(payload: &JamQueueMoveRequest) -> Result<QueueMoveFingerprint, String> {
+    if payload.queue_entry_ids.is_empty()
+        || payload.queue_entry_ids.len() > MAX_QUEUE_REMOVAL_ENTRIES
+    {
+        return Err(format!(
+            "queue_entry_ids must contain 1-{MAX_QUEUE_REMOVAL_ENTRIES} entries"
+        ));
+    }
+    if !payload
+        .queue_entry_ids
+        .iter()
+        .chain(&payload.before_queue_entry_id)
+        .all(|entry_id| valid_queue_entry_id(entry_id))
+    {
+        return Err("queue_entry_ids contains an invalid Echo queue entry ID".to_string());
+    }
+    let mut unique = HashSet::new();
+    if !payload
+        .queue_entry_ids
+        .iter()
+        .all(|entry_id| unique.insert(entry_id.as_str()))
+    {
+        return Err("queue_entry_ids must not contain duplicates".to_string());
+    }
+    if let Some(anchor) = payload.before_queue_entry_id.as_deref() {
+        if unique.contains(anchor) {
+            return Err("before_queue_entry_id must not be one of the moved entries".to_string());
+        }
+    }
+    Ok(QueueMoveFingerprint {
+        expected_queue_revision: payload.expected_queue_revision,
+        queue_entry_ids: payload.queue_entry_ids.clone(),
+        before_queue_entry_id: payload.before_queue_entry_id.clone(),
+    })
+}
+
 fn queue_removal_conflict_response(
     code: &'static str,
     message: impl Into<String>,
@@ -4753,6 +5843,9 @@ pub(crate) async fn jam_queue_remove(
             "A current Echo participant token is required",
         )
     })?;
+    let state = state
+        .for_jam_room(&actor.room)
+        .map_err(IntoResponse::into_response)?;
     if !playlist_queue_request_id_valid(&payload.request_id) {
         return Err(playlist_queue_error_response(
             StatusCode::BAD_REQUEST,
@@ -4835,6 +5928,140 @@ pub(crate) async fn jam_queue_remove(
     Ok(Json(response))
 }
 
+/// Reorder pending entries. Retries with the same `request_id` replay the
+/// stored response, exactly like queue removals.
+pub(crate) async fn jam_queue_move(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<JamQueueMoveRequest>,
+) -> Result<Json<JamQueueMoveResponse>, Response> {
+    ensure_admin(&state, &headers).map_err(|status| {
+        playlist_queue_error_response(status, "unauthorized", "Authentication required")
+    })?;
+    let actor = ensure_jam_actor(&state, &headers).map_err(|status| {
+        playlist_queue_error_response(
+            status,
+            "actor_required",
+            "A current Echo participant token is required",
+        )
+    })?;
+    let state = state
+        .for_jam_room(&actor.room)
+        .map_err(IntoResponse::into_response)?;
+    if !playlist_queue_request_id_valid(&payload.request_id) {
+        return Err(playlist_queue_error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_id",
+            "request_id must be 8-128 ASCII letters, digits, dashes, or underscores",
+        ));
+    }
+    let fingerprint = queue_move_fingerprint(&payload).map_err(|message| {
+        playlist_queue_error_response(StatusCode::BAD_REQUEST, "invalid_queue_entry_ids", message)
+    })?;
+    let _queue_lifecycle = state.jam_queue_lifecycle.lock().await;
+    let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+
+    match queue_move_receipt_response(
+        &jam,
+        &payload.request_id,
+        &actor.actor_id,
+        payload.generation,
+        &fingerprint,
+    ) {
+        Ok(Some(response)) => return Ok(Json(response)),
+        Ok(None) => {}
+        Err(()) => {
+            return Err(queue_removal_conflict_response(
+                "request_id_conflict",
+                "request_id was already used for a different queue move",
+                jam.queue_revision,
+            ));
+        }
+    }
+
+    if !active_generation_matches(&jam, payload.generation) {
+        return Err(queue_removal_conflict_response(
+            "generation_changed",
+            "Jam generation changed",
+            jam.queue_revision,
+        ));
+    }
+    if jam.queue_revision != payload.expected_queue_revision {
+        return Err(queue_removal_conflict_response(
+            "queue_changed",
+            "The Jam queue changed; refresh it before moving songs",
+            jam.queue_revision,
+        ));
+    }
+
+    match move_pending_queue_entries(
+        &mut jam,
+        &fingerprint.queue_entry_ids,
+        fingerprint.before_queue_entry_id.as_deref(),
+    ) {
+        Ok(()) => {}
+        Err(QueueMoveMutationError::QueueChanged) => {
+            return Err(queue_removal_conflict_response(
+                "queue_changed",
+                "One or more selected songs are no longer in the Jam queue",
+                jam.queue_revision,
+            ));
+        }
+        Err(QueueMoveMutationError::NotMovable) => {
+            return Err(queue_removal_conflict_response(
+                "queue_entries_not_movable",
+                "One or more selected songs were already handed to Spotify and cannot be moved",
+                jam.queue_revision,
+            ));
+        }
+    }
+    let response = JamQueueMoveResponse {
+        ok: true,
+        generation: payload.generation,
+        queue_revision: jam.queue_revision,
+        moved_entry_ids: fingerprint.queue_entry_ids.clone(),
+        before_queue_entry_id: fingerprint.before_queue_entry_id.clone(),
+    };
+    insert_queue_move_receipt(
+        &mut jam,
+        payload.request_id,
+        QueueMoveReceipt {
+            actor_id: actor.actor_id,
+            generation: payload.generation,
+            fingerprint,
+            created_at_ms: now_ts_ms(),
+            response: response.clone(),
+        },
+    );
+    Ok(Json(response))
+}
+
+/// The error response for a refused single-track admission, if any.
+fn single_queue_admission_refusal(admission: &QueueAdmission) -> Option<Response> {
+    if !admission.duplicates.is_empty() {
+        return Some(playlist_queue_error_response(
+            StatusCode::CONFLICT,
+            "duplicate_track",
+            "That track is already in the Jam queue",
+        ));
+    }
+    if !admission.over_limit.is_empty() {
+        return Some(queue_limit_error_response());
+    }
+    None
+}
+
+fn queue_limit_error_response() -> Response {
+    playlist_queue_error_response(
+        StatusCode::CONFLICT,
+        "actor_queue_limit",
+        QUEUE_LIMIT_MESSAGE,
+    )
+}
+
+const QUEUE_LIMIT_MESSAGE: &str =
+    "You already have the maximum number of tracks waiting in the Jam queue";
+
 fn playlist_queue_error_response(
     status: StatusCode,
     code: &'static str,
@@ -4912,11 +6139,17 @@ async fn jam_queue_playlist_impl(
             "A current Echo participant token is required",
         )
     })?;
-    if !valid_spotify_id(&payload.playlist_id) {
+    let state = state
+        .for_jam_room(&actor.room)
+        .map_err(IntoResponse::into_response)?;
+    if !valid_spotify_id(&payload.playlist_id)
+        && !valid_echo_playlist_id(&payload.playlist_id)
+        && !valid_jam_import_id(&payload.playlist_id)
+    {
         return Err(playlist_queue_error_response(
             StatusCode::BAD_REQUEST,
             "invalid_playlist_id",
-            "Invalid Spotify playlist ID",
+            "Invalid Spotify playlist, Echo playlist, or import ID",
         ));
     }
     if !playlist_queue_request_id_valid(&payload.request_id) {
@@ -4927,6 +6160,9 @@ async fn jam_queue_playlist_impl(
         ));
     }
     let selection = playlist_queue_selection_fingerprint(&payload)?;
+    if active_local_generation(&state, payload.generation) {
+        return Err(playback_source_mismatch_response());
+    }
 
     let generation =
         ensure_jam_recovery_controls_ready(&state)
@@ -4975,27 +6211,64 @@ async fn jam_queue_playlist_impl(
         (jam.queue_control_epoch, jam.queue_stop_epoch)
     };
 
-    let expansion = match selection.selected_positions.as_deref() {
-        Some(selected_positions) => {
-            fetch_playlist_selection(
-                &state,
-                &payload.playlist_id,
-                selected_positions,
-                selection.snapshot_id.as_deref(),
-            )
-            .await
-        }
-        None => {
-            fetch_playlist_expansion(
-                &state,
-                &payload.playlist_id,
-                selection.snapshot_id.as_deref(),
-            )
-            .await
+    let expansion = if valid_echo_playlist_id(&payload.playlist_id) {
+        echo_playlist_expansion(
+            &state,
+            &payload.playlist_id,
+            selection.selected_positions.as_deref(),
+            selection.snapshot_id.as_deref(),
+        )
+    } else if valid_jam_import_id(&payload.playlist_id) {
+        jam_import_expansion(
+            &state,
+            &actor.actor_id,
+            &payload.playlist_id,
+            selection.selected_positions.as_deref(),
+            selection.snapshot_id.as_deref(),
+        )
+    } else {
+        match selection.selected_positions.as_deref() {
+            Some(selected_positions) => {
+                fetch_playlist_selection(
+                    &state,
+                    &payload.playlist_id,
+                    selected_positions,
+                    selection.snapshot_id.as_deref(),
+                )
+                .await
+            }
+            None => {
+                fetch_playlist_expansion(
+                    &state,
+                    &payload.playlist_id,
+                    selection.snapshot_id.as_deref(),
+                )
+                .await
+            }
         }
     }
     .map_err(JamApiError::into_response)?;
+    let mut expansion = expansion;
+    let content_filter = state.jam_content_filters.for_room(&actor.room);
+    let offered_count = expansion.tracks.len();
+    expansion.tracks.retain(|(position, summary)| {
+        let Some(reason) = content_filter.check_summary(summary) else {
+            return true;
+        };
+        expansion.skipped.push(SkippedPlaylistItem {
+            position: *position,
+            reason: reason.as_str().to_string(),
+        });
+        false
+    });
     if expansion.tracks.is_empty() {
+        if offered_count > 0 {
+            return Err(playlist_queue_error_response(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "content_filtered",
+                "Every selected track is blocked by this room's content filter",
+            ));
+        }
         return Err(playlist_queue_error_response(
             StatusCode::UNPROCESSABLE_ENTITY,
             "playlist_has_no_playable_tracks",
@@ -5049,11 +6322,6 @@ async fn jam_queue_playlist_impl(
     let batch_id = format!("qb1_{}", random_secret());
     let batch_added_at_ms = now_ts_ms();
     let provenance = playlist_provenance(&expansion.playlist);
-    let queued_positions = expansion
-        .tracks
-        .iter()
-        .map(|(position, _)| *position)
-        .collect::<Vec<_>>();
     let tracks = expansion
         .tracks
         .iter()
@@ -5068,7 +6336,7 @@ async fn jam_queue_playlist_impl(
             ))
         })
         .collect::<Vec<_>>();
-    {
+    let admission = {
         let _refresh = state.jam_state_refresh.lock().await;
         let _lifecycle = state.jam_lifecycle.lock().await;
         let current_generation =
@@ -5115,9 +6383,22 @@ async fn jam_queue_playlist_impl(
                 "The active Jam has no bound Spotify device",
             ));
         }
-        jam.queue.extend(tracks);
+        let policy = JamQueuePolicy::from_config(&state.config);
+        let admission = admit_queue_entries(&jam.queue, &policy, &actor.actor_id, tracks);
+        if admission.admitted.is_empty() {
+            if !admission.over_limit.is_empty() {
+                return Err(queue_limit_error_response());
+            }
+            return Err(playlist_queue_error_response(
+                StatusCode::CONFLICT,
+                "duplicate_track",
+                "Every playable playlist track is already in the Jam queue",
+            ));
+        }
+        place_queue_entries(&mut jam.queue, &policy, admission.admitted.clone());
         jam.queue_revision = jam.queue_revision.wrapping_add(1);
-    }
+        admission
+    };
     pump_queue_frontier_locked(&state, generation, request_control_epoch, true).await;
 
     let _receipt_lifecycle = state.jam_lifecycle.lock().await;
@@ -5132,24 +6413,53 @@ async fn jam_queue_playlist_impl(
             "Jam ended before the playlist enqueue receipt was committed",
         ));
     }
-    let skipped = expansion.skipped;
+    let entry_position = |entry: &JamQueueEntry| entry.track.playlist_position.unwrap_or_default();
+    let queued_positions = admission
+        .admitted
+        .iter()
+        .map(entry_position)
+        .collect::<Vec<_>>();
+    let remaining_positions = admission
+        .over_limit
+        .iter()
+        .map(entry_position)
+        .collect::<Vec<_>>();
+    let mut skipped = expansion.skipped;
+    skipped.extend(
+        admission
+            .duplicates
+            .iter()
+            .map(|entry| SkippedPlaylistItem {
+                position: entry_position(entry),
+                reason: "duplicate".to_string(),
+            }),
+    );
+    skipped.sort_by_key(|item| item.position);
     let queued_count = queued_positions.len();
+    // Tracks past the contributor's pending cap are reported like an
+    // interrupted batch so clients can offer to queue the rest later.
+    let failure = (!remaining_positions.is_empty()).then(|| PlaylistQueueFailure {
+        status: StatusCode::CONFLICT.as_u16(),
+        error: "actor_queue_limit".to_string(),
+        message: QUEUE_LIMIT_MESSAGE.to_string(),
+        retry_after: None,
+    });
     let response = PlaylistQueueResponse {
         schema_version: 1,
         ok: true,
-        partial: false,
+        partial: failure.is_some(),
         request_id: payload.request_id.clone(),
         queue_batch_id: batch_id.clone(),
         batch_id,
         generation,
         playlist: provenance,
         queued_positions,
-        remaining_positions: Vec::new(),
+        remaining_positions,
         queued_count,
         skipped_count: skipped.len(),
         skipped,
-        complete: true,
-        failure: None,
+        complete: failure.is_none(),
+        failure,
     };
     let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
     insert_playlist_queue_receipt(
@@ -5175,13 +6485,20 @@ pub(crate) async fn jam_stop_playback(
     ensure_admin(&state, &headers).map_err(|status| {
         playlist_queue_error_response(status, "unauthorized", "Authentication required")
     })?;
-    ensure_jam_participant(&state, &headers, None).map_err(|status| {
+    let participant = ensure_jam_participant(&state, &headers, None).map_err(|status| {
         playlist_queue_error_response(
             status,
             "participant_required",
             "A current Echo participant token is required",
         )
     })?;
+    let state = state
+        .for_jam_room(&participant.video.room)
+        .map_err(IntoResponse::into_response)?;
+
+    if active_local_generation(&state, payload.generation) {
+        return jam_local_playback_control(&state, payload.generation, true).await;
+    }
 
     // Fence the Spotify observation used by /api/jam/state. Otherwise an
     // older in-flight GET can arrive after this pause and incorrectly mark
@@ -5242,13 +6559,69 @@ pub(crate) async fn jam_skip(
     ensure_admin(&state, &headers).map_err(|status| {
         playlist_queue_error_response(status, "unauthorized", "Authentication required")
     })?;
-    ensure_jam_participant(&state, &headers, None).map_err(|status| {
+    let participant = ensure_jam_participant(&state, &headers, None).map_err(|status| {
         playlist_queue_error_response(
             status,
             "participant_required",
             "A current Echo participant token is required",
         )
     })?;
+    let state = state
+        .for_jam_room(&participant.video.room)
+        .map_err(IntoResponse::into_response)?;
+    if let Some(percent) = state.config.jam_skip_vote_percent {
+        let outcome = {
+            let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+            if !active_generation_matches(&jam, payload.generation) {
+                return Err(playlist_queue_error_response(
+                    StatusCode::CONFLICT,
+                    "generation_changed",
+                    "Jam generation changed",
+                ));
+            }
+            record_skip_vote(
+                &mut jam,
+                percent,
+                &participant.sub,
+                participant
+                    .echo_participant_auth_id
+                    .as_deref()
+                    .unwrap_or_default(),
+            )
+        };
+        match outcome {
+            SkipVoteOutcome::Skip => {}
+            SkipVoteOutcome::Recorded(tally) => {
+                return Ok(Json(serde_json::json!({
+                    "ok": true,
+                    "skipped": false,
+                    "skip_votes": tally,
+                })));
+            }
+            SkipVoteOutcome::NotListening => {
+                return Err(playlist_queue_error_response(
+                    StatusCode::FORBIDDEN,
+                    "skip_vote_requires_listener",
+                    "Join the Jam to vote to skip",
+                ));
+            }
+            SkipVoteOutcome::NothingPlaying => {
+                return Err(playlist_queue_error_response(
+                    StatusCode::CONFLICT,
+                    "nothing_playing",
+                    "Nothing is playing to skip",
+                ));
+            }
+        }
+    }
+    if active_local_generation(&state, payload.generation) {
+        let response = jam_local_playback_control(&state, payload.generation, false).await?;
+        let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+        if active_generation_matches(&jam, payload.generation) {
+            jam.skip_votes = None;
+        }
+        return Ok(response);
+    }
     let _queue_lifecycle = state.jam_queue_lifecycle.lock().await;
     let (generation, next_control_epoch) = {
         let _refresh = state.jam_state_refresh.lock().await;
@@ -5516,6 +6889,7 @@ pub(crate) async fn jam_skip(
                     jam.queue_stop_epoch = jam.queue_stop_epoch.wrapping_add(1);
                 }
                 jam.now_playing = None;
+                jam.skip_votes = None;
                 Some(jam.queue_control_epoch)
             }
         };
@@ -5541,6 +6915,63 @@ pub(crate) async fn jam_skip(
     Ok(Json(serde_json::json!({ "ok": true })))
 }
 
+/// Turn autoplay radio on or off for the caller's room. Turning it off
+/// withdraws autoplay picks that have not reached Spotify yet.
+pub(crate) async fn jam_autoplay(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<JamAutoplayRequest>,
+) -> Result<Json<serde_json::Value>, Response> {
+    ensure_admin(&state, &headers).map_err(|status| {
+        playlist_queue_error_response(status, "unauthorized", "Authentication required")
+    })?;
+    let participant = ensure_jam_participant(&state, &headers, None).map_err(|status| {
+        playlist_queue_error_response(
+            status,
+            "participant_required",
+            "A current Echo participant token is required",
+        )
+    })?;
+    let state = state
+        .for_jam_room(&participant.video.room)
+        .map_err(IntoResponse::into_response)?;
+    let _queue_lifecycle = state.jam_queue_lifecycle.lock().await;
+    {
+        let mut jam = state.jam.lock().unwrap_or_else(|error| error.into_inner());
+        if !active_generation_matches(&jam, payload.generation) {
+            return Err(playlist_queue_error_response(
+                StatusCode::CONFLICT,
+                "generation_changed",
+                "Jam generation changed",
+            ));
+        }
+        if payload.enabled && jam.playback_source != JamPlaybackSource::Spotify {
+            return Err(playback_source_mismatch_response());
+        }
+        jam.autoplay = payload.enabled;
+        if !payload.enabled {
+            let before = jam.queue.len();
+            jam.queue.retain(|entry| !is_pending_autoplay(entry));
+            if jam.queue.len() != before {
+                jam.queue_revision = jam.queue_revision.wrapping_add(1);
+            }
+        }
+    }
+    if payload.enabled {
+        let control_epoch = state
+            .jam
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .queue_control_epoch;
+        pump_queue_frontier_locked(&state, payload.generation, control_epoch, false).await;
+    }
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "generation": payload.generation,
+        "autoplay": payload.enabled,
+    })))
+}
+
 pub(crate) async fn jam_join(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -5554,6 +6985,7 @@ pub(crate) async fn jam_join(
     let actor_auth_id = actor
         .echo_participant_auth_id
         .ok_or(StatusCode::UNAUTHORIZED)?;
+    let state = state.for_jam_room(&actor.video.room)?;
     let actor_identity = actor.sub;
     let _lifecycle = state.jam_lifecycle.lock().await;
     {
@@ -5568,13 +7000,25 @@ pub(crate) async fn jam_join(
     if generation != payload.generation {
         return Err(StatusCode::CONFLICT);
     }
+    let relay_audio_ms = state
+        .jam_bot
+        .lock()
+        .await
+        .as_ref()
+        .filter(|bot| bot.generation() == generation)
+        .map(|bot| bot.relayed_audio_ms());
     let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
     if !active_generation_matches(&jam, generation) {
         return Err(StatusCode::CONFLICT);
     }
     jam.listeners.insert(actor_identity.clone(), actor_auth_id);
     info!("Jam: {} joined", actor_identity);
-    Ok(Json(serde_json::json!({ "ok": true })))
+    // A late joiner gets the position model with its first response.
+    let playback_clock = publish_playback_clock(&mut jam, generation, relay_audio_ms);
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "playback_clock": playback_clock,
+    })))
 }
 
 pub(crate) async fn jam_leave(
@@ -5590,6 +7034,7 @@ pub(crate) async fn jam_leave(
     let actor_auth_id = actor
         .echo_participant_auth_id
         .ok_or(StatusCode::UNAUTHORIZED)?;
+    let state = state.for_jam_room(&actor.video.room)?;
     let actor_identity = actor.sub;
     let _lifecycle = state.jam_lifecycle.lock().await;
 
@@ -5650,7 +7095,7 @@ fn listener_protocol_is_current(params: &std::collections::HashMap<String, Strin
         && params
             .get("jam_protocol_version")
             .and_then(|value| value.parse::<u8>().ok())
-            == Some(crate::jam_source::JAM_SOURCE_PROTOCOL_VERSION)
+            == Some(crate::jam_source::JAM_AUDIO_PROTOCOL_VERSION)
         && params.contains_key("generation")
 }
 
@@ -5816,6 +7261,10 @@ async fn jam_audio_ws_handler(mut socket: WebSocket, state: AppState, generation
             return;
         }
     };
+    let Ok(state) = state.for_jam_room(&participant_claims.video.room) else {
+        let _ = socket.send(Message::Close(None)).await;
+        return;
+    };
     let identity = participant_claims.sub;
     let Some(participant_auth_id) = participant_claims.echo_participant_auth_id else {
         let _ = socket.send(Message::Close(None)).await;
@@ -5957,6 +7406,30 @@ mod tests {
         assert!(!spotify_library_scopes_authorized(Some(&token)));
     }
 
+    #[test]
+    fn idle_jam_rooms_are_evictable_only_when_unreferenced_and_quiet() {
+        let room = JamRoom::default();
+        room.last_used_ms
+            .store(1_000, std::sync::atomic::Ordering::Relaxed);
+        assert!(!room.evictable(1_000 + JAM_ROOM_IDLE_EVICT_MS - 1));
+        assert!(room.evictable(1_000 + JAM_ROOM_IDLE_EVICT_MS));
+
+        let later = 1_000 + JAM_ROOM_IDLE_EVICT_MS;
+        let view = room.clone();
+        assert!(!room.evictable(later));
+        drop(view);
+
+        room.jam.lock().unwrap().active = true;
+        assert!(!room.evictable(later));
+        room.jam.lock().unwrap().active = false;
+        room.jam
+            .lock()
+            .unwrap()
+            .listeners
+            .insert("sam".to_string(), "auth".to_string());
+        assert!(!room.evictable(later));
+    }
+
     #[test]
     fn spotify_callback_does_not_claim_connection_before_validation() {
         assert!(SPOTIFY_CALLBACK_RECEIVED_HTML.contains("Spotify authorization received"));
@@ -5974,6 +7447,18 @@ mod tests {
         assert!(!spotify_library_scopes_authorized(None));
     }
 
+    #[test]
+    fn spotify_playlist_export_requires_both_modify_scopes() {
+        let mut token = spotify_token();
+        assert!(!spotify_playlist_write_authorized(Some(&token)));
+
+        token
+            .scope
+            .push_str(" playlist-modify-private playlist-modify-public");
+        assert!(spotify_playlist_write_authorized(Some(&token)));
+        assert!(spotify_library_scopes_authorized(Some(&token)));
+    }
+
     #[test]
     fn spotify_library_scope_error_has_a_stable_machine_code() {
         let error = spotify_library_scope_required_error();
@@ -6061,6 +7546,7 @@ mod tests {
             added_by_name: "sam-7475".to_string(),
             playlist: None,
             playlist_position: None,
+            autoplay: None,
             added_by: "sam-7475".to_string(),
         }
     }
@@ -6070,6 +7556,7 @@ mod tests {
             track: queued_track(uri),
             delivery_state: QueueDeliveryState::SpotifyCommitted,
             can_remove: false,
+            pinned: false,
             current_match_state: QueueCurrentMatchState::Eligible,
         }
     }
@@ -6109,6 +7596,13 @@ mod tests {
             last_frame_ms: None,
             peak: 0.0,
             spotify_connect_repair_supported: false,
+            owner_room: None,
+            source_id: None,
+            health_score: 0,
+            sources: Vec::new(),
+            protocol: None,
+            resuming: false,
+            frames_lost: 0,
         }
     }
 
@@ -6130,7 +7624,6 @@ mod tests {
     #[test]
     fn failed_bot_start_does_not_activate_jam() {
         let mut jam = JamState {
-            spotify_token: Some(spotify_token()),
             ..JamState::default()
         };
 
@@ -6233,7 +7726,6 @@ mod tests {
     #[test]
     fn successful_bot_start_activates_host_listener() {
         let mut jam = JamState {
-            spotify_token: Some(spotify_token()),
             ..JamState::default()
         };
 
@@ -6651,7 +8143,7 @@ mod tests {
         assert!(!listener_protocol_is_current(&params));
         params.insert(
             "jam_protocol_version".to_string(),
-            crate::jam_source::JAM_SOURCE_PROTOCOL_VERSION.to_string(),
+            crate::jam_source::JAM_AUDIO_PROTOCOL_VERSION.to_string(),
         );
         assert!(listener_protocol_is_current(&params));
         params.insert("identity".to_string(), "must-not-be-in-url".to_string());
@@ -8268,6 +9760,7 @@ mod tests {
         let actor = JamActor {
             actor_id: "ea1_actor".to_string(),
             display_name: "Sam".to_string(),
+            room: "main".to_string(),
         };
         let playlist = QueuedPlaylistProvenance {
             spotify_id: "3n3Ppam7vgaVa1iaRUc9Lp".to_string(),
@@ -8556,6 +10049,172 @@ mod tests {
         .is_err());
     }
 
+    #[test]
+    fn queue_move_reorders_pending_entries_around_a_pending_anchor() {
+        let mut committed = committed_queue_entry("head");
+        committed.track.queue_entry_id = "qe_committed_head".to_string();
+        let mut jam = JamState {
+            queue_revision: 2,
+            queue: vec![
+                committed,
+                pending_queue_entry_with_id("one", "qe_pending_one"),
+                pending_queue_entry_with_id("two", "qe_pending_two"),
+                pending_queue_entry_with_id("three", "qe_pending_three"),
+            ],
+            ..JamState::default()
+        };
+        let ids = |jam: &JamState| {
+            jam.queue
+                .iter()
+                .map(|entry| entry.track.queue_entry_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        move_pending_queue_entries(
+            &mut jam,
+            &["qe_pending_three".to_string(), "qe_pending_two".to_string()],
+            Some("qe_pending_one"),
+        )
+        .unwrap();
+        assert_eq!(
+            ids(&jam),
+            [
+                "qe_committed_head",
+                "qe_pending_three",
+                "qe_pending_two",
+                "qe_pending_one"
+            ]
+        );
+        assert_eq!(jam.queue_revision, 3);
+
+        move_pending_queue_entries(&mut jam, &["qe_pending_three".to_string()], None).unwrap();
+        assert_eq!(
+            ids(&jam),
+            [
+                "qe_committed_head",
+                "qe_pending_two",
+                "qe_pending_one",
+                "qe_pending_three"
+            ]
+        );
+
+        assert_eq!(
+            move_pending_queue_entries(&mut jam, &["qe_committed_head".to_string()], None),
+            Err(QueueMoveMutationError::NotMovable)
+        );
+        assert_eq!(
+            move_pending_queue_entries(
+                &mut jam,
+                &["qe_pending_one".to_string()],
+                Some("qe_committed_head"),
+            ),
+            Err(QueueMoveMutationError::NotMovable)
+        );
+        assert_eq!(
+            move_pending_queue_entries(&mut jam, &["qe_missing_four".to_string()], None),
+            Err(QueueMoveMutationError::QueueChanged)
+        );
+        assert_eq!(jam.queue_revision, 4);
+        assert_eq!(jam.queue[0].track.queue_entry_id, "qe_committed_head");
+    }
+
+    #[test]
+    fn fair_share_placement_keeps_moved_entries_where_they_were_put() {
+        let fair = JamQueuePolicy {
+            ordering: crate::jam_queue_policy::QueueOrdering::FairShare,
+            ..JamQueuePolicy::default()
+        };
+        let contributed = |uri: &str, actor_id: &str| {
+            let mut entry = pending_queue_entry_with_id(uri, &format!("qe_{uri}"));
+            entry.track.added_by_actor_id = actor_id.to_string();
+            entry
+        };
+        let mut jam = JamState {
+            queue: vec![
+                contributed("alex_one", "alex"),
+                contributed("alex_two", "alex"),
+                contributed("sam_one", "sam"),
+            ],
+            ..JamState::default()
+        };
+        let ids = |jam: &JamState| {
+            jam.queue
+                .iter()
+                .map(|entry| entry.track.queue_entry_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        move_pending_queue_entries(&mut jam, &["qe_alex_two".to_string()], Some("qe_alex_one"))
+            .unwrap();
+        place_queue_entries(&mut jam.queue, &fair, vec![contributed("jo_one", "jo")]);
+
+        // The moved track stays at the front; only the untouched entries are
+        // interleaved around it.
+        assert_eq!(
+            ids(&jam),
+            ["qe_alex_two", "qe_alex_one", "qe_sam_one", "qe_jo_one"]
+        );
+        assert!(jam.queue[0].pinned);
+        assert!(!jam.queue[3].pinned);
+    }
+
+    #[test]
+    fn queue_move_receipt_replays_only_the_same_actor_and_fingerprint() {
+        let fingerprint = queue_move_fingerprint(&JamQueueMoveRequest {
+            generation: 3,
+            request_id: "move_request_1".to_string(),
+            expected_queue_revision: 11,
+            queue_entry_ids: vec!["qe_pending_two".to_string(), "qe_pending_one".to_string()],
+            before_queue_entry_id: None,
+        })
+        .unwrap();
+        let mut jam = JamState::default();
+        insert_queue_move_receipt(
+            &mut jam,
+            "move_request_1".to_string(),
+            QueueMoveReceipt {
+                actor_id: "actor".to_string(),
+                generation: 3,
+                fingerprint: fingerprint.clone(),
+                created_at_ms: 1,
+                response: JamQueueMoveResponse {
+                    ok: true,
+                    generation: 3,
+                    queue_revision: 12,
+                    moved_entry_ids: fingerprint.queue_entry_ids.clone(),
+                    before_queue_entry_id: None,
+                },
+            },
+        );
+
+        assert_eq!(
+            queue_move_receipt_response(&jam, "move_request_1", "actor", 3, &fingerprint)
+                .unwrap()
+                .unwrap()
+                .queue_revision,
+            12
+        );
+        assert!(
+            queue_move_receipt_response(&jam, "move_request_1", "other", 3, &fingerprint).is_err()
+        );
+        // The same entries in a different order are a different move.
+        let reordered = QueueMoveFingerprint {
+            queue_entry_ids: vec!["qe_pending_one".to_string(), "qe_pending_two".to_string()],
+            ..fingerprint.clone()
+        };
+        assert!(
+            queue_move_receipt_response(&jam, "move_request_1", "actor", 3, &reordered).is_err()
+        );
+        assert!(queue_move_fingerprint(&JamQueueMoveRequest {
+            generation: 3,
+            request_id: "move_request_2".to_string(),
+            expected_queue_revision: 11,
+            queue_entry_ids: vec!["qe_pending_one".to_string()],
+            before_queue_entry_id: Some("qe_pending_one".to_string()),
+        })
+        .is_err());
+    }
+
     #[test]
     fn queue_removal_receipt_replays_only_the_same_actor_and_fingerprint() {
         let fingerprint = QueueRemovalFingerprint {
@@ -8610,4 +10269,84 @@ mod tests {
         )
         .is_err());
     }
+
+    fn local_jam(queue: Vec<JamQueueEntry>) -> JamState {
+        JamState {
+            active: true,
+            generation: 12,
+            queue,
+            playback_source: JamPlaybackSource::Local,
+            ..JamState::default()
+        }
+    }
+
+    #[test]
+    fn local_player_claims_the_queue_head_and_retires_it_after_playback() {
+        let mut jam = local_jam(vec![
+            pending_queue_entry_with_id("echo-local:track:one", "entry-one"),
+            pending_queue_entry_with_id("echo-local:track:two", "entry-two"),
+        ]);
+        let revision = jam.queue_revision;
+
+        let LocalQueueStep::Play(track) = begin_local_queue_track(&mut jam, 12) else {
+            panic!("pending head must start playing");
+        };
+        assert_eq!(track.queue_entry_id, "entry-one");
+        assert_eq!(
+            jam.queue[0].delivery_state,
+            QueueDeliveryState::LocalPlaying
+        );
+        assert!(!jam.queue[0].can_remove);
+        assert!(jam.queue_revision != revision);
+        assert_eq!(
+            jam.now_playing
+                .as_ref()
+                .map(|playing| playing.spotify_uri.as_str()),
+            Some("echo-local:track:one")
+        );
+
+        update_local_queue_progress(&mut jam, 12, "entry-one", 4_000);
+        assert_eq!(jam.now_playing.as_ref().unwrap().progress_ms, 4_000);
+        update_local_queue_progress(&mut jam, 12, "entry-two", 9_000);
+        assert_eq!(jam.now_playing.as_ref().unwrap().progress_ms, 4_000);
+
+        assert!(finish_local_queue_track(&mut jam, 12, "entry-one", None));
+        assert_eq!(jam.queue.len(), 1);
+        assert_eq!(jam.queue[0].track.queue_entry_id, "entry-two");
+        assert!(jam.now_playing.is_none());
+        assert!(!finish_local_queue_track(&mut jam, 13, "entry-two", None));
+    }
+
+    #[test]
+    fn stopped_local_entry_is_kept_for_resume_and_other_jams_are_not_drained() {
+        let mut jam = local_jam(vec![pending_queue_entry_with_id(
+            "echo-local:track:one",
+            "entry-one",
+        )]);
+        assert!(matches!(
+            begin_local_queue_track(&mut jam, 12),
+            LocalQueueStep::Play(_)
+        ));
+        // Stop Music returns the playing entry to Pending before interrupting.
+        jam.queue[0].delivery_state = QueueDeliveryState::Pending;
+        jam.queue_control_stopped = true;
+        assert!(finish_local_queue_track(&mut jam, 12, "entry-one", None));
+        assert_eq!(jam.queue.len(), 1);
+        assert!(matches!(
+            begin_local_queue_track(&mut jam, 12),
+            LocalQueueStep::Idle
+        ));
+
+        let mut spotify_jam = local_jam(vec![pending_queue_entry_with_id(
+            "spotify:track:one",
+            "entry-one",
+        )]);
+        spotify_jam.playback_source = JamPlaybackSource::Spotify;
+        assert!(matches!(
+            begin_local_queue_track(&mut spotify_jam, 12),
+            LocalQueueStep::Ended
+        ));
+        clear_active_jam_state(&mut jam);
+        assert_eq!(jam.playback_source, JamPlaybackSource::Spotify);
+    }
 }
@@ -0,0 +1,218 @@
+//! Vote-to-skip for Jams running with `CORE_JAM_SKIP_VOTE_PERCENT`.
+//!
+//! Votes belong to the track occurrence that was playing when they were cast,
+//! identified by `JamState::track_epoch`. A tally from an earlier occurrence is
+//! stale and counts as zero, so votes reset on every track change, including
+//! the same song playing again, without hooking each place playback advances.
+
+use crate::jam_session::JamState;
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SkipVotes {
+    track_epoch: u64,
+    // Participant auth IDs, so a reconnect under a new binding cannot vote twice.
+    voters: HashSet<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct SkipVoteTally {
+    pub(crate) track_uri: String,
+    pub(crate) votes: usize,
+    pub(crate) required: usize,
+    pub(crate) listener_count: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum SkipVoteOutcome {
+    /// The vote reached the threshold, or the host forced the skip.
+    Skip,
+    Recorded(SkipVoteTally),
+    NotListening,
+    NothingPlaying,
+}
+
+/// Votes needed to skip: at least `percent` of current listeners, never zero.
+pub(crate) fn skip_votes_required(percent: u8, listener_count: usize) -> usize {
+    (listener_count * usize::from(percent)).div_ceil(100).max(1)
+}
+
+pub(crate) fn skip_vote_tally(jam: &JamState, percent: u8) -> SkipVoteTally {
+    let track_uri = jam
+        .now_playing
+        .as_ref()
+        .map(|playing| playing.spotify_uri.as_str())
+        .unwrap_or_default();
+    let votes = jam
+        .skip_votes
+        .as_ref()
+        .filter(|votes| jam.now_playing.is_some() && votes.track_epoch == jam.track_epoch)
+        .map(|votes| {
+            jam.listeners
+                .values()
+                .filter(|auth_id| votes.voters.contains(*auth_id))
+                .count()
+        })
+        .unwrap_or(0);
+    SkipVoteTally {
+        track_uri: track_uri.to_string(),
+        votes,
+        required: skip_votes_required(percent, jam.listeners.len()),
+        listener_count: jam.listeners.len(),
+    }
+}
+
+/// Count a skip request. The host always skips; other participants must be
+/// registered listeners and only skip once enough listeners agree. Votes are
+/// kept on `Skip`; the caller clears them once the skip has gone through, so a
+/// failed skip leaves the tally standing.
+pub(crate) fn record_skip_vote(
+    jam: &mut JamState,
+    percent: u8,
+    identity: &str,
+    participant_auth_id: &str,
+) -> SkipVoteOutcome {
+    let is_host = !jam.host_identity.is_empty()
+        && jam.host_identity == identity
+        && jam.host_participant_auth_id == participant_auth_id;
+    if !is_host {
+        if jam.listeners.get(identity).map(String::as_str) != Some(participant_auth_id) {
+            return SkipVoteOutcome::NotListening;
+        }
+        if jam.now_playing.is_none() {
+            return SkipVoteOutcome::NothingPlaying;
+        }
+        let track_epoch = jam.track_epoch;
+        let votes = jam.skip_votes.get_or_insert_with(SkipVotes::default);
+        if votes.track_epoch != track_epoch {
+            *votes = SkipVotes {
+                track_epoch,
+                voters: HashSet::new(),
+            };
+        }
+        votes.voters.insert(participant_auth_id.to_string());
+        let tally = skip_vote_tally(jam, percent);
+        if tally.votes < tally.required {
+            return SkipVoteOutcome::Recorded(tally);
+        }
+    }
+    SkipVoteOutcome::Skip
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jam_session::NowPlayingInfo;
+
+    fn playing(uri: &str) -> Option<NowPlayingInfo> {
+        Some(NowPlayingInfo {
+            spotify_id: String::new(),
+            spotify_uri: uri.to_string(),
+            spotify_url: String::new(),
+            name: String::new(),
+            artist: String::new(),
+            album_art_url: String::new(),
+            duration_ms: 0,
+            progress_ms: 0,
+            is_playing: true,
+            fetched_at: None,
+        })
+    }
+
+    fn jam_with_listeners(count: usize) -> JamState {
+        let mut jam = JamState {
+            active: true,
+            host_identity: "host".to_string(),
+            host_participant_auth_id: "host-binding".to_string(),
+            now_playing: playing("spotify:track:one"),
+            ..JamState::default()
+        };
+        for index in 0..count {
+            jam.listeners
+                .insert(format!("listener-{index}"), format!("binding-{index}"));
+        }
+        jam
+    }
+
+    #[test]
+    fn threshold_rounds_up_and_never_reaches_zero() {
+        assert_eq!(skip_votes_required(50, 4), 2);
+        assert_eq!(skip_votes_required(50, 3), 2);
+        assert_eq!(skip_votes_required(100, 3), 3);
+        assert_eq!(skip_votes_required(50, 0), 1);
+    }
+
+    #[test]
+    fn listener_votes_skip_at_the_threshold_and_reset_on_track_change() {
+        let mut jam = jam_with_listeners(4);
+
+        let first = record_skip_vote(&mut jam, 50, "listener-0", "binding-0");
+        assert!(matches!(first, SkipVoteOutcome::Recorded(ref tally) if tally.votes == 1));
+        // A repeat vote from the same listener does not count twice.
+        let repeat = record_skip_vote(&mut jam, 50, "listener-0", "binding-0");
+        assert!(matches!(repeat, SkipVoteOutcome::Recorded(ref tally) if tally.votes == 1));
+
+        jam.now_playing = playing("spotify:track:two");
+        jam.track_epoch += 1;
+        assert_eq!(skip_vote_tally(&jam, 50).votes, 0);
+        let after_change = record_skip_vote(&mut jam, 50, "listener-1", "binding-1");
+        assert!(matches!(after_change, SkipVoteOutcome::Recorded(ref tally) if tally.votes == 1));
+
+        assert_eq!(
+            record_skip_vote(&mut jam, 50, "listener-2", "binding-2"),
+            SkipVoteOutcome::Skip
+        );
+        // Until the skip goes through, the votes stand.
+        assert_eq!(skip_vote_tally(&jam, 50).votes, 2);
+    }
+
+    #[test]
+    fn same_song_playing_again_starts_a_fresh_tally() {
+        let mut jam = jam_with_listeners(4);
+        record_skip_vote(&mut jam, 50, "listener-0", "binding-0");
+        assert_eq!(skip_vote_tally(&jam, 50).votes, 1);
+
+        // The same URI, queued twice in a row, is a new occurrence.
+        jam.track_epoch += 1;
+        assert_eq!(skip_vote_tally(&jam, 50).votes, 0);
+        let fresh = record_skip_vote(&mut jam, 50, "listener-1", "binding-1");
+        assert!(matches!(fresh, SkipVoteOutcome::Recorded(ref tally) if tally.votes == 1));
+    }
+
+    #[test]
+    fn votes_are_refused_while_nothing_is_playing() {
+        let mut jam = jam_with_listeners(2);
+        jam.now_playing = None;
+        assert_eq!(
+            record_skip_vote(&mut jam, 50, "listener-0", "binding-0"),
+            SkipVoteOutcome::NothingPlaying
+        );
+        assert!(jam.skip_votes.is_none());
+        assert_eq!(skip_vote_tally(&jam, 50).votes, 0);
+    }
+
+    #[test]
+    fn host_force_skips_and_non_listeners_cannot_vote() {
+        let mut jam = jam_with_listeners(5);
+
+        assert_eq!(
+            record_skip_vote(&mut jam, 50, "listener-9", "binding-9"),
+            SkipVoteOutcome::NotListening
+        );
+        assert_eq!(
+            record_skip_vote(&mut jam, 50, "listener-0", "stale-binding"),
+            SkipVoteOutcome::NotListening
+        );
+        assert_eq!(
+            record_skip_vote(&mut jam, 50, "host", "host-binding"),
+            SkipVoteOutcome::Skip
+        );
+
+        // Votes from listeners who left stop counting.
+        record_skip_vote(&mut jam, 50, "listener-0", "binding-0");
+        jam.listeners.remove("listener-0");
+        assert_eq!(skip_vote_tally(&jam, 50).votes, 0);
+    }
+}
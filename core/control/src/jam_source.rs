@@ -1,3 +1,5 @@
+use audiopus::coder::Decoder as OpusDecoder;
+use audiopus::{packet::Packet, Channels, MutSignals, SampleRate};
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::{
     extract::{Query, State},
@@ -10,19 +12,40 @@ use std::sync::{
     atomic::{AtomicU64, Ordering},
     Arc,
 };
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, mpsc, Mutex};
 use tracing::{info, warn};
 
+use crate::config::{Config, JamSourceAgentConfig};
 use crate::AppState;
 
-pub(crate) const JAM_SOURCE_PROTOCOL_VERSION: u8 = 3;
+/// Newest source agent protocol. v4 adds sequence-numbered, codec-tagged
+/// audio frames and session resume; v3 agents are still accepted.
+pub(crate) const JAM_SOURCE_PROTOCOL_VERSION: u8 = 4;
+const JAM_SOURCE_MIN_PROTOCOL_VERSION: u8 = 3;
+/// Listener audio WebSocket protocol, independent of the source protocol.
+pub(crate) const JAM_AUDIO_PROTOCOL_VERSION: u8 = 3;
 pub(crate) const AUDIBLE_PEAK_THRESHOLD: f32 = 0.0005;
 const MAX_AUDIO_MESSAGE_BYTES: usize = 4 * 1024 * 1024;
 const SOURCE_ACTIVITY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
 const SOURCE_FRAME_STALL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
 const CAPTURE_RESTART_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(15);
 const SPOTIFY_CONNECT_REPAIR_CAPABILITY: &str = "spotify_connect_repair_v1";
+/// How long a dropped v4 connection may come back with its resume token
+/// before the generation is treated as disconnected.
+const SOURCE_RESUME_GRACE: Duration = Duration::from_secs(5);
+const RESUME_TOKEN_HEADER: &str = "x-echo-jam-resume";
+const V4_FRAME_HEADER_BYTES: usize = 17;
+const CODEC_PCM_F32: u8 = 0;
+const CODEC_PCM_S16: u8 = 1;
+/// One Opus packet per frame, decoded at the announced format.
+const CODEC_OPUS: u8 = 2;
+const SUPPORTED_UPLOAD_CODECS: [&str; 3] = ["pcm_f32", "pcm_s16", "opus"];
+/// The longest Opus packet, 120 ms, in samples per channel at 48 kHz.
+const MAX_OPUS_FRAME_SAMPLES: usize = 5_760;
+/// Minimum health score for a standby to take over a generation.
+const HEALTHY_SOURCE_SCORE: u8 = 50;
+const DEFAULT_STANDBY_PRIORITY: u32 = 1;
 
 #[derive(Clone, Debug)]
 pub(crate) enum SourceEvent {
@@ -107,11 +130,59 @@ pub(crate) struct JamSourceSnapshot {
     pub(crate) last_frame_ms: Option<u64>,
     pub(crate) peak: f32,
     pub(crate) spotify_connect_repair_supported: bool,
+    pub(crate) owner_room: Option<String>,
+    pub(crate) source_id: Option<String>,
+    pub(crate) health_score: u8,
+    pub(crate) sources: Vec<JamSourceAgentSnapshot>,
+    pub(crate) protocol: Option<u8>,
+    pub(crate) resuming: bool,
+    pub(crate) frames_lost: u64,
+}
+
+/// Health of one registered source agent, active or standby.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct JamSourceAgentSnapshot {
+    pub(crate) source_id: String,
+    pub(crate) priority: u32,
+    pub(crate) active: bool,
+    pub(crate) connected: bool,
+    pub(crate) status: String,
+    pub(crate) health_score: u8,
+    pub(crate) protocol: Option<u8>,
+}
+
+/// The room whose Jam generation holds the single capture source. Local
+/// library Jams never claim it, so any number of rooms can run those.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct SourceOwner {
+    pub(crate) room: String,
+    pub(crate) generation: u64,
 }
 
 struct ConnectedSource {
     connection_id: u64,
     command_tx: mpsc::UnboundedSender<Message>,
+    protocol: u8,
+    /// Token a v4 agent presents to resume this connection's generation.
+    resume_token: Option<String>,
+    /// Opus is stateful across packets, so each connection keeps its own
+    /// decoder, rebuilt whenever the announced format changes.
+    opus: Option<OpusStream>,
+    opus_rejected: bool,
+}
+
+struct OpusStream {
+    sample_rate: u32,
+    channels: u32,
+    decoder: OpusDecoder,
+}
+
+/// A v4 connection that dropped mid-generation and may still resume.
+struct PendingResume {
+    connection_id: u64,
+    token: String,
+    generation: u64,
+    expires_at: Instant,
 }
 
 struct PendingRepairCancelGuard {
@@ -162,6 +233,9 @@ struct SourceInner {
     restart_pending_generation: Option<u64>,
     last_restart: Option<(u64, Instant)>,
     peak: f32,
+    resume: Option<PendingResume>,
+    next_sequence: Option<u64>,
+    frames_lost: u64,
 }
 
 impl Default for SourceInner {
@@ -187,6 +261,102 @@ impl Default for SourceInner {
             restart_pending_generation: None,
             last_restart: None,
             peak: 0.0,
+            resume: None,
+            next_sequence: None,
+            frames_lost: 0,
+        }
+    }
+}
+
+impl SourceInner {
+    fn resuming(&self) -> bool {
+        self.connection.is_none()
+            && self
+                .resume
+                .as_ref()
+                .map(|resume| resume.expires_at > Instant::now())
+                .unwrap_or(false)
+    }
+}
+
+struct SourceSlot {
+    source_id: String,
+    priority: u32,
+    inner: SourceInner,
+}
+
+/// Every configured source agent. Exactly one slot is active: it receives
+/// start/stop commands and only its lifecycle events reach the Jam. The rest
+/// are standbys that keep negotiating and heartbeating so a generation can be
+/// handed to them.
+struct RegistryInner {
+    slots: Vec<SourceSlot>,
+    active: usize,
+    owner: Option<SourceOwner>,
+}
+
+impl RegistryInner {
+    fn active(&self) -> &SourceInner {
+        &self.slots[self.active].inner
+    }
+
+    fn active_mut(&mut self) -> &mut SourceInner {
+        &mut self.slots[self.active].inner
+    }
+
+    fn connection_slot(&mut self, connection_id: u64) -> Option<(bool, &mut SourceInner)> {
+        let active = self.active;
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| {
+                slot.inner
+                    .connection
+                    .as_ref()
+                    .map(|connection| connection.connection_id)
+                    == Some(connection_id)
+            })
+            .map(|(index, slot)| (index == active, &mut slot.inner))
+    }
+
+    /// The healthiest standby by priority, if any could take over now.
+    fn best_standby(&self) -> Option<usize> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != self.active)
+            .map(|(index, slot)| (index, slot.priority, health_score(&slot.inner)))
+            .filter(|(_, _, score)| *score >= HEALTHY_SOURCE_SCORE)
+            .min_by_key(|(_, priority, score)| (*priority, std::cmp::Reverse(*score)))
+            .map(|(index, _, _)| index)
+    }
+
+    /// While no generation holds the source, follow the preferred healthy
+    /// agent so the next Jam starts on it. The current slot wins ties, so
+    /// equal-priority sources do not flap between polls.
+    fn select_idle_source(&mut self) {
+        if self.owner.is_some()
+            || self.active().desired_generation.is_some()
+            || self.active().pending_repair.is_some()
+        {
+            return;
+        }
+        let rank = |slot: &SourceSlot| {
+            (
+                health_score(&slot.inner) < HEALTHY_SOURCE_SCORE,
+                slot.priority,
+            )
+        };
+        let current = rank(&self.slots[self.active]);
+        if let Some((index, _)) = self
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| (index, rank(slot)))
+            .filter(|(_, candidate)| *candidate < current)
+            .min_by_key(|(_, candidate)| *candidate)
+        {
+            self.active = index;
         }
     }
 }
@@ -194,22 +364,58 @@ impl Default for SourceInner {
 #[derive(Clone)]
 pub(crate) struct JamSourceRegistry {
     configured: bool,
-    inner: Arc<Mutex<SourceInner>>,
+    inner: Arc<Mutex<RegistryInner>>,
     events: broadcast::Sender<SourceEvent>,
     next_connection_id: Arc<AtomicU64>,
     next_repair_request_id: Arc<AtomicU64>,
 }
 
 impl JamSourceRegistry {
+    /// A registry with at most one source agent, named `primary`.
+    #[cfg(test)]
     pub(crate) fn new(configured: bool) -> Self {
+        let sources = configured
+            .then(|| JamSourceAgentConfig {
+                id: "primary".to_string(),
+                token: String::new(),
+                priority: 0,
+                spotify_device_name: None,
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+        Self::with_sources(&sources)
+    }
+
+    pub(crate) fn with_sources(sources: &[JamSourceAgentConfig]) -> Self {
         let (events, _) = broadcast::channel(256);
-        let mut inner = SourceInner::default();
-        if !configured {
-            inner.status = "unconfigured".to_string();
+        let configured = !sources.is_empty();
+        let mut slots = sources
+            .iter()
+            .map(|source| SourceSlot {
+                source_id: source.id.clone(),
+                priority: source.priority,
+                inner: SourceInner::default(),
+            })
+            .collect::<Vec<_>>();
+        if slots.is_empty() {
+            // Placeholder so the active slot always exists; no socket can
+            // authenticate as it.
+            slots.push(SourceSlot {
+                source_id: String::new(),
+                priority: 0,
+                inner: SourceInner {
+                    status: "unconfigured".to_string(),
+                    ..SourceInner::default()
+                },
+            });
         }
         Self {
             configured,
-            inner: Arc::new(Mutex::new(inner)),
+            inner: Arc::new(Mutex::new(RegistryInner {
+                slots,
+                active: 0,
+                owner: None,
+            })),
             events,
             next_connection_id: Arc::new(AtomicU64::new(1)),
             next_repair_request_id: Arc::new(AtomicU64::new(1)),
@@ -220,55 +426,65 @@ impl JamSourceRegistry {
         self.events.subscribe()
     }
 
+    /// Relay audio decoded by the server-side local library player. It shares
+    /// the source event bus but never touches the connected agent's state.
+    pub(crate) fn publish_local_audio(
+        &self,
+        generation: u64,
+        sample_rate: u32,
+        channels: u32,
+        samples: Vec<f32>,
+    ) {
+        let _ = self.events.send(SourceEvent::Audio {
+            generation,
+            sample_rate,
+            channels,
+            samples,
+        });
+    }
+
+    /// Reserve the capture source for one room's generation. Another room's
+    /// claim is displaced only when `owner_live` reports that its Jam ended
+    /// without releasing it.
+    pub(crate) async fn claim(
+        &self,
+        room: &str,
+        generation: u64,
+        owner_live: impl FnOnce(&SourceOwner) -> bool,
+    ) -> Result<(), SourceOwner> {
+        let mut sources = self.inner.lock().await;
+        if let Some(owner) = &sources.owner {
+            if owner.room != room && owner_live(owner) {
+                return Err(owner.clone());
+            }
+        }
+        sources.owner = Some(SourceOwner {
+            room: room.to_string(),
+            generation,
+        });
+        Ok(())
+    }
+
+    pub(crate) async fn release(&self, generation: u64) {
+        let mut sources = self.inner.lock().await;
+        if sources.owner.as_ref().map(|owner| owner.generation) == Some(generation) {
+            sources.owner = None;
+        }
+    }
+
     pub(crate) async fn start(&self, generation: u64) -> Result<(), String> {
         if !self.configured {
             return Err("Jam source is not configured".to_string());
         }
-        let message = serde_json::json!({
-            "type": "start",
-            "generation": generation,
-        })
-        .to_string();
-        let mut inner = self.inner.lock().await;
-        let command_tx = inner
-            .connection
+        let mut sources = self.inner.lock().await;
+        if let Some(owner) = sources
+            .owner
             .as_ref()
-            .map(|connection| connection.command_tx.clone())
-            .ok_or_else(|| "Configured Jam source is offline".to_string())?;
-        if inner
-            .last_activity_at
-            .map(|at| at.elapsed() > SOURCE_ACTIVITY_TIMEOUT)
-            .unwrap_or(true)
+            .filter(|owner| owner.generation != generation)
         {
-            return Err("Configured Jam source heartbeat is stale".to_string());
-        }
-        if !inner.availability_known {
-            return Err("Jam source availability is still negotiating".to_string());
-        }
-        if !inner.enabled {
-            return Err("Jam source is disabled on the source PC".to_string());
-        }
-        if inner.pending_repair.is_some() {
-            return Err("Spotify Connect repair is still finishing on the source PC".to_string());
+            return Err(format!("Jam source is in use by room {}", owner.room));
         }
-        command_tx
-            .send(Message::Text(message))
-            .map_err(|_| "Configured Jam source disconnected".to_string())?;
-        inner.desired_generation = Some(generation);
-        inner.ready_generation = None;
-        inner.format_generation = None;
-        inner.sample_rate = None;
-        inner.channels = None;
-        inner.pid = None;
-        inner.status = "starting".to_string();
-        inner.error = None;
-        inner.ready_at = None;
-        inner.last_frame_at = None;
-        inner.last_audible_at = None;
-        inner.restart_pending_generation = None;
-        inner.last_restart = None;
-        inner.peak = 0.0;
-        Ok(())
+        begin_capture(sources.active_mut(), generation)
     }
 
     pub(crate) async fn stop(&self, generation: u64) {
@@ -277,49 +493,138 @@ impl JamSourceRegistry {
             "generation": generation,
         })
         .to_string();
-        let mut inner = self.inner.lock().await;
+        let mut sources = self.inner.lock().await;
+        if sources.owner.as_ref().map(|owner| owner.generation) == Some(generation) {
+            sources.owner = None;
+        }
+        let inner = sources.active_mut();
         if let Some(connection) = &inner.connection {
             let _ = connection.command_tx.send(Message::Text(message));
         }
         if inner.desired_generation == Some(generation) {
-            inner.desired_generation = None;
-            inner.ready_generation = None;
-            inner.format_generation = None;
-            inner.sample_rate = None;
-            inner.channels = None;
-            inner.pid = None;
-            inner.error = None;
-            inner.status = if !self.configured {
-                "unconfigured".to_string()
-            } else if inner.connection.is_none() {
-                "offline".to_string()
-            } else if !inner.availability_known {
-                "negotiating".to_string()
-            } else if inner.enabled {
-                "ready".to_string()
-            } else {
-                "disabled".to_string()
+            release_capture(inner, self.configured);
+        }
+    }
+
+    /// Move `generation` from the active source to the standby `source_id`
+    /// and wait for its capture to report ready. The previous source is told
+    /// to stop and its later events no longer reach the Jam. On failure the
+    /// new source stays active, so ending the generation stops it too.
+    pub(crate) async fn hand_off(
+        &self,
+        generation: u64,
+        source_id: &str,
+        timeout: Duration,
+    ) -> Result<(), String> {
+        let mut events = {
+            let mut sources = self.inner.lock().await;
+            if sources.owner.as_ref().map(|owner| owner.generation) != Some(generation) {
+                return Err("The Jam source is no longer held by this generation".to_string());
+            }
+            let target = sources
+                .slots
+                .iter()
+                .position(|slot| slot.source_id == source_id)
+                .filter(|index| *index != sources.active)
+                .ok_or_else(|| format!("Jam source {source_id} is not a standby"))?;
+            begin_capture(&mut sources.slots[target].inner, generation)?;
+            let previous = std::mem::replace(&mut sources.active, target);
+            let previous = &mut sources.slots[previous].inner;
+            if let Some(connection) = &previous.connection {
+                let _ = connection.command_tx.send(Message::Text(
+                    serde_json::json!({
+                        "type": "stop",
+                        "generation": generation,
+                    })
+                    .to_string(),
+                ));
+            }
+            release_capture(previous, self.configured);
+            // A dropped previous source must reconnect from scratch.
+            previous.resume = None;
+            // Subscribe under the registry lock so only the new source's
+            // lifecycle can answer this handoff.
+            self.subscribe()
+        };
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Ok(event)) => event,
+                Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                Ok(Err(broadcast::error::RecvError::Closed)) => {
+                    return Err("Jam source event channel closed".to_string())
+                }
+                Err(_) => {
+                    return Err(format!(
+                        "Jam source {source_id} did not become ready before timeout"
+                    ))
+                }
             };
-            inner.ready_at = None;
-            inner.last_frame_at = None;
-            inner.last_audible_at = None;
-            inner.restart_pending_generation = None;
-            inner.last_restart = None;
-            inner.peak = 0.0;
+            match event {
+                SourceEvent::Ready {
+                    generation: event_generation,
+                    ..
+                } if event_generation == generation => return Ok(()),
+                SourceEvent::Error {
+                    generation: event_generation,
+                    message,
+                } if event_generation == generation => {
+                    return Err(format!("Jam source {source_id} failed: {message}"));
+                }
+                SourceEvent::AvailabilityChanged {
+                    enabled: false,
+                    generation: event_generation,
+                    ..
+                }
+                | SourceEvent::Disconnected {
+                    generation: event_generation,
+                }
+                | SourceEvent::ConnectionReplaced {
+                    generation: event_generation,
+                } if event_generation.is_none() || event_generation == Some(generation) => {
+                    return Err(format!("Jam source {source_id} went away during handoff"));
+                }
+                _ => {}
+            }
         }
     }
 
+    /// The standby a failing generation should move to, if any is healthy.
+    pub(crate) async fn failover_candidate(&self) -> Option<String> {
+        let sources = self.inner.lock().await;
+        sources
+            .best_standby()
+            .map(|index| sources.slots[index].source_id.clone())
+    }
+
+    /// True once the active capture is still packet-stalled a full debounce
+    /// after `restart_stalled_capture` asked it to rebind.
+    pub(crate) async fn capture_failover_due(&self, generation: u64) -> bool {
+        let sources = self.inner.lock().await;
+        let inner = sources.active();
+        inner.desired_generation == Some(generation)
+            && inner.ready_generation == Some(generation)
+            && capture_packets_stalled(inner)
+            && inner
+                .last_restart
+                .filter(|(restart_generation, _)| *restart_generation == generation)
+                .map(|(_, at)| at.elapsed() >= CAPTURE_RESTART_DEBOUNCE)
+                .unwrap_or(false)
+    }
+
     /// Ask the current source connection to replace a packet-stalled WASAPI
     /// capture without ending the Jam generation or its listener sockets.
     /// Playback expectation is checked by the Jam session; this method adds
     /// generation, connection, raw-packet-stall, and debounce fencing.
     pub(crate) async fn restart_stalled_capture(&self, generation: u64) -> bool {
-        let mut inner = self.inner.lock().await;
+        let mut sources = self.inner.lock().await;
+        let inner = sources.active_mut();
         if inner.desired_generation != Some(generation)
             || inner.ready_generation != Some(generation)
             || !inner.availability_known
             || !inner.enabled
-            || !capture_packets_stalled(&inner)
+            || !capture_packets_stalled(inner)
             || inner
                 .last_activity_at
                 .map(|at| at.elapsed() > SOURCE_ACTIVITY_TIMEOUT)
@@ -368,9 +673,10 @@ impl JamSourceRegistry {
         }
         let request_id = self.next_repair_request_id.fetch_add(1, Ordering::Relaxed);
         let (connection_id, mut events, mut cancel_guard) = {
-            let mut inner = tokio::time::timeout_at(deadline, self.inner.lock())
+            let mut sources = tokio::time::timeout_at(deadline, self.inner.lock())
                 .await
                 .map_err(|_| "Spotify Connect repair start deadline expired".to_string())?;
+            let inner = sources.active_mut();
             if inner
                 .last_activity_at
                 .map(|at| at.elapsed() > SOURCE_ACTIVITY_TIMEOUT)
@@ -469,52 +775,15 @@ impl JamSourceRegistry {
     }
 
     pub(crate) async fn snapshot(&self) -> JamSourceSnapshot {
-        let inner = self.inner.lock().await;
-        let activity_stale = inner.connection.is_some()
-            && inner
-                .last_activity_at
-                .map(|at| at.elapsed() > SOURCE_ACTIVITY_TIMEOUT)
-                .unwrap_or(true);
-        let last_frame_ms = inner
-            .last_frame_at
-            .map(|at| at.elapsed().as_millis().min(u64::MAX as u128) as u64);
-        let status = if !self.configured {
-            "unconfigured".to_string()
-        } else if inner.connection.is_none() || activity_stale {
-            "offline".to_string()
-        } else if !inner.availability_known {
-            "negotiating".to_string()
-        } else if !inner.enabled {
-            "disabled".to_string()
-        } else if inner.error.is_some() {
-            "error".to_string()
-        } else if inner.desired_generation.is_some()
-            && inner.ready_generation != inner.desired_generation
-        {
-            "starting".to_string()
-        } else if inner.ready_generation.is_some() {
-            match last_frame_ms {
-                None if capture_packets_stalled(&inner) => "stalled".to_string(),
-                None => "ready".to_string(),
-                Some(age) if age > SOURCE_FRAME_STALL_TIMEOUT.as_millis() as u64 => {
-                    "stalled".to_string()
-                }
-                Some(_)
-                    if inner
-                        .last_audible_at
-                        .map(|at| at.elapsed().as_millis() <= 2_000)
-                        .unwrap_or(false) =>
-                {
-                    "live".to_string()
-                }
-                Some(_) => "silent".to_string(),
-            }
-        } else {
-            inner.status.clone()
-        };
+        let mut sources = self.inner.lock().await;
+        sources.select_idle_source();
+        let inner = sources.active();
+        let (status, activity_stale, last_frame_ms) = source_status(self.configured, inner);
+        // A v4 source inside its resume grace still holds the generation.
+        let connected = (inner.connection.is_some() || inner.resuming()) && !activity_stale;
         JamSourceSnapshot {
             configured: self.configured,
-            connected: inner.connection.is_some() && !activity_stale,
+            connected,
             availability_known: inner.availability_known,
             enabled: inner.enabled,
             status,
@@ -524,7 +793,7 @@ impl JamSourceRegistry {
                 inner.error.clone()
             },
             generation: inner.desired_generation,
-            ready: !activity_stale
+            ready: connected
                 && inner.availability_known
                 && inner.enabled
                 && inner.desired_generation.is_some()
@@ -535,16 +804,113 @@ impl JamSourceRegistry {
             last_frame_ms,
             peak: inner.peak,
             spotify_connect_repair_supported: inner.spotify_connect_repair_supported,
+            owner_room: sources.owner.as_ref().map(|owner| owner.room.clone()),
+            source_id: self
+                .configured
+                .then(|| sources.slots[sources.active].source_id.clone()),
+            health_score: health_score(inner),
+            protocol: inner
+                .connection
+                .as_ref()
+                .map(|connection| connection.protocol),
+            resuming: inner.resuming(),
+            frames_lost: inner.frames_lost,
+            sources: if self.configured {
+                sources
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .map(|(index, slot)| {
+                        let (status, activity_stale, _) =
+                            source_status(self.configured, &slot.inner);
+                        JamSourceAgentSnapshot {
+                            source_id: slot.source_id.clone(),
+                            priority: slot.priority,
+                            active: index == sources.active,
+                            connected: slot.inner.connection.is_some() && !activity_stale,
+                            status,
+                            health_score: health_score(&slot.inner),
+                            protocol: slot
+                                .inner
+                                .connection
+                                .as_ref()
+                                .map(|connection| connection.protocol),
+                        }
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
         }
     }
 
-    async fn register(&self, command_tx: mpsc::UnboundedSender<Message>) -> u64 {
+    async fn register(
+        &self,
+        source_id: &str,
+        command_tx: mpsc::UnboundedSender<Message>,
+        protocol: u8,
+        resume_token: Option<&str>,
+    ) -> Option<u64> {
         let connection_id = self.next_connection_id.fetch_add(1, Ordering::Relaxed);
         {
-            let mut inner = self.inner.lock().await;
+            let mut sources = self.inner.lock().await;
+            let index = sources
+                .slots
+                .iter()
+                .position(|slot| slot.source_id == source_id)?;
+            let active = index == sources.active;
+            let inner = &mut sources.slots[index].inner;
+            let issued_token = (protocol >= 4).then(random_resume_token);
+
+            let resumable = protocol >= 4
+                && inner.resuming()
+                && match (resume_token, inner.resume.as_ref()) {
+                    (Some(supplied), Some(resume)) => {
+                        constant_time_eq(supplied.as_bytes(), resume.token.as_bytes())
+                    }
+                    _ => false,
+                };
+            if resumable {
+                // Same agent after a network blip: keep the generation, its
+                // capture state, and the Jam's listeners exactly as they were.
+                inner.resume = None;
+                inner.connection = Some(ConnectedSource {
+                    connection_id,
+                    command_tx: command_tx.clone(),
+                    protocol,
+                    resume_token: issued_token.clone(),
+                    opus: None,
+                    opus_rejected: false,
+                });
+                inner.last_activity_at = Some(Instant::now());
+                let _ = command_tx.send(session_message(
+                    protocol,
+                    issued_token.as_deref(),
+                    true,
+                    inner.desired_generation,
+                    inner.next_sequence.unwrap_or(0),
+                ));
+                info!(
+                    "[jam-source] source {} resumed generation {:?}",
+                    source_id, inner.desired_generation
+                );
+                return Some(connection_id);
+            }
+            if let Some(generation) = expire_pending_resume(inner, self.configured) {
+                if active {
+                    let _ = self.events.send(SourceEvent::Disconnected {
+                        generation: Some(generation),
+                    });
+                }
+            }
+
             let old = inner.connection.replace(ConnectedSource {
                 connection_id,
-                command_tx,
+                command_tx: command_tx.clone(),
+                protocol,
+                resume_token: issued_token.clone(),
+                opus: None,
+                opus_rejected: false,
             });
             let replaced_generation = old.as_ref().and(inner.desired_generation);
             if replaced_generation.is_some() {
@@ -572,6 +938,15 @@ impl JamSourceRegistry {
             inner.restart_pending_generation = None;
             inner.last_restart = None;
             inner.peak = 0.0;
+            if protocol >= 4 {
+                let _ = command_tx.send(session_message(
+                    protocol,
+                    issued_token.as_deref(),
+                    false,
+                    None,
+                    0,
+                ));
+            }
             let event = if let Some(old) = old {
                 let _ = old.command_tx.send(Message::Close(None));
                 SourceEvent::ConnectionReplaced {
@@ -582,51 +957,89 @@ impl JamSourceRegistry {
             };
             // Publish before releasing the registry lock. A repair subscribes
             // and captures its connection under this same lock, so it cannot
-            // mistake this event for a later connection's lifecycle.
-            let _ = self.events.send(event);
+            // mistake this event for a later connection's lifecycle. Standby
+            // connections never disturb the active generation.
+            if active {
+                let _ = self.events.send(event);
+            }
         }
-        connection_id
+        Some(connection_id)
     }
 
     async fn unregister(&self, connection_id: u64) {
-        let mut inner = self.inner.lock().await;
-        if inner
+        let mut sources = self.inner.lock().await;
+        let Some((active, inner)) = sources.connection_slot(connection_id) else {
+            return;
+        };
+        let resume = inner
             .connection
             .as_ref()
-            .map(|connection| connection.connection_id)
-            == Some(connection_id)
-        {
-            let generation = inner.desired_generation;
+            .and_then(|connection| connection.resume_token.clone())
+            .zip(inner.desired_generation)
+            .filter(|_| inner.pending_repair.is_none());
+        if let Some((token, generation)) = resume {
+            // Hold the generation open briefly. The agent keeps capturing and
+            // resumes with its token; otherwise the timer below disconnects.
             inner.connection = None;
-            inner.desired_generation = None;
-            inner.availability_known = false;
-            inner.enabled = false;
-            inner.spotify_connect_repair_supported = false;
-            inner.pending_repair = None;
-            inner.ready_generation = None;
-            inner.format_generation = None;
-            inner.sample_rate = None;
-            inner.channels = None;
-            inner.pid = None;
-            inner.ready_at = None;
-            inner.last_activity_at = None;
-            inner.last_frame_at = None;
-            inner.last_audible_at = None;
-            inner.restart_pending_generation = None;
-            inner.last_restart = None;
-            inner.peak = 0.0;
-            inner.status = if self.configured {
-                "offline".to_string()
-            } else {
-                "unconfigured".to_string()
-            };
+            inner.resume = Some(PendingResume {
+                connection_id,
+                token,
+                generation,
+                expires_at: Instant::now() + SOURCE_RESUME_GRACE,
+            });
+            let registry = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(SOURCE_RESUME_GRACE).await;
+                registry.expire_resume(connection_id).await;
+            });
+            return;
+        }
+        let generation = disconnect_source(inner, self.configured);
+        if active {
             let _ = self.events.send(SourceEvent::Disconnected { generation });
         }
     }
 
+    async fn expire_resume(&self, connection_id: u64) {
+        let mut sources = self.inner.lock().await;
+        let active = sources.active;
+        let Some((index, slot)) = sources.slots.iter_mut().enumerate().find(|(_, slot)| {
+            slot.inner
+                .resume
+                .as_ref()
+                .map(|resume| resume.connection_id)
+                == Some(connection_id)
+        }) else {
+            return;
+        };
+        if let Some(generation) = expire_pending_resume(&mut slot.inner, self.configured) {
+            info!(
+                "[jam-source] source {} did not resume generation {}",
+                slot.source_id, generation
+            );
+            if index == active {
+                let _ = self.events.send(SourceEvent::Disconnected {
+                    generation: Some(generation),
+                });
+            }
+        }
+    }
+
     #[cfg(test)]
     pub(crate) async fn test_register(&self, command_tx: mpsc::UnboundedSender<Message>) -> u64 {
-        self.register(command_tx).await
+        let source_id = self.inner.lock().await.slots[0].source_id.clone();
+        self.register(&source_id, command_tx, 3, None)
+            .await
+            .unwrap()
+    }
+
+    #[cfg(test)]
+    pub(crate) async fn test_register_source(
+        &self,
+        source_id: &str,
+        command_tx: mpsc::UnboundedSender<Message>,
+    ) -> u64 {
+        self.register(source_id, command_tx, 3, None).await.unwrap()
     }
 
     #[cfg(test)]
@@ -666,6 +1079,323 @@ fn capture_packets_stalled(inner: &SourceInner) -> bool {
         .unwrap_or(false)
 }
 
+/// Send `start` for `generation` to one source and reset its capture state.
+/// Nothing is sent unless the source is connected, fresh, enabled, and idle
+/// of Spotify Connect repairs.
+fn begin_capture(inner: &mut SourceInner, generation: u64) -> Result<(), String> {
+    let command_tx = inner
+        .connection
+        .as_ref()
+        .map(|connection| connection.command_tx.clone())
+        .ok_or_else(|| "Configured Jam source is offline".to_string())?;
+    if inner
+        .last_activity_at
+        .map(|at| at.elapsed() > SOURCE_ACTIVITY_TIMEOUT)
+        .unwrap_or(true)
+    {
+        return Err("Configured Jam source heartbeat is stale".to_string());
+    }
+    if !inner.availability_known {
+        return Err("Jam source availability is still negotiating".to_string());
+    }
+    if !inner.enabled {
+        return Err("Jam source is disabled on the source PC".to_string());
+    }
+    if inner.pending_repair.is_some() {
+        return Err("Spotify Connect repair is still finishing on the source PC".to_string());
+    }
+    command_tx
+        .send(Message::Text(
+            serde_json::json!({
+                "type": "start",
+                "generation": generation,
+            })
+            .to_string(),
+        ))
+        .map_err(|_| "Configured Jam source disconnected".to_string())?;
+    inner.desired_generation = Some(generation);
+    inner.ready_generation = None;
+    inner.format_generation = None;
+    inner.sample_rate = None;
+    inner.channels = None;
+    inner.pid = None;
+    inner.status = "starting".to_string();
+    inner.error = None;
+    inner.ready_at = None;
+    inner.last_frame_at = None;
+    inner.last_audible_at = None;
+    inner.restart_pending_generation = None;
+    inner.last_restart = None;
+    inner.peak = 0.0;
+    inner.next_sequence = None;
+    inner.frames_lost = 0;
+    Ok(())
+}
+
+fn release_capture(inner: &mut SourceInner, configured: bool) {
+    inner.desired_generation = None;
+    inner.ready_generation = None;
+    inner.format_generation = None;
+    inner.sample_rate = None;
+    inner.channels = None;
+    inner.pid = None;
+    inner.error = None;
+    inner.status = if !configured {
+        "unconfigured".to_string()
+    } else if inner.connection.is_none() {
+        "offline".to_string()
+    } else if !inner.availability_known {
+        "negotiating".to_string()
+    } else if inner.enabled {
+        "ready".to_string()
+    } else {
+        "disabled".to_string()
+    };
+    inner.ready_at = None;
+    inner.last_frame_at = None;
+    inner.last_audible_at = None;
+    inner.restart_pending_generation = None;
+    inner.last_restart = None;
+    inner.peak = 0.0;
+}
+
+/// Clear everything a dropped connection held and return the generation it
+/// was capturing.
+fn disconnect_source(inner: &mut SourceInner, configured: bool) -> Option<u64> {
+    let generation = inner.desired_generation;
+    inner.connection = None;
+    inner.resume = None;
+    inner.desired_generation = None;
+    inner.availability_known = false;
+    inner.enabled = false;
+    inner.spotify_connect_repair_supported = false;
+    inner.pending_repair = None;
+    inner.ready_generation = None;
+    inner.format_generation = None;
+    inner.sample_rate = None;
+    inner.channels = None;
+    inner.pid = None;
+    inner.ready_at = None;
+    inner.last_activity_at = None;
+    inner.last_frame_at = None;
+    inner.last_audible_at = None;
+    inner.restart_pending_generation = None;
+    inner.last_restart = None;
+    inner.peak = 0.0;
+    inner.status = if configured {
+        "offline".to_string()
+    } else {
+        "unconfigured".to_string()
+    };
+    generation
+}
+
+/// Give up on a pending resume, returning the generation that is now lost.
+fn expire_pending_resume(inner: &mut SourceInner, configured: bool) -> Option<u64> {
+    if inner.connection.is_some() {
+        return None;
+    }
+    let resume = inner.resume.take()?;
+    disconnect_source(inner, configured);
+    Some(resume.generation)
+}
+
+fn random_resume_token() -> String {
+    let mut bytes = [0_u8; 32];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// First message to a v4 agent: its resume token and the upload codecs this
+/// server decodes. `next_sequence` is where a resumed upload continues.
+fn session_message(
+    protocol: u8,
+    resume_token: Option<&str>,
+    resumed: bool,
+    generation: Option<u64>,
+    next_sequence: u64,
+) -> Message {
+    Message::Text(
+        serde_json::json!({
+            "type": "session",
+            "protocol": protocol,
+            "resume_token": resume_token,
+            "resume_grace_ms": SOURCE_RESUME_GRACE.as_millis() as u64,
+            "resumed": resumed,
+            "generation": generation,
+            "next_sequence": next_sequence,
+            "codecs": SUPPORTED_UPLOAD_CODECS,
+        })
+        .to_string(),
+    )
+}
+
+/// Public status of one source, whether its heartbeat is stale, and the age
+/// of its last PCM frame.
+fn source_status(configured: bool, inner: &SourceInner) -> (String, bool, Option<u64>) {
+    let activity_stale = inner.connection.is_some()
+        && inner
+            .last_activity_at
+            .map(|at| at.elapsed() > SOURCE_ACTIVITY_TIMEOUT)
+            .unwrap_or(true);
+    let last_frame_ms = inner
+        .last_frame_at
+        .map(|at| at.elapsed().as_millis().min(u64::MAX as u128) as u64);
+    let status = if !configured {
+        "unconfigured".to_string()
+    } else if inner.resuming() {
+        "resuming".to_string()
+    } else if inner.connection.is_none() || activity_stale {
+        "offline".to_string()
+    } else if !inner.availability_known {
+        "negotiating".to_string()
+    } else if !inner.enabled {
+        "disabled".to_string()
+    } else if inner.error.is_some() {
+        "error".to_string()
+    } else if inner.desired_generation.is_some()
+        && inner.ready_generation != inner.desired_generation
+    {
+        "starting".to_string()
+    } else if inner.ready_generation.is_some() {
+        match last_frame_ms {
+            None if capture_packets_stalled(inner) => "stalled".to_string(),
+            None => "ready".to_string(),
+            Some(age) if age > SOURCE_FRAME_STALL_TIMEOUT.as_millis() as u64 => {
+                "stalled".to_string()
+            }
+            Some(_)
+                if inner
+                    .last_audible_at
+                    .map(|at| at.elapsed().as_millis() <= 2_000)
+                    .unwrap_or(false) =>
+            {
+                "live".to_string()
+            }
+            Some(_) => "silent".to_string(),
+        }
+    } else {
+        inner.status.clone()
+    };
+    (status, activity_stale, last_frame_ms)
+}
+
+/// 0-100 from the heartbeat and frame-stall timers. Zero means the source
+/// cannot capture at all; heartbeat age, a reported error, a packet stall,
+/// and an unfinished Spotify Connect repair each cost points.
+fn health_score(inner: &SourceInner) -> u8 {
+    if inner.connection.is_none() || !inner.availability_known || !inner.enabled {
+        return 0;
+    }
+    let Some(age) = inner.last_activity_at.map(|at| at.elapsed()) else {
+        return 0;
+    };
+    if age > SOURCE_ACTIVITY_TIMEOUT {
+        return 0;
+    }
+    let mut score = 100 - (age.as_millis() * 40 / SOURCE_ACTIVITY_TIMEOUT.as_millis()) as i32;
+    if inner.error.is_some() {
+        score -= 60;
+    }
+    if capture_packets_stalled(inner) {
+        score -= 40;
+    }
+    if inner.pending_repair.is_some() {
+        score -= 20;
+    }
+    score.clamp(0, 100) as u8
+}
+
+/// Parse `CORE_JAM_STANDBY_SOURCES`: `;`-separated
+/// `id|token[|priority[|spotify device name]]` entries. Malformed entries are
+/// logged and skipped.
+pub(crate) fn parse_standby_sources(value: &str) -> Vec<JamSourceAgentConfig> {
+    let mut sources: Vec<JamSourceAgentConfig> = Vec::new();
+    for entry in value
+        .split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let mut fields = entry.splitn(4, '|').map(str::trim);
+        let id = fields.next().unwrap_or_default();
+        let token = fields.next().unwrap_or_default();
+        let priority = match fields.next().filter(|field| !field.is_empty()) {
+            None => Some(DEFAULT_STANDBY_PRIORITY),
+            Some(field) => field.parse::<u32>().ok(),
+        };
+        let spotify_device_name = fields
+            .next()
+            .filter(|name| !name.is_empty())
+            .map(str::to_string);
+        let Some(priority) = priority.filter(|_| !id.is_empty() && !token.is_empty()) else {
+            warn!("[jam-source] ignoring malformed standby source entry");
+            continue;
+        };
+        if sources.iter().any(|source| source.id == id) {
+            warn!("[jam-source] ignoring duplicate standby source {}", id);
+            continue;
+        }
+        sources.push(JamSourceAgentConfig {
+            id: id.to_string(),
+            token: token.to_string(),
+            priority,
+            spotify_device_name,
+        });
+    }
+    sources
+}
+
+/// The primary `JAM_SOURCE_ID` source (priority 0) followed by the standbys.
+pub(crate) fn configured_jam_sources(config: &Config) -> Vec<JamSourceAgentConfig> {
+    let primary = config
+        .jam_source_id
+        .clone()
+        .zip(config.jam_source_token.clone())
+        .map(|(id, token)| JamSourceAgentConfig {
+            id,
+            token,
+            priority: 0,
+            spotify_device_name: config.spotify_device_name.clone(),
+        });
+    let mut sources = primary.into_iter().collect::<Vec<_>>();
+    for standby in &config.jam_standby_sources {
+        if !sources.iter().any(|source| source.id == standby.id) {
+            sources.push(standby.clone());
+        }
+    }
+    sources
+}
+
+/// The Spotify Connect device ID and name to bind while `source_id` is
+/// capturing. Standbys match by name only: `SPOTIFY_DEVICE_ID` belongs to the
+/// primary PC.
+pub(crate) fn jam_source_device_preference(
+    config: &Config,
+    source_id: Option<&str>,
+) -> (Option<String>, Option<String>) {
+    let standby = source_id
+        .filter(|source_id| config.jam_source_id.as_deref() != Some(*source_id))
+        .and_then(|source_id| {
+            config
+                .jam_standby_sources
+                .iter()
+                .find(|standby| standby.id == source_id)
+        });
+    match standby {
+        Some(standby) => (
+            None,
+            standby
+                .spotify_device_name
+                .clone()
+                .or_else(|| config.spotify_device_name.clone()),
+        ),
+        None => (
+            config.spotify_device_id.clone(),
+            config.spotify_device_name.clone(),
+        ),
+    }
+}
+
 #[derive(Deserialize)]
 pub(crate) struct JamSourceQuery {
     source_id: String,
@@ -716,19 +1446,19 @@ pub(crate) async fn jam_source_ws(
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let configured_id = state
-        .config
-        .jam_source_id
-        .as_deref()
-        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
-    let configured_token = state
-        .config
-        .jam_source_token
-        .as_deref()
-        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
-    if query.protocol != JAM_SOURCE_PROTOCOL_VERSION || query.source_id != configured_id {
-        return Err(StatusCode::FORBIDDEN);
+    let sources = configured_jam_sources(&state.config);
+    if sources.is_empty() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
     }
+    let configured_token = sources
+        .into_iter()
+        .find(|source| source.id == query.source_id)
+        .map(|source| source.token)
+        .filter(|_| {
+            (JAM_SOURCE_MIN_PROTOCOL_VERSION..=JAM_SOURCE_PROTOCOL_VERSION)
+                .contains(&query.protocol)
+        })
+        .ok_or(StatusCode::FORBIDDEN)?;
     let supplied_token = headers
         .get("authorization")
         .and_then(|value| value.to_str().ok())
@@ -737,17 +1467,37 @@ pub(crate) async fn jam_source_ws(
     if !constant_time_eq(supplied_token.as_bytes(), configured_token.as_bytes()) {
         return Err(StatusCode::UNAUTHORIZED);
     }
+    let resume_token = headers
+        .get(RESUME_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
     let registry = state.jam_source.clone();
-    Ok(ws.on_upgrade(move |socket| source_socket(socket, registry)))
+    let source_id = query.source_id;
+    let protocol = query.protocol;
+    Ok(ws.on_upgrade(move |socket| {
+        source_socket(socket, registry, source_id, protocol, resume_token)
+    }))
 }
 
-async fn source_socket(socket: WebSocket, registry: JamSourceRegistry) {
+async fn source_socket(
+    socket: WebSocket,
+    registry: JamSourceRegistry,
+    source_id: String,
+    protocol: u8,
+    resume_token: Option<String>,
+) {
     let (mut socket_tx, mut socket_rx) = socket.split();
     let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-    let connection_id = registry.register(command_tx).await;
+    let Some(connection_id) = registry
+        .register(&source_id, command_tx, protocol, resume_token.as_deref())
+        .await
+    else {
+        warn!("[jam-source] source {} is not registered", source_id);
+        return;
+    };
     info!(
-        "[jam-source] protocol v3 source connected id={}",
-        connection_id
+        "[jam-source] protocol v{} source {} connected id={}",
+        protocol, source_id, connection_id
     );
 
     let writer = tokio::spawn(async move {
@@ -781,15 +1531,16 @@ async fn handle_text(registry: &JamSourceRegistry, connection_id: u64, text: &st
             return;
         }
     };
-    let mut inner = registry.inner.lock().await;
-    if inner
-        .connection
-        .as_ref()
-        .map(|connection| connection.connection_id)
-        != Some(connection_id)
-    {
+    let mut sources = registry.inner.lock().await;
+    let Some((active, inner)) = sources.connection_slot(connection_id) else {
         return;
-    }
+    };
+    // Standbys keep their own health current but stay silent on the bus.
+    let emit = |event| {
+        if active {
+            let _ = registry.events.send(event);
+        }
+    };
     match message {
         SourceTextMessage::Availability {
             enabled,
@@ -843,14 +1594,14 @@ async fn handle_text(registry: &JamSourceRegistry, connection_id: u64, text: &st
                 inner.restart_pending_generation = None;
                 inner.last_restart = None;
                 inner.peak = 0.0;
-                let _ = registry.events.send(SourceEvent::AvailabilityChanged {
+                emit(SourceEvent::AvailabilityChanged {
                     enabled,
                     generation: prior_generation,
                     error,
                 });
                 return;
             }
-            let _ = registry.events.send(SourceEvent::AvailabilityChanged {
+            emit(SourceEvent::AvailabilityChanged {
                 enabled,
                 generation: inner.desired_generation,
                 error: None,
@@ -873,7 +1624,7 @@ async fn handle_text(registry: &JamSourceRegistry, connection_id: u64, text: &st
             inner.format_generation = Some(generation);
             inner.sample_rate = Some(sample_rate);
             inner.channels = Some(channels);
-            let _ = registry.events.send(SourceEvent::Format {
+            emit(SourceEvent::Format {
                 generation,
                 sample_rate,
                 channels,
@@ -895,7 +1646,7 @@ async fn handle_text(registry: &JamSourceRegistry, connection_id: u64, text: &st
             inner.last_frame_at = None;
             inner.last_audible_at = None;
             inner.peak = 0.0;
-            let _ = registry.events.send(SourceEvent::Ready { generation, pid });
+            emit(SourceEvent::Ready { generation, pid });
         }
         SourceTextMessage::Error {
             generation,
@@ -913,7 +1664,7 @@ async fn handle_text(registry: &JamSourceRegistry, connection_id: u64, text: &st
             inner.ready_at = None;
             inner.status = "error".to_string();
             inner.error = Some(message.clone());
-            let _ = registry.events.send(SourceEvent::Error {
+            emit(SourceEvent::Error {
                 generation,
                 message,
             });
@@ -949,7 +1700,7 @@ async fn handle_text(registry: &JamSourceRegistry, connection_id: u64, text: &st
             inner.last_audible_at = None;
             inner.restart_pending_generation = None;
             inner.peak = 0.0;
-            let _ = registry.events.send(SourceEvent::Restarting { generation });
+            emit(SourceEvent::Restarting { generation });
         }
         SourceTextMessage::SpotifyConnectRepair {
             request_id,
@@ -967,7 +1718,7 @@ async fn handle_text(registry: &JamSourceRegistry, connection_id: u64, text: &st
             let error = error
                 .map(|message| message.chars().take(500).collect::<String>())
                 .filter(|message| !message.trim().is_empty());
-            let _ = registry.events.send(SourceEvent::SpotifyConnectRepair {
+            emit(SourceEvent::SpotifyConnectRepair {
                 connection_id,
                 request_id,
                 success,
@@ -979,23 +1730,138 @@ async fn handle_text(registry: &JamSourceRegistry, connection_id: u64, text: &st
     }
 }
 
+/// Split an upload into generation, v4 sequence number, codec, and payload.
+/// v3 frames are an 8-byte generation followed by f32 samples; v4 frames add
+/// an 8-byte sequence number and a codec byte.
+fn parse_audio_frame(protocol: u8, bytes: &[u8]) -> Option<(u64, Option<u64>, u8, &[u8])> {
+    if bytes.len() > MAX_AUDIO_MESSAGE_BYTES {
+        return None;
+    }
+    let generation = u64::from_le_bytes(bytes.get(..8)?.try_into().ok()?);
+    if protocol < 4 {
+        return Some((generation, None, CODEC_PCM_F32, &bytes[8..]));
+    }
+    let sequence = u64::from_le_bytes(bytes.get(8..16)?.try_into().ok()?);
+    let codec = *bytes.get(16)?;
+    Some((
+        generation,
+        Some(sequence),
+        codec,
+        &bytes[V4_FRAME_HEADER_BYTES..],
+    ))
+}
+
+/// Decode PCM samples and their peak. `None` rejects the whole frame.
+fn decode_pcm(codec: u8, payload: &[u8]) -> Option<(Vec<f32>, f32)> {
+    let mut peak = 0.0_f32;
+    let samples = match codec {
+        CODEC_PCM_F32 if payload.len().is_multiple_of(4) => {
+            let mut samples = Vec::with_capacity(payload.len() / 4);
+            for chunk in payload.chunks_exact(4) {
+                let sample = f32::from_le_bytes(chunk.try_into().unwrap());
+                if !sample.is_finite() {
+                    return None;
+                }
+                peak = peak.max(sample.abs());
+                samples.push(sample.clamp(-1.0, 1.0));
+            }
+            samples
+        }
+        CODEC_PCM_S16 if payload.len().is_multiple_of(2) => payload
+            .chunks_exact(2)
+            .map(|chunk| {
+                let sample = i16::from_le_bytes(chunk.try_into().unwrap()) as f32 / 32_768.0;
+                peak = peak.max(sample.abs());
+                sample
+            })
+            .collect(),
+        _ => return None,
+    };
+    (!samples.is_empty()).then_some((samples, peak))
+}
+
+/// Opus only runs at its own sample rates, in mono or stereo.
+fn opus_decoder(sample_rate: u32, channels: u32) -> Option<OpusDecoder> {
+    let sample_rate = SampleRate::try_from(i32::try_from(sample_rate).ok()?).ok()?;
+    let channels = match channels {
+        1 => Channels::Mono,
+        2 => Channels::Stereo,
+        _ => return None,
+    };
+    OpusDecoder::new(sample_rate, channels).ok()
+}
+
+/// Decode one Opus packet into interleaved samples and their peak. `None`
+/// rejects the frame.
+fn decode_opus(stream: &mut OpusStream, payload: &[u8]) -> Option<(Vec<f32>, f32)> {
+    let packet = Packet::try_from(payload).ok()?;
+    let channels = stream.channels as usize;
+    let mut samples = vec![0.0_f32; MAX_OPUS_FRAME_SAMPLES * channels];
+    let output = MutSignals::try_from(&mut samples[..]).ok()?;
+    let decoded = stream
+        .decoder
+        .decode_float(Some(packet), output, false)
+        .ok()?;
+    samples.truncate(decoded * channels);
+    let mut peak = 0.0_f32;
+    for sample in &mut samples {
+        peak = peak.max(sample.abs());
+        *sample = sample.clamp(-1.0, 1.0);
+    }
+    (!samples.is_empty()).then_some((samples, peak))
+}
+
+/// Decode an Opus frame with the connection's decoder, building one for the
+/// current format first if needed.
+fn decode_opus_frame(
+    connection: &mut ConnectedSource,
+    sample_rate: u32,
+    channels: u32,
+    payload: &[u8],
+) -> Option<(Vec<f32>, f32)> {
+    let current = connection
+        .opus
+        .as_ref()
+        .is_some_and(|stream| stream.sample_rate == sample_rate && stream.channels == channels);
+    if !current {
+        let Some(decoder) = opus_decoder(sample_rate, channels) else {
+            if !connection.opus_rejected {
+                connection.opus_rejected = true;
+                warn!(
+                    "[jam-source] dropping Opus uploads: {} Hz x {} channels is not an Opus format",
+                    sample_rate, channels
+                );
+            }
+            connection.opus = None;
+            return None;
+        };
+        connection.opus = Some(OpusStream {
+            sample_rate,
+            channels,
+            decoder,
+        });
+    }
+    decode_opus(connection.opus.as_mut()?, payload)
+}
+
 async fn handle_audio(registry: &JamSourceRegistry, connection_id: u64, bytes: &[u8]) {
-    if bytes.len() < 12
-        || bytes.len() > MAX_AUDIO_MESSAGE_BYTES
-        || (bytes.len() - 8) % std::mem::size_of::<f32>() != 0
-    {
+    let mut sources = registry.inner.lock().await;
+    let Some((active, inner)) = sources.connection_slot(connection_id) else {
+        return;
+    };
+    if !active {
         return;
     }
-    let generation = u64::from_le_bytes(bytes[..8].try_into().unwrap());
-    let mut inner = registry.inner.lock().await;
-    if inner
+    let Some(protocol) = inner
         .connection
         .as_ref()
-        .map(|connection| connection.connection_id)
-        != Some(connection_id)
-    {
+        .map(|connection| connection.protocol)
+    else {
         return;
-    }
+    };
+    let Some((generation, sequence, codec, payload)) = parse_audio_frame(protocol, bytes) else {
+        return;
+    };
     if !inner.availability_known
         || !inner.enabled
         || inner.desired_generation != Some(generation)
@@ -1007,21 +1873,28 @@ async fn handle_audio(registry: &JamSourceRegistry, connection_id: u64, bytes: &
     let (Some(sample_rate), Some(channels)) = (inner.sample_rate, inner.channels) else {
         return;
     };
-    let sample_count = (bytes.len() - 8) / std::mem::size_of::<f32>();
-    if sample_count % channels as usize != 0 {
-        return;
-    }
-    let mut samples = Vec::with_capacity((bytes.len() - 8) / 4);
-    let mut peak = 0.0_f32;
-    for chunk in bytes[8..].chunks_exact(4) {
-        let sample = f32::from_le_bytes(chunk.try_into().unwrap());
-        if !sample.is_finite() {
-            return;
+    if let Some(sequence) = sequence {
+        // Sequence numbers run for the whole generation, across capture
+        // restarts and resumes. Late or repeated frames are dropped.
+        match inner.next_sequence {
+            Some(expected) if sequence < expected => return,
+            Some(expected) => inner.frames_lost += sequence - expected,
+            None => {}
         }
-        peak = peak.max(sample.abs());
-        samples.push(sample.clamp(-1.0, 1.0));
+        inner.next_sequence = sequence.checked_add(1);
     }
-    if samples.is_empty() {
+    let decoded = if codec == CODEC_OPUS {
+        let Some(connection) = inner.connection.as_mut() else {
+            return;
+        };
+        decode_opus_frame(connection, sample_rate, channels, payload)
+    } else {
+        decode_pcm(codec, payload)
+    };
+    let Some((samples, peak)) = decoded else {
+        return;
+    };
+    if !samples.len().is_multiple_of(channels as usize) {
         return;
     }
     inner.last_activity_at = Some(Instant::now());
@@ -1030,7 +1903,7 @@ async fn handle_audio(registry: &JamSourceRegistry, connection_id: u64, bytes: &
     if peak >= AUDIBLE_PEAK_THRESHOLD {
         inner.last_audible_at = Some(Instant::now());
     }
-    drop(inner);
+    drop(sources);
     let _ = registry.events.send(SourceEvent::Audio {
         generation,
         sample_rate,
@@ -1080,11 +1953,44 @@ mod tests {
         assert!(!constant_time_eq(b"short", b"longer"));
     }
 
+    #[tokio::test]
+    async fn one_room_at_a_time_owns_the_capture_source() {
+        let registry = JamSourceRegistry::new(true);
+        let (command_tx, _command_rx) = mpsc::unbounded_channel();
+        let connection_id = registry.test_register(command_tx).await;
+        arm(&registry, connection_id).await;
+
+        registry.claim("main", 4, |_| true).await.unwrap();
+        let owner = registry.claim("lounge", 5, |_| true).await.unwrap_err();
+        assert_eq!(owner.room, "main");
+        assert!(registry
+            .start(5)
+            .await
+            .unwrap_err()
+            .contains("in use by room main"));
+        assert_eq!(
+            registry.snapshot().await.owner_room.as_deref(),
+            Some("main")
+        );
+
+        registry.start(4).await.unwrap();
+        registry.stop(4).await;
+        assert_eq!(registry.snapshot().await.owner_room, None);
+        registry.claim("lounge", 5, |_| true).await.unwrap();
+
+        // A claim whose Jam ended without releasing it does not wedge others.
+        registry.claim("main", 6, |_| false).await.unwrap();
+        assert_eq!(
+            registry.snapshot().await.owner_room.as_deref(),
+            Some("main")
+        );
+    }
+
     #[tokio::test]
     async fn spotify_connect_repair_is_capability_gated() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
 
         assert!(!registry.snapshot().await.spotify_connect_repair_supported);
@@ -1103,7 +2009,7 @@ mod tests {
     async fn spotify_connect_repair_is_single_flight_and_connection_fenced() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm_with_spotify_connect_repair(&registry, connection_id).await;
         assert!(registry.snapshot().await.spotify_connect_repair_supported);
 
@@ -1148,14 +2054,20 @@ mod tests {
         let result = first.await.unwrap().unwrap();
         assert_eq!(result.outcome, "activated");
         assert!(result.was_running_before);
-        assert!(registry.inner.lock().await.pending_repair.is_none());
+        assert!(registry
+            .inner
+            .lock()
+            .await
+            .active()
+            .pending_repair
+            .is_none());
     }
 
     #[tokio::test]
     async fn replacing_source_connection_cancels_and_clears_pending_repair() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm_with_spotify_connect_repair(&registry, connection_id).await;
 
         let repair_registry = registry.clone();
@@ -1169,11 +2081,12 @@ mod tests {
         });
         command_rx.recv().await.expect("repair command");
         let (replacement_tx, _replacement_rx) = mpsc::unbounded_channel();
-        registry.register(replacement_tx).await;
+        registry.test_register(replacement_tx).await;
 
         let error = repair.await.unwrap().unwrap_err();
         assert!(error.contains("disconnected during Spotify Connect repair"));
-        let inner = registry.inner.lock().await;
+        let sources = registry.inner.lock().await;
+        let inner = sources.active();
         assert!(inner.pending_repair.is_none());
         assert!(!inner.spotify_connect_repair_supported);
     }
@@ -1182,7 +2095,7 @@ mod tests {
     async fn repair_deadline_cancels_exact_worker_and_blocks_start_until_reply() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm_with_spotify_connect_repair(&registry, connection_id).await;
 
         let repair_registry = registry.clone();
@@ -1227,7 +2140,13 @@ mod tests {
             .to_string(),
         )
         .await;
-        assert!(registry.inner.lock().await.pending_repair.is_none());
+        assert!(registry
+            .inner
+            .lock()
+            .await
+            .active()
+            .pending_repair
+            .is_none());
         registry
             .start(9)
             .await
@@ -1238,7 +2157,7 @@ mod tests {
     async fn dropped_repair_future_cancels_exact_worker_without_releasing_start_fence() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm_with_spotify_connect_repair(&registry, connection_id).await;
 
         let repair_registry = registry.clone();
@@ -1292,7 +2211,7 @@ mod tests {
     async fn repair_never_sends_after_deadline_while_registry_lock_is_contended() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm_with_spotify_connect_repair(&registry, connection_id).await;
 
         let held = registry.inner.lock().await;
@@ -1307,14 +2226,20 @@ mod tests {
 
         assert!(error.contains("deadline expired"));
         assert!(command_rx.try_recv().is_err());
-        assert!(registry.inner.lock().await.pending_repair.is_none());
+        assert!(registry
+            .inner
+            .lock()
+            .await
+            .active()
+            .pending_repair
+            .is_none());
     }
 
     #[tokio::test]
     async fn new_connection_fails_closed_while_availability_is_negotiating() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        registry.register(command_tx).await;
+        registry.test_register(command_tx).await;
 
         let snapshot = registry.snapshot().await;
         assert!(snapshot.connected);
@@ -1332,7 +2257,7 @@ mod tests {
     async fn disabled_source_rejects_start_with_a_clear_diagnostic() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         handle_text(
             &registry,
             connection_id,
@@ -1359,7 +2284,7 @@ mod tests {
     async fn availability_true_arms_an_idle_source() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
 
         let snapshot = registry.snapshot().await;
@@ -1380,12 +2305,13 @@ mod tests {
     async fn disabling_clears_capture_state_and_emits_the_prior_generation() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         registry.start(4).await.expect("enabled source starts");
         command_rx.recv().await.expect("start command");
         {
-            let mut inner = registry.inner.lock().await;
+            let mut sources = registry.inner.lock().await;
+            let inner = sources.active_mut();
             inner.ready_generation = Some(4);
             inner.format_generation = Some(4);
             inner.sample_rate = Some(48_000);
@@ -1435,13 +2361,13 @@ mod tests {
     async fn superseded_connection_cannot_arm_or_replay_capture() {
         let registry = JamSourceRegistry::new(true);
         let (old_tx, mut old_rx) = mpsc::unbounded_channel();
-        let old_connection_id = registry.register(old_tx).await;
+        let old_connection_id = registry.test_register(old_tx).await;
         arm(&registry, old_connection_id).await;
         registry.start(5).await.expect("enabled source starts");
         old_rx.recv().await.expect("initial start command");
 
         let (new_tx, mut new_rx) = mpsc::unbounded_channel();
-        let new_connection_id = registry.register(new_tx).await;
+        let new_connection_id = registry.test_register(new_tx).await;
         assert!(new_rx.try_recv().is_err());
         handle_text(
             &registry,
@@ -1474,9 +2400,9 @@ mod tests {
     #[tokio::test]
     async fn capture_reports_cannot_bypass_availability_negotiation() {
         let registry = JamSourceRegistry::new(true);
-        registry.inner.lock().await.desired_generation = Some(8);
+        registry.inner.lock().await.active_mut().desired_generation = Some(8);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
 
         handle_text(
             &registry,
@@ -1504,7 +2430,7 @@ mod tests {
     async fn stop_returns_to_ready_only_while_the_source_is_enabled() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         registry.start(6).await.expect("enabled source starts");
         command_rx.recv().await.expect("start command");
@@ -1528,10 +2454,11 @@ mod tests {
     async fn stale_generation_audio_is_ignored() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, _command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         {
-            let mut inner = registry.inner.lock().await;
+            let mut sources = registry.inner.lock().await;
+            let inner = sources.active_mut();
             inner.desired_generation = Some(8);
             inner.ready_generation = Some(8);
             inner.format_generation = Some(8);
@@ -1551,10 +2478,11 @@ mod tests {
     async fn valid_audio_updates_measured_health() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, _command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         {
-            let mut inner = registry.inner.lock().await;
+            let mut sources = registry.inner.lock().await;
+            let inner = sources.active_mut();
             inner.desired_generation = Some(9);
             inner.ready_generation = Some(9);
             inner.format_generation = Some(9);
@@ -1575,14 +2503,14 @@ mod tests {
     async fn reconnect_does_not_replay_a_disconnected_generation() {
         let registry = JamSourceRegistry::new(true);
         let (first_tx, mut first_rx) = mpsc::unbounded_channel();
-        let first_connection = registry.register(first_tx).await;
+        let first_connection = registry.test_register(first_tx).await;
         arm(&registry, first_connection).await;
         registry.start(44).await.expect("source starts");
         first_rx.recv().await.expect("start command");
         registry.unregister(first_connection).await;
 
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
 
         arm(&registry, connection_id).await;
         assert!(command_rx.try_recv().is_err());
@@ -1595,7 +2523,7 @@ mod tests {
     async fn disconnect_event_is_fenced_to_the_connection_generation() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         registry.start(45).await.expect("source starts");
         command_rx.recv().await.expect("start command");
@@ -1615,14 +2543,14 @@ mod tests {
     async fn replacement_event_is_fenced_to_the_superseded_generation() {
         let registry = JamSourceRegistry::new(true);
         let (first_tx, mut first_rx) = mpsc::unbounded_channel();
-        let first_connection = registry.register(first_tx).await;
+        let first_connection = registry.test_register(first_tx).await;
         arm(&registry, first_connection).await;
         registry.start(46).await.expect("source starts");
         first_rx.recv().await.expect("start command");
         let mut events = registry.subscribe();
         let (replacement_tx, _replacement_rx) = mpsc::unbounded_channel();
 
-        registry.register(replacement_tx).await;
+        registry.test_register(replacement_tx).await;
 
         match events.recv().await.expect("replacement event") {
             SourceEvent::ConnectionReplaced { generation } => {
@@ -1636,10 +2564,11 @@ mod tests {
     async fn stalled_capture_restart_is_generation_scoped_and_debounced() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, mut command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         {
-            let mut inner = registry.inner.lock().await;
+            let mut sources = registry.inner.lock().await;
+            let inner = sources.active_mut();
             inner.desired_generation = Some(45);
             inner.ready_generation = Some(45);
             inner.format_generation = Some(45);
@@ -1701,10 +2630,11 @@ mod tests {
     async fn ready_capture_without_any_packets_eventually_becomes_stalled() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, _command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         {
-            let mut inner = registry.inner.lock().await;
+            let mut sources = registry.inner.lock().await;
+            let inner = sources.active_mut();
             inner.desired_generation = Some(46);
             inner.ready_generation = Some(46);
             inner.ready_at = Some(
@@ -1719,10 +2649,11 @@ mod tests {
     async fn old_frame_health_becomes_stalled() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, _command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         {
-            let mut inner = registry.inner.lock().await;
+            let mut sources = registry.inner.lock().await;
+            let inner = sources.active_mut();
             inner.desired_generation = Some(3);
             inner.ready_generation = Some(3);
             inner.status = "live".to_string();
@@ -1735,10 +2666,11 @@ mod tests {
     async fn recent_frames_without_recent_audible_signal_are_silent() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, _command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         {
-            let mut inner = registry.inner.lock().await;
+            let mut sources = registry.inner.lock().await;
+            let inner = sources.active_mut();
             inner.desired_generation = Some(4);
             inner.ready_generation = Some(4);
             inner.last_frame_at = Some(Instant::now());
@@ -1751,9 +2683,9 @@ mod tests {
     async fn stale_heartbeat_is_offline_and_start_is_rejected() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, _command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
-        registry.inner.lock().await.last_activity_at =
+        registry.inner.lock().await.active_mut().last_activity_at =
             Some(Instant::now() - SOURCE_ACTIVITY_TIMEOUT - std::time::Duration::from_secs(1));
 
         let snapshot = registry.snapshot().await;
@@ -1770,10 +2702,11 @@ mod tests {
     async fn active_generation_rejects_idle_or_stale_heartbeat_liveness() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, _command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
         {
-            let mut inner = registry.inner.lock().await;
+            let mut sources = registry.inner.lock().await;
+            let inner = sources.active_mut();
             inner.desired_generation = Some(12);
             inner.ready_generation = Some(12);
             inner.last_activity_at =
@@ -1803,11 +2736,11 @@ mod tests {
     async fn superseded_connection_cannot_report_ready() {
         let registry = JamSourceRegistry::new(true);
         let (old_tx, _old_rx) = mpsc::unbounded_channel();
-        let old_connection_id = registry.register(old_tx).await;
+        let old_connection_id = registry.test_register(old_tx).await;
         let (new_tx, _new_rx) = mpsc::unbounded_channel();
-        let new_connection_id = registry.register(new_tx).await;
+        let new_connection_id = registry.test_register(new_tx).await;
         arm(&registry, new_connection_id).await;
-        registry.inner.lock().await.desired_generation = Some(17);
+        registry.inner.lock().await.active_mut().desired_generation = Some(17);
 
         handle_text(
             &registry,
@@ -1823,9 +2756,9 @@ mod tests {
     async fn ready_clears_a_prior_capture_error() {
         let registry = JamSourceRegistry::new(true);
         let (command_tx, _command_rx) = mpsc::unbounded_channel();
-        let connection_id = registry.register(command_tx).await;
+        let connection_id = registry.test_register(command_tx).await;
         arm(&registry, connection_id).await;
-        registry.inner.lock().await.desired_generation = Some(21);
+        registry.inner.lock().await.active_mut().desired_generation = Some(21);
 
         handle_text(
             &registry,
@@ -1846,4 +2779,330 @@ mod tests {
         assert_eq!(snapshot.status, "ready");
         assert!(snapshot.error.is_none());
     }
+
+    fn two_sources() -> JamSourceRegistry {
+        JamSourceRegistry::with_sources(&[
+            JamSourceAgentConfig {
+                id: "desk".to_string(),
+                token: "desk-token".to_string(),
+                priority: 0,
+                spotify_device_name: None,
+            },
+            JamSourceAgentConfig {
+                id: "laptop".to_string(),
+                token: "laptop-token".to_string(),
+                priority: 1,
+                spotify_device_name: Some("LAPTOP".to_string()),
+            },
+        ])
+    }
+
+    #[test]
+    fn standby_sources_parse_priority_and_device_name() {
+        let sources = parse_standby_sources(
+            " laptop|secret|2|Living Room PC ; htpc|other ; bad|; laptop|dupe|1 ; x|y|high",
+        );
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].id, "laptop");
+        assert_eq!(sources[0].token, "secret");
+        assert_eq!(sources[0].priority, 2);
+        assert_eq!(
+            sources[0].spotify_device_name.as_deref(),
+            Some("Living Room PC")
+        );
+        assert_eq!(sources[1].id, "htpc");
+        assert_eq!(sources[1].priority, DEFAULT_STANDBY_PRIORITY);
+        assert_eq!(sources[1].spotify_device_name, None);
+    }
+
+    #[tokio::test]
+    async fn idle_registry_follows_the_preferred_healthy_source() {
+        let registry = two_sources();
+        let (laptop_tx, _laptop_rx) = mpsc::unbounded_channel();
+        let laptop = registry.test_register_source("laptop", laptop_tx).await;
+        arm(&registry, laptop).await;
+        assert_eq!(
+            registry.snapshot().await.source_id.as_deref(),
+            Some("laptop")
+        );
+
+        let (desk_tx, _desk_rx) = mpsc::unbounded_channel();
+        let desk = registry.test_register_source("desk", desk_tx).await;
+        arm(&registry, desk).await;
+        let snapshot = registry.snapshot().await;
+        assert_eq!(snapshot.source_id.as_deref(), Some("desk"));
+        assert_eq!(snapshot.sources.len(), 2);
+        assert!(snapshot
+            .sources
+            .iter()
+            .all(|source| source.health_score >= HEALTHY_SOURCE_SCORE));
+
+        // A claimed source never moves underneath its generation.
+        registry.claim("main", 3, |_| true).await.unwrap();
+        registry.unregister(desk).await;
+        assert_eq!(registry.snapshot().await.source_id.as_deref(), Some("desk"));
+    }
+
+    #[tokio::test]
+    async fn failing_generation_hands_off_to_a_healthy_standby() {
+        let registry = two_sources();
+        let (desk_tx, mut desk_rx) = mpsc::unbounded_channel();
+        let desk = registry.test_register_source("desk", desk_tx).await;
+        arm(&registry, desk).await;
+        let (laptop_tx, mut laptop_rx) = mpsc::unbounded_channel();
+        let laptop = registry.test_register_source("laptop", laptop_tx).await;
+        arm(&registry, laptop).await;
+        assert_eq!(registry.snapshot().await.source_id.as_deref(), Some("desk"));
+        registry.claim("main", 9, |_| true).await.unwrap();
+        registry.start(9).await.unwrap();
+        desk_rx.recv().await.expect("start command");
+        let mut events = registry.subscribe();
+
+        // Standby lifecycle stays off the bus the Jam listens to.
+        handle_text(
+            &registry,
+            laptop,
+            r#"{"type":"availability","enabled":true}"#,
+        )
+        .await;
+        assert!(matches!(
+            events.try_recv(),
+            Err(broadcast::error::TryRecvError::Empty)
+        ));
+
+        registry.unregister(desk).await;
+        assert!(matches!(
+            events.recv().await,
+            Ok(SourceEvent::Disconnected {
+                generation: Some(9)
+            })
+        ));
+        assert_eq!(
+            registry.failover_candidate().await.as_deref(),
+            Some("laptop")
+        );
+
+        let handoff = {
+            let registry = registry.clone();
+            tokio::spawn(
+                async move { registry.hand_off(9, "laptop", Duration::from_secs(5)).await },
+            )
+        };
+        let Some(Message::Text(start)) = laptop_rx.recv().await else {
+            panic!("expected a start command");
+        };
+        assert!(start.contains(r#""generation":9"#));
+        handle_text(
+            &registry,
+            laptop,
+            r#"{"type":"ready","generation":9,"pid":77}"#,
+        )
+        .await;
+        handoff.await.unwrap().expect("handoff completes");
+
+        let snapshot = registry.snapshot().await;
+        assert_eq!(snapshot.source_id.as_deref(), Some("laptop"));
+        assert_eq!(snapshot.generation, Some(9));
+        assert!(snapshot.ready);
+        assert_eq!(snapshot.owner_room.as_deref(), Some("main"));
+        assert_eq!(registry.failover_candidate().await, None);
+    }
+
+    async fn register_v4(
+        registry: &JamSourceRegistry,
+        resume_token: Option<&str>,
+    ) -> (u64, mpsc::UnboundedReceiver<Message>, serde_json::Value) {
+        let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+        let connection_id = registry
+            .register("primary", command_tx, 4, resume_token)
+            .await
+            .unwrap();
+        let Some(Message::Text(session)) = command_rx.recv().await else {
+            panic!("expected a session message");
+        };
+        let session = serde_json::from_str(&session).unwrap();
+        (connection_id, command_rx, session)
+    }
+
+    fn v4_frame(generation: u64, sequence: u64, codec: u8, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = generation.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&sequence.to_le_bytes());
+        bytes.push(codec);
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    async fn arm_generation(registry: &JamSourceRegistry, connection_id: u64, generation: u64) {
+        arm(registry, connection_id).await;
+        registry.start(generation).await.unwrap();
+        for message in [
+            format!(
+                r#"{{"type":"format","generation":{generation},"sample_rate":48000,"channels":2}}"#
+            ),
+            format!(r#"{{"type":"ready","generation":{generation},"pid":5}}"#),
+        ] {
+            handle_text(registry, connection_id, &message).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn v4_frames_decode_s16_and_count_sequence_gaps() {
+        let registry = JamSourceRegistry::new(true);
+        let (connection_id, _command_rx, session) = register_v4(&registry, None).await;
+        assert_eq!(session["protocol"], 4);
+        assert_eq!(
+            session["codecs"],
+            serde_json::json!(["pcm_f32", "pcm_s16", "opus"])
+        );
+        assert_eq!(session["resume_token"].as_str().unwrap().len(), 64);
+        arm_generation(&registry, connection_id, 14).await;
+        let mut events = registry.subscribe();
+
+        let stereo = [16_384_i16, -8_192]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<_>>();
+        for sequence in [0, 3, 2] {
+            handle_audio(
+                &registry,
+                connection_id,
+                &v4_frame(14, sequence, CODEC_PCM_S16, &stereo),
+            )
+            .await;
+        }
+        handle_audio(&registry, connection_id, &v4_frame(14, 4, CODEC_OPUS, &[])).await;
+
+        let mut relayed = 0;
+        while let Ok(event) = events.try_recv() {
+            if let SourceEvent::Audio { samples, .. } = event {
+                assert_eq!(samples, vec![0.5, -0.25]);
+                relayed += 1;
+            }
+        }
+        assert_eq!(
+            relayed, 2,
+            "the late frame and the empty Opus frame are dropped"
+        );
+        let snapshot = registry.snapshot().await;
+        assert_eq!(snapshot.protocol, Some(4));
+        assert_eq!(snapshot.frames_lost, 2);
+        assert_eq!(snapshot.peak, 0.5);
+    }
+
+    #[tokio::test]
+    async fn v4_opus_frames_round_trip_through_the_decoder() {
+        use audiopus::coder::Encoder;
+        use audiopus::Application;
+
+        let registry = JamSourceRegistry::new(true);
+        let (connection_id, _command_rx, _session) = register_v4(&registry, None).await;
+        arm_generation(&registry, connection_id, 15).await;
+        let mut events = registry.subscribe();
+
+        // 20 ms of a 440 Hz tone at half scale, interleaved stereo.
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let mut relayed = Vec::new();
+        for sequence in 0..5_u64 {
+            let pcm = (0..960)
+                .flat_map(|index| {
+                    let at = (sequence as usize * 960 + index) as f32 / 48_000.0;
+                    let sample = 0.5 * (2.0 * std::f32::consts::PI * 440.0 * at).sin();
+                    [sample, sample]
+                })
+                .collect::<Vec<_>>();
+            let mut packet = vec![0_u8; 4_000];
+            let length = encoder.encode_float(&pcm, &mut packet).unwrap();
+            handle_audio(
+                &registry,
+                connection_id,
+                &v4_frame(15, sequence, CODEC_OPUS, &packet[..length]),
+            )
+            .await;
+            while let Ok(event) = events.try_recv() {
+                if let SourceEvent::Audio {
+                    generation,
+                    sample_rate,
+                    channels,
+                    samples,
+                } = event
+                {
+                    assert_eq!((generation, sample_rate, channels), (15, 48_000, 2));
+                    relayed.push(samples);
+                }
+            }
+        }
+
+        assert_eq!(relayed.len(), 5);
+        assert!(relayed.iter().all(|samples| samples.len() == 1_920));
+        // Past the codec's start-up delay the tone comes back near its level.
+        let snapshot = registry.snapshot().await;
+        assert!(
+            (0.35..=0.65).contains(&snapshot.peak),
+            "peak {}",
+            snapshot.peak
+        );
+        assert_eq!(snapshot.frames_lost, 0);
+    }
+
+    #[tokio::test]
+    async fn v4_source_resumes_its_generation_within_the_grace_period() {
+        let registry = JamSourceRegistry::new(true);
+        let (connection_id, _command_rx, session) = register_v4(&registry, None).await;
+        let token = session["resume_token"].as_str().unwrap().to_string();
+        arm_generation(&registry, connection_id, 12).await;
+        let mut events = registry.subscribe();
+
+        registry.unregister(connection_id).await;
+        assert!(matches!(
+            events.try_recv(),
+            Err(broadcast::error::TryRecvError::Empty)
+        ));
+        let snapshot = registry.snapshot().await;
+        assert_eq!(snapshot.status, "resuming");
+        assert!(snapshot.connected);
+        assert_eq!(snapshot.generation, Some(12));
+
+        let (resumed_id, _resumed_rx, session) = register_v4(&registry, Some(&token)).await;
+        assert_eq!(session["resumed"], true);
+        assert_eq!(session["generation"], 12);
+        assert_ne!(session["resume_token"].as_str(), Some(token.as_str()));
+        assert!(matches!(
+            events.try_recv(),
+            Err(broadcast::error::TryRecvError::Empty)
+        ));
+        let snapshot = registry.snapshot().await;
+        assert!(snapshot.ready);
+        assert_eq!(snapshot.generation, Some(12));
+
+        // Without a resume the generation is reported lost when grace ends.
+        registry.unregister(resumed_id).await;
+        registry.expire_resume(resumed_id).await;
+        assert!(matches!(
+            events.recv().await,
+            Ok(SourceEvent::Disconnected {
+                generation: Some(12)
+            })
+        ));
+        assert_eq!(registry.snapshot().await.generation, None);
+    }
+
+    #[tokio::test]
+    async fn v4_resume_with_the_wrong_token_starts_a_fresh_connection() {
+        let registry = JamSourceRegistry::new(true);
+        let (connection_id, _command_rx, _) = register_v4(&registry, None).await;
+        arm_generation(&registry, connection_id, 30).await;
+        let mut events = registry.subscribe();
+        registry.unregister(connection_id).await;
+
+        let (_, _rx, session) = register_v4(&registry, Some("not-the-token")).await;
+        assert_eq!(session["resumed"], false);
+        assert!(matches!(
+            events.recv().await,
+            Ok(SourceEvent::Disconnected {
+                generation: Some(30)
+            })
+        ));
+        assert!(matches!(events.recv().await, Ok(SourceEvent::Connected)));
+    }
 }
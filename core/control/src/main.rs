@@ -1,41 +1,73 @@
 mod admin;
+mod alerts;
 mod auth;
+mod bug_reports;
 mod chat;
 mod config;
 mod diagnostics;
 mod diagnostics_api;
 mod diagnostics_auth;
+mod diagnostics_groups;
+mod diagnostics_symbols;
 pub mod file_serving;
+mod jam_autoplay;
 mod jam_bot;
+mod jam_content_filter;
+mod jam_echo_playlists;
+mod jam_export;
 mod jam_history;
+mod jam_history_stats;
+mod jam_import;
 mod jam_library;
+mod jam_local_library;
+mod jam_playback_clock;
 mod jam_playlist_cache;
+mod jam_queue_policy;
+mod jam_recording;
 mod jam_session;
+mod jam_skip_vote;
 mod jam_source;
 mod rooms;
 pub mod sfu_proxy;
 mod soundboard;
+mod soundboard_audio;
+mod soundboard_bundle;
+mod soundboard_play;
 mod spotify_public_catalog;
+mod stats_series;
+mod stream_matrix;
 
 use admin::*;
+use alerts::*;
 use auth::*;
+use bug_reports::*;
 use chat::*;
 use config::*;
 use diagnostics_api::*;
 use diagnostics_auth::*;
 use file_serving::*;
+use jam_content_filter::*;
+use jam_echo_playlists::*;
+use jam_export::*;
 use jam_history::*;
+use jam_history_stats::*;
+use jam_import::*;
 use jam_library::*;
+use jam_local_library::*;
+use jam_recording::*;
 use jam_session::*;
 use jam_source::*;
 use rooms::*;
 use sfu_proxy::*;
 use soundboard::*;
+use soundboard_bundle::*;
+use stats_series::*;
+use stream_matrix::*;
 
 use axum::http::{HeaderName, HeaderValue};
 use axum::{
     extract::DefaultBodyLimit,
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use axum_server::tls_rustls::RustlsConfig;
@@ -45,7 +77,7 @@ use std::{
     fs,
     net::{IpAddr, SocketAddr},
     path::PathBuf,
-    sync::{Arc, Mutex, RwLock},
+    sync::{atomic::AtomicU64, Arc, Mutex, RwLock},
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tower::{Layer, ServiceBuilder};
@@ -70,9 +102,17 @@ pub(crate) struct AppState {
     pub(crate) joined_at: Arc<Mutex<HashMap<String, u64>>>, // identity -> join timestamp
     pub(crate) session_log_dir: PathBuf,
     pub(crate) stats_history: Arc<Mutex<Vec<StatsSnapshot>>>,
+    pub(crate) stats_series: Arc<StatsSeriesStore>,
     pub(crate) bug_reports: Arc<Mutex<Vec<BugReport>>>,
     pub(crate) bug_log_dir: PathBuf,
-    // Jam Session (Spotify)
+    pub(crate) bug_workflow: Arc<BugWorkflowStore>,
+    // Jam Session (Spotify). The per-room fields below are the Jam of
+    // `jam_room`; handlers scope them with `AppState::for_jam_room`. On the
+    // unscoped state (`jam_room == ""`) they are a detached placeholder that
+    // is never registered in `jam_rooms` and never acted on.
+    pub(crate) jam_room: Arc<str>,
+    pub(crate) jam_rooms: Arc<Mutex<HashMap<String, JamRoom>>>,
+    pub(crate) jam_generation: Arc<AtomicU64>,
     pub(crate) jam: Arc<Mutex<JamState>>,
     pub(crate) jam_bot: Arc<tokio::sync::Mutex<Option<jam_bot::JamBot>>>,
     pub(crate) jam_source: jam_source::JamSourceRegistry,
@@ -80,14 +120,21 @@ pub(crate) struct AppState {
     pub(crate) jam_queue_lifecycle: Arc<tokio::sync::Mutex<()>>,
     pub(crate) jam_state_refresh: Arc<tokio::sync::Mutex<()>>,
     pub(crate) spotify_client_id: String,
+    pub(crate) spotify_token: Arc<Mutex<Option<SpotifyToken>>>,
     pub(crate) spotify_pending: Arc<Mutex<Option<SpotifyPending>>>,
     pub(crate) spotify_token_file: PathBuf,
     pub(crate) spotify_token_storage_enabled: bool,
     pub(crate) jam_actor_secret: Arc<Option<Vec<u8>>>,
     pub(crate) jam_favorites: Arc<jam_library::FavoriteStore>,
+    pub(crate) jam_echo_playlists: Arc<jam_echo_playlists::EchoPlaylistStore>,
+    pub(crate) jam_content_filters: Arc<jam_content_filter::ContentFilterStore>,
+    pub(crate) jam_imports: Arc<jam_import::JamImportStore>,
     pub(crate) jam_playlist_cache: Arc<jam_playlist_cache::PlaylistItemsCache>,
     pub(crate) jam_playlist_cache_refresh: Arc<tokio::sync::Mutex<()>>,
     pub(crate) jam_history: Arc<jam_history::JamHistoryStore>,
+    pub(crate) jam_recordings: Arc<jam_recording::JamRecordingStore>,
+    pub(crate) jam_local_library: Arc<jam_local_library::LocalLibrary>,
+    pub(crate) jam_local_player: Arc<tokio::sync::Mutex<Option<jam_local_library::LocalPlayer>>>,
     pub(crate) spotify_request_limit: Arc<tokio::sync::Semaphore>,
     pub(crate) spotify_refresh_lock: Arc<tokio::sync::Mutex<()>>,
     pub(crate) spotify_rate_limit_until: Arc<Mutex<Option<Instant>>>,
@@ -96,6 +143,7 @@ pub(crate) struct AppState {
     pub(crate) login_attempts: Arc<Mutex<HashMap<IpAddr, (u32, Instant)>>>,
     pub(crate) owner_login_attempts: Arc<Mutex<OwnerLoginLimiter>>,
     pub(crate) diagnostics: Option<Arc<DiagnosticsRuntime>>,
+    pub(crate) alerts: Option<Arc<Mutex<AlertEngine>>>,
 }
 
 #[derive(Clone, Serialize)]
@@ -119,12 +167,16 @@ pub(crate) struct ParticipantBinding {
     pub(crate) auth_id: String,
 }
 
+/// Evict stale participants across every room's Jam. A live audio socket in
+/// any room fences its participant; otherwise the exact binding is removed
+/// from each room's listeners. Returns the evicted entries and the generations
+/// of active Jams left without listeners.
 fn remove_stale_participants_exact(
     participants: &mut HashMap<String, ParticipantEntry>,
     bindings: &HashMap<String, ParticipantBinding>,
-    jam: &mut JamState,
+    jams: &mut [&mut JamState],
     now: u64,
-) -> (Vec<ParticipantEntry>, Option<u64>) {
+) -> (Vec<ParticipantEntry>, Vec<u64>) {
     let stale_identities: Vec<String> = participants
         .iter()
         .filter(|(_, participant)| now.saturating_sub(participant.last_seen) >= 20)
@@ -139,16 +191,18 @@ fn remove_stale_participants_exact(
         else {
             continue;
         };
-        let has_current_audio = jam.active
-            && jam.listeners.get(&identity) == Some(&binding_auth_id)
-            && jam
-                .audio_connections
-                .get(&identity)
-                .map(|connection| {
-                    connection.participant_auth_id == binding_auth_id
-                        && connection.generation == jam.generation
-                })
-                .unwrap_or(false);
+        let has_current_audio = jams.iter().any(|jam| {
+            jam.active
+                && jam.listeners.get(&identity) == Some(&binding_auth_id)
+                && jam
+                    .audio_connections
+                    .get(&identity)
+                    .map(|connection| {
+                        connection.participant_auth_id == binding_auth_id
+                            && connection.generation == jam.generation
+                    })
+                    .unwrap_or(false)
+        });
         if has_current_audio {
             continue;
         }
@@ -156,22 +210,28 @@ fn remove_stale_participants_exact(
         if let Some(entry) = participants.remove(&identity) {
             removed.push(entry);
         }
-        if jam.listeners.get(&identity) == Some(&binding_auth_id) {
-            jam.listeners.remove(&identity);
-            info!("Jam: removed stale listener {}", identity);
-        }
-        let remove_audio = jam
-            .audio_connections
-            .get(&identity)
-            .map(|connection| connection.participant_auth_id == binding_auth_id)
-            .unwrap_or(false);
-        if remove_audio {
-            jam.audio_connections.remove(&identity);
+        for jam in jams.iter_mut() {
+            if jam.listeners.get(&identity) == Some(&binding_auth_id) {
+                jam.listeners.remove(&identity);
+                info!("Jam: removed stale listener {}", identity);
+            }
+            let remove_audio = jam
+                .audio_connections
+                .get(&identity)
+                .map(|connection| connection.participant_auth_id == binding_auth_id)
+                .unwrap_or(false);
+            if remove_audio {
+                jam.audio_connections.remove(&identity);
+            }
         }
     }
 
-    let auto_end_generation = (jam.active && jam.listeners.is_empty()).then_some(jam.generation);
-    (removed, auto_end_generation)
+    let auto_end_generations = jams
+        .iter()
+        .filter(|jam| jam.active && jam.listeners.is_empty())
+        .map(|jam| jam.generation)
+        .collect();
+    (removed, auto_end_generations)
 }
 
 #[tokio::main]
@@ -201,9 +261,20 @@ async fn main() {
     let mut soundboard_state = SoundboardState {
         dir: config.soundboard_dir.clone(),
         max_bytes: config.soundboard_max_bytes,
+        max_duration_ms: config.soundboard_max_duration_ms,
         max_sounds_per_room: config.soundboard_max_sounds_per_room,
         rooms: HashMap::new(),
         index: HashMap::new(),
+        folders: HashMap::new(),
+        favorites: HashMap::new(),
+        library: HashMap::new(),
+        plays: soundboard_play::SoundboardPlays::new(soundboard_play::SoundboardPlayLimits {
+            user_cooldown_ms: config.soundboard_user_cooldown_ms,
+            room_cooldown_ms: config.soundboard_room_cooldown_ms,
+            user_max_concurrent: config.soundboard_user_max_concurrent,
+            room_max_concurrent: config.soundboard_room_max_concurrent,
+        }),
+        play_counts_dirty: false,
     };
     load_soundboard(&mut soundboard_state);
     let chat_state = ChatState {
@@ -282,6 +353,42 @@ async fn main() {
         .unwrap_or(std::path::Path::new("."))
         .join("bugs");
     fs::create_dir_all(&bug_log_dir).ok();
+    let bug_workflow = match BugWorkflowStore::open(&bug_log_dir) {
+        Ok(store) => store,
+        Err(error) => {
+            warn!(
+                "bug report workflow disabled: {:?} could not be loaded: {}",
+                bug_log_dir, error
+            );
+            BugWorkflowStore::disabled(&bug_log_dir)
+        }
+    };
+
+    // Alerting stays off unless a rules file is configured; a broken file is
+    // logged and ignored rather than blocking startup.
+    let alerts = std::env::var("CORE_ALERT_RULES_FILE")
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+        .and_then(
+            |path| match AlertConfig::load(std::path::Path::new(&path)) {
+                Ok(config) => {
+                    info!(
+                        "alerting enabled: {} rule(s), {} webhook(s)",
+                        config.rules.len(),
+                        config.webhooks.len()
+                    );
+                    Some(Arc::new(Mutex::new(AlertEngine::new(config))))
+                }
+                Err(error) => {
+                    warn!("alerting disabled: {}", error);
+                    None
+                }
+            },
+        );
+    let alert_log_path = session_log_dir
+        .parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("alerts.jsonl");
 
     let viewer_dir = resolve_viewer_dir();
     info!("viewer dir: {:?}", viewer_dir);
@@ -383,8 +490,11 @@ async fn main() {
         .join("spotify-token.json");
     let jam_library_dir = session_log_dir.join("jam-library");
     let jam_favorites_file = jam_library_dir.join("favorites-v1.json");
+    let jam_echo_playlists_file = jam_library_dir.join("echo-playlists-v1.json");
+    let jam_content_filters_file = jam_library_dir.join("content-filters-v1.json");
     let jam_playlist_cache_file = jam_library_dir.join("playlist-items-cache-v2.json");
     let jam_history_dir = session_log_dir.join("jam-history");
+    let jam_recordings_dir = session_log_dir.join("jam-recordings");
     let static_roots = [
         viewer_dir.as_path(),
         admin_dir.as_path(),
@@ -470,10 +580,6 @@ async fn main() {
         None
     };
 
-    let mut initial_jam = JamState::default();
-    if let Some(token) = persisted_spotify_token {
-        initial_jam.spotify_token = Some(token);
-    }
     let jam_favorites = if !jam_storage_isolated {
         jam_library::FavoriteStore::disabled(jam_favorites_file.clone())
     } else {
@@ -488,10 +594,47 @@ async fn main() {
             }
         }
     };
+    let jam_echo_playlists = if !jam_storage_isolated {
+        jam_echo_playlists::EchoPlaylistStore::disabled(jam_echo_playlists_file.clone())
+    } else {
+        match jam_echo_playlists::EchoPlaylistStore::open(jam_echo_playlists_file.clone()) {
+            Ok(store) => store,
+            Err(error) => {
+                warn!(
+                "Echo playlist primary store could not be loaded; preserving it and disabling writes: {}",
+                error
+            );
+                jam_echo_playlists::EchoPlaylistStore::recover_read_only(jam_echo_playlists_file)
+            }
+        }
+    };
+    let jam_content_filters = if !jam_storage_isolated {
+        jam_content_filter::ContentFilterStore::disabled(jam_content_filters_file.clone())
+    } else {
+        match jam_content_filter::ContentFilterStore::open(jam_content_filters_file.clone()) {
+            Ok(store) => store,
+            Err(error) => {
+                warn!(
+                "Jam content filter primary store could not be loaded; preserving it and disabling writes: {}",
+                error
+            );
+                jam_content_filter::ContentFilterStore::recover_read_only(jam_content_filters_file)
+            }
+        }
+    };
+    let jam_history_retention_days = std::env::var("CORE_JAM_HISTORY_RETENTION_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(jam_history::HISTORY_RETENTION_DAYS)
+        .clamp(1, jam_history::MAX_HISTORY_RETENTION_DAYS);
     let jam_history = if !jam_storage_isolated {
         jam_history::JamHistoryStore::disabled(jam_history_dir.clone())
     } else {
-        match jam_history::JamHistoryStore::open(jam_history_dir.clone(), now_ts_ms()) {
+        match jam_history::JamHistoryStore::open(
+            jam_history_dir.clone(),
+            jam_history_retention_days,
+            now_ts_ms(),
+        ) {
             Ok(store) => store,
             Err(error) => {
                 warn!(
@@ -502,6 +645,78 @@ async fn main() {
             }
         }
     };
+    let jam_recording_retention_days = std::env::var("CORE_JAM_RECORDING_RETENTION_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(jam_recording::RECORDING_RETENTION_DAYS)
+        .clamp(1, jam_recording::MAX_RECORDING_RETENTION_DAYS);
+    let jam_recording_max_mb = std::env::var("CORE_JAM_RECORDING_MAX_MB")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(jam_recording::RECORDING_MAX_TOTAL_MB)
+        .max(1);
+    let jam_recordings = if !private_path_isolated("Jam recordings", &jam_recordings_dir) {
+        jam_recording::JamRecordingStore::disabled(jam_recordings_dir.clone())
+    } else {
+        match jam_recording::JamRecordingStore::open(
+            jam_recordings_dir.clone(),
+            jam_recording_retention_days,
+            jam_recording_max_mb,
+            now_ts_ms(),
+        ) {
+            Ok(store) => store,
+            Err(error) => {
+                warn!(
+                    "Jam recordings disabled because their store could not be opened: {}",
+                    error
+                );
+                jam_recording::JamRecordingStore::disabled(jam_recordings_dir)
+            }
+        }
+    };
+    let stats_series_dir = std::env::var("CORE_STATS_SERIES_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            session_log_dir
+                .parent()
+                .unwrap_or(std::path::Path::new("."))
+                .join("stats-series")
+        });
+    let stats_retention_days = |name: &str, default: u64| {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(default)
+            .clamp(1, stats_series::MAX_RETENTION_DAYS)
+    };
+    let stats_retention = StatsRetention {
+        raw_days: stats_retention_days(
+            "CORE_STATS_RAW_RETENTION_DAYS",
+            stats_series::RAW_RETENTION_DAYS,
+        ),
+        minute_days: stats_retention_days(
+            "CORE_STATS_MINUTE_RETENTION_DAYS",
+            stats_series::MINUTE_RETENTION_DAYS,
+        ),
+        hour_days: stats_retention_days(
+            "CORE_STATS_HOUR_RETENTION_DAYS",
+            stats_series::HOUR_RETENTION_DAYS,
+        ),
+    };
+    let stats_series = if !private_path_isolated("Client stats series", &stats_series_dir) {
+        StatsSeriesStore::disabled(stats_series_dir)
+    } else {
+        match StatsSeriesStore::open(stats_series_dir.clone(), stats_retention, now_ts_ms()) {
+            Ok(store) => store,
+            Err(error) => {
+                warn!(
+                    "client stats series disabled because its store could not be opened: {}",
+                    error
+                );
+                StatsSeriesStore::disabled(stats_series_dir)
+            }
+        }
+    };
     let jam_playlist_cache = if !jam_storage_isolated {
         jam_playlist_cache::PlaylistItemsCache::disabled(jam_playlist_cache_file.clone())
     } else {
@@ -527,7 +742,6 @@ async fn main() {
             .as_secs()
     );
 
-    let jam_source_configured = config.jam_source_id.is_some() && config.jam_source_token.is_some();
     let http_client = reqwest::Client::builder()
         .connect_timeout(Duration::from_secs(5))
         .timeout(Duration::from_secs(15))
@@ -548,14 +762,22 @@ async fn main() {
         joined_at: Arc::new(Mutex::new(HashMap::new())),
         session_log_dir: session_log_dir.clone(),
         stats_history: Arc::new(Mutex::new(Vec::new())),
+        stats_series: Arc::new(stats_series),
         bug_reports: Arc::new(Mutex::new(Vec::new())),
         bug_log_dir,
+        bug_workflow: Arc::new(bug_workflow),
         // Jam Session (Spotify)
         spotify_client_id: std::env::var("SPOTIFY_CLIENT_ID").unwrap_or_default(),
+        spotify_token: Arc::new(Mutex::new(persisted_spotify_token)),
         spotify_pending: Arc::new(Mutex::new(None)),
-        jam: Arc::new(Mutex::new(initial_jam)),
+        jam_room: Arc::from(""),
+        jam_rooms: Arc::new(Mutex::new(HashMap::new())),
+        jam_generation: Arc::new(AtomicU64::new(0)),
+        jam: Arc::new(Mutex::new(JamState::default())),
         jam_bot: Arc::new(tokio::sync::Mutex::new(None)),
-        jam_source: jam_source::JamSourceRegistry::new(jam_source_configured),
+        jam_source: jam_source::JamSourceRegistry::with_sources(
+            &jam_source::configured_jam_sources(&config),
+        ),
         jam_lifecycle: Arc::new(tokio::sync::Mutex::new(())),
         jam_queue_lifecycle: Arc::new(tokio::sync::Mutex::new(())),
         jam_state_refresh: Arc::new(tokio::sync::Mutex::new(())),
@@ -563,9 +785,17 @@ async fn main() {
         spotify_token_storage_enabled,
         jam_actor_secret: Arc::new(jam_actor_secret),
         jam_favorites: Arc::new(jam_favorites),
+        jam_echo_playlists: Arc::new(jam_echo_playlists),
+        jam_content_filters: Arc::new(jam_content_filters),
+        jam_imports: Arc::new(jam_import::JamImportStore::default()),
         jam_playlist_cache: Arc::new(jam_playlist_cache),
         jam_playlist_cache_refresh: Arc::new(tokio::sync::Mutex::new(())),
         jam_history: Arc::new(jam_history),
+        jam_recordings: Arc::new(jam_recordings),
+        jam_local_library: Arc::new(jam_local_library::LocalLibrary::new(
+            config.jam_local_library_dir.clone(),
+        )),
+        jam_local_player: Arc::new(tokio::sync::Mutex::new(None)),
         spotify_request_limit: Arc::new(tokio::sync::Semaphore::new(4)),
         spotify_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
         spotify_rate_limit_until: Arc::new(Mutex::new(None)),
@@ -574,8 +804,20 @@ async fn main() {
         login_attempts: Arc::new(Mutex::new(HashMap::new())),
         owner_login_attempts: Arc::new(Mutex::new(OwnerLoginLimiter::default())),
         diagnostics,
+        alerts,
     };
 
+    if state.jam_local_library.enabled() {
+        let library = Arc::clone(&state.jam_local_library);
+        tokio::spawn(async move {
+            match tokio::task::spawn_blocking(move || library.rescan()).await {
+                Ok(Ok(count)) => info!("Jam local library indexed {} track(s)", count),
+                Ok(Err(error)) => warn!("Jam local library scan failed: {}", error),
+                Err(error) => warn!("Jam local library scan task failed: {}", error),
+            }
+        });
+    }
+
     {
         let history = Arc::clone(&state.jam_history);
         tokio::spawn(async move {
@@ -592,6 +834,23 @@ async fn main() {
         });
     }
 
+    {
+        let recordings = Arc::clone(&state.jam_recordings);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(6 * 60 * 60)).await;
+                let recordings = Arc::clone(&recordings);
+                let result =
+                    tokio::task::spawn_blocking(move || recordings.prune(now_ts_ms())).await;
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(error)) => warn!("Jam recording retention failed: {}", error),
+                    Err(error) => warn!("Jam recording retention task failed: {}", error),
+                }
+            }
+        });
+    }
+
     // Enforce age retention even when the service is idle or receives only
     // duplicate uploads. Store locking serializes this with ingest and owner
     // reads, and diagnostics remain disabled when no private owner secret is
@@ -612,11 +871,53 @@ async fn main() {
         });
     }
 
+    // Close finished minute and hour buckets even when no client is
+    // reporting, and apply the per-resolution retention.
+    {
+        let series = Arc::clone(&state.stats_series);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                let series = Arc::clone(&series);
+                let result =
+                    tokio::task::spawn_blocking(move || series.maintain(now_ts_ms())).await;
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(error)) => warn!("client stats series maintenance failed: {}", error),
+                    Err(error) => warn!("client stats series task failed: {}", error),
+                }
+            }
+        });
+    }
+
+    // Soundboard plays only mark the board dirty; write play counts in
+    // batches instead of on every play.
+    {
+        let soundboard = Arc::clone(&state.soundboard);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                let soundboard = Arc::clone(&soundboard);
+                let result =
+                    tokio::task::spawn_blocking(move || flush_soundboard_play_counts(&soundboard))
+                        .await;
+                if let Err(error) = result {
+                    warn!("soundboard play count flush task failed: {}", error);
+                }
+            }
+        });
+    }
+
+    if let Some(engine) = state.alerts.clone() {
+        tokio::spawn(run_alerts(state.clone(), engine, alert_log_path));
+    }
+
     // Local source consent is authoritative. Turning Jam sharing off on the
-    // source PC pauses the bound Spotify device, ends only the current
-    // generation, and then releases the native per-app output route. A source
-    // disconnect fails closed immediately: the desktop deliberately delays
-    // local route release long enough for this pause-first teardown to run.
+    // source PC pauses the bound Spotify device, then either hands the
+    // generation to a healthy standby source or ends it, and releases the
+    // native per-app output route. A source disconnect fails closed
+    // immediately: the desktop deliberately delays local route release long
+    // enough for this pause-first teardown to run.
     {
         let source_event_state = state.clone();
         let mut source_events = state.jam_source.subscribe();
@@ -634,14 +935,14 @@ async fn main() {
                             let reason = error.unwrap_or_else(|| {
                                 "Jam sharing was turned off on the source PC".to_string()
                             });
-                            end_jam_for_source_unavailable(&source_event_state, generation, reason)
+                            handle_jam_source_loss(&source_event_state, generation, reason)
                                 .await;
                         }
                         Ok(SourceEvent::Error {
                             generation,
                             message,
                         }) => {
-                            end_jam_for_source_unavailable(
+                            handle_jam_source_loss(
                                 &source_event_state,
                                 generation,
                                 format!("Jam source failed: {message}"),
@@ -651,7 +952,7 @@ async fn main() {
                         Ok(SourceEvent::Disconnected {
                             generation: Some(generation),
                         }) => {
-                            end_jam_for_source_unavailable(
+                            handle_jam_source_loss(
                                 &source_event_state,
                                 generation,
                                 "Jam source disconnected from the source PC".to_string(),
@@ -661,7 +962,7 @@ async fn main() {
                         Ok(SourceEvent::ConnectionReplaced {
                             generation: Some(generation),
                         }) => {
-                            end_jam_for_source_unavailable(
+                            handle_jam_source_loss(
                                 &source_event_state,
                                 generation,
                                 "Jam source connection was replaced".to_string(),
@@ -689,7 +990,6 @@ async fn main() {
         let joined_at = state.joined_at.clone();
         let client_stats = state.client_stats.clone();
         let session_log_dir = state.session_log_dir.clone();
-        let jam_for_cleanup = state.jam.clone();
         let state_for_cleanup = state.clone();
         tokio::spawn(async move {
             loop {
@@ -701,13 +1001,21 @@ async fn main() {
                 // Final presence, binding, listener, and audio checks are one
                 // critical section. A live audio socket fences a throttled
                 // browser from stale-heartbeat eviction.
-                let (removed_entries, auto_end_generation) = {
+                let rooms = state_for_cleanup.jam_room_states();
+                let (removed_entries, auto_end_generations) = {
                     let mut participants = participants.lock().unwrap_or_else(|e| e.into_inner());
                     let bindings = participant_bindings
                         .lock()
                         .unwrap_or_else(|e| e.into_inner());
-                    let mut jam = jam_for_cleanup.lock().unwrap_or_else(|e| e.into_inner());
-                    remove_stale_participants_exact(&mut participants, &bindings, &mut jam, now)
+                    // Room Jams are locked in room order; nothing else holds
+                    // two of them at once.
+                    let mut guards: Vec<_> = rooms
+                        .iter()
+                        .map(|room| room.jam.lock().unwrap_or_else(|e| e.into_inner()))
+                        .collect();
+                    let mut jams: Vec<&mut JamState> =
+                        guards.iter_mut().map(|guard| &mut **guard).collect();
+                    remove_stale_participants_exact(&mut participants, &bindings, &mut jams, now)
                 };
                 if !removed_entries.is_empty() {
                     info!("cleaned up {} stale participant(s)", removed_entries.len());
@@ -733,9 +1041,15 @@ async fn main() {
                     };
                     append_session_event(&session_log_dir, &event);
                 }
-                if let Some(generation) = auto_end_generation {
+                for generation in auto_end_generations {
                     schedule_jam_auto_end(state_for_cleanup.clone(), generation, "stale cleanup");
                 }
+                // Room views pin their rooms; release them before the sweep.
+                drop(rooms);
+                let evicted = state_for_cleanup.evict_idle_jam_rooms(now_ts_ms());
+                if evicted > 0 {
+                    info!("evicted {} idle Jam room(s)", evicted);
+                }
             }
         });
     }
@@ -783,6 +1097,24 @@ async fn main() {
 
     let diagnostics_owner_routes = Router::new()
         .route("/", get(diagnostics_list))
+        .route("/groups", get(diagnostics_groups_list))
+        .route("/groups/:group_id", get(diagnostics_group_get))
+        .route("/groups/:group_id/resolve", post(diagnostics_group_resolve))
+        .route(
+            "/bug-reports/:report_id/incidents",
+            get(diagnostics_bug_report_incidents),
+        )
+        .route("/symbols", get(diagnostics_symbols_list))
+        .route(
+            "/symbols/:git_sha/:version",
+            delete(diagnostics_symbols_delete),
+        )
+        .route(
+            "/symbols/:git_sha/:version/:module",
+            put(diagnostics_symbols_upload).layer(DefaultBodyLimit::max(
+                diagnostics_symbols::MAX_SYMBOL_FILE_BYTES,
+            )),
+        )
         .route(
             "/:incident_id",
             get(diagnostics_get).delete(diagnostics_delete),
@@ -806,6 +1138,8 @@ async fn main() {
         .route("/admin/api/dashboard", get(admin_dashboard))
         .route("/admin/api/sessions", get(admin_sessions))
         .route("/admin/api/stats", post(admin_report_stats))
+        .route("/admin/api/stats/series", get(admin_stats_series))
+        .route("/admin/api/stats/matrix", get(admin_stream_matrix))
         .route("/api/client-stats-report", post(client_stats_report))
         .route(
             "/api/diagnostics/v1/envelopes",
@@ -818,9 +1152,40 @@ async fn main() {
         )
         .route("/admin/api/metrics", get(admin_metrics))
         .route("/admin/api/bugs", get(admin_bug_reports))
+        .route("/admin/api/bugs/:id/status", post(admin_bug_report_status))
+        .route(
+            "/admin/api/bugs/:id/comments",
+            post(admin_bug_report_comment),
+        )
+        .route(
+            "/admin/api/bugs/:id/attachments/:attachment_id",
+            get(admin_bug_report_attachment),
+        )
         .route("/admin/api/metrics/dashboard", get(admin_dashboard_metrics))
         .route("/admin/api/deploys", get(admin_deploys))
         .route("/admin/api/force-reload", post(admin_force_reload))
+        .route("/admin/api/alerts", get(admin_alerts))
+        .route(
+            "/admin/api/soundboard/mute/:room",
+            post(admin_soundboard_mute),
+        )
+        .route(
+            "/admin/api/jam/content-filters",
+            get(admin_jam_content_filters),
+        )
+        .route(
+            "/admin/api/jam/content-filters/:room",
+            put(admin_jam_content_filter_put),
+        )
+        .route("/admin/api/jam/recordings", get(admin_jam_recordings))
+        .route(
+            "/admin/api/jam/recordings/:recording_id",
+            get(admin_jam_recording_download).delete(admin_jam_recording_delete),
+        )
+        .route(
+            "/admin/api/jam/recording/:room",
+            post(admin_jam_recording_set),
+        )
         .nest("/admin/api/diagnostics", diagnostics_owner_routes)
         .nest_service(
             "/admin/diagnostics",
@@ -865,6 +1230,27 @@ async fn main() {
         .route("/api/soundboard/file/:sound_id", get(soundboard_file))
         .route("/api/soundboard/upload", post(soundboard_upload))
         .route("/api/soundboard/update", post(soundboard_update))
+        .route("/api/soundboard/play", post(soundboard_play))
+        .route("/api/soundboard/export", get(soundboard_export))
+        // The import handler reads its own body, capped by caller role.
+        .route("/api/soundboard/import", post(soundboard_import))
+        .route("/api/soundboard/trim", post(soundboard_trim))
+        .route("/api/soundboard/arrange", post(soundboard_arrange))
+        .route("/api/soundboard/favorite", post(soundboard_favorite))
+        .route("/api/soundboard/copy", post(soundboard_copy))
+        .route("/api/soundboard/library", get(soundboard_library_list))
+        .route(
+            "/api/soundboard/library/publish",
+            post(soundboard_library_publish),
+        )
+        .route(
+            "/api/soundboard/library/import",
+            post(soundboard_library_import),
+        )
+        .route(
+            "/api/soundboard/library/remove",
+            post(soundboard_library_remove),
+        )
         .route("/api/chat/message", post(chat_save_message))
         .route("/api/chat/delete", post(chat_delete_message))
         .route("/api/chat/history/:room", get(chat_get_history))
@@ -877,6 +1263,12 @@ async fn main() {
         .route("/api/chime/:identity/:kind", get(chime_get))
         .route("/api/chime/delete", post(chime_delete))
         .route("/api/bug-report", post(submit_bug_report))
+        .route("/api/bug-report/:id", get(bug_report_status))
+        .route(
+            "/api/bug-report/:id/attachments",
+            post(bug_report_attachment_upload)
+                .layer(DefaultBodyLimit::max(bug_reports::MAX_ATTACHMENT_BYTES)),
+        )
         .route("/api/version", get(api_version))
         .route("/api/update/latest.json", get(api_update_latest))
         .route("/api/open-url", post(open_url))
@@ -890,9 +1282,40 @@ async fn main() {
         .route("/api/jam/state", get(jam_state))
         .route("/api/jam/search", post(jam_search))
         .route("/api/jam/catalog/search", post(jam_catalog_search))
+        .route("/api/jam/import", post(jam_import_preview))
+        .route("/api/jam/local/rescan", post(jam_local_rescan))
+        .route("/api/jam/local/artwork/:id", get(jam_local_artwork))
         .route("/api/jam/playlists/:id/items", get(jam_playlist_items))
         .route("/api/jam/favorites", get(jam_favorites_list))
+        .route(
+            "/api/jam/echo-playlists",
+            get(jam_echo_playlists_list).post(jam_echo_playlist_create),
+        )
+        .route(
+            "/api/jam/echo-playlists/:id",
+            get(jam_echo_playlist_get)
+                .post(jam_echo_playlist_update)
+                .delete(jam_echo_playlist_delete),
+        )
+        .route(
+            "/api/jam/echo-playlists/:id/items",
+            post(jam_echo_playlist_add),
+        )
+        .route(
+            "/api/jam/echo-playlists/:id/items/remove",
+            post(jam_echo_playlist_remove),
+        )
+        .route(
+            "/api/jam/echo-playlists/:id/items/move",
+            post(jam_echo_playlist_move),
+        )
         .route("/api/jam/history", get(jam_history_list))
+        .route("/api/jam/history/stats", get(jam_history_stats))
+        .route("/api/jam/export", get(jam_export))
+        .route(
+            "/api/jam/export/spotify-playlist",
+            post(jam_export_spotify_playlist),
+        )
         .route(
             "/api/jam/favorites/import-spotify",
             post(jam_favorites_import_spotify),
@@ -903,6 +1326,7 @@ async fn main() {
         )
         .route("/api/jam/queue", post(jam_queue_add))
         .route("/api/jam/queue/remove", post(jam_queue_remove))
+        .route("/api/jam/queue/move", post(jam_queue_move))
         .route("/api/jam/queue/playlist", post(jam_queue_playlist))
         .route(
             "/api/jam/queue/playlist/selection",
@@ -910,6 +1334,7 @@ async fn main() {
         )
         .route("/api/jam/playback/stop", post(jam_stop_playback))
         .route("/api/jam/skip", post(jam_skip))
+        .route("/api/jam/autoplay", post(jam_autoplay))
         .route("/api/jam/join", post(jam_join))
         .route("/api/jam/leave", post(jam_leave))
         .route("/api/jam/audio", get(jam_audio_ws))
@@ -1026,10 +1451,33 @@ fn load_config() -> Config {
         .and_then(|v| v.parse::<usize>().ok())
         .unwrap_or(8);
     let soundboard_max_bytes = soundboard_max_mb.max(1) * 1024 * 1024;
+    let soundboard_max_seconds = std::env::var("CORE_SOUNDBOARD_MAX_SECONDS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(15)
+        .clamp(1, 300);
     let soundboard_max_sounds_per_room = std::env::var("CORE_SOUNDBOARD_MAX_SOUNDS_PER_ROOM")
         .ok()
         .and_then(|v| v.parse().ok())
         .unwrap_or(60);
+    let soundboard_user_cooldown_ms = std::env::var("CORE_SOUNDBOARD_USER_COOLDOWN_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3000);
+    let soundboard_room_cooldown_ms = std::env::var("CORE_SOUNDBOARD_ROOM_COOLDOWN_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(500);
+    let soundboard_user_max_concurrent = std::env::var("CORE_SOUNDBOARD_USER_MAX_CONCURRENT")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1);
+    let soundboard_room_max_concurrent = std::env::var("CORE_SOUNDBOARD_ROOM_MAX_CONCURRENT")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(3)
+        .max(1);
 
     let chat_dir = std::env::var("CORE_CHAT_DIR").unwrap_or_else(|_| "../logs/chat".to_string());
     let chat_uploads_dir = std::env::var("CORE_CHAT_UPLOADS_DIR")
@@ -1064,6 +1512,27 @@ fn load_config() -> Config {
     let spotify_device_name = std::env::var("SPOTIFY_DEVICE_NAME")
         .ok()
         .filter(|s| !s.is_empty());
+    let jam_local_library_dir = std::env::var("CORE_JAM_LOCAL_LIBRARY_DIR")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(resolve_path);
+    let jam_queue_fair_share = std::env::var("CORE_JAM_QUEUE_ORDERING")
+        .map(|v| v.trim().eq_ignore_ascii_case("fair_share"))
+        .unwrap_or(false);
+    let jam_queue_max_pending_per_actor = std::env::var("CORE_JAM_QUEUE_MAX_PENDING_PER_ACTOR")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|limit| *limit > 0);
+    let jam_queue_reject_duplicates = std::env::var("CORE_JAM_QUEUE_REJECT_DUPLICATES")
+        .map(|v| matches!(v.trim(), "1" | "true" | "yes"))
+        .unwrap_or(false);
+    let jam_skip_vote_percent = std::env::var("CORE_JAM_SKIP_VOTE_PERCENT")
+        .ok()
+        .and_then(|v| v.trim().parse::<u8>().ok())
+        .filter(|percent| (1..=100).contains(percent));
+    let jam_standby_sources = std::env::var("CORE_JAM_STANDBY_SOURCES")
+        .map(|v| jam_source::parse_standby_sources(&v))
+        .unwrap_or_default();
 
     Config {
         host,
@@ -1078,7 +1547,12 @@ fn load_config() -> Config {
         livekit_token_ttl_secs,
         soundboard_dir: resolve_path(soundboard_dir),
         soundboard_max_bytes,
+        soundboard_max_duration_ms: soundboard_max_seconds * 1000,
         soundboard_max_sounds_per_room,
+        soundboard_user_cooldown_ms,
+        soundboard_room_cooldown_ms,
+        soundboard_user_max_concurrent,
+        soundboard_room_max_concurrent,
         chat_dir: resolve_path(chat_dir),
         chat_uploads_dir: resolve_path(chat_uploads_dir),
         chat_max_upload_bytes,
@@ -1092,6 +1566,12 @@ fn load_config() -> Config {
         jam_source_token,
         spotify_device_id,
         spotify_device_name,
+        jam_local_library_dir,
+        jam_queue_fair_share,
+        jam_queue_max_pending_per_actor,
+        jam_queue_reject_duplicates,
+        jam_skip_vote_percent,
+        jam_standby_sources,
     }
 }
 
@@ -1166,14 +1646,14 @@ mod lifecycle_tests {
         );
 
         let (removed, auto_end) =
-            remove_stale_participants_exact(&mut participants, &bindings, &mut jam, 30);
+            remove_stale_participants_exact(&mut participants, &bindings, &mut [&mut jam], 30);
 
         assert_eq!(removed.len(), 1);
         assert_eq!(removed[0].identity, "alex-2222");
         assert!(participants.contains_key("sam-7475"));
         assert!(jam.listeners.contains_key("sam-7475"));
         assert!(!jam.listeners.contains_key("alex-2222"));
-        assert_eq!(auto_end, None);
+        assert!(auto_end.is_empty());
     }
 
     #[test]
@@ -1190,13 +1670,62 @@ mod lifecycle_tests {
             .insert("sam-7475".to_string(), "binding-old".to_string());
 
         let (removed, auto_end) =
-            remove_stale_participants_exact(&mut participants, &bindings, &mut jam, 30);
+            remove_stale_participants_exact(&mut participants, &bindings, &mut [&mut jam], 30);
 
         assert_eq!(removed.len(), 1);
         assert_eq!(
             jam.listeners.get("sam-7475").map(String::as_str),
             Some("binding-old")
         );
-        assert_eq!(auto_end, None);
+        assert!(auto_end.is_empty());
+    }
+
+    #[test]
+    fn stale_cleanup_spans_every_room_jam() {
+        let mut participants = HashMap::from([
+            ("sam-7475".to_string(), participant("sam-7475", 1)),
+            ("alex-2222".to_string(), participant("alex-2222", 1)),
+        ]);
+        let bindings = HashMap::from([
+            ("sam-7475".to_string(), binding("binding-a")),
+            ("alex-2222".to_string(), binding("binding-b")),
+        ]);
+        let mut main_jam = JamState {
+            active: true,
+            generation: 9,
+            ..JamState::default()
+        };
+        main_jam
+            .listeners
+            .insert("sam-7475".to_string(), "binding-a".to_string());
+        main_jam.audio_connections.insert(
+            "sam-7475".to_string(),
+            jam_session::JamAudioConnection {
+                participant_auth_id: "binding-a".to_string(),
+                generation: 9,
+                connection_id: 1,
+            },
+        );
+        let mut lounge_jam = JamState {
+            active: true,
+            generation: 10,
+            ..JamState::default()
+        };
+        lounge_jam
+            .listeners
+            .insert("alex-2222".to_string(), "binding-b".to_string());
+
+        let (removed, auto_end) = remove_stale_participants_exact(
+            &mut participants,
+            &bindings,
+            &mut [&mut main_jam, &mut lounge_jam],
+            30,
+        );
+
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].identity, "alex-2222");
+        assert!(main_jam.listeners.contains_key("sam-7475"));
+        assert!(lounge_jam.listeners.is_empty());
+        assert_eq!(auto_end, vec![10]);
     }
 }
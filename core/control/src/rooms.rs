@@ -7,6 +7,7 @@ use axum::{
     http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
+use base64::Engine as _;
 use jsonwebtoken::{encode, EncodingKey, Header};
 use serde::{Deserialize, Serialize};
 use std::{
@@ -219,6 +220,7 @@ pub(crate) async fn participant_leave(
     Json(payload): Json<ParticipantLeaveRequest>,
 ) -> Result<StatusCode, StatusCode> {
     let claims = ensure_livekit_participant(&state, &headers, &payload.identity)?;
+    let room_jam = state.existing_jam_room(&claims.video.room);
     let identity = claims.sub;
     let participant_auth_id = claims
         .echo_participant_auth_id
@@ -272,23 +274,25 @@ pub(crate) async fn participant_leave(
         }
         participants.remove(&identity);
 
-        let mut jam = state.jam.lock().unwrap_or_else(|e| e.into_inner());
-        if jam.listeners.get(&identity) == Some(&participant_auth_id) {
-            jam.listeners.remove(&identity);
-            info!(
-                "Jam: removed leaving participant {} from listeners",
-                identity
-            );
-        }
-        let remove_audio = jam
-            .audio_connections
-            .get(&identity)
-            .map(|connection| connection.participant_auth_id == participant_auth_id)
-            .unwrap_or(false);
-        if remove_audio {
-            jam.audio_connections.remove(&identity);
-        }
-        (jam.active && jam.listeners.is_empty()).then_some(jam.generation)
+        room_jam.as_ref().and_then(|room_jam| {
+            let mut jam = room_jam.jam.lock().unwrap_or_else(|e| e.into_inner());
+            if jam.listeners.get(&identity) == Some(&participant_auth_id) {
+                jam.listeners.remove(&identity);
+                info!(
+                    "Jam: removed leaving participant {} from listeners",
+                    identity
+                );
+            }
+            let remove_audio = jam
+                .audio_connections
+                .get(&identity)
+                .map(|connection| connection.participant_auth_id == participant_auth_id)
+                .unwrap_or(false);
+            if remove_audio {
+                jam.audio_connections.remove(&identity);
+            }
+            (jam.active && jam.listeners.is_empty()).then_some(jam.generation)
+        })
     };
     if let Some(generation) = auto_end_generation {
         schedule_jam_auto_end(state.clone(), generation, "participant left");
@@ -381,7 +385,8 @@ pub(crate) fn livekit_service_token(
 
 // ── LiveKit twirp RPC helpers ────────────────────────────────────────
 //
-// Used by admin_kick_participant (single kick) and admin_force_reload (nuclear).
+// Used by admin_kick_participant (single kick), admin_force_reload (nuclear),
+// and the soundboard play broker (SendData).
 // All call LiveKit's twirp API directly via reqwest with a service token.
 
 fn livekit_sfu_url() -> String {
@@ -431,6 +436,42 @@ pub(crate) async fn livekit_list_rooms(state: &AppState) -> Result<Vec<String>,
     Ok(rooms)
 }
 
+/// Call LiveKit SendData: deliver a reliable JSON data message to everyone in
+/// `room`, exactly as if a participant had published it.
+pub(crate) async fn livekit_send_data(
+    state: &AppState,
+    room: &str,
+    message: &serde_json::Value,
+) -> Result<(), String> {
+    let token = livekit_service_token(
+        &state.config.livekit_api_key,
+        &state.config.livekit_api_secret,
+        room,
+    )
+    .map_err(|_| "service token build failed".to_string())?;
+    let data = base64::engine::general_purpose::STANDARD.encode(message.to_string());
+    let resp = state
+        .http_client
+        .post(format!(
+            "{}/twirp/livekit.RoomService/SendData",
+            livekit_sfu_url()
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({
+            "room": room,
+            "data": data,
+            "kind": "RELIABLE",
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("SendData request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("SendData HTTP {}", resp.status()));
+    }
+    Ok(())
+}
+
 /// Call LiveKit ListParticipants for a given room. Returns identity strings
 /// (including `$screen` companion publishers, which the control plane filters
 /// out of its dashboard but which still hold media tracks in the SFU).
@@ -722,6 +763,9 @@ pub(crate) fn schedule_jam_auto_end(state: AppState, generation: u64, reason: &'
     tokio::spawn(async move {
         info!("Jam auto-end ({}): no listeners, waiting 30s...", reason);
         tokio::time::sleep(Duration::from_secs(30)).await;
+        let Some(state) = state.jam_room_for_generation(generation) else {
+            return;
+        };
         if crate::jam_session::end_jam_if_still_empty(&state, generation, reason).await {
             info!("Jam auto-ended ({}): no listeners for 30s", reason);
         } else {
@@ -1,6 +1,12 @@
 use crate::AppState;
 use crate::auth::*;
 use crate::config::*;
+use crate::rooms::livekit_send_data;
+use crate::soundboard_audio::{
+    process_soundboard_clip, trim_soundboard_clip, ProcessedClip, SOUNDBOARD_AUDIO_EXTENSION,
+    SOUNDBOARD_AUDIO_MIME,
+};
+use crate::soundboard_play::{PlayDenied, SoundboardPlays, LEGACY_CLIP_MS, MAX_MUTE_MINUTES};
 
 use axum::{
     body::Bytes,
@@ -13,18 +19,39 @@ use std::{
     collections::HashMap,
     fs,
     path::PathBuf,
+    sync::Mutex,
 };
 use tracing::{info, warn};
 
+/// Directory and `roomId` of the global library; never used for a room.
+const LIBRARY_ROOM_ID: &str = "_library";
+const MAX_LIBRARY_SOUNDS: usize = 500;
+const MAX_FOLDERS_PER_ROOM: usize = 30;
+const MAX_FOLDER_NAME_CHARS: usize = 40;
+const MAX_FAVORITES_PER_IDENTITY: usize = 200;
+
 // ── Structs ──────────────────────────────────────────────────────────
 
 #[derive(Clone)]
 pub(crate) struct SoundboardState {
     pub(crate) dir: PathBuf,
     pub(crate) max_bytes: usize,
+    pub(crate) max_duration_ms: u64,
     pub(crate) max_sounds_per_room: usize,
     pub(crate) rooms: HashMap<String, HashMap<String, SoundboardSound>>,
     pub(crate) index: HashMap<String, SoundboardSound>,
+    /// Ordered folder names per room.
+    pub(crate) folders: HashMap<String, Vec<String>>,
+    /// Favorite sound IDs per identity (the stable Echo actor ID when the
+    /// token carries one).
+    pub(crate) favorites: HashMap<String, Vec<String>>,
+    /// Clips published for any room to import, keyed by library ID.
+    pub(crate) library: HashMap<String, SoundboardSound>,
+    /// Cooldowns, in-flight plays, and moderator mutes (memory-only).
+    pub(crate) plays: SoundboardPlays,
+    /// Play counts changed since the last write. Plays never persist on the
+    /// request path; `flush_soundboard_play_counts` writes them in batches.
+    pub(crate) play_counts_dirty: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -43,6 +70,32 @@ pub(crate) struct SoundboardSound {
     pub(crate) mime: Option<String>,
     #[serde(rename = "uploadedAt", default)]
     pub(crate) uploaded_at: u64,
+    // Set by server-side processing; absent for sounds stored before
+    // uploads were normalized.
+    #[serde(rename = "durationMs", default)]
+    pub(crate) duration_ms: Option<u64>,
+    #[serde(rename = "peakDbfs", default)]
+    pub(crate) peak_dbfs: Option<f64>,
+    #[serde(rename = "loudnessLufs", default)]
+    pub(crate) loudness_lufs: Option<f64>,
+    #[serde(rename = "gainDb", default)]
+    pub(crate) gain_db: Option<f64>,
+    #[serde(default)]
+    pub(crate) folder: Option<String>,
+    #[serde(default)]
+    pub(crate) position: u32,
+    // Library entries only.
+    #[serde(rename = "publishedBy", default)]
+    pub(crate) published_by: Option<String>,
+    #[serde(rename = "publisherId", default)]
+    pub(crate) publisher_id: Option<String>,
+    #[serde(rename = "sourceSoundId", default)]
+    pub(crate) source_sound_id: Option<String>,
+    // Brokered plays only; plays published directly by clients are not seen.
+    #[serde(rename = "playCount", default)]
+    pub(crate) play_count: u64,
+    #[serde(rename = "lastPlayedAt", default)]
+    pub(crate) last_played_at: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -53,6 +106,29 @@ pub(crate) struct SoundboardPublic {
     pub(crate) name: String,
     pub(crate) icon: String,
     pub(crate) volume: u16,
+    #[serde(rename = "durationMs", skip_serializing_if = "Option::is_none")]
+    pub(crate) duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) folder: Option<String>,
+    pub(crate) position: u32,
+    #[serde(rename = "playCount")]
+    pub(crate) play_count: u64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SoundboardLibraryPublic {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) icon: String,
+    pub(crate) volume: u16,
+    #[serde(rename = "durationMs", skip_serializing_if = "Option::is_none")]
+    pub(crate) duration_ms: Option<u64>,
+    #[serde(rename = "publishedBy")]
+    pub(crate) published_by: String,
+    #[serde(rename = "publishedAt")]
+    pub(crate) published_at: u64,
+    #[serde(rename = "publishedByMe")]
+    pub(crate) published_by_me: bool,
 }
 
 #[derive(Deserialize)]
@@ -78,12 +154,147 @@ pub(crate) struct SoundboardUpdateRequest {
     pub(crate) name: Option<String>,
     pub(crate) icon: Option<String>,
     pub(crate) volume: Option<u16>,
+    /// Move the sound to the end of this folder; an empty string unfiles it.
+    pub(crate) folder: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardTrimRequest {
+    pub(crate) room_id: String,
+    pub(crate) sound_id: String,
+    pub(crate) start_ms: u64,
+    pub(crate) end_ms: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardPlacement {
+    pub(crate) sound_id: String,
+    #[serde(default)]
+    pub(crate) folder: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardArrangeRequest {
+    pub(crate) room_id: String,
+    /// The room's complete, ordered folder list.
+    pub(crate) folders: Vec<String>,
+    /// Sounds in display order; unlisted sounds keep their folder and follow.
+    #[serde(default)]
+    pub(crate) order: Vec<SoundboardPlacement>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardFavoriteRequest {
+    pub(crate) room_id: String,
+    pub(crate) sound_id: String,
+    pub(crate) favorite: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardCopyRequest {
+    pub(crate) room_id: String,
+    pub(crate) sound_id: String,
+    pub(crate) target_room_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardPublishRequest {
+    pub(crate) room_id: String,
+    pub(crate) sound_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardImportRequest {
+    pub(crate) room_id: String,
+    pub(crate) library_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardLibraryRemoveRequest {
+    pub(crate) library_id: String,
 }
 
 #[derive(Serialize)]
 pub(crate) struct SoundboardListResponse {
     pub(crate) ok: bool,
     pub(crate) sounds: Vec<SoundboardPublic>,
+    pub(crate) folders: Vec<String>,
+    /// The caller's favorites among this room's sounds.
+    pub(crate) favorites: Vec<String>,
+    /// Set while a moderator has muted the soundboard in this room.
+    #[serde(rename = "mutedUntil", skip_serializing_if = "Option::is_none")]
+    pub(crate) muted_until: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SoundboardLibraryResponse {
+    pub(crate) ok: bool,
+    pub(crate) sounds: Vec<SoundboardLibraryPublic>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SoundboardLibrarySoundResponse {
+    pub(crate) ok: bool,
+    pub(crate) sound: Option<SoundboardLibraryPublic>,
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardPlayRequest {
+    pub(crate) room_id: String,
+    pub(crate) sound_id: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardPlayEvent {
+    pub(crate) sound_id: String,
+    pub(crate) sound_name: String,
+    pub(crate) sender_identity: String,
+    pub(crate) sender_name: String,
+    pub(crate) played_at: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardPlayResponse {
+    pub(crate) ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) play: Option<SoundboardPlayEvent>,
+    /// False when the room broadcast failed; the caller may publish the
+    /// play itself.
+    pub(crate) broadcast: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) retry_after_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SoundboardMuteRequest {
+    /// 0 lifts the mute.
+    pub(crate) minutes: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct SoundboardLayoutFile {
+    #[serde(default)]
+    folders: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    favorites: HashMap<String, Vec<String>>,
 }
 
 #[derive(Serialize)]
@@ -112,25 +323,74 @@ pub(crate) fn soundboard_public(sound: &SoundboardSound) -> SoundboardPublic {
         name: sound.name.clone(),
         icon: sound.icon.clone(),
         volume: sound.volume,
+        duration_ms: sound.duration_ms,
+        folder: sound.folder.clone(),
+        position: sound.position,
+        play_count: sound.play_count,
+    }
+}
+
+fn soundboard_library_public(sound: &SoundboardSound, identity: &str) -> SoundboardLibraryPublic {
+    SoundboardLibraryPublic {
+        id: sound.id.clone(),
+        name: sound.name.clone(),
+        icon: sound.icon.clone(),
+        volume: sound.volume,
+        duration_ms: sound.duration_ms,
+        published_by: sound.published_by.clone().unwrap_or_default(),
+        published_at: sound.uploaded_at,
+        published_by_me: sound.publisher_id.as_deref() == Some(identity),
     }
 }
 
+/// Key for per-person soundboard state: the stable Echo actor ID, or the
+/// LiveKit identity for tokens issued without one.
+fn soundboard_identity(claims: &LiveKitClaims) -> String {
+    claims
+        .echo_actor_id
+        .clone()
+        .filter(|actor_id| !actor_id.trim().is_empty())
+        .unwrap_or_else(|| claims.sub.clone())
+}
+
+fn upload_error(error: impl Into<String>) -> Json<SoundboardSoundResponse> {
+    Json(SoundboardSoundResponse {
+        ok: false,
+        sound: None,
+        error: Some(error.into()),
+    })
+}
+
+fn round_tenth(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
 fn soundboard_meta_path(dir: &PathBuf) -> PathBuf {
     dir.join("soundboard.json")
 }
 
+fn soundboard_layout_path(dir: &std::path::Path) -> PathBuf {
+    dir.join("soundboard-layout.json")
+}
+
+fn soundboard_library_path(dir: &std::path::Path) -> PathBuf {
+    dir.join("soundboard-library.json")
+}
+
 fn soundboard_room_dir(dir: &PathBuf, room_id: &str) -> PathBuf {
     let safe = if crate::is_safe_path_component(room_id) { room_id } else { "_invalid" };
     dir.join(safe)
 }
 
-fn soundboard_file_path(dir: &PathBuf, room_id: &str, file_name: &str) -> PathBuf {
+pub(crate) fn soundboard_file_path(dir: &PathBuf, room_id: &str, file_name: &str) -> PathBuf {
     let safe_name = if crate::is_safe_path_component(file_name) { file_name } else { "_invalid" };
     soundboard_room_dir(dir, room_id).join(safe_name)
 }
 
 pub(crate) fn load_soundboard(state: &mut SoundboardState) {
     let _ = fs::create_dir_all(&state.dir);
+    load_soundboard_layout(state);
+    load_soundboard_library(state);
     let meta = soundboard_meta_path(&state.dir);
     if !meta.exists() {
         return;
@@ -167,12 +427,43 @@ pub(crate) fn load_soundboard(state: &mut SoundboardState) {
     info!("soundboard loaded ({} sounds)", state.index.len());
 }
 
-fn persist_soundboard(state: &SoundboardState) {
-    let sounds: Vec<SoundboardSound> = state.index.values().cloned().collect();
-    let meta = soundboard_meta_path(&state.dir);
-    match serde_json::to_string_pretty(&sounds) {
+fn load_soundboard_layout(state: &mut SoundboardState) {
+    let path = soundboard_layout_path(&state.dir);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<SoundboardLayoutFile>(&contents) {
+        Ok(layout) => {
+            state.folders = layout.folders;
+            state.favorites = layout.favorites;
+        }
+        Err(err) => warn!("soundboard layout parse failed: {}", err),
+    }
+}
+
+fn load_soundboard_library(state: &mut SoundboardState) {
+    let path = soundboard_library_path(&state.dir);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return;
+    };
+    let sounds: Vec<SoundboardSound> = match serde_json::from_str(&contents) {
+        Ok(list) => list,
+        Err(err) => {
+            warn!("soundboard library parse failed: {}", err);
+            return;
+        }
+    };
+    for sound in sounds {
+        if soundboard_file_path(&state.dir, LIBRARY_ROOM_ID, &sound.file_name).exists() {
+            state.library.insert(sound.id.clone(), sound);
+        }
+    }
+}
+
+fn write_soundboard_json(path: PathBuf, value: &impl Serialize) {
+    match serde_json::to_string_pretty(value) {
         Ok(payload) => {
-            if let Err(err) = fs::write(&meta, payload) {
+            if let Err(err) = fs::write(&path, payload) {
                 warn!("soundboard persist failed: {}", err);
             }
         }
@@ -182,6 +473,190 @@ fn persist_soundboard(state: &SoundboardState) {
     }
 }
 
+pub(crate) fn persist_soundboard(state: &SoundboardState) {
+    let sounds: Vec<SoundboardSound> = state.index.values().cloned().collect();
+    write_soundboard_json(soundboard_meta_path(&state.dir), &sounds);
+    let layout = SoundboardLayoutFile {
+        folders: state.folders.clone(),
+        favorites: state.favorites.clone(),
+    };
+    write_soundboard_json(soundboard_layout_path(&state.dir), &layout);
+    let library: Vec<SoundboardSound> = state.library.values().cloned().collect();
+    write_soundboard_json(soundboard_library_path(&state.dir), &library);
+}
+
+/// Write the board if plays changed it since the last flush. Blocking; run it
+/// off the async runtime.
+pub(crate) fn flush_soundboard_play_counts(soundboard: &Mutex<SoundboardState>) {
+    let mut board = soundboard.lock().unwrap_or_else(|e| e.into_inner());
+    if !std::mem::take(&mut board.play_counts_dirty) {
+        return;
+    }
+    persist_soundboard(&board);
+}
+
+pub(crate) fn valid_soundboard_room(room_id: &str) -> bool {
+    !room_id.trim().is_empty() && room_id != LIBRARY_ROOM_ID
+}
+
+/// A room's sounds in display order: unfiled first, then each folder in the
+/// room's folder order, by position within each.
+pub(crate) fn ordered_room_sounds(board: &SoundboardState, room_id: &str) -> Vec<SoundboardSound> {
+    let folders = board.folders.get(room_id);
+    let folder_rank = |folder: &Option<String>| match folder {
+        None => 0,
+        Some(name) => folders
+            .and_then(|list| list.iter().position(|known| known == name))
+            .map(|index| index + 1)
+            .unwrap_or(usize::MAX),
+    };
+    let mut sounds = board
+        .rooms
+        .get(room_id)
+        .map(|room| room.values().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    sounds.sort_by(|a, b| {
+        (folder_rank(&a.folder), a.position, a.uploaded_at, &a.id).cmp(&(
+            folder_rank(&b.folder),
+            b.position,
+            b.uploaded_at,
+            &b.id,
+        ))
+    });
+    sounds
+}
+
+fn next_position(board: &SoundboardState, room_id: &str, folder: Option<&str>) -> u32 {
+    board
+        .rooms
+        .get(room_id)
+        .into_iter()
+        .flat_map(|room| room.values())
+        .filter(|sound| sound.folder.as_deref() == folder)
+        .map(|sound| sound.position.saturating_add(1))
+        .max()
+        .unwrap_or(0)
+}
+
+fn room_favorites(board: &SoundboardState, room_id: &str, identity: &str) -> Vec<String> {
+    board
+        .favorites
+        .get(identity)
+        .into_iter()
+        .flatten()
+        .filter(|sound_id| {
+            board
+                .index
+                .get(*sound_id)
+                .is_some_and(|sound| sound.room_id == room_id)
+        })
+        .cloned()
+        .collect()
+}
+
+fn room_listing(board: &SoundboardState, room_id: &str, identity: &str) -> SoundboardListResponse {
+    SoundboardListResponse {
+        ok: true,
+        sounds: ordered_room_sounds(board, room_id)
+            .iter()
+            .map(soundboard_public)
+            .collect(),
+        folders: board.folders.get(room_id).cloned().unwrap_or_default(),
+        favorites: room_favorites(board, room_id, identity),
+        muted_until: board.plays.muted_until(room_id, now_ts_ms()),
+        error: None,
+    }
+}
+
+fn folder_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    (!name.is_empty() && name.chars().count() <= MAX_FOLDER_NAME_CHARS).then(|| name.to_string())
+}
+
+/// File freshly imported sounds into named folders, creating any folder the
+/// room lacks while it stays under the folder cap. Sounds whose folder cannot
+/// be created stay unfiled. The caller persists.
+pub(crate) fn file_imported_sounds(
+    board: &mut SoundboardState,
+    room_id: &str,
+    placements: &[(String, String)],
+) {
+    for (sound_id, folder) in placements {
+        let Some(folder) = folder_name(folder) else {
+            continue;
+        };
+        let folders = board.folders.entry(room_id.to_string()).or_default();
+        let folder = match folders
+            .iter()
+            .find(|known| known.eq_ignore_ascii_case(&folder))
+        {
+            Some(known) => known.clone(),
+            None if folders.len() < MAX_FOLDERS_PER_ROOM => {
+                folders.push(folder.clone());
+                folder
+            }
+            None => continue,
+        };
+        let position = next_position(board, room_id, Some(&folder));
+        let Some(sound) = board.index.get_mut(sound_id) else {
+            continue;
+        };
+        sound.folder = Some(folder);
+        sound.position = position;
+        let updated = sound.clone();
+        if let Some(room) = board.rooms.get_mut(room_id) {
+            room.insert(updated.id.clone(), updated);
+        }
+    }
+}
+
+/// Copy a stored clip into `room_id` as a new, unfiled sound, enforcing the
+/// room's sound limit. The caller persists.
+fn copy_sound_into_room(
+    board: &mut SoundboardState,
+    source: &SoundboardSound,
+    room_id: &str,
+) -> Result<SoundboardSound, String> {
+    let room_len = board.rooms.get(room_id).map(|room| room.len()).unwrap_or(0);
+    if room_len >= board.max_sounds_per_room {
+        return Err("Soundboard is full for this room".into());
+    }
+    let id = random_secret();
+    let extension = std::path::Path::new(&source.file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("bin");
+    let file_name = format!("{}.{}", id, extension);
+    let source_path = soundboard_file_path(&board.dir, &source.room_id, &source.file_name);
+    let room_dir = soundboard_room_dir(&board.dir, room_id);
+    let _ = fs::create_dir_all(&room_dir);
+    if let Err(err) = fs::copy(&source_path, room_dir.join(&file_name)) {
+        warn!("soundboard copy failed: {}", err);
+        return Err("Unable to copy audio".into());
+    }
+    let sound = SoundboardSound {
+        id: id.clone(),
+        room_id: room_id.to_string(),
+        file_name,
+        uploaded_at: now_ts_ms(),
+        folder: None,
+        position: next_position(board, room_id, None),
+        published_by: None,
+        publisher_id: None,
+        source_sound_id: None,
+        play_count: 0,
+        last_played_at: None,
+        ..source.clone()
+    };
+    board
+        .rooms
+        .entry(room_id.to_string())
+        .or_default()
+        .insert(id.clone(), sound.clone());
+    board.index.insert(id, sound.clone());
+    Ok(sound)
+}
+
 // ── API handlers ─────────────────────────────────────────────────────
 
 pub(crate) async fn soundboard_list(
@@ -189,17 +664,9 @@ pub(crate) async fn soundboard_list(
     headers: HeaderMap,
     Query(query): Query<SoundboardListQuery>,
 ) -> Result<Json<SoundboardListResponse>, StatusCode> {
-    ensure_livekit(&state, &headers)?;
+    let claims = ensure_livekit(&state, &headers)?;
     let board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
-    let sounds = board
-        .rooms
-        .get(&query.room_id)
-        .map(|room| room.values().cloned().collect::<Vec<_>>())
-        .unwrap_or_else(Vec::new);
-    let payload = SoundboardListResponse {
-        ok: true,
-        sounds: sounds.iter().map(soundboard_public).collect(),
-    };
+    let payload = room_listing(&board, &query.room_id, &soundboard_identity(&claims));
     Ok(Json(payload))
 }
 
@@ -210,7 +677,11 @@ pub(crate) async fn soundboard_file(
 ) -> Result<impl IntoResponse, StatusCode> {
     ensure_livekit(&state, &headers)?;
     let board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
-    let Some(sound) = board.index.get(&sound_id) else {
+    let Some(sound) = board
+        .index
+        .get(&sound_id)
+        .or_else(|| board.library.get(&sound_id))
+    else {
         return Err(StatusCode::NOT_FOUND);
     };
     let path = soundboard_file_path(&board.dir, &sound.room_id, &sound.file_name);
@@ -235,70 +706,89 @@ pub(crate) async fn soundboard_upload(
     body: Bytes,
 ) -> Result<Json<SoundboardSoundResponse>, StatusCode> {
     ensure_livekit(&state, &headers)?;
+    let mime_hint = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    match store_soundboard_upload(&state, query, mime_hint, body.to_vec()).await {
+        Ok(sound) => Ok(Json(SoundboardSoundResponse {
+            ok: true,
+            sound: Some(soundboard_public(&sound)),
+            error: None,
+        })),
+        Err(error) => Ok(upload_error(error)),
+    }
+}
+
+/// Validate, process, and store one uploaded clip in `query.room_id`. Shared
+/// by `soundboard_upload` and bundle import; errors are user-facing.
+pub(crate) async fn store_soundboard_upload(
+    state: &AppState,
+    query: SoundboardUploadQuery,
+    mime_hint: Option<String>,
+    body: Vec<u8>,
+) -> Result<SoundboardSound, String> {
     if query.room_id.trim().is_empty() {
-        return Ok(Json(SoundboardSoundResponse {
-            ok: false,
-            sound: None,
-            error: Some("Missing roomId".into()),
-        }));
+        return Err("Missing roomId".into());
+    }
+    if !valid_soundboard_room(&query.room_id) {
+        return Err("Invalid roomId".into());
     }
     if body.is_empty() {
-        return Ok(Json(SoundboardSoundResponse {
-            ok: false,
-            sound: None,
-            error: Some("Empty audio payload".into()),
-        }));
+        return Err("Empty audio payload".into());
     }
+    let (max_duration_ms, max_sounds) = {
+        let board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+        if body.len() > board.max_bytes {
+            return Err("Audio file too large".into());
+        }
+        let room_len = board.rooms.get(&query.room_id).map(|room| room.len()).unwrap_or(0);
+        if room_len >= board.max_sounds_per_room {
+            return Err("Soundboard is full for this room".into());
+        }
+        (board.max_duration_ms, board.max_sounds_per_room)
+    };
+
+    // Decode and re-encode off the runtime; the content type is only a hint
+    // for the prober, never trusted for the stored format.
+    let processed = tokio::task::spawn_blocking(move || {
+        process_soundboard_clip(body, mime_hint.as_deref(), max_duration_ms)
+    })
+    .await
+    .map_err(|_| "Audio processing failed".to_string())?;
+    let clip = match processed {
+        Ok(clip) => clip,
+        Err(err) => {
+            info!("soundboard upload rejected: {}", err.message());
+            return Err(err.message());
+        }
+    };
+
     let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
-    if body.len() > board.max_bytes {
-        return Ok(Json(SoundboardSoundResponse {
-            ok: false,
-            sound: None,
-            error: Some("Audio file too large".into()),
-        }));
-    }
-    let max_sounds = board.max_sounds_per_room;
     let room_id = query.room_id.clone();
     let board_dir = board.dir.clone();
+    let position = next_position(&board, &room_id, None);
     let room = board
         .rooms
         .entry(room_id.clone())
-        .or_insert_with(HashMap::new);
+        .or_default();
+    // Re-checked: another upload may have filled the room while this one
+    // was being processed.
     if room.len() >= max_sounds {
-        return Ok(Json(SoundboardSoundResponse {
-            ok: false,
-            sound: None,
-            error: Some("Soundboard is full for this room".into()),
-        }));
+        return Err("Soundboard is full for this room".into());
     }
 
     let id = random_secret();
     let name = query.name.unwrap_or_else(|| "Sound".to_string());
     let icon = query.icon.unwrap_or_else(default_soundboard_icon);
     let volume = query.volume.unwrap_or(default_soundboard_volume());
-    let mime = headers
-        .get(axum::http::header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .map(|v| v.to_string());
-    let ext = match mime.as_deref().unwrap_or("") {
-        "audio/mpeg" => "mp3",
-        "audio/wav" => "wav",
-        "audio/ogg" => "ogg",
-        "audio/webm" => "webm",
-        "audio/mp4" => "mp4",
-        _ => "bin",
-    };
-    let file_name = format!("{}.{}", id, ext);
+    let file_name = format!("{}.{}", id, SOUNDBOARD_AUDIO_EXTENSION);
     let room_dir = soundboard_room_dir(&board_dir, &room_id);
     let _ = fs::create_dir_all(&room_dir);
     let file_path = room_dir.join(&file_name);
-    if let Err(err) = fs::write(&file_path, &body) {
+    if let Err(err) = fs::write(&file_path, &clip.flac) {
         warn!("soundboard upload failed: {}", err);
-        return Ok(Json(SoundboardSoundResponse {
-            ok: false,
-            sound: None,
-            error: Some("Unable to save audio".into()),
-        }));
+        return Err("Unable to save audio".into());
     }
     let sound = SoundboardSound {
         id: id.clone(),
@@ -307,17 +797,24 @@ pub(crate) async fn soundboard_upload(
         icon,
         volume: volume.min(200),
         file_name,
-        mime,
+        mime: Some(SOUNDBOARD_AUDIO_MIME.to_string()),
         uploaded_at: now_ts_ms(),
+        duration_ms: Some(clip.duration_ms),
+        peak_dbfs: Some(round_tenth(clip.peak_dbfs)),
+        loudness_lufs: Some(round_tenth(clip.loudness_lufs)),
+        gain_db: Some(round_tenth(clip.gain_db)),
+        folder: None,
+        position,
+        published_by: None,
+        publisher_id: None,
+        source_sound_id: None,
+        play_count: 0,
+        last_played_at: None,
     };
     room.insert(id.clone(), sound.clone());
     board.index.insert(id.clone(), sound.clone());
     persist_soundboard(&board);
-    Ok(Json(SoundboardSoundResponse {
-        ok: true,
-        sound: Some(soundboard_public(&sound)),
-        error: None,
-    }))
+    Ok(sound)
 }
 
 pub(crate) async fn soundboard_update(
@@ -328,6 +825,22 @@ pub(crate) async fn soundboard_update(
     ensure_livekit(&state, &headers)?;
     let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
     let sound_id = payload.sound_id.clone();
+    // Resolved before the sound is borrowed mutably.
+    let folder_move = match payload.folder.as_deref().map(str::trim) {
+        None => None,
+        Some("") => Some((None, next_position(&board, &payload.room_id, None))),
+        Some(name) => {
+            let known = board
+                .folders
+                .get(&payload.room_id)
+                .is_some_and(|folders| folders.iter().any(|folder| folder == name));
+            if !known {
+                return Ok(upload_error("Unknown folder"));
+            }
+            let position = next_position(&board, &payload.room_id, Some(name));
+            Some((Some(name.to_string()), position))
+        }
+    };
     let sound = match board.index.get_mut(&sound_id) {
         Some(sound) => sound,
         None => {
@@ -354,6 +867,12 @@ pub(crate) async fn soundboard_update(
     if let Some(volume) = payload.volume {
         sound.volume = volume.min(200);
     }
+    if let Some((folder, position)) = folder_move {
+        if sound.folder != folder {
+            sound.folder = folder;
+            sound.position = position;
+        }
+    }
     sound.uploaded_at = now_ts_ms();
     let updated = sound.clone();
     if let Some(room) = board.rooms.get_mut(&updated.room_id) {
@@ -366,3 +885,528 @@ pub(crate) async fn soundboard_update(
         error: None,
     }))
 }
+
+pub(crate) async fn soundboard_trim(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SoundboardTrimRequest>,
+) -> Result<Json<SoundboardSoundResponse>, StatusCode> {
+    ensure_livekit(&state, &headers)?;
+    let (source_file_name, source_path, mime, max_duration_ms) = {
+        let board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(sound) = board.index.get(&payload.sound_id) else {
+            return Ok(upload_error("Sound not found"));
+        };
+        if sound.room_id != payload.room_id {
+            return Ok(upload_error("Room mismatch"));
+        }
+        (
+            sound.file_name.clone(),
+            soundboard_file_path(&board.dir, &sound.room_id, &sound.file_name),
+            sound.mime.clone(),
+            board.max_duration_ms,
+        )
+    };
+    let (start_ms, end_ms) = (payload.start_ms, payload.end_ms);
+    let processed = tokio::task::spawn_blocking(move || {
+        let bytes = fs::read(&source_path).map_err(|_| "Sound file is missing".to_string())?;
+        trim_soundboard_clip(bytes, mime.as_deref(), start_ms, end_ms, max_duration_ms)
+            .map_err(|err| err.message())
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let clip = match processed {
+        Ok(clip) => clip,
+        Err(message) => return Ok(upload_error(message)),
+    };
+
+    let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(current) = board.index.get(&payload.sound_id).cloned() else {
+        return Ok(upload_error("Sound not found"));
+    };
+    // Another trim replaced the clip while this one was decoding. Saving now
+    // would drop that trim and orphan its file, so make the caller retry.
+    if current.file_name != source_file_name {
+        return Ok(upload_error("Sound changed while trimming; try again"));
+    }
+    // A fresh file per trim, so a download in flight never reads a
+    // half-written clip.
+    let file_name = format!(
+        "{}-{}.{}",
+        current.id,
+        &random_secret()[..8],
+        SOUNDBOARD_AUDIO_EXTENSION
+    );
+    let room_dir = soundboard_room_dir(&board.dir, &current.room_id);
+    if let Err(err) = fs::write(room_dir.join(&file_name), &clip.flac) {
+        warn!("soundboard trim failed: {}", err);
+        return Ok(upload_error("Unable to save audio"));
+    }
+    let old_path = soundboard_file_path(&board.dir, &current.room_id, &current.file_name);
+    let updated = SoundboardSound {
+        file_name,
+        ..with_clip_metadata(current, &clip)
+    };
+    if let Some(room) = board.rooms.get_mut(&updated.room_id) {
+        room.insert(updated.id.clone(), updated.clone());
+    }
+    board.index.insert(updated.id.clone(), updated.clone());
+    persist_soundboard(&board);
+    let _ = fs::remove_file(old_path);
+    Ok(Json(SoundboardSoundResponse {
+        ok: true,
+        sound: Some(soundboard_public(&updated)),
+        error: None,
+    }))
+}
+
+fn with_clip_metadata(sound: SoundboardSound, clip: &ProcessedClip) -> SoundboardSound {
+    SoundboardSound {
+        mime: Some(SOUNDBOARD_AUDIO_MIME.to_string()),
+        duration_ms: Some(clip.duration_ms),
+        peak_dbfs: Some(round_tenth(clip.peak_dbfs)),
+        loudness_lufs: Some(round_tenth(clip.loudness_lufs)),
+        gain_db: Some(round_tenth(clip.gain_db)),
+        ..sound
+    }
+}
+
+fn listing_error(message: impl Into<String>) -> Json<SoundboardListResponse> {
+    Json(SoundboardListResponse {
+        ok: false,
+        sounds: Vec::new(),
+        folders: Vec::new(),
+        favorites: Vec::new(),
+        muted_until: None,
+        error: Some(message.into()),
+    })
+}
+
+pub(crate) async fn soundboard_arrange(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SoundboardArrangeRequest>,
+) -> Result<Json<SoundboardListResponse>, StatusCode> {
+    let claims = ensure_livekit(&state, &headers)?;
+    if !valid_soundboard_room(&payload.room_id) {
+        return Ok(listing_error("Invalid roomId"));
+    }
+    if payload.folders.len() > MAX_FOLDERS_PER_ROOM {
+        return Ok(listing_error(format!(
+            "A room can have at most {} folders",
+            MAX_FOLDERS_PER_ROOM
+        )));
+    }
+    let mut folders: Vec<String> = Vec::new();
+    for name in &payload.folders {
+        let Some(name) = folder_name(name) else {
+            return Ok(listing_error(format!(
+                "Folder names must be 1-{} characters",
+                MAX_FOLDER_NAME_CHARS
+            )));
+        };
+        if folders.iter().any(|known| known.eq_ignore_ascii_case(&name)) {
+            return Ok(listing_error("Folder names must be unique"));
+        }
+        folders.push(name);
+    }
+
+    let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+    let current = ordered_room_sounds(&board, &payload.room_id);
+    let mut placed: HashMap<String, (Option<String>, usize)> = HashMap::new();
+    for (rank, placement) in payload.order.iter().enumerate() {
+        if !current.iter().any(|sound| sound.id == placement.sound_id) {
+            return Ok(listing_error("Sound not found"));
+        }
+        let folder = match placement.folder.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(name) => match folders.iter().find(|known| known.as_str() == name) {
+                Some(known) => Some(known.clone()),
+                None => return Ok(listing_error("Unknown folder")),
+            },
+        };
+        if placed
+            .insert(placement.sound_id.clone(), (folder, rank))
+            .is_some()
+        {
+            return Ok(listing_error("Each sound can only be placed once"));
+        }
+    }
+    // Listed sounds take their rank; the rest keep their previous relative
+    // order after them, and lose a folder that no longer exists.
+    let mut next_rank = payload.order.len();
+    for sound in current {
+        let (folder, position) = match placed.remove(&sound.id) {
+            Some(placement) => placement,
+            None => {
+                next_rank += 1;
+                let folder = sound.folder.clone().filter(|name| folders.contains(name));
+                (folder, next_rank - 1)
+            }
+        };
+        let updated = SoundboardSound {
+            folder,
+            position: position as u32,
+            ..sound
+        };
+        if let Some(room) = board.rooms.get_mut(&updated.room_id) {
+            room.insert(updated.id.clone(), updated.clone());
+        }
+        board.index.insert(updated.id.clone(), updated);
+    }
+    if folders.is_empty() {
+        board.folders.remove(&payload.room_id);
+    } else {
+        board.folders.insert(payload.room_id.clone(), folders);
+    }
+    persist_soundboard(&board);
+    Ok(Json(room_listing(
+        &board,
+        &payload.room_id,
+        &soundboard_identity(&claims),
+    )))
+}
+
+pub(crate) async fn soundboard_favorite(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SoundboardFavoriteRequest>,
+) -> Result<Json<SoundboardListResponse>, StatusCode> {
+    let claims = ensure_livekit(&state, &headers)?;
+    let identity = soundboard_identity(&claims);
+    let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+    match board.index.get(&payload.sound_id) {
+        Some(sound) if sound.room_id == payload.room_id => {}
+        Some(_) => return Ok(listing_error("Room mismatch")),
+        None => return Ok(listing_error("Sound not found")),
+    }
+    let favorites = board.favorites.entry(identity.clone()).or_default();
+    favorites.retain(|sound_id| sound_id != &payload.sound_id);
+    if payload.favorite {
+        if favorites.len() >= MAX_FAVORITES_PER_IDENTITY {
+            return Ok(listing_error(format!(
+                "You can favorite at most {} sounds",
+                MAX_FAVORITES_PER_IDENTITY
+            )));
+        }
+        favorites.push(payload.sound_id.clone());
+    }
+    if favorites.is_empty() {
+        board.favorites.remove(&identity);
+    }
+    persist_soundboard(&board);
+    Ok(Json(room_listing(&board, &payload.room_id, &identity)))
+}
+
+pub(crate) async fn soundboard_copy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SoundboardCopyRequest>,
+) -> Result<Json<SoundboardSoundResponse>, StatusCode> {
+    ensure_livekit(&state, &headers)?;
+    if !valid_soundboard_room(&payload.target_room_id) {
+        return Ok(upload_error("Invalid targetRoomId"));
+    }
+    let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+    let source = match board.index.get(&payload.sound_id) {
+        Some(sound) if sound.room_id == payload.room_id => sound.clone(),
+        Some(_) => return Ok(upload_error("Room mismatch")),
+        None => return Ok(upload_error("Sound not found")),
+    };
+    match copy_sound_into_room(&mut board, &source, &payload.target_room_id) {
+        Ok(sound) => {
+            persist_soundboard(&board);
+            Ok(Json(SoundboardSoundResponse {
+                ok: true,
+                sound: Some(soundboard_public(&sound)),
+                error: None,
+            }))
+        }
+        Err(message) => Ok(upload_error(message)),
+    }
+}
+
+// ── Global library ───────────────────────────────────────────────────
+
+fn library_error(message: impl Into<String>) -> Json<SoundboardLibrarySoundResponse> {
+    Json(SoundboardLibrarySoundResponse {
+        ok: false,
+        sound: None,
+        error: Some(message.into()),
+    })
+}
+
+pub(crate) async fn soundboard_library_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<SoundboardLibraryResponse>, StatusCode> {
+    let claims = ensure_livekit(&state, &headers)?;
+    let identity = soundboard_identity(&claims);
+    let board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+    let mut sounds = board.library.values().collect::<Vec<_>>();
+    sounds.sort_by(|a, b| b.uploaded_at.cmp(&a.uploaded_at).then_with(|| a.id.cmp(&b.id)));
+    Ok(Json(SoundboardLibraryResponse {
+        ok: true,
+        sounds: sounds
+            .into_iter()
+            .map(|sound| soundboard_library_public(sound, &identity))
+            .collect(),
+    }))
+}
+
+pub(crate) async fn soundboard_library_publish(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SoundboardPublishRequest>,
+) -> Result<Json<SoundboardLibrarySoundResponse>, StatusCode> {
+    let claims = ensure_livekit(&state, &headers)?;
+    let identity = soundboard_identity(&claims);
+    let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+    let source = match board.index.get(&payload.sound_id) {
+        Some(sound) if sound.room_id == payload.room_id => sound.clone(),
+        Some(_) => return Ok(library_error("Room mismatch")),
+        None => return Ok(library_error("Sound not found")),
+    };
+    // Publishing the same clip twice returns the existing entry.
+    if let Some(existing) = board
+        .library
+        .values()
+        .find(|entry| entry.source_sound_id.as_deref() == Some(source.id.as_str()))
+    {
+        return Ok(Json(SoundboardLibrarySoundResponse {
+            ok: true,
+            sound: Some(soundboard_library_public(existing, &identity)),
+            error: None,
+        }));
+    }
+    if board.library.len() >= MAX_LIBRARY_SOUNDS {
+        return Ok(library_error("The soundboard library is full"));
+    }
+    let id = random_secret();
+    let extension = std::path::Path::new(&source.file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("bin");
+    let file_name = format!("{}.{}", id, extension);
+    let library_dir = soundboard_room_dir(&board.dir, LIBRARY_ROOM_ID);
+    let _ = fs::create_dir_all(&library_dir);
+    let source_path = soundboard_file_path(&board.dir, &source.room_id, &source.file_name);
+    if let Err(err) = fs::copy(&source_path, library_dir.join(&file_name)) {
+        warn!("soundboard publish failed: {}", err);
+        return Ok(library_error("Unable to copy audio"));
+    }
+    let publisher = claims
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&claims.sub)
+        .chars()
+        .take(60)
+        .collect::<String>();
+    let entry = SoundboardSound {
+        id: id.clone(),
+        room_id: LIBRARY_ROOM_ID.to_string(),
+        file_name,
+        uploaded_at: now_ts_ms(),
+        folder: None,
+        position: 0,
+        published_by: Some(publisher),
+        publisher_id: Some(identity.clone()),
+        source_sound_id: Some(source.id.clone()),
+        play_count: 0,
+        last_played_at: None,
+        ..source
+    };
+    board.library.insert(id, entry.clone());
+    persist_soundboard(&board);
+    Ok(Json(SoundboardLibrarySoundResponse {
+        ok: true,
+        sound: Some(soundboard_library_public(&entry, &identity)),
+        error: None,
+    }))
+}
+
+pub(crate) async fn soundboard_library_import(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SoundboardImportRequest>,
+) -> Result<Json<SoundboardSoundResponse>, StatusCode> {
+    ensure_livekit(&state, &headers)?;
+    if !valid_soundboard_room(&payload.room_id) {
+        return Ok(upload_error("Invalid roomId"));
+    }
+    let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(entry) = board.library.get(&payload.library_id).cloned() else {
+        return Ok(upload_error("Library sound not found"));
+    };
+    match copy_sound_into_room(&mut board, &entry, &payload.room_id) {
+        Ok(sound) => {
+            persist_soundboard(&board);
+            Ok(Json(SoundboardSoundResponse {
+                ok: true,
+                sound: Some(soundboard_public(&sound)),
+                error: None,
+            }))
+        }
+        Err(message) => Ok(upload_error(message)),
+    }
+}
+
+pub(crate) async fn soundboard_library_remove(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SoundboardLibraryRemoveRequest>,
+) -> Result<Json<SoundboardLibrarySoundResponse>, StatusCode> {
+    let claims = ensure_livekit(&state, &headers)?;
+    let identity = soundboard_identity(&claims);
+    let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(entry) = board.library.get(&payload.library_id).cloned() else {
+        return Ok(library_error("Library sound not found"));
+    };
+    if entry.publisher_id.as_deref() != Some(identity.as_str()) {
+        return Ok(library_error("Only the publisher can remove this sound"));
+    }
+    board.library.remove(&entry.id);
+    persist_soundboard(&board);
+    let _ = fs::remove_file(soundboard_file_path(
+        &board.dir,
+        LIBRARY_ROOM_ID,
+        &entry.file_name,
+    ));
+    Ok(Json(SoundboardLibrarySoundResponse {
+        ok: true,
+        sound: None,
+        error: None,
+    }))
+}
+
+// ── Brokered playback ────────────────────────────────────────────────
+
+fn play_error(message: impl Into<String>) -> Json<SoundboardPlayResponse> {
+    Json(SoundboardPlayResponse {
+        ok: false,
+        play: None,
+        broadcast: false,
+        code: None,
+        retry_after_ms: None,
+        error: Some(message.into()),
+    })
+}
+
+/// Admit a play against the room's cooldowns and caps, count it, and send
+/// the `sound-play` data message to the whole room (the caller included).
+pub(crate) async fn soundboard_play(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SoundboardPlayRequest>,
+) -> Result<Json<SoundboardPlayResponse>, StatusCode> {
+    let participant = ensure_livekit_active_participant(&state, &headers)?;
+    if participant.room != payload.room_id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let event = {
+        let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+        let sound = match board.index.get(&payload.sound_id) {
+            Some(sound) if sound.room_id == payload.room_id => sound.clone(),
+            Some(_) => return Ok(play_error("Room mismatch")),
+            None => return Ok(play_error("Sound not found")),
+        };
+        let now = now_ts_ms();
+        let clip_ms = sound.duration_ms.unwrap_or(LEGACY_CLIP_MS);
+        if let Err(denied) = board
+            .plays
+            .admit(&payload.room_id, &participant.identity, clip_ms, now)
+        {
+            return Ok(play_denied(&denied, now));
+        }
+        let updated = SoundboardSound {
+            play_count: sound.play_count + 1,
+            last_played_at: Some(now),
+            ..sound
+        };
+        if let Some(room) = board.rooms.get_mut(&updated.room_id) {
+            room.insert(updated.id.clone(), updated.clone());
+        }
+        board.index.insert(updated.id.clone(), updated.clone());
+        board.play_counts_dirty = true;
+        SoundboardPlayEvent {
+            sound_id: updated.id,
+            sound_name: updated.name,
+            sender_identity: participant.identity.clone(),
+            sender_name: participant.name.clone(),
+            played_at: now,
+        }
+    };
+    info!(
+        "soundboard play: {} played {} in {}",
+        event.sender_identity, event.sound_id, payload.room_id
+    );
+    // Same shape clients publish themselves, so older viewers play it too.
+    let message = serde_json::json!({
+        "type": "sound-play",
+        "soundId": event.sound_id,
+        "soundName": event.sound_name,
+        "senderName": event.sender_name,
+        "senderIdentity": event.sender_identity,
+        "brokered": true,
+    });
+    let broadcast = match livekit_send_data(&state, &payload.room_id, &message).await {
+        Ok(()) => true,
+        Err(err) => {
+            warn!("soundboard play broadcast failed: {}", err);
+            false
+        }
+    };
+    Ok(Json(SoundboardPlayResponse {
+        ok: true,
+        play: Some(event),
+        broadcast,
+        code: None,
+        retry_after_ms: None,
+        error: None,
+    }))
+}
+
+fn play_denied(denied: &PlayDenied, now: u64) -> Json<SoundboardPlayResponse> {
+    Json(SoundboardPlayResponse {
+        code: Some(denied.code()),
+        retry_after_ms: Some(denied.retry_after_ms(now)),
+        ..play_error(denied.message()).0
+    })
+}
+
+/// Mute or unmute the soundboard in a room for `minutes` (0 lifts it). Only
+/// brokered plays are refused; the room is told so viewers can grey it out.
+pub(crate) async fn admin_soundboard_mute(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(room): Path<String>,
+    Json(payload): Json<SoundboardMuteRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    let room = room.trim().to_string();
+    if !valid_soundboard_room(&room) || room.len() > 128 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let now = now_ts_ms();
+    let muted_until =
+        (payload.minutes > 0).then(|| now + payload.minutes.min(MAX_MUTE_MINUTES) * 60_000);
+    {
+        let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+        board.plays.set_mute(&room, muted_until, now);
+    }
+    info!("soundboard mute in {}: {:?}", room, muted_until);
+    let message = serde_json::json!({
+        "type": "soundboard-muted",
+        "mutedUntil": muted_until,
+    });
+    if let Err(err) = livekit_send_data(&state, &room, &message).await {
+        warn!("soundboard mute broadcast failed: {}", err);
+    }
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "room": room,
+        "mutedUntil": muted_until,
+    })))
+}
@@ -0,0 +1,787 @@
+//! Soundboard clip processing: every upload is decoded on the server,
+//! rejected unless it is real audio within the duration limit, normalized to
+//! a common integrated loudness, and re-encoded as 48 kHz stereo FLAC.
+//!
+//! Loudness follows ITU-R BS.1770 (K-weighting, 400 ms gated blocks), so a
+//! clip's `volume` is a trim relative to the same reference level for every
+//! sound rather than relative to however loud the original file was.
+
+use crate::jam_bot::{convert_samples, TARGET_CHANNELS, TARGET_RATE};
+use crate::jam_recording::FlacWriter;
+
+use audiopus::coder::Decoder as OpusDecoder;
+use audiopus::{packet::Packet as OpusPacket, Channels, MutSignals, SampleRate};
+use std::io::{self, Cursor};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{CodecParameters, DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS},
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader},
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+pub(crate) const SOUNDBOARD_AUDIO_MIME: &str = "audio/flac";
+pub(crate) const SOUNDBOARD_AUDIO_EXTENSION: &str = "flac";
+/// Integrated loudness every clip is normalized to.
+pub(crate) const SOUNDBOARD_TARGET_LUFS: f64 = -18.0;
+/// Sample-peak ceiling; quieter-than-target clips are boosted only this far.
+const PEAK_CEILING_DBFS: f64 = -1.0;
+/// Limit on the boost for very quiet clips, so a near-silent recording is
+/// not turned into amplified hiss.
+const MAX_GAIN_DB: f64 = 24.0;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+const BLOCK_MS: u64 = 400;
+const BLOCK_STEP_MS: u64 = 100;
+/// Longest stored clip a trim will decode; covers sounds uploaded before
+/// the duration limit existed.
+const MAX_TRIM_SOURCE_MS: u64 = 10 * 60 * 1000;
+const MIN_TRIMMED_MS: u64 = 100;
+/// Opus always decodes at 48 kHz; the longest packet is 120 ms.
+const OPUS_RATE: u32 = 48_000;
+const MAX_OPUS_PACKET_FRAMES: usize = 5_760;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum SoundboardAudioError {
+    NotAudio(String),
+    TooLong { max_ms: u64 },
+    Silent,
+    InvalidTrim,
+    Encode(String),
+}
+
+impl SoundboardAudioError {
+    /// Message returned in the upload response's `error` field.
+    pub(crate) fn message(&self) -> String {
+        match self {
+            Self::NotAudio(detail) => format!(
+                "Not a supported audio file ({detail}); use MP3, WAV, FLAC, Ogg Vorbis, Ogg or WebM Opus, or M4A"
+            ),
+            Self::TooLong { max_ms } => {
+                format!("Sound is longer than {} seconds", max_ms.div_ceil(1000))
+            }
+            Self::Silent => "Sound is silent".to_string(),
+            Self::InvalidTrim => format!(
+                "Trim must keep at least {} ms between start and end",
+                MIN_TRIMMED_MS
+            ),
+            Self::Encode(detail) => format!("Unable to convert audio: {detail}"),
+        }
+    }
+}
+
+/// A normalized clip ready to store, with the metadata kept on
+/// `SoundboardSound`.
+#[derive(Debug)]
+pub(crate) struct ProcessedClip {
+    pub(crate) flac: Vec<u8>,
+    pub(crate) duration_ms: u64,
+    /// Sample peak of the stored clip.
+    pub(crate) peak_dbfs: f64,
+    /// Integrated loudness of the stored clip.
+    pub(crate) loudness_lufs: f64,
+    /// Gain applied to the upload to reach `loudness_lufs`.
+    pub(crate) gain_db: f64,
+}
+
+fn extension_hint(mime: Option<&str>) -> Option<&'static str> {
+    let mime = mime?.split(';').next()?.trim();
+    Some(match mime {
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/ogg" | "audio/vorbis" | "audio/opus" => "ogg",
+        "audio/flac" | "audio/x-flac" => "flac",
+        "audio/mp4" | "audio/x-m4a" | "audio/aac" => "m4a",
+        "audio/webm" => "webm",
+        _ => return None,
+    })
+}
+
+/// Decode an upload into interleaved f32 samples, stopping as soon as it runs
+/// past `max_duration_ms` so an hour-long file is not decoded in full.
+fn decode_clip(
+    bytes: Vec<u8>,
+    mime: Option<&str>,
+    max_duration_ms: u64,
+) -> Result<(Vec<f32>, u32, u32), SoundboardAudioError> {
+    let not_audio = |error: SymphoniaError| SoundboardAudioError::NotAudio(error.to_string());
+    let stream = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = extension_hint(mime) {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(not_audio)?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| SoundboardAudioError::NotAudio("no audio track".to_string()))?;
+    let track_id = track.id;
+    if let (Some(frames), Some(rate)) =
+        (track.codec_params.n_frames, track.codec_params.sample_rate)
+    {
+        if rate > 0 && frames.saturating_mul(1000) / u64::from(rate) > max_duration_ms {
+            return Err(SoundboardAudioError::TooLong {
+                max_ms: max_duration_ms,
+            });
+        }
+    }
+    // Symphonia demuxes Ogg and WebM Opus but has no Opus decoder.
+    if track.codec_params.codec == CODEC_TYPE_OPUS {
+        let params = track.codec_params.clone();
+        return decode_opus_track(format.as_mut(), track_id, &params, max_duration_ms);
+    }
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(not_audio)?;
+    let mut samples = Vec::new();
+    let mut format_spec = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                break;
+            }
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(error) => return Err(not_audio(error)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(error) => return Err(not_audio(error)),
+        };
+        let spec = *decoded.spec();
+        let (rate, channels) = (spec.rate, spec.channels.count() as u32);
+        if rate == 0 || channels == 0 {
+            return Err(SoundboardAudioError::NotAudio(
+                "invalid stream parameters".to_string(),
+            ));
+        }
+        // A format change mid-stream is not something a clip should do.
+        match format_spec {
+            None => format_spec = Some((rate, channels)),
+            Some(known) if known != (rate, channels) => {
+                return Err(SoundboardAudioError::NotAudio(
+                    "stream parameters change mid-file".to_string(),
+                ));
+            }
+            Some(_) => {}
+        }
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+        let frames = (samples.len() / channels as usize) as u64;
+        if frames * 1000 / u64::from(rate) > max_duration_ms {
+            return Err(SoundboardAudioError::TooLong {
+                max_ms: max_duration_ms,
+            });
+        }
+    }
+    let (rate, channels) = format_spec
+        .filter(|_| !samples.is_empty())
+        .ok_or_else(|| SoundboardAudioError::NotAudio("no decodable audio".to_string()))?;
+    Ok((samples, rate, channels))
+}
+
+/// Channel count and pre-skip of an Opus track, from its `OpusHead` when the
+/// container carries one.
+fn opus_layout(params: &CodecParameters) -> Result<(u32, usize), SoundboardAudioError> {
+    let head = params
+        .extra_data
+        .as_deref()
+        .filter(|head| head.len() >= 19 && head.starts_with(b"OpusHead"));
+    let (channels, pre_skip, mapping_family) = match head {
+        Some(head) => (
+            u32::from(head[9]),
+            usize::from(u16::from_le_bytes([head[10], head[11]])),
+            head[18],
+        ),
+        None => (
+            params
+                .channels
+                .map_or(0, |channels| channels.count() as u32),
+            params.delay.unwrap_or(0) as usize,
+            0,
+        ),
+    };
+    // Multistream layouts beyond stereo need a surround decoder.
+    if !(1..=2).contains(&channels) || mapping_family > 1 {
+        return Err(SoundboardAudioError::NotAudio(
+            "only mono or stereo Opus is supported".to_string(),
+        ));
+    }
+    Ok((channels, pre_skip))
+}
+
+fn decode_opus_track(
+    format: &mut dyn FormatReader,
+    track_id: u32,
+    params: &CodecParameters,
+    max_duration_ms: u64,
+) -> Result<(Vec<f32>, u32, u32), SoundboardAudioError> {
+    let not_audio = |error: SymphoniaError| SoundboardAudioError::NotAudio(error.to_string());
+    let (channels, pre_skip) = opus_layout(params)?;
+    let mut decoder = OpusDecoder::new(
+        SampleRate::Hz48000,
+        if channels == 1 {
+            Channels::Mono
+        } else {
+            Channels::Stereo
+        },
+    )
+    .map_err(|error| SoundboardAudioError::NotAudio(error.to_string()))?;
+    let width = channels as usize;
+    let mut samples = Vec::new();
+    let mut frame = vec![0.0_f32; MAX_OPUS_PACKET_FRAMES * width];
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                break;
+            }
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(error) => return Err(not_audio(error)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let Ok(data) = OpusPacket::try_from(packet.buf()) else {
+            continue;
+        };
+        let Ok(output) = MutSignals::try_from(&mut frame[..]) else {
+            continue;
+        };
+        // Like other codecs, a damaged packet is skipped rather than fatal.
+        let Ok(decoded) = decoder.decode_float(Some(data), output, false) else {
+            continue;
+        };
+        samples.extend_from_slice(&frame[..decoded * width]);
+        let frames = (samples.len() / width).saturating_sub(pre_skip) as u64;
+        if frames * 1000 / u64::from(OPUS_RATE) > max_duration_ms {
+            return Err(SoundboardAudioError::TooLong {
+                max_ms: max_duration_ms,
+            });
+        }
+    }
+    // The encoder's look-ahead comes first and is not part of the clip.
+    samples.drain(..(pre_skip * width).min(samples.len()));
+    if samples.is_empty() {
+        return Err(SoundboardAudioError::NotAudio(
+            "no decodable audio".to_string(),
+        ));
+    }
+    Ok((samples, OPUS_RATE, channels))
+}
+
+/// Biquad in direct form I.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// BS.1770 K-weighting (high shelf, then high pass) for 48 kHz audio.
+fn k_weighting() -> [Biquad; 2] {
+    [
+        Biquad::new(
+            [
+                1.535_124_859_586_97,
+                -2.691_696_189_406_38,
+                1.198_392_810_852_85,
+            ],
+            [-1.690_659_293_182_41, 0.732_480_774_215_85],
+        ),
+        Biquad::new(
+            [1.0, -2.0, 1.0],
+            [-1.990_047_454_833_98, 0.990_072_250_366_21],
+        ),
+    ]
+}
+
+fn block_loudness(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.max(f64::MIN_POSITIVE).log10()
+}
+
+/// Gated integrated loudness of interleaved 48 kHz audio, or `None` when
+/// every block is below the absolute gate. Clips shorter than one block are
+/// measured as a single block.
+pub(crate) fn integrated_loudness(samples: &[f32], channels: u32) -> Option<f64> {
+    let channels = channels.max(1) as usize;
+    let frames = samples.len() / channels;
+    if frames == 0 {
+        return None;
+    }
+    // Per-frame K-weighted power summed over channels (all weights are 1.0
+    // for mono and stereo).
+    let mut filters = (0..channels).map(|_| k_weighting()).collect::<Vec<_>>();
+    let power = samples
+        .chunks_exact(channels)
+        .map(|frame| {
+            frame
+                .iter()
+                .zip(filters.iter_mut())
+                .map(|(sample, [shelf, high_pass])| {
+                    let weighted = high_pass.process(shelf.process(f64::from(*sample)));
+                    weighted * weighted
+                })
+                .sum::<f64>()
+        })
+        .collect::<Vec<_>>();
+    let block = (u64::from(TARGET_RATE) * BLOCK_MS / 1000) as usize;
+    let step = (u64::from(TARGET_RATE) * BLOCK_STEP_MS / 1000) as usize;
+    let mut prefix = Vec::with_capacity(power.len() + 1);
+    prefix.push(0.0);
+    for value in &power {
+        prefix.push(prefix.last().copied().unwrap_or(0.0) + value);
+    }
+    let blocks = if frames < block {
+        vec![prefix[frames] / frames as f64]
+    } else {
+        (0..=(frames - block) / step)
+            .map(|index| {
+                let start = index * step;
+                (prefix[start + block] - prefix[start]) / block as f64
+            })
+            .collect()
+    };
+    let above_absolute = blocks
+        .into_iter()
+        .filter(|mean_square| block_loudness(*mean_square) > ABSOLUTE_GATE_LUFS)
+        .collect::<Vec<_>>();
+    if above_absolute.is_empty() {
+        return None;
+    }
+    let relative_gate =
+        block_loudness(above_absolute.iter().sum::<f64>() / above_absolute.len() as f64)
+            + RELATIVE_GATE_LU;
+    let gated = above_absolute
+        .into_iter()
+        .filter(|mean_square| block_loudness(*mean_square) > relative_gate)
+        .collect::<Vec<_>>();
+    (!gated.is_empty()).then(|| block_loudness(gated.iter().sum::<f64>() / gated.len() as f64))
+}
+
+fn peak_dbfs(samples: &[f32]) -> f64 {
+    let peak = samples
+        .iter()
+        .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+    20.0 * f64::from(peak).max(f64::MIN_POSITIVE).log10()
+}
+
+/// Gain that brings a clip to the target loudness without pushing its peak
+/// over the ceiling.
+pub(crate) fn normalization_gain_db(loudness_lufs: f64, peak_dbfs: f64) -> f64 {
+    (SOUNDBOARD_TARGET_LUFS - loudness_lufs)
+        .min(PEAK_CEILING_DBFS - peak_dbfs)
+        .min(MAX_GAIN_DB)
+}
+
+/// Normalize 48 kHz stereo samples and encode them as the stored clip.
+fn normalize_and_encode(mut samples: Vec<f32>) -> Result<ProcessedClip, SoundboardAudioError> {
+    let frames = samples.len() / TARGET_CHANNELS as usize;
+    if frames == 0 {
+        return Err(SoundboardAudioError::NotAudio(
+            "no decodable audio".to_string(),
+        ));
+    }
+    let loudness =
+        integrated_loudness(&samples, TARGET_CHANNELS).ok_or(SoundboardAudioError::Silent)?;
+    let gain_db = normalization_gain_db(loudness, peak_dbfs(&samples));
+    let gain = 10f64.powf(gain_db / 20.0) as f32;
+    for sample in &mut samples {
+        *sample *= gain;
+    }
+    let mut writer = FlacWriter::new(
+        Cursor::new(Vec::new()),
+        TARGET_RATE,
+        TARGET_CHANNELS,
+        &[("ENCODER", "Echo Chamber soundboard")],
+    )
+    .map_err(|error| SoundboardAudioError::Encode(error.to_string()))?;
+    writer
+        .write_samples(&samples)
+        .map_err(|error| SoundboardAudioError::Encode(error.to_string()))?;
+    let (flac, _) = writer
+        .finish()
+        .map_err(|error| SoundboardAudioError::Encode(error.to_string()))?;
+    Ok(ProcessedClip {
+        flac: flac.into_inner(),
+        duration_ms: frames as u64 * 1000 / u64::from(TARGET_RATE),
+        peak_dbfs: peak_dbfs(&samples),
+        loudness_lufs: loudness + gain_db,
+        gain_db,
+    })
+}
+
+/// Decode, validate, normalize, and transcode one upload. CPU-bound; call it
+/// from a blocking task.
+pub(crate) fn process_soundboard_clip(
+    bytes: Vec<u8>,
+    mime: Option<&str>,
+    max_duration_ms: u64,
+) -> Result<ProcessedClip, SoundboardAudioError> {
+    let (samples, rate, channels) = decode_clip(bytes, mime, max_duration_ms)?;
+    normalize_and_encode(convert_samples(
+        &samples,
+        rate,
+        channels,
+        TARGET_RATE,
+        TARGET_CHANNELS,
+    ))
+}
+
+/// Cut a stored clip to `start_ms..end_ms` and re-normalize the result. An
+/// `end_ms` past the end of the clip means "to the end". CPU-bound.
+pub(crate) fn trim_soundboard_clip(
+    bytes: Vec<u8>,
+    mime: Option<&str>,
+    start_ms: u64,
+    end_ms: u64,
+    max_duration_ms: u64,
+) -> Result<ProcessedClip, SoundboardAudioError> {
+    let (samples, rate, channels) = decode_clip(bytes, mime, MAX_TRIM_SOURCE_MS)?;
+    let samples = convert_samples(&samples, rate, channels, TARGET_RATE, TARGET_CHANNELS);
+    let frames = (samples.len() / TARGET_CHANNELS as usize) as u64;
+    let duration_ms = frames * 1000 / u64::from(TARGET_RATE);
+    let end_ms = end_ms.min(duration_ms);
+    if end_ms <= start_ms || end_ms - start_ms < MIN_TRIMMED_MS {
+        return Err(SoundboardAudioError::InvalidTrim);
+    }
+    if end_ms - start_ms > max_duration_ms {
+        return Err(SoundboardAudioError::TooLong {
+            max_ms: max_duration_ms,
+        });
+    }
+    let sample_index = |ms: u64| {
+        ((ms * u64::from(TARGET_RATE) / 1000).min(frames) * u64::from(TARGET_CHANNELS)) as usize
+    };
+    normalize_and_encode(samples[sample_index(start_ms)..sample_index(end_ms)].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(sample_rate: u32, channels: u16, samples: &[i16]) -> Vec<u8> {
+        let data_len = (samples.len() * 2) as u32;
+        let mut out = Vec::with_capacity(44 + data_len as usize);
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data_len).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&channels.to_le_bytes());
+        out.extend_from_slice(&sample_rate.to_le_bytes());
+        out.extend_from_slice(&(sample_rate * u32::from(channels) * 2).to_le_bytes());
+        out.extend_from_slice(&(channels * 2).to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            out.extend_from_slice(&sample.to_le_bytes());
+        }
+        out
+    }
+
+    fn sine(sample_rate: u32, seconds: f64, amplitude: f64) -> Vec<i16> {
+        let frames = (f64::from(sample_rate) * seconds) as usize;
+        (0..frames)
+            .map(|index| {
+                let phase = index as f64 * 1_000.0 * std::f64::consts::TAU / f64::from(sample_rate);
+                (phase.sin() * amplitude * 32_767.0) as i16
+            })
+            .collect()
+    }
+
+    /// A stereo 1 kHz sine encoded as 20 ms Opus packets, with its OpusHead.
+    fn opus_clip(seconds: f64, amplitude: f64) -> (Vec<Vec<u8>>, Vec<u8>) {
+        use audiopus::{coder::Encoder, Application};
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let pre_skip = encoder.lookahead().unwrap() as u16;
+        let samples = sine(OPUS_RATE, seconds, amplitude)
+            .iter()
+            .flat_map(|sample| {
+                let value = f32::from(*sample) / 32_767.0;
+                [value, value]
+            })
+            .collect::<Vec<_>>();
+        let mut output = [0_u8; 4_000];
+        let packets = samples
+            .chunks(960 * 2)
+            .map(|chunk| {
+                let mut frame = chunk.to_vec();
+                frame.resize(960 * 2, 0.0);
+                let len = encoder.encode_float(&frame, &mut output).unwrap();
+                output[..len].to_vec()
+            })
+            .collect();
+        let mut head = b"OpusHead".to_vec();
+        head.extend_from_slice(&[1, 2]);
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&OPUS_RATE.to_le_bytes());
+        head.extend_from_slice(&[0, 0, 0]);
+        (packets, head)
+    }
+
+    fn ogg_crc(data: &[u8]) -> u32 {
+        data.iter().fold(0_u32, |mut crc, byte| {
+            crc ^= u32::from(*byte) << 24;
+            for _ in 0..8 {
+                crc = if crc & 0x8000_0000 != 0 {
+                    (crc << 1) ^ 0x04c1_1db7
+                } else {
+                    crc << 1
+                };
+            }
+            crc
+        })
+    }
+
+    /// One packet per page, the way a minimal Ogg muxer would write it.
+    fn ogg_opus(packets: &[Vec<u8>], head: &[u8]) -> Vec<u8> {
+        let pre_skip = u64::from(u16::from_le_bytes([head[10], head[11]]));
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&4u32.to_le_bytes());
+        tags.extend_from_slice(b"test");
+        tags.extend_from_slice(&0u32.to_le_bytes());
+        let mut pages = vec![(0x02, 0, head.to_vec()), (0, 0, tags)];
+        for (index, packet) in packets.iter().enumerate() {
+            let flags = if index + 1 == packets.len() { 0x04 } else { 0 };
+            let granule = pre_skip + (index as u64 + 1) * 960;
+            pages.push((flags, granule, packet.clone()));
+        }
+        let mut out = Vec::new();
+        for (sequence, (flags, granule, packet)) in pages.into_iter().enumerate() {
+            let mut page = b"OggS".to_vec();
+            page.extend_from_slice(&[0, flags]);
+            page.extend_from_slice(&granule.to_le_bytes());
+            page.extend_from_slice(&7u32.to_le_bytes());
+            page.extend_from_slice(&(sequence as u32).to_le_bytes());
+            page.extend_from_slice(&0u32.to_le_bytes());
+            let mut lacing = vec![255_u8; packet.len() / 255];
+            lacing.push((packet.len() % 255) as u8);
+            page.push(lacing.len() as u8);
+            page.extend_from_slice(&lacing);
+            page.extend_from_slice(&packet);
+            let crc = ogg_crc(&page);
+            page[22..26].copy_from_slice(&crc.to_le_bytes());
+            out.extend_from_slice(&page);
+        }
+        out
+    }
+
+    fn ebml(id: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.push(0x01);
+        out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// Laid out like a browser MediaRecorder file: the Segment and Cluster
+    /// sizes are left unknown because the recorder streams them.
+    fn webm_opus(packets: &[Vec<u8>], head: &[u8]) -> Vec<u8> {
+        const UNKNOWN_SIZE: [u8; 8] = [0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let mut out = ebml(&[0x1a, 0x45, 0xdf, 0xa3], &ebml(&[0x42, 0x82], b"webm"));
+        out.extend_from_slice(&[0x18, 0x53, 0x80, 0x67]);
+        out.extend_from_slice(&UNKNOWN_SIZE);
+        out.extend(ebml(
+            &[0x15, 0x49, 0xa9, 0x66],
+            &ebml(&[0x2a, 0xd7, 0xb1], &1_000_000u32.to_be_bytes()),
+        ));
+        let mut audio = ebml(&[0xb5], &f64::from(OPUS_RATE).to_be_bytes());
+        audio.extend(ebml(&[0x9f], &[2]));
+        let mut entry = ebml(&[0xd7], &[1]);
+        entry.extend(ebml(&[0x73, 0xc5], &[1]));
+        entry.extend(ebml(&[0x83], &[2]));
+        entry.extend(ebml(&[0x86], b"A_OPUS"));
+        entry.extend(ebml(&[0x63, 0xa2], head));
+        entry.extend(ebml(&[0xe1], &audio));
+        out.extend(ebml(&[0x16, 0x54, 0xae, 0x6b], &ebml(&[0xae], &entry)));
+        out.extend_from_slice(&[0x1f, 0x43, 0xb6, 0x75]);
+        out.extend_from_slice(&UNKNOWN_SIZE);
+        out.extend(ebml(&[0xe7], &[0]));
+        for (index, packet) in packets.iter().enumerate() {
+            let mut block = vec![0x81];
+            block.extend_from_slice(&(index as i16 * 20).to_be_bytes());
+            block.push(0x80);
+            block.extend_from_slice(packet);
+            out.extend(ebml(&[0xa3], &block));
+        }
+        out
+    }
+
+    fn decode_flac(bytes: Vec<u8>) -> (Vec<f32>, u32, u32) {
+        decode_clip(bytes, Some(SOUNDBOARD_AUDIO_MIME), u64::MAX).unwrap()
+    }
+
+    #[test]
+    fn full_scale_stereo_sine_measures_near_its_reference_loudness() {
+        // BS.1770: a 0 dBFS 1 kHz sine reads -3.01 LUFS in one channel, so
+        // the same sine in both channels reads 0 LUFS.
+        let mono = sine(TARGET_RATE, 2.0, 1.0);
+        let stereo = mono
+            .iter()
+            .flat_map(|sample| {
+                let value = f32::from(*sample) / 32_767.0;
+                [value, value]
+            })
+            .collect::<Vec<_>>();
+        let loudness = integrated_loudness(&stereo, 2).unwrap();
+        assert!(loudness.abs() < 0.1, "loudness {loudness}");
+        assert_eq!(integrated_loudness(&vec![0.0; 96_000], 2), None);
+    }
+
+    #[test]
+    fn quiet_and_loud_uploads_are_normalized_to_the_same_loudness() {
+        let quiet = process_soundboard_clip(
+            wav(44_100, 1, &sine(44_100, 1.5, 0.05)),
+            Some("audio/wav"),
+            10_000,
+        )
+        .unwrap();
+        let loud = process_soundboard_clip(
+            wav(22_050, 2, &sine(22_050, 1.5, 0.7).repeat(2)),
+            None,
+            10_000,
+        )
+        .unwrap();
+        assert!(quiet.gain_db > 0.0 && loud.gain_db < 0.0);
+        for clip in [&quiet, &loud] {
+            assert!((clip.loudness_lufs - SOUNDBOARD_TARGET_LUFS).abs() < 0.5);
+            assert!(clip.peak_dbfs <= PEAK_CEILING_DBFS + 0.01);
+            let (samples, rate, channels) = decode_flac(clip.flac.clone());
+            assert_eq!((rate, channels), (TARGET_RATE, TARGET_CHANNELS));
+            let measured = integrated_loudness(&samples, channels).unwrap();
+            assert!((measured - SOUNDBOARD_TARGET_LUFS).abs() < 0.5);
+        }
+        assert!((quiet.duration_ms as i64 - 1_500).abs() <= 1);
+    }
+
+    #[test]
+    fn peak_ceiling_limits_the_boost_for_spiky_clips() {
+        // Mostly quiet with one full-scale click: reaching the target would
+        // clip, so the gain stops at the ceiling.
+        let mut samples = sine(TARGET_RATE, 1.0, 0.01);
+        samples[1_000] = i16::MAX;
+        let clip = process_soundboard_clip(wav(TARGET_RATE, 1, &samples), None, 10_000).unwrap();
+        assert!((clip.peak_dbfs - PEAK_CEILING_DBFS).abs() < 0.05);
+        assert!(clip.loudness_lufs < SOUNDBOARD_TARGET_LUFS);
+        assert_eq!(normalization_gain_db(-80.0, -60.0), MAX_GAIN_DB);
+    }
+
+    #[test]
+    fn trim_cuts_the_stored_clip_and_renormalizes_it() {
+        // One quiet second followed by one loud second.
+        let mut samples = sine(TARGET_RATE, 1.0, 0.1);
+        samples.extend(sine(TARGET_RATE, 1.0, 0.8));
+        let stored = process_soundboard_clip(wav(TARGET_RATE, 1, &samples), None, 10_000)
+            .unwrap()
+            .flac;
+        let trimmed = trim_soundboard_clip(
+            stored.clone(),
+            Some(SOUNDBOARD_AUDIO_MIME),
+            0,
+            1_000,
+            10_000,
+        )
+        .unwrap();
+        assert_eq!(trimmed.duration_ms, 1_000);
+        // The quiet half alone is boosted back up to the target.
+        assert!((trimmed.loudness_lufs - SOUNDBOARD_TARGET_LUFS).abs() < 0.5);
+        let tail = trim_soundboard_clip(stored.clone(), None, 1_500, u64::MAX, 10_000).unwrap();
+        assert_eq!(tail.duration_ms, 500);
+        assert_eq!(
+            trim_soundboard_clip(stored.clone(), None, 1_000, 1_050, 10_000).unwrap_err(),
+            SoundboardAudioError::InvalidTrim
+        );
+        assert_eq!(
+            trim_soundboard_clip(stored, None, 0, 2_000, 1_000).unwrap_err(),
+            SoundboardAudioError::TooLong { max_ms: 1_000 }
+        );
+    }
+
+    #[test]
+    fn ogg_and_webm_opus_uploads_are_decoded_and_normalized() {
+        let (packets, head) = opus_clip(1.0, 0.1);
+        for (bytes, mime) in [
+            (ogg_opus(&packets, &head), "audio/ogg"),
+            (webm_opus(&packets, &head), "audio/webm;codecs=opus"),
+        ] {
+            let clip = process_soundboard_clip(bytes, Some(mime), 10_000).unwrap();
+            assert!(clip.gain_db > 0.0, "{mime}");
+            assert!(
+                (clip.loudness_lufs - SOUNDBOARD_TARGET_LUFS).abs() < 0.5,
+                "{mime}: {}",
+                clip.loudness_lufs
+            );
+            // The encoder look-ahead is dropped from the front; the test
+            // muxer does not flush it, so the tail is short by as much.
+            assert!(
+                (985..=1_000).contains(&clip.duration_ms),
+                "{mime}: {}",
+                clip.duration_ms
+            );
+        }
+        assert_eq!(
+            process_soundboard_clip(ogg_opus(&packets, &head), Some("audio/ogg"), 500).unwrap_err(),
+            SoundboardAudioError::TooLong { max_ms: 500 }
+        );
+    }
+
+    #[test]
+    fn rejects_non_audio_overlong_and_silent_uploads() {
+        assert!(matches!(
+            process_soundboard_clip(
+                b"<html>not audio</html>".to_vec(),
+                Some("audio/mpeg"),
+                10_000
+            ),
+            Err(SoundboardAudioError::NotAudio(_))
+        ));
+        assert_eq!(
+            process_soundboard_clip(wav(8_000, 1, &sine(8_000, 3.0, 0.5)), None, 2_000)
+                .unwrap_err(),
+            SoundboardAudioError::TooLong { max_ms: 2_000 }
+        );
+        assert_eq!(
+            process_soundboard_clip(wav(8_000, 1, &vec![0; 8_000]), None, 2_000).unwrap_err(),
+            SoundboardAudioError::Silent
+        );
+    }
+}
@@ -0,0 +1,580 @@
+//! Soundboard packs: a room's soundboard, optionally with everyone's
+//! enter/exit chimes, as one uncompressed tar archive.
+//!
+//! The archive holds `manifest.json` plus the audio files it names. Import
+//! pushes every entry through the same path as a single upload
+//! (`store_soundboard_upload` / `store_chime`), so the size, duration,
+//! decoding, and room-cap checks apply per item and failures are reported
+//! per item rather than failing the whole pack.
+
+use crate::auth::*;
+use crate::chat::{chime_mime_from_ext, store_chime};
+use crate::config::*;
+use crate::soundboard::{
+    file_imported_sounds, ordered_room_sounds, persist_soundboard, soundboard_file_path,
+    soundboard_public, store_soundboard_upload, valid_soundboard_room, SoundboardPublic,
+    SoundboardUploadQuery,
+};
+use crate::AppState;
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Query, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs};
+use tracing::info;
+
+pub(crate) const BUNDLE_SCHEMA_VERSION: u32 = 1;
+/// Request body cap for `/api/soundboard/import` with an admin token.
+pub(crate) const MAX_BUNDLE_BYTES: usize = 200 * 1024 * 1024;
+/// Participants can only import sounds, never chimes, so their packs stay
+/// much smaller.
+pub(crate) const MAX_PARTICIPANT_BUNDLE_BYTES: usize = 50 * 1024 * 1024;
+const MAX_BUNDLE_ENTRIES: usize = 1_000;
+const MANIFEST_PATH: &str = "manifest.json";
+const TAR_BLOCK: usize = 512;
+
+// ── Manifest ─────────────────────────────────────────────────────────
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BundleManifest {
+    pub(crate) schema_version: u32,
+    #[serde(default)]
+    pub(crate) room_id: String,
+    #[serde(default)]
+    pub(crate) exported_at: u64,
+    #[serde(default)]
+    pub(crate) folders: Vec<String>,
+    /// In board order: unfiled sounds first, then each folder's.
+    #[serde(default)]
+    pub(crate) sounds: Vec<BundleSound>,
+    #[serde(default)]
+    pub(crate) chimes: Vec<BundleChime>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BundleSound {
+    pub(crate) file: String,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) icon: Option<String>,
+    #[serde(default)]
+    pub(crate) volume: Option<u16>,
+    #[serde(default)]
+    pub(crate) folder: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BundleChime {
+    pub(crate) file: String,
+    /// Identity base (the participant identity without its `-XXXX` suffix).
+    pub(crate) identity: String,
+    pub(crate) kind: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardExportQuery {
+    pub(crate) room_id: String,
+    #[serde(default)]
+    pub(crate) chimes: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SoundboardImportQuery {
+    pub(crate) room_id: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BundleImportFailure {
+    /// "sound", "chime", or "bundle".
+    pub(crate) item: &'static str,
+    pub(crate) name: String,
+    pub(crate) error: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BundleImportedChime {
+    pub(crate) identity: String,
+    pub(crate) kind: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BundleImportResponse {
+    pub(crate) ok: bool,
+    pub(crate) sounds: Vec<SoundboardPublic>,
+    pub(crate) chimes: Vec<BundleImportedChime>,
+    pub(crate) failures: Vec<BundleImportFailure>,
+}
+
+// ── Tar (ustar, regular files only) ──────────────────────────────────
+
+#[derive(Default)]
+pub(crate) struct TarWriter {
+    out: Vec<u8>,
+}
+
+impl TarWriter {
+    /// Append a regular file. Paths are generated by export and always fit
+    /// the 100-byte name field.
+    pub(crate) fn append(&mut self, path: &str, data: &[u8], mtime_secs: u64) {
+        let mut header = [0u8; TAR_BLOCK];
+        let name = path.as_bytes();
+        let name_len = name.len().min(100);
+        header[..name_len].copy_from_slice(&name[..name_len]);
+        write_octal(&mut header[100..108], 0o644);
+        write_octal(&mut header[108..116], 0);
+        write_octal(&mut header[116..124], 0);
+        write_octal(&mut header[124..136], data.len() as u64);
+        write_octal(&mut header[136..148], mtime_secs);
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        // The checksum is computed with its own field set to spaces.
+        header[148..156].fill(b' ');
+        let checksum: u64 = header.iter().map(|byte| u64::from(*byte)).sum();
+        write_octal(&mut header[148..155], checksum);
+        header[155] = b' ';
+        self.out.extend_from_slice(&header);
+        self.out.extend_from_slice(data);
+        self.out
+            .resize(self.out.len().next_multiple_of(TAR_BLOCK), 0);
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.out.extend_from_slice(&[0u8; TAR_BLOCK * 2]);
+        self.out
+    }
+}
+
+/// Zero-padded octal with a trailing NUL, filling `field`.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    let text = format!("{:0width$o}", value, width = digits);
+    field[..digits].copy_from_slice(&text.as_bytes()[text.len() - digits..]);
+    field[digits] = 0;
+}
+
+fn read_octal(field: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// Read the regular files of a tar archive into path → contents. Directories,
+/// links, and extended headers are skipped. Contents are slices of `bytes`, so
+/// a large pack is never held twice.
+pub(crate) fn read_tar(bytes: &Bytes) -> Result<HashMap<String, Bytes>, String> {
+    let mut files = HashMap::new();
+    let mut offset = 0;
+    while offset + TAR_BLOCK <= bytes.len() {
+        let header = &bytes[offset..offset + TAR_BLOCK];
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        }
+        let stored = read_octal(&header[148..156]).ok_or("Corrupt archive header")?;
+        let actual: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(index, byte)| {
+                if (148..156).contains(&index) {
+                    u64::from(b' ')
+                } else {
+                    u64::from(*byte)
+                }
+            })
+            .sum();
+        if stored != actual {
+            return Err("Corrupt archive header".into());
+        }
+        let size = read_octal(&header[124..136]).ok_or("Corrupt archive header")? as usize;
+        let data_start = offset + TAR_BLOCK;
+        let data_end = data_start
+            .checked_add(size)
+            .filter(|end| *end <= bytes.len())
+            .ok_or("Truncated archive")?;
+        if matches!(header[156], b'0' | 0) {
+            let field = |range: std::ops::Range<usize>| {
+                let raw = &header[range];
+                let len = raw.iter().position(|byte| *byte == 0).unwrap_or(raw.len());
+                String::from_utf8_lossy(&raw[..len]).into_owned()
+            };
+            let name = field(0..100);
+            let prefix = if &header[257..262] == b"ustar" {
+                field(345..500)
+            } else {
+                String::new()
+            };
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", prefix, name)
+            };
+            let path = path.trim_start_matches("./").to_string();
+            if files.len() >= MAX_BUNDLE_ENTRIES {
+                return Err(format!(
+                    "Archive has more than {} files",
+                    MAX_BUNDLE_ENTRIES
+                ));
+            }
+            files.insert(path, bytes.slice(data_start..data_end));
+        }
+        offset = data_start + size.next_multiple_of(TAR_BLOCK);
+    }
+    Ok(files)
+}
+
+fn file_extension(file_name: &str) -> &str {
+    std::path::Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("bin")
+}
+
+// ── Handlers ─────────────────────────────────────────────────────────
+
+/// Download a room's soundboard as `soundboard-{room}.tar`. With
+/// `chimes=true` every stored enter/exit chime is included too.
+pub(crate) async fn soundboard_export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SoundboardExportQuery>,
+) -> Result<Response, StatusCode> {
+    ensure_livekit(&state, &headers)?;
+    if !valid_soundboard_room(&query.room_id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let (sounds, folders) = {
+        let board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+        let sounds = ordered_room_sounds(&board, &query.room_id)
+            .into_iter()
+            .map(|sound| {
+                let path = soundboard_file_path(&board.dir, &sound.room_id, &sound.file_name);
+                (sound, path)
+            })
+            .collect::<Vec<_>>();
+        let folders = board
+            .folders
+            .get(&query.room_id)
+            .cloned()
+            .unwrap_or_default();
+        (sounds, folders)
+    };
+    let chimes = if query.chimes {
+        let chimes = state.chimes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut chimes = chimes
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.file_name.clone()))
+            .collect::<Vec<_>>();
+        chimes.sort();
+        chimes
+    } else {
+        Vec::new()
+    };
+
+    let now = now_ts_ms();
+    let mut tar = TarWriter::default();
+    let mut manifest = BundleManifest {
+        schema_version: BUNDLE_SCHEMA_VERSION,
+        room_id: query.room_id.clone(),
+        exported_at: now,
+        folders,
+        sounds: Vec::new(),
+        chimes: Vec::new(),
+    };
+    for (index, (sound, path)) in sounds.iter().enumerate() {
+        // Sounds whose file went missing are left out of the pack.
+        let Ok(bytes) = fs::read(path) else {
+            continue;
+        };
+        let file = format!("sounds/{:03}.{}", index, file_extension(&sound.file_name));
+        tar.append(&file, &bytes, now / 1000);
+        manifest.sounds.push(BundleSound {
+            file,
+            name: sound.name.clone(),
+            icon: Some(sound.icon.clone()),
+            volume: Some(sound.volume),
+            folder: sound.folder.clone(),
+        });
+    }
+    for (key, file_name) in chimes {
+        let Some((identity, kind)) = key.rsplit_once('-') else {
+            continue;
+        };
+        let Ok(bytes) = fs::read(state.chimes_dir.join(&file_name)) else {
+            continue;
+        };
+        let file = format!(
+            "chimes/{:03}.{}",
+            manifest.chimes.len(),
+            file_extension(&file_name)
+        );
+        tar.append(&file, &bytes, now / 1000);
+        manifest.chimes.push(BundleChime {
+            file,
+            identity: identity.to_string(),
+            kind: kind.to_string(),
+        });
+    }
+    let manifest_json =
+        serde_json::to_vec_pretty(&manifest).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tar.append(MANIFEST_PATH, &manifest_json, now / 1000);
+    info!(
+        "soundboard export: room={} sounds={} chimes={}",
+        query.room_id,
+        manifest.sounds.len(),
+        manifest.chimes.len()
+    );
+
+    let mut response = tar.finish().into_response();
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/x-tar"));
+    response
+        .headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    let safe_room = query
+        .room_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let disposition = format!("attachment; filename=\"soundboard-{}.tar\"", safe_room);
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        response.headers_mut().insert(CONTENT_DISPOSITION, value);
+    }
+    Ok(response)
+}
+
+fn bundle_failure(message: impl Into<String>) -> Json<BundleImportResponse> {
+    Json(BundleImportResponse {
+        ok: false,
+        sounds: Vec::new(),
+        chimes: Vec::new(),
+        failures: vec![BundleImportFailure {
+            item: "bundle",
+            name: String::new(),
+            error: message.into(),
+        }],
+    })
+}
+
+/// Import a pack into `roomId`. Participants can import sounds; chimes need
+/// an admin token, as with `chime_upload`. `ok` is true when the archive was
+/// readable, even if some items failed. The body is only read once the caller
+/// is authorized, up to `MAX_BUNDLE_BYTES` for admins and
+/// `MAX_PARTICIPANT_BUNDLE_BYTES` for everyone else.
+pub(crate) async fn soundboard_import(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SoundboardImportQuery>,
+    body: Body,
+) -> Result<Json<BundleImportResponse>, StatusCode> {
+    let admin = ensure_admin(&state, &headers).is_ok();
+    if !admin {
+        ensure_livekit(&state, &headers)?;
+    }
+    if !valid_soundboard_room(&query.room_id) {
+        return Ok(bundle_failure("Invalid roomId"));
+    }
+    let limit = if admin {
+        MAX_BUNDLE_BYTES
+    } else {
+        MAX_PARTICIPANT_BUNDLE_BYTES
+    };
+    let body = to_bytes(body, limit)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+    let mut files = match read_tar(&body) {
+        Ok(files) => files,
+        Err(error) => return Ok(bundle_failure(error)),
+    };
+    let Some(manifest) = files.remove(MANIFEST_PATH) else {
+        return Ok(bundle_failure("Archive has no manifest.json"));
+    };
+    let manifest: BundleManifest = match serde_json::from_slice(&manifest) {
+        Ok(manifest) => manifest,
+        Err(_) => return Ok(bundle_failure("manifest.json is not valid")),
+    };
+    if manifest.schema_version != BUNDLE_SCHEMA_VERSION {
+        return Ok(bundle_failure(format!(
+            "Unsupported bundle version {}",
+            manifest.schema_version
+        )));
+    }
+    // No room holds more than this, so a longer pack would only fail item by
+    // item after decoding each clip.
+    let max_sounds = state
+        .soundboard
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .max_sounds_per_room;
+    if manifest.sounds.len() > max_sounds {
+        return Ok(bundle_failure(format!(
+            "Pack has {} sounds; a room holds at most {}",
+            manifest.sounds.len(),
+            max_sounds
+        )));
+    }
+
+    let mut failures = Vec::new();
+    let mut imported = Vec::new();
+    let mut placements = Vec::new();
+    for entry in manifest.sounds {
+        let Some(bytes) = files.remove(&entry.file) else {
+            failures.push(BundleImportFailure {
+                item: "sound",
+                name: entry.name,
+                error: format!("{} is missing from the archive", entry.file),
+            });
+            continue;
+        };
+        let upload = SoundboardUploadQuery {
+            room_id: query.room_id.clone(),
+            name: Some(entry.name.clone()),
+            icon: entry.icon,
+            volume: entry.volume,
+        };
+        let mime_hint = Some(chime_mime_from_ext(&entry.file));
+        match store_soundboard_upload(&state, upload, mime_hint, bytes.to_vec()).await {
+            Ok(sound) => {
+                if let Some(folder) = entry.folder {
+                    placements.push((sound.id.clone(), folder));
+                }
+                imported.push(sound.id);
+            }
+            Err(error) => failures.push(BundleImportFailure {
+                item: "sound",
+                name: entry.name,
+                error,
+            }),
+        }
+    }
+    let sounds = {
+        let mut board = state.soundboard.lock().unwrap_or_else(|e| e.into_inner());
+        if !placements.is_empty() {
+            file_imported_sounds(&mut board, &query.room_id, &placements);
+            persist_soundboard(&board);
+        }
+        imported
+            .iter()
+            .filter_map(|id| board.index.get(id))
+            .map(soundboard_public)
+            .collect::<Vec<_>>()
+    };
+
+    let mut chimes = Vec::new();
+    for entry in manifest.chimes {
+        let name = format!("{} ({})", entry.identity, entry.kind);
+        if !admin {
+            failures.push(BundleImportFailure {
+                item: "chime",
+                name,
+                error: "Importing chimes requires an admin token".into(),
+            });
+            continue;
+        }
+        let Some(bytes) = files.remove(&entry.file) else {
+            failures.push(BundleImportFailure {
+                item: "chime",
+                name,
+                error: format!("{} is missing from the archive", entry.file),
+            });
+            continue;
+        };
+        let content_type = chime_mime_from_ext(&entry.file);
+        match store_chime(&state, &entry.identity, &entry.kind, &content_type, &bytes) {
+            Ok(_) => chimes.push(BundleImportedChime {
+                identity: entry.identity,
+                kind: entry.kind,
+            }),
+            Err(error) => failures.push(BundleImportFailure {
+                item: "chime",
+                name,
+                error,
+            }),
+        }
+    }
+    info!(
+        "soundboard import: room={} sounds={} chimes={} failures={}",
+        query.room_id,
+        sounds.len(),
+        chimes.len(),
+        failures.len()
+    );
+    Ok(Json(BundleImportResponse {
+        ok: true,
+        sounds,
+        chimes,
+        failures,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tar_round_trips_files_and_rejects_corruption() {
+        let mut tar = TarWriter::default();
+        tar.append("manifest.json", b"{}", 1_700_000_000);
+        tar.append("sounds/000.flac", &[7u8; 1_000], 1_700_000_000);
+        tar.append("empty", b"", 0);
+        let bytes = Bytes::from(tar.finish());
+        assert_eq!(bytes.len() % TAR_BLOCK, 0);
+
+        let files = read_tar(&bytes).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files["manifest.json"], &b"{}"[..]);
+        assert_eq!(files["sounds/000.flac"], vec![7u8; 1_000]);
+        assert!(files["empty"].is_empty());
+        // Entries borrow the request body instead of copying it.
+        let body = bytes.as_ptr_range();
+        assert!(body.contains(&files["sounds/000.flac"].as_ptr()));
+
+        let mut corrupt = bytes.to_vec();
+        corrupt[0] = b'x';
+        assert_eq!(
+            read_tar(&Bytes::from(corrupt)).unwrap_err(),
+            "Corrupt archive header"
+        );
+        assert_eq!(
+            // Cut inside the second file's data.
+            read_tar(&bytes.slice(..TAR_BLOCK * 3 + 100)).unwrap_err(),
+            "Truncated archive"
+        );
+    }
+
+    #[test]
+    fn manifest_defaults_optional_fields() {
+        let manifest: BundleManifest = serde_json::from_str(
+            r#"{"schemaVersion":1,"sounds":[{"file":"sounds/000.mp3","name":"Horn"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.sounds.len(), 1);
+        assert!(manifest.sounds[0].icon.is_none());
+        assert!(manifest.sounds[0].folder.is_none());
+        assert!(manifest.chimes.is_empty());
+        assert_eq!(chime_mime_from_ext(&manifest.sounds[0].file), "audio/mpeg");
+    }
+}
@@ -0,0 +1,256 @@
+//! Admission control for server-brokered soundboard plays.
+//!
+//! Every `/api/soundboard/play` request is checked against a room mute, a
+//! per-identity and per-room cooldown, and per-identity and per-room caps on
+//! clips still playing. A play counts as "playing" until its clip duration has
+//! elapsed. State is memory-only; a restart simply clears cooldowns and mutes.
+
+use std::collections::HashMap;
+
+/// Assumed length of clips stored before durations were recorded.
+pub(crate) const LEGACY_CLIP_MS: u64 = 5_000;
+/// Longest moderator mute, in minutes.
+pub(crate) const MAX_MUTE_MINUTES: u64 = 24 * 60;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SoundboardPlayLimits {
+    pub(crate) user_cooldown_ms: u64,
+    pub(crate) room_cooldown_ms: u64,
+    pub(crate) user_max_concurrent: usize,
+    pub(crate) room_max_concurrent: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PlayDenied {
+    Muted { until_ms: u64 },
+    UserCooldown { retry_after_ms: u64 },
+    RoomCooldown { retry_after_ms: u64 },
+    UserBusy { retry_after_ms: u64 },
+    RoomBusy { retry_after_ms: u64 },
+}
+
+impl PlayDenied {
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            PlayDenied::Muted { .. } => "muted",
+            PlayDenied::UserCooldown { .. } => "user_cooldown",
+            PlayDenied::RoomCooldown { .. } => "room_cooldown",
+            PlayDenied::UserBusy { .. } => "user_busy",
+            PlayDenied::RoomBusy { .. } => "room_busy",
+        }
+    }
+
+    pub(crate) fn message(&self) -> &'static str {
+        match self {
+            PlayDenied::Muted { .. } => "The soundboard is muted in this room",
+            PlayDenied::UserCooldown { .. } => "You are playing sounds too quickly",
+            PlayDenied::RoomCooldown { .. } => "Another sound just played",
+            PlayDenied::UserBusy { .. } => "Wait for your current sound to finish",
+            PlayDenied::RoomBusy { .. } => "Too many sounds are playing in this room",
+        }
+    }
+
+    pub(crate) fn retry_after_ms(&self, now_ms: u64) -> u64 {
+        match self {
+            PlayDenied::Muted { until_ms } => until_ms.saturating_sub(now_ms),
+            PlayDenied::UserCooldown { retry_after_ms }
+            | PlayDenied::RoomCooldown { retry_after_ms }
+            | PlayDenied::UserBusy { retry_after_ms }
+            | PlayDenied::RoomBusy { retry_after_ms } => *retry_after_ms,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ActivePlay {
+    identity: String,
+    ends_at_ms: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct RoomPlays {
+    last_play_ms: Option<u64>,
+    last_by_identity: HashMap<String, u64>,
+    active: Vec<ActivePlay>,
+    muted_until_ms: Option<u64>,
+}
+
+impl RoomPlays {
+    fn prune(&mut self, now_ms: u64, limits: &SoundboardPlayLimits) {
+        self.active.retain(|play| play.ends_at_ms > now_ms);
+        self.last_by_identity
+            .retain(|_, at| now_ms.saturating_sub(*at) < limits.user_cooldown_ms);
+        if self.muted_until_ms.is_some_and(|until| until <= now_ms) {
+            self.muted_until_ms = None;
+        }
+    }
+
+    fn is_idle(&self, now_ms: u64, limits: &SoundboardPlayLimits) -> bool {
+        self.active.is_empty()
+            && self.last_by_identity.is_empty()
+            && self.muted_until_ms.is_none()
+            && self
+                .last_play_ms
+                .is_none_or(|at| now_ms.saturating_sub(at) >= limits.room_cooldown_ms)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SoundboardPlays {
+    limits: SoundboardPlayLimits,
+    rooms: HashMap<String, RoomPlays>,
+}
+
+impl SoundboardPlays {
+    pub(crate) fn new(limits: SoundboardPlayLimits) -> Self {
+        Self {
+            limits,
+            rooms: HashMap::new(),
+        }
+    }
+
+    /// Admit a play of a `clip_ms`-long clip, recording it on success.
+    pub(crate) fn admit(
+        &mut self,
+        room_id: &str,
+        identity: &str,
+        clip_ms: u64,
+        now_ms: u64,
+    ) -> Result<(), PlayDenied> {
+        self.sweep(now_ms);
+        let limits = self.limits;
+        let room = self.rooms.entry(room_id.to_string()).or_default();
+        if let Some(until_ms) = room.muted_until_ms {
+            return Err(PlayDenied::Muted { until_ms });
+        }
+        if let Some(at) = room.last_by_identity.get(identity) {
+            return Err(PlayDenied::UserCooldown {
+                retry_after_ms: (at + limits.user_cooldown_ms).saturating_sub(now_ms),
+            });
+        }
+        if let Some(at) = room.last_play_ms {
+            let ready_at = at + limits.room_cooldown_ms;
+            if ready_at > now_ms {
+                return Err(PlayDenied::RoomCooldown {
+                    retry_after_ms: ready_at - now_ms,
+                });
+            }
+        }
+        // Retry once the earliest of the plays holding the slot finishes.
+        let first_end = |plays: &mut dyn Iterator<Item = &ActivePlay>| {
+            plays
+                .map(|play| play.ends_at_ms)
+                .min()
+                .unwrap_or(now_ms)
+                .saturating_sub(now_ms)
+        };
+        let mut own = room.active.iter().filter(|play| play.identity == identity);
+        if own.clone().count() >= limits.user_max_concurrent {
+            return Err(PlayDenied::UserBusy {
+                retry_after_ms: first_end(&mut own),
+            });
+        }
+        if room.active.len() >= limits.room_max_concurrent {
+            return Err(PlayDenied::RoomBusy {
+                retry_after_ms: first_end(&mut room.active.iter()),
+            });
+        }
+        room.last_play_ms = Some(now_ms);
+        room.last_by_identity.insert(identity.to_string(), now_ms);
+        room.active.push(ActivePlay {
+            identity: identity.to_string(),
+            ends_at_ms: now_ms + clip_ms.max(1),
+        });
+        Ok(())
+    }
+
+    /// Mute the room until `until_ms`, or lift the mute with `None`.
+    pub(crate) fn set_mute(&mut self, room_id: &str, until_ms: Option<u64>, now_ms: u64) {
+        self.rooms
+            .entry(room_id.to_string())
+            .or_default()
+            .muted_until_ms = until_ms.filter(|until| *until > now_ms);
+        self.sweep(now_ms);
+    }
+
+    pub(crate) fn muted_until(&self, room_id: &str, now_ms: u64) -> Option<u64> {
+        self.rooms
+            .get(room_id)
+            .and_then(|room| room.muted_until_ms)
+            .filter(|until| *until > now_ms)
+    }
+
+    /// Drop rooms with no cooldown, play, or mute left.
+    fn sweep(&mut self, now_ms: u64) {
+        let limits = self.limits;
+        self.rooms.retain(|_, room| {
+            room.prune(now_ms, &limits);
+            !room.is_idle(now_ms, &limits)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plays() -> SoundboardPlays {
+        SoundboardPlays::new(SoundboardPlayLimits {
+            user_cooldown_ms: 2_000,
+            room_cooldown_ms: 300,
+            user_max_concurrent: 1,
+            room_max_concurrent: 2,
+        })
+    }
+
+    #[test]
+    fn cooldowns_and_concurrency_caps_gate_plays() {
+        let mut plays = plays();
+        assert_eq!(plays.admit("room", "alice", 10_000, 0), Ok(()));
+        assert_eq!(
+            plays.admit("room", "bob", 1_000, 100),
+            Err(PlayDenied::RoomCooldown {
+                retry_after_ms: 200
+            })
+        );
+        assert_eq!(
+            plays.admit("room", "alice", 1_000, 1_000),
+            Err(PlayDenied::UserCooldown {
+                retry_after_ms: 1_000
+            })
+        );
+        // Off cooldown, but her first clip is still playing.
+        assert_eq!(
+            plays.admit("room", "alice", 1_000, 3_000),
+            Err(PlayDenied::UserBusy {
+                retry_after_ms: 7_000
+            })
+        );
+        assert_eq!(plays.admit("room", "bob", 8_000, 3_000), Ok(()));
+        assert_eq!(
+            plays.admit("room", "carol", 1_000, 4_000),
+            Err(PlayDenied::RoomBusy {
+                retry_after_ms: 6_000
+            })
+        );
+        // Other rooms are unaffected.
+        assert_eq!(plays.admit("other", "carol", 1_000, 4_000), Ok(()));
+        assert_eq!(plays.admit("room", "carol", 1_000, 10_000), Ok(()));
+    }
+
+    #[test]
+    fn mutes_expire_and_idle_rooms_are_swept() {
+        let mut plays = plays();
+        plays.set_mute("room", Some(5_000), 0);
+        assert_eq!(plays.muted_until("room", 1_000), Some(5_000));
+        let denied = plays.admit("room", "alice", 1_000, 1_000).unwrap_err();
+        assert_eq!(denied.code(), "muted");
+        assert_eq!(denied.retry_after_ms(1_000), 4_000);
+        assert_eq!(plays.admit("room", "alice", 1_000, 5_000), Ok(()));
+        plays.set_mute("room", Some(60_000), 5_500);
+        plays.set_mute("room", None, 5_600);
+        assert_eq!(plays.muted_until("room", 5_600), None);
+        plays.sweep(20_000);
+        assert!(plays.rooms.is_empty());
+    }
+}
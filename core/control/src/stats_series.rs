@@ -0,0 +1,943 @@
+//! Long-term time series of client quality stats.
+//!
+//! Every stats report is split into publisher rows (what an identity sends)
+//! and subscription rows (what a receiver sees from each publisher) and kept
+//! at three resolutions: raw samples, one-minute rollups, and one-hour
+//! rollups, each in daily `{tier}-v1-{day}.jsonl` files with its own
+//! retention. Rollups store count, sum, minimum, and maximum per metric so
+//! averages stay exact when buckets are merged. Cumulative receiver counters
+//! (lost packets, NACKs, PLIs, dropped frames) are turned into per-second
+//! rates between consecutive reports before they are stored.
+//!
+//! Minute and hour buckets stay in memory until they close. On startup, hour
+//! buckets are rebuilt from minute rows that have no hour row yet, so a
+//! restart loses at most the open minute.
+
+use crate::admin::ClientStats;
+use crate::auth::ensure_admin;
+use crate::config::now_ts_ms;
+use crate::jam_history::DAY_MS;
+use crate::AppState;
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tracing::warn;
+
+pub(crate) const RAW_RETENTION_DAYS: u64 = 2;
+pub(crate) const MINUTE_RETENTION_DAYS: u64 = 30;
+pub(crate) const HOUR_RETENTION_DAYS: u64 = 400;
+pub(crate) const MAX_RETENTION_DAYS: u64 = 3_650;
+const MINUTE_MS: u64 = 60 * 1_000;
+const HOUR_MS: u64 = 60 * MINUTE_MS;
+/// Longest range each resolution answers, so one query stays a few thousand
+/// points per series.
+const MAX_RAW_SPAN_MS: u64 = DAY_MS;
+const MAX_MINUTE_SPAN_MS: u64 = 7 * DAY_MS;
+const MAX_HOUR_SPAN_MS: u64 = 400 * DAY_MS;
+/// Automatic resolution picks raw up to this span, then minutes up to three
+/// days, then hours.
+const AUTO_RAW_SPAN_MS: u64 = 6 * HOUR_MS;
+const AUTO_MINUTE_SPAN_MS: u64 = 3 * DAY_MS;
+const MAX_INBOUND_ROWS: usize = 32;
+const MAX_LABEL_CHARS: usize = 128;
+/// Counter pairs further apart than this are not turned into a rate.
+const MAX_RATE_GAP_MS: u64 = 5 * MINUTE_MS;
+const PRUNE_INTERVAL_MS: u64 = HOUR_MS;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Resolution {
+    Raw,
+    Minute,
+    Hour,
+}
+
+impl Resolution {
+    fn file_prefix(self) -> &'static str {
+        match self {
+            Resolution::Raw => "raw",
+            Resolution::Minute => "1m",
+            Resolution::Hour => "1h",
+        }
+    }
+
+    fn max_span_ms(self) -> u64 {
+        match self {
+            Resolution::Raw => MAX_RAW_SPAN_MS,
+            Resolution::Minute => MAX_MINUTE_SPAN_MS,
+            Resolution::Hour => MAX_HOUR_SPAN_MS,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct StatsRetention {
+    pub(crate) raw_days: u64,
+    pub(crate) minute_days: u64,
+    pub(crate) hour_days: u64,
+}
+
+impl Default for StatsRetention {
+    fn default() -> Self {
+        Self {
+            raw_days: RAW_RETENTION_DAYS,
+            minute_days: MINUTE_RETENTION_DAYS,
+            hour_days: HOUR_RETENTION_DAYS,
+        }
+    }
+}
+
+impl StatsRetention {
+    fn days(&self, resolution: Resolution) -> u64 {
+        match resolution {
+            Resolution::Raw => self.raw_days,
+            Resolution::Minute => self.minute_days,
+            Resolution::Hour => self.hour_days,
+        }
+    }
+}
+
+/// One series: a publisher row when `from` is empty, otherwise what
+/// `identity` receives from `from`'s `source` track.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub(crate) struct SeriesKey {
+    pub(crate) room: String,
+    pub(crate) identity: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) source: Option<String>,
+}
+
+/// `[count, sum, min, max]`, serialized as an array to keep rows compact.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+struct Aggregate(u32, f64, f64, f64);
+
+impl Aggregate {
+    fn single(value: f64) -> Self {
+        Aggregate(1, value, value, value)
+    }
+
+    fn merge(&mut self, other: &Aggregate) {
+        self.0 = self.0.saturating_add(other.0);
+        self.1 += other.1;
+        self.2 = self.2.min(other.2);
+        self.3 = self.3.max(other.3);
+    }
+}
+
+type Metrics = BTreeMap<String, Aggregate>;
+
+fn merge_metrics(into: &mut Metrics, from: &Metrics) {
+    for (name, aggregate) in from {
+        into.entry(name.clone())
+            .and_modify(|existing| existing.merge(aggregate))
+            .or_insert(*aggregate);
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct SeriesRecord {
+    t: u64,
+    #[serde(flatten)]
+    key: SeriesKey,
+    m: Metrics,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct CounterSample {
+    t_ms: u64,
+    lost: Option<u64>,
+    nack: Option<u64>,
+    pli: Option<u64>,
+    dropped: Option<u64>,
+}
+
+#[derive(Default)]
+struct SeriesState {
+    counters: HashMap<SeriesKey, CounterSample>,
+    minute: BTreeMap<(u64, SeriesKey), Metrics>,
+    hour: BTreeMap<(u64, SeriesKey), Metrics>,
+    last_prune_ms: u64,
+}
+
+/// Which series a query covers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum SeriesScope {
+    /// Publisher rows of one identity.
+    Identity(String),
+    /// Publisher rows of every identity in a room.
+    Room(String),
+    /// Subscription rows, filtered by publisher and/or receiver.
+    Pair {
+        publisher: Option<String>,
+        receiver: Option<String>,
+        source: Option<String>,
+    },
+}
+
+impl SeriesScope {
+    fn matches(&self, key: &SeriesKey) -> bool {
+        match self {
+            SeriesScope::Identity(identity) => key.from.is_none() && key.identity == *identity,
+            SeriesScope::Room(room) => key.from.is_none() && key.room == *room,
+            SeriesScope::Pair {
+                publisher,
+                receiver,
+                source,
+            } => {
+                key.from.is_some()
+                    && publisher
+                        .as_ref()
+                        .is_none_or(|p| key.from.as_ref() == Some(p))
+                    && receiver.as_ref().is_none_or(|r| key.identity == *r)
+                    && source
+                        .as_ref()
+                        .is_none_or(|s| key.source.as_ref() == Some(s))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct MetricSummary {
+    pub(crate) avg: f64,
+    pub(crate) min: f64,
+    pub(crate) max: f64,
+    pub(crate) samples: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct SeriesPoint {
+    pub(crate) t_ms: u64,
+    pub(crate) metrics: BTreeMap<String, MetricSummary>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct SeriesLine {
+    #[serde(flatten)]
+    pub(crate) key: SeriesKey,
+    pub(crate) points: Vec<SeriesPoint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct SeriesQueryResult {
+    pub(crate) resolution: Resolution,
+    pub(crate) start_ms: u64,
+    pub(crate) end_ms: u64,
+    pub(crate) series: Vec<SeriesLine>,
+}
+
+pub(crate) struct StatsSeriesStore {
+    dir: PathBuf,
+    enabled: bool,
+    retention: StatsRetention,
+    inner: Mutex<SeriesState>,
+}
+
+impl StatsSeriesStore {
+    pub(crate) fn open(dir: PathBuf, retention: StatsRetention, now_ms: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let retention = StatsRetention {
+            raw_days: retention.raw_days.clamp(1, MAX_RETENTION_DAYS),
+            minute_days: retention.minute_days.clamp(1, MAX_RETENTION_DAYS),
+            hour_days: retention.hour_days.clamp(1, MAX_RETENTION_DAYS),
+        };
+        let store = Self {
+            dir,
+            enabled: true,
+            retention,
+            inner: Mutex::new(SeriesState::default()),
+        };
+        {
+            let mut state = store.inner.lock().unwrap_or_else(|e| e.into_inner());
+            store.recover_hours(&mut state, now_ms)?;
+            store.prune_locked(&mut state, now_ms)?;
+        }
+        Ok(store)
+    }
+
+    pub(crate) fn disabled(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            enabled: false,
+            retention: StatsRetention::default(),
+            inner: Mutex::new(SeriesState::default()),
+        }
+    }
+
+    /// Records one stats report. A disabled store ignores reports.
+    pub(crate) fn record(&self, stats: &ClientStats, now_ms: u64) -> io::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let mut state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let records = series_records(stats, now_ms, &mut state.counters);
+        if !records.is_empty() {
+            append_records(&self.dir, Resolution::Raw, &records)?;
+            for record in records {
+                let bucket = record.t - record.t % MINUTE_MS;
+                merge_metrics(
+                    state.minute.entry((bucket, record.key)).or_default(),
+                    &record.m,
+                );
+            }
+        }
+        self.roll_locked(&mut state, now_ms)
+    }
+
+    /// Writes closed buckets and, at most hourly, applies retention.
+    pub(crate) fn maintain(&self, now_ms: u64) -> io::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let mut state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        self.roll_locked(&mut state, now_ms)?;
+        state
+            .counters
+            .retain(|_, sample| now_ms.saturating_sub(sample.t_ms) <= MAX_RATE_GAP_MS);
+        if now_ms.saturating_sub(state.last_prune_ms) >= PRUNE_INTERVAL_MS {
+            self.prune_locked(&mut state, now_ms)?;
+        }
+        Ok(())
+    }
+
+    fn roll_locked(&self, state: &mut SeriesState, now_ms: u64) -> io::Result<()> {
+        let closed_minutes: Vec<(u64, SeriesKey)> = state
+            .minute
+            .keys()
+            .filter(|(t, _)| t + MINUTE_MS <= now_ms)
+            .cloned()
+            .collect();
+        if !closed_minutes.is_empty() {
+            let mut records = Vec::with_capacity(closed_minutes.len());
+            for slot in closed_minutes {
+                let metrics = state.minute.remove(&slot).unwrap_or_default();
+                let (t, key) = slot;
+                merge_metrics(
+                    state
+                        .hour
+                        .entry((t - t % HOUR_MS, key.clone()))
+                        .or_default(),
+                    &metrics,
+                );
+                records.push(SeriesRecord { t, key, m: metrics });
+            }
+            append_records(&self.dir, Resolution::Minute, &records)?;
+        }
+
+        let closed_hours: Vec<(u64, SeriesKey)> = state
+            .hour
+            .keys()
+            .filter(|(t, _)| t + HOUR_MS <= now_ms)
+            .cloned()
+            .collect();
+        if !closed_hours.is_empty() {
+            let records: Vec<SeriesRecord> = closed_hours
+                .into_iter()
+                .map(|slot| {
+                    let metrics = state.hour.remove(&slot).unwrap_or_default();
+                    SeriesRecord {
+                        t: slot.0,
+                        key: slot.1,
+                        m: metrics,
+                    }
+                })
+                .collect();
+            append_records(&self.dir, Resolution::Hour, &records)?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds hour buckets from minute rows written before a restart.
+    fn recover_hours(&self, state: &mut SeriesState, now_ms: u64) -> io::Result<()> {
+        let today = now_ms / DAY_MS;
+        let days = today.saturating_sub(1)..=today;
+        let mut written: HashSet<(u64, SeriesKey)> = HashSet::new();
+        for day in days.clone() {
+            read_records(&self.dir, Resolution::Hour, day, |record| {
+                written.insert((record.t, record.key));
+            })?;
+        }
+        for day in days {
+            read_records(&self.dir, Resolution::Minute, day, |record| {
+                let slot = (record.t - record.t % HOUR_MS, record.key);
+                if !written.contains(&slot) {
+                    merge_metrics(state.hour.entry(slot).or_default(), &record.m);
+                }
+            })?;
+        }
+        Ok(())
+    }
+
+    fn prune_locked(&self, state: &mut SeriesState, now_ms: u64) -> io::Result<()> {
+        state.last_prune_ms = now_ms;
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some((resolution, day)) = parse_series_file_name(&path) else {
+                continue;
+            };
+            let cutoff = now_ms.saturating_sub(self.retention.days(resolution) * DAY_MS);
+            if (day + 1) * DAY_MS <= cutoff {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn query(
+        &self,
+        scope: &SeriesScope,
+        start_ms: u64,
+        end_ms: u64,
+        resolution: Option<Resolution>,
+        now_ms: u64,
+    ) -> io::Result<SeriesQueryResult> {
+        if !self.enabled {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "client stats series storage is unavailable",
+            ));
+        }
+        if start_ms >= end_ms {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "start_ms must be before end_ms",
+            ));
+        }
+        let span = end_ms - start_ms;
+        let resolution = resolution.unwrap_or_else(|| self.auto_resolution(start_ms, span, now_ms));
+        if span > resolution.max_span_ms() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "range is too long for this resolution",
+            ));
+        }
+
+        let mut lines: BTreeMap<SeriesKey, BTreeMap<u64, Metrics>> = BTreeMap::new();
+        let mut add = |t: u64, key: &SeriesKey, metrics: &Metrics| {
+            if t >= start_ms && t < end_ms && scope.matches(key) {
+                merge_metrics(
+                    lines.entry(key.clone()).or_default().entry(t).or_default(),
+                    metrics,
+                );
+            }
+        };
+        // Rollup buckets that started before the range still overlap it.
+        let bucket_start = match resolution {
+            Resolution::Raw => start_ms,
+            Resolution::Minute => start_ms - start_ms % MINUTE_MS,
+            Resolution::Hour => start_ms - start_ms % HOUR_MS,
+        };
+        let state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        for day in bucket_start / DAY_MS..=(end_ms - 1) / DAY_MS {
+            read_records(&self.dir, resolution, day, |record| {
+                add(record.t.max(start_ms), &record.key, &record.m)
+            })?;
+        }
+        match resolution {
+            Resolution::Raw => {}
+            Resolution::Minute => {
+                for ((t, key), metrics) in &state.minute {
+                    add((*t).max(start_ms), key, metrics);
+                }
+            }
+            Resolution::Hour => {
+                for ((t, key), metrics) in &state.hour {
+                    add((*t).max(start_ms), key, metrics);
+                }
+                for ((t, key), metrics) in &state.minute {
+                    add((t - t % HOUR_MS).max(start_ms), key, metrics);
+                }
+            }
+        }
+        drop(state);
+
+        let series = lines
+            .into_iter()
+            .map(|(key, points)| SeriesLine {
+                key,
+                points: points
+                    .into_iter()
+                    .map(|(t_ms, metrics)| SeriesPoint {
+                        t_ms,
+                        metrics: metrics
+                            .into_iter()
+                            .map(|(name, Aggregate(count, sum, min, max))| {
+                                (
+                                    name,
+                                    MetricSummary {
+                                        avg: if count == 0 { 0.0 } else { sum / count as f64 },
+                                        min,
+                                        max,
+                                        samples: count,
+                                    },
+                                )
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(SeriesQueryResult {
+            resolution,
+            start_ms,
+            end_ms,
+            series,
+        })
+    }
+
+    fn auto_resolution(&self, start_ms: u64, span: u64, now_ms: u64) -> Resolution {
+        let covered = |resolution| {
+            start_ms >= now_ms.saturating_sub(self.retention.days(resolution) * DAY_MS)
+        };
+        if span <= AUTO_RAW_SPAN_MS && covered(Resolution::Raw) {
+            Resolution::Raw
+        } else if span <= AUTO_MINUTE_SPAN_MS && covered(Resolution::Minute) {
+            Resolution::Minute
+        } else {
+            Resolution::Hour
+        }
+    }
+}
+
+fn series_file(dir: &Path, resolution: Resolution, day: u64) -> PathBuf {
+    dir.join(format!("{}-v1-{}.jsonl", resolution.file_prefix(), day))
+}
+
+fn parse_series_file_name(path: &Path) -> Option<(Resolution, u64)> {
+    let name = path.file_name()?.to_str()?;
+    let stem = name.strip_suffix(".jsonl")?;
+    let (prefix, day) = stem.split_once("-v1-")?;
+    let resolution = [Resolution::Raw, Resolution::Minute, Resolution::Hour]
+        .into_iter()
+        .find(|resolution| resolution.file_prefix() == prefix)?;
+    Some((resolution, day.parse().ok()?))
+}
+
+fn append_records(dir: &Path, resolution: Resolution, records: &[SeriesRecord]) -> io::Result<()> {
+    let mut by_day: BTreeMap<u64, Vec<u8>> = BTreeMap::new();
+    for record in records {
+        let buffer = by_day.entry(record.t / DAY_MS).or_default();
+        serde_json::to_writer(&mut *buffer, record)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        buffer.push(b'\n');
+    }
+    for (day, bytes) in by_day {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(series_file(dir, resolution, day))?;
+        file.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+fn read_records(
+    dir: &Path,
+    resolution: Resolution,
+    day: u64,
+    mut visit: impl FnMut(SeriesRecord),
+) -> io::Result<()> {
+    let path = series_file(dir, resolution, day);
+    let file = match fs::File::open(&path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error),
+    };
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // A torn final line after a crash is skipped, not fatal.
+        if let Ok(record) = serde_json::from_str::<SeriesRecord>(&line) {
+            visit(record);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn label(value: &str) -> String {
+    value.chars().take(MAX_LABEL_CHARS).collect()
+}
+
+fn push_metric(metrics: &mut Metrics, name: &str, value: Option<f64>) {
+    if let Some(value) = value.filter(|value| value.is_finite() && *value >= 0.0) {
+        metrics.insert(name.to_string(), Aggregate::single(value));
+    }
+}
+
+/// Per-second rate between two cumulative counter readings; `None` across a
+/// counter reset or a gap too long to be meaningful.
+fn counter_rate(previous: Option<u64>, current: Option<u64>, elapsed_ms: u64) -> Option<f64> {
+    let (previous, current) = (previous?, current?);
+    if current < previous || !(1_000..=MAX_RATE_GAP_MS).contains(&elapsed_ms) {
+        return None;
+    }
+    Some((current - previous) as f64 * 1_000.0 / elapsed_ms as f64)
+}
+
+fn series_records(
+    stats: &ClientStats,
+    now_ms: u64,
+    counters: &mut HashMap<SeriesKey, CounterSample>,
+) -> Vec<SeriesRecord> {
+    let room = label(&stats.room);
+    let identity = label(&stats.identity);
+    if identity.is_empty() {
+        return Vec::new();
+    }
+    let mut records = Vec::new();
+
+    let mut publisher = Metrics::new();
+    push_metric(&mut publisher, "screen_fps", stats.screen_fps);
+    push_metric(
+        &mut publisher,
+        "screen_bitrate_kbps",
+        stats.screen_bitrate_kbps.map(f64::from),
+    );
+    push_metric(&mut publisher, "bwe_kbps", stats.bwe_kbps.map(f64::from));
+    push_metric(&mut publisher, "camera_fps", stats.camera_fps);
+    push_metric(
+        &mut publisher,
+        "camera_bitrate_kbps",
+        stats.camera_bitrate_kbps.map(f64::from),
+    );
+    // Averaged over a bucket these become the fraction of limited samples.
+    if let Some(limitation) = stats.quality_limitation.as_deref() {
+        push_metric(
+            &mut publisher,
+            "cpu_limited",
+            Some(if limitation == "cpu" { 1.0 } else { 0.0 }),
+        );
+        push_metric(
+            &mut publisher,
+            "bandwidth_limited",
+            Some(if limitation == "bandwidth" { 1.0 } else { 0.0 }),
+        );
+    }
+    if !publisher.is_empty() {
+        records.push(SeriesRecord {
+            t: now_ms,
+            key: SeriesKey {
+                room: room.clone(),
+                identity: identity.clone(),
+                from: None,
+                source: None,
+            },
+            m: publisher,
+        });
+    }
+
+    for row in stats.inbound.iter().flatten().take(MAX_INBOUND_ROWS) {
+        let from = label(&row.from);
+        if from.is_empty() {
+            continue;
+        }
+        let key = SeriesKey {
+            room: room.clone(),
+            identity: identity.clone(),
+            from: Some(from),
+            source: Some(label(&row.source)),
+        };
+        let current = CounterSample {
+            t_ms: now_ms,
+            lost: row.lost.map(u64::from),
+            nack: row.nack.map(u64::from),
+            pli: row.pli.map(u64::from),
+            dropped: row.dropped.map(u64::from),
+        };
+        let mut metrics = Metrics::new();
+        push_metric(&mut metrics, "fps", row.fps);
+        push_metric(
+            &mut metrics,
+            "bitrate_kbps",
+            row.bitrate_kbps.map(f64::from),
+        );
+        push_metric(&mut metrics, "jitter_ms", row.jitter_ms);
+        push_metric(&mut metrics, "presented_fps", row.presented_fps);
+        if let Some(previous) = counters.insert(key.clone(), current) {
+            let elapsed = now_ms.saturating_sub(previous.t_ms);
+            push_metric(
+                &mut metrics,
+                "loss_per_s",
+                counter_rate(previous.lost, current.lost, elapsed),
+            );
+            push_metric(
+                &mut metrics,
+                "nack_per_s",
+                counter_rate(previous.nack, current.nack, elapsed),
+            );
+            push_metric(
+                &mut metrics,
+                "pli_per_s",
+                counter_rate(previous.pli, current.pli, elapsed),
+            );
+            push_metric(
+                &mut metrics,
+                "dropped_per_s",
+                counter_rate(previous.dropped, current.dropped, elapsed),
+            );
+        }
+        if !metrics.is_empty() {
+            records.push(SeriesRecord {
+                t: now_ms,
+                key,
+                m: metrics,
+            });
+        }
+    }
+    records
+}
+
+#[derive(Deserialize)]
+pub(crate) struct StatsSeriesQuery {
+    #[serde(default)]
+    identity: Option<String>,
+    #[serde(default)]
+    room: Option<String>,
+    #[serde(default)]
+    publisher: Option<String>,
+    #[serde(default)]
+    receiver: Option<String>,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    start_ms: Option<u64>,
+    #[serde(default)]
+    end_ms: Option<u64>,
+    #[serde(default)]
+    resolution: Option<Resolution>,
+}
+
+/// Records a report from a request handler. `record` appends to disk, so it
+/// runs on the blocking pool; the report keeps the time it arrived.
+pub(crate) async fn record_stats_sample(state: &AppState, sample: ClientStats) {
+    let series = std::sync::Arc::clone(&state.stats_series);
+    let now_ms = now_ts_ms();
+    match tokio::task::spawn_blocking(move || series.record(&sample, now_ms)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(error)) => warn!("Failed to record client stats series: {}", error),
+        Err(error) => warn!("client stats series record task failed: {}", error),
+    }
+}
+
+/// GET /admin/api/stats/series — exactly one of `identity`, `room`, or
+/// `publisher`/`receiver` (optionally `source`); the range defaults to the
+/// last hour.
+pub(crate) async fn admin_stats_series(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<StatsSeriesQuery>,
+) -> Result<Json<SeriesQueryResult>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    let pair = query.publisher.is_some() || query.receiver.is_some();
+    let scope = match (query.identity, query.room, pair) {
+        (Some(identity), None, false) => SeriesScope::Identity(identity),
+        (None, Some(room), false) => SeriesScope::Room(room),
+        (None, None, true) => SeriesScope::Pair {
+            publisher: query.publisher,
+            receiver: query.receiver,
+            source: query.source,
+        },
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    let now_ms = now_ts_ms();
+    let end_ms = query.end_ms.unwrap_or(now_ms);
+    let start_ms = query.start_ms.unwrap_or(end_ms.saturating_sub(HOUR_MS));
+    let store = state.stats_series.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        store.query(&scope, start_ms, end_ms, query.resolution, now_ms)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    match result {
+        Ok(result) => Ok(Json(result)),
+        Err(error) if error.kind() == io::ErrorKind::InvalidInput => Err(StatusCode::BAD_REQUEST),
+        Err(error) if error.kind() == io::ErrorKind::NotConnected => {
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
+        Err(error) => {
+            warn!("client stats series query failed: {}", error);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::SubscriptionStats;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "echo-stats-series-{label}-{}",
+            rand::random::<u64>()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn publisher(identity: &str, fps: f64) -> ClientStats {
+        ClientStats {
+            identity: identity.to_string(),
+            room: "main".to_string(),
+            screen_fps: Some(fps),
+            quality_limitation: Some("cpu".to_string()),
+            ..ClientStats::default()
+        }
+    }
+
+    fn receiver(identity: &str, from: &str, fps: f64, nack: u32) -> ClientStats {
+        ClientStats {
+            identity: identity.to_string(),
+            room: "main".to_string(),
+            inbound: Some(vec![SubscriptionStats {
+                from: from.to_string(),
+                source: "screen".to_string(),
+                fps: Some(fps),
+                nack: Some(nack),
+                ..SubscriptionStats::default()
+            }]),
+            ..ClientStats::default()
+        }
+    }
+
+    #[test]
+    fn rolls_raw_samples_into_minutes_and_hours() {
+        let dir = temp_dir("rollup");
+        let base = 100 * DAY_MS;
+        let store = StatsSeriesStore::open(dir.clone(), StatsRetention::default(), base).unwrap();
+        store.record(&publisher("sam", 30.0), base).unwrap();
+        store
+            .record(&publisher("sam", 20.0), base + 10_000)
+            .unwrap();
+        store
+            .record(&publisher("sam", 60.0), base + MINUTE_MS)
+            .unwrap();
+
+        let identity = SeriesScope::Identity("sam".to_string());
+        let raw = store
+            .query(
+                &identity,
+                base,
+                base + HOUR_MS,
+                Some(Resolution::Raw),
+                base + MINUTE_MS,
+            )
+            .unwrap();
+        assert_eq!(raw.series.len(), 1);
+        assert_eq!(raw.series[0].points.len(), 3);
+
+        // The first minute closed when the third sample arrived; the open
+        // one is still answered from memory.
+        let minutes = store
+            .query(
+                &identity,
+                base,
+                base + HOUR_MS,
+                Some(Resolution::Minute),
+                base + MINUTE_MS,
+            )
+            .unwrap();
+        let points = &minutes.series[0].points;
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].metrics["screen_fps"].avg, 25.0);
+        assert_eq!(points[0].metrics["screen_fps"].samples, 2);
+        assert_eq!(points[0].metrics["cpu_limited"].avg, 1.0);
+        assert_eq!(points[1].metrics["screen_fps"].max, 60.0);
+
+        // After a restart the open hour is rebuilt from minute rows and
+        // written once it closes.
+        store.maintain(base + 2 * MINUTE_MS).unwrap();
+        drop(store);
+        let store =
+            StatsSeriesStore::open(dir.clone(), StatsRetention::default(), base + 2 * MINUTE_MS)
+                .unwrap();
+        store.maintain(base + HOUR_MS).unwrap();
+        let hours = store
+            .query(
+                &identity,
+                base,
+                base + DAY_MS,
+                Some(Resolution::Hour),
+                base + HOUR_MS,
+            )
+            .unwrap();
+        let fps = &hours.series[0].points[0].metrics["screen_fps"];
+        assert_eq!(fps.samples, 3);
+        assert!((fps.avg - 110.0 / 3.0).abs() < 1e-9);
+        assert_eq!(
+            parse_series_file_name(&series_file(&dir, Resolution::Hour, 100)),
+            Some((Resolution::Hour, 100))
+        );
+        assert!(series_file(&dir, Resolution::Hour, 100).exists());
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn pairs_carry_counter_rates_and_retention_drops_old_days() {
+        let dir = temp_dir("pairs");
+        let base = 100 * DAY_MS;
+        let store = StatsSeriesStore::open(dir.clone(), StatsRetention::default(), base).unwrap();
+        store
+            .record(&receiver("david", "sam", 4.0, 100), base)
+            .unwrap();
+        store
+            .record(&receiver("david", "sam", 5.0, 160), base + 20_000)
+            .unwrap();
+        store
+            .record(&receiver("decker", "sam", 7.0, 0), base + 20_000)
+            .unwrap();
+
+        let seen_from_sam = SeriesScope::Pair {
+            publisher: Some("sam".to_string()),
+            receiver: None,
+            source: None,
+        };
+        let result = store
+            .query(&seen_from_sam, base, base + MINUTE_MS, None, base + 30_000)
+            .unwrap();
+        assert_eq!(result.resolution, Resolution::Raw);
+        assert_eq!(result.series.len(), 2);
+        let david = &result.series[0];
+        assert_eq!(david.key.identity, "david");
+        assert_eq!(david.points[1].metrics["nack_per_s"].avg, 3.0);
+        assert!(!david.points[0].metrics.contains_key("nack_per_s"));
+        // Publisher rows never match a pair scope.
+        assert!(store
+            .query(
+                &SeriesScope::Identity("david".to_string()),
+                base,
+                base + MINUTE_MS,
+                None,
+                base + 30_000
+            )
+            .unwrap()
+            .series
+            .is_empty());
+
+        let later = base + (RAW_RETENTION_DAYS + 1) * DAY_MS;
+        store.maintain(later).unwrap();
+        assert!(!series_file(&dir, Resolution::Raw, 100).exists());
+        assert!(series_file(&dir, Resolution::Minute, 100).exists());
+        assert!(store
+            .query(&seen_from_sam, base, later, Some(Resolution::Raw), later)
+            .is_err());
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
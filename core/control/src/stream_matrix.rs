@@ -0,0 +1,596 @@
+//! Publisher × receiver stream quality matrix.
+//!
+//! Each cell is what one receiver sees of one publisher's screen or camera
+//! track, taken from the receiver's `ClientStats.inbound` rows. Loss, NACK,
+//! PLI, and dropped-frame figures are per-second rates read back from the
+//! client stats series, because the rows themselves only carry cumulative
+//! counters. A cell is flagged when it diverges from the median of the other
+//! receivers of the same track, which separates "David's path is bad" from
+//! "Sam's encoder is bad".
+//!
+//! The live matrix combines current stats with the last minute of rates.
+//! Past matrices are rebuilt from the series alone, so they follow its
+//! retention and carry no ICE candidate types.
+
+use crate::admin::ClientStats;
+use crate::auth::ensure_admin;
+use crate::config::{now_ts, now_ts_ms};
+use crate::stats_series::{label, Resolution, SeriesQueryResult, SeriesScope};
+use crate::AppState;
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io,
+};
+use tracing::warn;
+
+const MINUTE_MS: u64 = 60 * 1_000;
+/// Stats older than this no longer describe a live subscription.
+const LIVE_FRESH_SECS: u64 = 120;
+/// Rates in the live matrix are averaged over this trailing window.
+const LIVE_RATE_WINDOW_MS: u64 = MINUTE_MS;
+const HISTORY_WINDOW_MINUTES: u64 = 5;
+const MAX_HISTORY_WINDOW_MINUTES: u64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AnomalyKind {
+    LowFps,
+    HighLoss,
+    HighNack,
+    HighPli,
+    HighJitter,
+    /// The receiver is on a TURN relay while every other receiver of the
+    /// track has a direct path.
+    RelayPath,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct PairAnomaly {
+    pub(crate) kind: AnomalyKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) value: Option<f64>,
+    /// Median of the same metric across the other receivers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) peer_median: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub(crate) struct PairCell {
+    pub(crate) room: String,
+    pub(crate) publisher: String,
+    pub(crate) receiver: String,
+    pub(crate) source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) fps: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) presented_fps: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) bitrate_kbps: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) jitter_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) loss_per_s: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) nack_per_s: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pli_per_s: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) dropped_per_s: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) ice_local_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) ice_remote_type: Option<String>,
+    /// Seconds since the epoch of the receiver's report; live cells only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) updated_at: Option<u64>,
+    pub(crate) anomalies: Vec<PairAnomaly>,
+}
+
+impl PairCell {
+    fn on_relay(&self) -> bool {
+        self.ice_local_type.as_deref() == Some("relay")
+            || self.ice_remote_type.as_deref() == Some("relay")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct StreamMatrix {
+    pub(crate) live: bool,
+    pub(crate) start_ms: u64,
+    pub(crate) end_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) resolution: Option<Resolution>,
+    pub(crate) publishers: Vec<String>,
+    pub(crate) receivers: Vec<String>,
+    pub(crate) cells: Vec<PairCell>,
+    pub(crate) anomalies: usize,
+}
+
+type PairKey = (String, String, String, String);
+
+fn pair_key(room: &str, publisher: &str, receiver: &str, source: &str) -> PairKey {
+    (
+        label(room),
+        label(publisher),
+        label(receiver),
+        label(source),
+    )
+}
+
+/// One cell per subscription series, with each metric averaged over every
+/// sample in the range.
+pub(crate) fn cells_from_series(result: &SeriesQueryResult) -> Vec<PairCell> {
+    result
+        .series
+        .iter()
+        .filter_map(|line| {
+            let publisher = line.key.from.clone()?;
+            let mut totals: BTreeMap<&str, (f64, u64)> = BTreeMap::new();
+            for point in &line.points {
+                for (name, summary) in &point.metrics {
+                    let total = totals.entry(name.as_str()).or_default();
+                    total.0 += summary.avg * f64::from(summary.samples);
+                    total.1 += u64::from(summary.samples);
+                }
+            }
+            let metric = |name: &str| {
+                totals
+                    .get(name)
+                    .filter(|(_, samples)| *samples > 0)
+                    .map(|(sum, samples)| sum / *samples as f64)
+            };
+            Some(PairCell {
+                room: line.key.room.clone(),
+                publisher,
+                receiver: line.key.identity.clone(),
+                source: line.key.source.clone().unwrap_or_default(),
+                fps: metric("fps"),
+                presented_fps: metric("presented_fps"),
+                bitrate_kbps: metric("bitrate_kbps"),
+                jitter_ms: metric("jitter_ms"),
+                loss_per_s: metric("loss_per_s"),
+                nack_per_s: metric("nack_per_s"),
+                pli_per_s: metric("pli_per_s"),
+                dropped_per_s: metric("dropped_per_s"),
+                ..PairCell::default()
+            })
+        })
+        .collect()
+}
+
+/// Cells from current receiver reports, with rates taken from `recent`
+/// (cells built from the trailing series window).
+pub(crate) fn live_cells(
+    stats: &[ClientStats],
+    recent: &[PairCell],
+    room: Option<&str>,
+    now_secs: u64,
+) -> Vec<PairCell> {
+    let rates: HashMap<PairKey, &PairCell> = recent
+        .iter()
+        .map(|cell| {
+            (
+                pair_key(&cell.room, &cell.publisher, &cell.receiver, &cell.source),
+                cell,
+            )
+        })
+        .collect();
+    let mut cells = Vec::new();
+    for receiver in stats {
+        if now_secs.saturating_sub(receiver.updated_at) > LIVE_FRESH_SECS
+            || room.is_some_and(|room| receiver.room != room)
+        {
+            continue;
+        }
+        for row in receiver.inbound.iter().flatten() {
+            if row.from.is_empty() {
+                continue;
+            }
+            let recent = rates.get(&pair_key(
+                &receiver.room,
+                &row.from,
+                &receiver.identity,
+                &row.source,
+            ));
+            cells.push(PairCell {
+                room: receiver.room.clone(),
+                publisher: row.from.clone(),
+                receiver: receiver.identity.clone(),
+                source: row.source.clone(),
+                fps: row.fps,
+                presented_fps: row.presented_fps,
+                bitrate_kbps: row.bitrate_kbps.map(f64::from),
+                jitter_ms: row.jitter_ms,
+                loss_per_s: recent.and_then(|cell| cell.loss_per_s),
+                nack_per_s: recent.and_then(|cell| cell.nack_per_s),
+                pli_per_s: recent.and_then(|cell| cell.pli_per_s),
+                dropped_per_s: recent.and_then(|cell| cell.dropped_per_s),
+                ice_local_type: row
+                    .ice_local_type
+                    .clone()
+                    .or_else(|| receiver.ice_local_type.clone()),
+                ice_remote_type: row
+                    .ice_remote_type
+                    .clone()
+                    .or_else(|| receiver.ice_remote_type.clone()),
+                updated_at: Some(receiver.updated_at),
+                anomalies: Vec::new(),
+            });
+        }
+    }
+    cells
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// How far a receiver must drift from its peers before it is flagged: the
+/// value must pass both the ratio and the absolute margin, so idle streams
+/// and small jitter do not raise flags.
+struct Divergence {
+    kind: AnomalyKind,
+    metric: fn(&PairCell) -> Option<f64>,
+    /// `true` when a low value is the problem (frame rate).
+    below: bool,
+    ratio: f64,
+    margin: f64,
+}
+
+const DIVERGENCES: [Divergence; 5] = [
+    Divergence {
+        kind: AnomalyKind::LowFps,
+        metric: |cell| cell.fps,
+        below: true,
+        ratio: 0.6,
+        margin: 5.0,
+    },
+    Divergence {
+        kind: AnomalyKind::HighLoss,
+        metric: |cell| cell.loss_per_s,
+        below: false,
+        ratio: 3.0,
+        margin: 2.0,
+    },
+    Divergence {
+        kind: AnomalyKind::HighNack,
+        metric: |cell| cell.nack_per_s,
+        below: false,
+        ratio: 3.0,
+        margin: 5.0,
+    },
+    Divergence {
+        kind: AnomalyKind::HighPli,
+        metric: |cell| cell.pli_per_s,
+        below: false,
+        ratio: 3.0,
+        margin: 0.5,
+    },
+    Divergence {
+        kind: AnomalyKind::HighJitter,
+        metric: |cell| cell.jitter_ms,
+        below: false,
+        ratio: 2.0,
+        margin: 20.0,
+    },
+];
+
+impl Divergence {
+    fn flag(&self, value: f64, peers: f64) -> bool {
+        if self.below {
+            value < peers * self.ratio && peers - value >= self.margin
+        } else {
+            value > peers * self.ratio && value - peers >= self.margin
+        }
+    }
+}
+
+/// Flags every cell against the other receivers of the same publisher track.
+pub(crate) fn flag_anomalies(cells: &mut [PairCell]) {
+    let mut tracks: HashMap<(String, String, String), Vec<usize>> = HashMap::new();
+    for (index, cell) in cells.iter().enumerate() {
+        tracks
+            .entry((
+                cell.room.clone(),
+                cell.publisher.clone(),
+                cell.source.clone(),
+            ))
+            .or_default()
+            .push(index);
+    }
+    for members in tracks.values() {
+        if members.len() < 2 {
+            continue;
+        }
+        let mut flags: Vec<(usize, PairAnomaly)> = Vec::new();
+        for &index in members {
+            let peers = members.iter().filter(|&&other| other != index);
+            for divergence in &DIVERGENCES {
+                let Some(value) = (divergence.metric)(&cells[index]) else {
+                    continue;
+                };
+                let Some(peer_median) = median(
+                    peers
+                        .clone()
+                        .filter_map(|&other| (divergence.metric)(&cells[other]))
+                        .collect(),
+                ) else {
+                    continue;
+                };
+                if divergence.flag(value, peer_median) {
+                    flags.push((
+                        index,
+                        PairAnomaly {
+                            kind: divergence.kind,
+                            value: Some(value),
+                            peer_median: Some(peer_median),
+                        },
+                    ));
+                }
+            }
+            let peers_known = peers
+                .clone()
+                .filter(|&&other| cells[other].ice_local_type.is_some())
+                .count();
+            if cells[index].on_relay()
+                && peers_known > 0
+                && peers.clone().all(|&other| !cells[other].on_relay())
+            {
+                flags.push((
+                    index,
+                    PairAnomaly {
+                        kind: AnomalyKind::RelayPath,
+                        value: None,
+                        peer_median: None,
+                    },
+                ));
+            }
+        }
+        for (index, anomaly) in flags {
+            cells[index].anomalies.push(anomaly);
+        }
+    }
+}
+
+fn build_matrix(
+    mut cells: Vec<PairCell>,
+    live: bool,
+    start_ms: u64,
+    end_ms: u64,
+    resolution: Option<Resolution>,
+) -> StreamMatrix {
+    flag_anomalies(&mut cells);
+    cells.sort_by(|a, b| {
+        (&a.room, &a.publisher, &a.source, &a.receiver).cmp(&(
+            &b.room,
+            &b.publisher,
+            &b.source,
+            &b.receiver,
+        ))
+    });
+    let publishers: BTreeSet<String> = cells.iter().map(|cell| cell.publisher.clone()).collect();
+    let receivers: BTreeSet<String> = cells.iter().map(|cell| cell.receiver.clone()).collect();
+    let anomalies = cells.iter().map(|cell| cell.anomalies.len()).sum();
+    StreamMatrix {
+        live,
+        start_ms,
+        end_ms,
+        resolution,
+        publishers: publishers.into_iter().collect(),
+        receivers: receivers.into_iter().collect(),
+        cells,
+        anomalies,
+    }
+}
+
+fn in_room(mut cells: Vec<PairCell>, room: Option<&str>) -> Vec<PairCell> {
+    if let Some(room) = room {
+        let room = label(room);
+        cells.retain(|cell| cell.room == room);
+    }
+    cells
+}
+
+#[derive(Deserialize)]
+pub(crate) struct StreamMatrixQuery {
+    #[serde(default)]
+    room: Option<String>,
+    /// End of a past window; omitted for the live matrix.
+    #[serde(default)]
+    at_ms: Option<u64>,
+    #[serde(default)]
+    window_minutes: Option<u64>,
+}
+
+/// GET /admin/api/stats/matrix — live, or rebuilt for the `window_minutes`
+/// ending at `at_ms` from the client stats series.
+pub(crate) async fn admin_stream_matrix(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<StreamMatrixQuery>,
+) -> Result<Json<StreamMatrix>, StatusCode> {
+    ensure_admin(&state, &headers)?;
+    let now_ms = now_ts_ms();
+    let room = query.room.filter(|room| !room.is_empty());
+    let all_pairs = SeriesScope::Pair {
+        publisher: None,
+        receiver: None,
+        source: None,
+    };
+    let series = state.stats_series.clone();
+
+    let Some(at_ms) = query.at_ms else {
+        let start_ms = now_ms.saturating_sub(LIVE_RATE_WINDOW_MS);
+        let recent = tokio::task::spawn_blocking(move || {
+            series.query(&all_pairs, start_ms, now_ms, Some(Resolution::Raw), now_ms)
+        })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // Without the series the live matrix still has fps, jitter, and ICE
+        // types; only the counter rates are missing.
+        let recent = match recent {
+            Ok(result) => cells_from_series(&result),
+            Err(error) if error.kind() == io::ErrorKind::NotConnected => Vec::new(),
+            Err(error) => {
+                warn!("stream matrix rate lookup failed: {}", error);
+                Vec::new()
+            }
+        };
+        let stats: Vec<ClientStats> = state
+            .client_stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        let cells = live_cells(&stats, &recent, room.as_deref(), now_ts());
+        return Ok(Json(build_matrix(cells, true, start_ms, now_ms, None)));
+    };
+
+    let window_minutes = query
+        .window_minutes
+        .unwrap_or(HISTORY_WINDOW_MINUTES)
+        .clamp(1, MAX_HISTORY_WINDOW_MINUTES);
+    let end_ms = at_ms.min(now_ms);
+    let start_ms = end_ms.saturating_sub(window_minutes * MINUTE_MS);
+    if start_ms >= end_ms {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let result = tokio::task::spawn_blocking(move || {
+        series.query(&all_pairs, start_ms, end_ms, None, now_ms)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    match result {
+        Ok(result) => {
+            let cells = in_room(cells_from_series(&result), room.as_deref());
+            Ok(Json(build_matrix(
+                cells,
+                false,
+                start_ms,
+                end_ms,
+                Some(result.resolution),
+            )))
+        }
+        Err(error) if error.kind() == io::ErrorKind::InvalidInput => Err(StatusCode::BAD_REQUEST),
+        Err(error) if error.kind() == io::ErrorKind::NotConnected => {
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
+        Err(error) => {
+            warn!("stream matrix history query failed: {}", error);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::SubscriptionStats;
+
+    fn receiver(identity: &str, rows: Vec<SubscriptionStats>, ice: &str) -> ClientStats {
+        ClientStats {
+            identity: identity.to_string(),
+            room: "main".to_string(),
+            updated_at: 1_000,
+            ice_local_type: Some(ice.to_string()),
+            inbound: Some(rows),
+            ..ClientStats::default()
+        }
+    }
+
+    fn row(from: &str, fps: f64, jitter_ms: f64) -> SubscriptionStats {
+        SubscriptionStats {
+            from: from.to_string(),
+            source: "screen".to_string(),
+            fps: Some(fps),
+            jitter_ms: Some(jitter_ms),
+            ..SubscriptionStats::default()
+        }
+    }
+
+    #[test]
+    fn flags_the_receiver_that_diverges_from_its_peers() {
+        let stats = vec![
+            receiver("sam", vec![row("decker", 30.0, 5.0)], "host"),
+            receiver("david", vec![row("sam", 4.0, 60.0)], "relay"),
+            receiver("decker", vec![row("sam", 29.0, 6.0)], "srflx"),
+            receiver("ellie", vec![row("sam", 30.0, 4.0)], "host"),
+            ClientStats {
+                updated_at: 500,
+                ..receiver("stale", vec![row("sam", 1.0, 500.0)], "relay")
+            },
+        ];
+        let recent = vec![PairCell {
+            room: "main".to_string(),
+            publisher: "sam".to_string(),
+            receiver: "david".to_string(),
+            source: "screen".to_string(),
+            nack_per_s: Some(40.0),
+            ..PairCell::default()
+        }];
+        let matrix = build_matrix(
+            live_cells(&stats, &recent, Some("main"), 1_010),
+            true,
+            0,
+            1,
+            None,
+        );
+
+        assert_eq!(matrix.publishers, vec!["decker", "sam"]);
+        assert_eq!(matrix.receivers, vec!["david", "decker", "ellie", "sam"]);
+        let david = matrix
+            .cells
+            .iter()
+            .find(|cell| cell.receiver == "david")
+            .unwrap();
+        let kinds: Vec<AnomalyKind> = david.anomalies.iter().map(|a| a.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AnomalyKind::LowFps,
+                AnomalyKind::HighJitter,
+                AnomalyKind::RelayPath
+            ]
+        );
+        assert_eq!(david.anomalies[0].peer_median, Some(29.5));
+        // A rate only David has cannot be compared against anyone.
+        assert_eq!(david.nack_per_s, Some(40.0));
+        assert_eq!(matrix.anomalies, 3);
+        assert!(matrix
+            .cells
+            .iter()
+            .filter(|cell| cell.receiver != "david")
+            .all(|cell| cell.anomalies.is_empty()));
+    }
+
+    #[test]
+    fn single_receiver_tracks_are_never_flagged() {
+        let mut cells = vec![PairCell {
+            publisher: "sam".to_string(),
+            receiver: "david".to_string(),
+            fps: Some(1.0),
+            ice_local_type: Some("relay".to_string()),
+            ..PairCell::default()
+        }];
+        flag_anomalies(&mut cells);
+        assert!(cells[0].anomalies.is_empty());
+        assert_eq!(median(vec![3.0, 1.0, 2.0, 10.0]), Some(2.5));
+    }
+}
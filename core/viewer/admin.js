@@ -16,7 +16,71 @@ var bugReportScreenshotBtn = document.getElementById("bug-report-screenshot-btn"
 var bugReportFileName = document.getElementById("bug-report-file-name");
 var bugReportPreview = document.getElementById("bug-report-screenshot-preview");
 var bugReportTitle = document.getElementById("bug-report-title");
-var _bugReportScreenshotUrl = null;
+var bugReportHistoryEl = document.getElementById("bug-report-history");
+// Held until the report exists, then uploaded as one of its attachments.
+var _bugReportScreenshotFile = null;
+// Reports this client filed, newest first: { id, token }. The status token is
+// only ever returned once, so it is kept here to read the report back.
+var BUG_REPORTS_KEY = "echo-bug-reports";
+var MAX_KEPT_BUG_REPORTS = 5;
+
+function keptBugReports() {
+  try {
+    var list = JSON.parse(echoGet(BUG_REPORTS_KEY) || "[]");
+    return Array.isArray(list) ? list.filter(function(r) { return r && r.id && r.token; }) : [];
+  } catch (e) {
+    return [];
+  }
+}
+
+function keepBugReport(id, token) {
+  var list = keptBugReports().filter(function(r) { return r.id !== id; });
+  list.unshift({ id: id, token: token });
+  echoSet(BUG_REPORTS_KEY, JSON.stringify(list.slice(0, MAX_KEPT_BUG_REPORTS)));
+}
+
+var BUG_STATUS_LABELS = { new: "Received", triaged: "Being looked at", fixed: "Fixed", wontfix: "Won't fix" };
+
+async function loadBugReportHistory() {
+  if (!bugReportHistoryEl) return;
+  var reports = keptBugReports();
+  if (!reports.length || !adminToken) {
+    bugReportHistoryEl.innerHTML = "";
+    bugReportHistoryEl.classList.add("hidden");
+    return;
+  }
+  var rows = await Promise.all(reports.map(async function(report) {
+    try {
+      var res = await fetch(apiUrl("/api/bug-report/" + encodeURIComponent(report.id)), {
+        headers: { Authorization: "Bearer " + adminToken, "x-echo-bug-token": report.token },
+      });
+      if (!res.ok) return null;
+      return await res.json();
+    } catch (e) {
+      return null;
+    }
+  }));
+  var html = "";
+  rows.forEach(function(row) {
+    if (!row) return;
+    html += '<div class="bug-report-history-item"><span class="bug-report-history-id">#' +
+      escAdm(String(row.id)) + '</span> ' + escAdm(BUG_STATUS_LABELS[row.status] || row.status) + '</div>';
+    (row.comments || []).forEach(function(comment) {
+      html += '<div class="bug-report-history-comment">' + escAdm(comment.body) + '</div>';
+    });
+  });
+  bugReportHistoryEl.innerHTML = html ? '<div class="bug-report-history-title">Your reports</div>' + html : "";
+  bugReportHistoryEl.classList.toggle("hidden", !html);
+}
+
+async function uploadBugReportScreenshot(id, token, file) {
+  var res = await fetch(apiUrl("/api/bug-report/" + encodeURIComponent(id) + "/attachments?name=" + encodeURIComponent(file.name || "screenshot.png")), {
+    method: "POST",
+    headers: { Authorization: "Bearer " + adminToken, "x-echo-bug-token": token },
+    body: await file.arrayBuffer(),
+  });
+  return res.ok;
+}
 
 function openBugReport() {
   if (!bugReportModal) return;
@@ -25,7 +89,7 @@ function openBugReport() {
   if (bugReportDesc) bugReportDesc.value = "";
   if (bugReportStatusEl) bugReportStatusEl.textContent = "";
   // Reset screenshot state
-  _bugReportScreenshotUrl = null;
+  _bugReportScreenshotFile = null;
   if (bugReportFileInput) bugReportFileInput.value = "";
   if (bugReportFileName) bugReportFileName.textContent = "";
   if (bugReportPreview) { bugReportPreview.innerHTML = ""; bugReportPreview.classList.add("hidden"); }
@@ -43,6 +107,7 @@ function openBugReport() {
     }
   }
   if (bugReportDesc) bugReportDesc.focus();
+  loadBugReportHistory();
 }
 
 function closeBugReportModal() {
@@ -77,9 +142,9 @@ async function sendBugReport() {
     connection_state: room?.state || "",
   };
   if (titleText) payload.title = titleText;
-  if (_bugReportScreenshotUrl) {
-    payload.screenshot_url = _bugReportScreenshotUrl;
-  }
+  // Links the report to this browser's diagnostics uploads, when enabled.
+  var installId = window.EchoWebDiagnosticsRuntime?.installId?.();
+  if (installId) payload.install_id = installId;
   if (_latestScreenStats) {
     Object.assign(payload, _latestScreenStats);
   }
@@ -92,9 +157,20 @@ async function sendBugReport() {
       body: JSON.stringify(payload),
     });
     if (res.ok) {
-      if (bugReportStatusEl) bugReportStatusEl.textContent = "Feedback sent! Thank you.";
+      var data = await res.json().catch(function() { return {}; });
+      var sent = "Feedback sent! Thank you.";
+      if (data.id && data.status_token) {
+        keepBugReport(data.id, data.status_token);
+        if (_bugReportScreenshotFile) {
+          if (bugReportStatusEl) bugReportStatusEl.textContent = "Uploading screenshot...";
+          var uploaded = await uploadBugReportScreenshot(data.id, data.status_token, _bugReportScreenshotFile).catch(function() { return false; });
+          if (!uploaded) sent = "Feedback sent, but the screenshot could not be attached.";
+        }
+      }
+      if (bugReportStatusEl) bugReportStatusEl.textContent = sent;
       if (bugReportTitle) bugReportTitle.value = "";
       bugReportDesc.value = "";
+      _bugReportScreenshotFile = null;
       setTimeout(closeBugReportModal, 1500);
     } else {
       if (bugReportStatusEl) bugReportStatusEl.textContent = "Failed (status " + res.status + ")";
@@ -124,7 +200,7 @@ if (bugReportDesc) {
   });
 }
 // Screenshot attachment for bug reports
-async function attachBugReportScreenshot(file) {
+function attachBugReportScreenshot(file) {
   if (!file) return;
   if (bugReportFileName) bugReportFileName.textContent = file.name;
   if (bugReportPreview) {
@@ -134,25 +210,8 @@ async function attachBugReportScreenshot(file) {
     bugReportPreview.appendChild(imgPreview);
     bugReportPreview.classList.remove("hidden");
   }
-  try {
-    if (bugReportStatusEl) bugReportStatusEl.textContent = "Uploading screenshot...";
-    var fileBytes = await file.arrayBuffer();
-    var uploadRoom = currentRoomName || "main";
-    var uploadResp = await fetch(apiUrl("/api/chat/upload?room=" + encodeURIComponent(uploadRoom)), {
-      method: "POST",
-      headers: { Authorization: "Bearer " + adminToken },
-      body: fileBytes,
-    });
-    var uploadData = await uploadResp.json().catch(function() { return {}; });
-    if (uploadData.ok && uploadData.url) {
-      _bugReportScreenshotUrl = uploadData.url;
-      if (bugReportStatusEl) bugReportStatusEl.textContent = "Screenshot attached.";
-    } else {
-      if (bugReportStatusEl) bugReportStatusEl.textContent = "Screenshot upload failed.";
-    }
-  } catch (e) {
-    if (bugReportStatusEl) bugReportStatusEl.textContent = "Screenshot upload error: " + e.message;
-  }
+  _bugReportScreenshotFile = file;
+  if (bugReportStatusEl) bugReportStatusEl.textContent = "Screenshot attached.";
 }
 if (bugReportScreenshotBtn && bugReportFileInput) {
   bugReportScreenshotBtn.addEventListener("click", function() {
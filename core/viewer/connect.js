@@ -1566,14 +1566,26 @@ async function connectToRoom({
         const msg = JSON.parse(text);
         if (!msg || !msg.type) return;
         if (msg.type === "sound-play" && msg.soundId) {
+          // Only the server's brokered plays count; a participant publishing
+          // sound-play directly would skip cooldowns and mutes.
+          if (participant || msg.brokered !== true) return;
+          if (soundboardMuted()) return;
           primeSoundboardAudio();
           playSoundboardSound(msg.soundId).catch(() => {});
-          // Show toast with who triggered it and what sound
-          if (msg.senderName && msg.soundName) {
+          // Show toast with who triggered it and what sound, except for our
+          // own brokered play echoed back by the server
+          const ownPlay = !!msg.senderIdentity && msg.senderIdentity === newRoom.localParticipant?.identity;
+          if (ownPlay) {
+            // no toast
+          } else if (msg.senderName && msg.soundName) {
             showToast(msg.senderName + " played " + msg.soundName, 2500);
           } else if (msg.senderName) {
             showToast(msg.senderName + " played a sound", 2500);
           }
+        } else if (msg.type === "soundboard-muted") {
+          if (participant) return;
+          soundboardMutedUntil = Number(msg.mutedUntil) || 0;
+          showToast(msg.mutedUntil ? "A moderator muted the soundboard" : "The soundboard is unmuted", 3000);
         } else if (msg.type === "sound-added" && msg.sound) {
           upsertSoundboardSound(msg.sound);
         } else if (msg.type === "sound-updated" && msg.sound) {
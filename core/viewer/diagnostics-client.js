@@ -1066,6 +1066,9 @@
       sendNow: async function () { await metadataPromise; sealDraft(); return uploadAvailable(true); },
       clearQueuedData: clearQueuedData,
       snapshot: snapshot,
+      // Only while diagnostics are enabled, so a report never names an
+      // install that uploads nothing.
+      installId: function () { return enabled ? installId : null; },
       _recordWindowError: function (event) { if (handlers) handlers.error(event); },
       _recordUnhandledRejection: function (event) { if (handlers) handlers.rejection(event); },
       _sealDraft: sealDraft,
@@ -1081,6 +1081,11 @@ async function skipTrack() {
       showJamError("Skip failed (status " + resp.status + ")");
       return;
     }
+    var result = await resp.json().catch(function() { return {}; });
+    // In vote-to-skip mode a listener's Skip is a vote until enough agree.
+    if (result.skipped === false && result.skip_votes) {
+      showJamToast("Skip vote counted (" + result.skip_votes.votes + " of " + result.skip_votes.required + ")");
+    }
     fetchJamState();
   } catch (e) {
     showJamError("Skip failed: " + e.message);
@@ -1088,6 +1093,26 @@ async function skipTrack() {
   }
 }
 
+async function toggleJamAutoplay() {
+  try {
+    if (!jamActionAllowed("control")) return;
+    var enabled = !(_jamState && _jamState.autoplay);
+    var resp = await fetch(apiUrl("/api/jam/autoplay"), {
+      method: "POST",
+      headers: jamActorHeaders(),
+      body: JSON.stringify({ generation: _jamState && _jamState.generation, enabled: enabled })
+    });
+    if (!resp.ok) {
+      showJamError("Autoplay change failed (status " + resp.status + ")");
+      return;
+    }
+    fetchJamState();
+  } catch (e) {
+    showJamError("Autoplay change failed: " + e.message);
+    debugLog("[jam] toggleJamAutoplay error: " + e);
+  }
+}
+
 // ──────────────────────────────────────────
 // Search
 // ──────────────────────────────────────────
@@ -3101,8 +3126,9 @@ async function fetchJamState() {
   refreshJamSourceLocalControl();
   var requestId = _jamStateRequestGate.begin();
   try {
+    // Jams are per room; the participant token names the caller's room.
     var resp = await fetch(apiUrl("/api/jam/state"), {
-      headers: { "Authorization": "Bearer " + adminToken }
+      headers: jamActorHeaders()
     });
     if (!_jamStateRequestGate.isCurrent(requestId)) return;
     if (!resp.ok) {
@@ -3187,6 +3213,19 @@ function renderJamPanel() {
   if (skipBtn) {
     skipBtn.style.display = _jamState.active ? "" : "none";
     skipBtn.disabled = !contract.canControl;
+    var votes = _jamState.skip_votes;
+    skipBtn.textContent = votes && votes.votes > 0
+      ? "Skip (" + votes.votes + "/" + votes.required + ")"
+      : "Skip";
+  }
+  var autoplayBtn = document.getElementById("jam-autoplay-btn");
+  if (autoplayBtn) {
+    // Autoplay draws on Spotify favorites and history, so local Jams hide it.
+    var spotifyJam = _jamState.active && _jamState.playback_source !== "local";
+    autoplayBtn.style.display = spotifyJam ? "" : "none";
+    autoplayBtn.disabled = !contract.canControl;
+    autoplayBtn.textContent = _jamState.autoplay ? "Autoplay: On" : "Autoplay: Off";
+    autoplayBtn.setAttribute("aria-pressed", _jamState.autoplay ? "true" : "false");
   }
 
   // Now Playing
@@ -4138,6 +4177,9 @@ function initJam() {
   var skipBtn = document.getElementById("jam-skip-btn");
   if (skipBtn) skipBtn.onclick = skipTrack;
 
+  var autoplayBtn = document.getElementById("jam-autoplay-btn");
+  if (autoplayBtn) autoplayBtn.onclick = toggleJamAutoplay;
+
   var joinBtn = document.getElementById("jam-join-btn");
   if (joinBtn) joinBtn.onclick = joinJam;
 
@@ -4278,7 +4320,7 @@ async function fetchBannerState() {
   var requestId = _jamStateRequestGate.begin();
   try {
     var resp = await fetch(apiUrl("/api/jam/state"), {
-      headers: { "Authorization": "Bearer " + adminToken }
+      headers: jamActorHeaders()
     });
     var requestMayApply = window.EchoJamSessionState &&
       typeof window.EchoJamSessionState.shouldApplyBannerResponse === "function"
@@ -15,6 +15,12 @@ let soundboardFavorites = (() => {
 let soundboardCustomOrder = (() => {
   try { return JSON.parse(echoGet("echo-soundboard-order")) || []; } catch { return []; }
 })();
+// Server time (ms) until which a moderator muted this room's soundboard.
+let soundboardMutedUntil = 0;
+
+function soundboardMuted() {
+  return soundboardMutedUntil > Date.now();
+}
 
 // ── Icon constants ──
 const SOUNDBOARD_ICONS = [
@@ -463,8 +469,7 @@ function renderSoundboardCompact(filter) {
     btn.addEventListener("click", () => {
       if (!room) return;
       primeSoundboardAudio();
-      playSoundboardSound(sound.id).catch(() => {});
-      sendSoundboardMessage({ type: "sound-play", soundId: sound.id, senderName: room?.localParticipant?.name || "", soundName: sound.name || "" });
+      requestSoundboardPlay(sound);
     });
     attachSoundboardDragDrop(btn, sound, soundboardCompactGrid, "sound-pill-btn", renderAllSoundboardViews);
     soundboardCompactGrid.appendChild(btn);
@@ -542,8 +547,7 @@ function renderSoundboard() {
     tile.addEventListener("click", () => {
       if (!room) return;
       primeSoundboardAudio();
-      playSoundboardSound(sound.id).catch(() => {});
-      sendSoundboardMessage({ type: "sound-play", soundId: sound.id, senderName: room?.localParticipant?.name || "", soundName: sound.name || "" });
+      requestSoundboardPlay(sound);
     });
 
     // --- Drag and drop (unrestricted) ---
@@ -704,6 +708,7 @@ async function loadSoundboardList() {
       return;
     }
     const data = await res.json().catch(() => ({}));
+    soundboardMutedUntil = Number(data?.mutedUntil) || 0;
     soundboardSounds.clear();
     (data?.sounds || []).forEach((sound) => {
       if (sound?.id) soundboardSounds.set(sound.id, sound);
@@ -823,6 +828,32 @@ async function updateSoundboardSound() {
   }
 }
 
+// The server enforces cooldowns and broadcasts the play to the whole room,
+// this client included. Receivers ignore plays published by participants, so
+// if that broadcast fails the clip only plays here.
+async function requestSoundboardPlay(sound) {
+  try {
+    const res = await fetch(apiUrl("/api/soundboard/play"), {
+      method: "POST",
+      headers: {
+        Authorization: `Bearer ${currentAccessToken}`,
+        "Content-Type": "application/json"
+      },
+      body: JSON.stringify({ roomId: currentRoomName, soundId: sound.id })
+    });
+    const data = await res.json().catch(() => ({}));
+    if (!res.ok || !data?.ok) {
+      setSoundboardHint(data?.error || "Couldn't play that sound.", true);
+      return;
+    }
+    if (data.broadcast) return;
+  } catch {
+    setSoundboardHint("Couldn't play that sound.", true);
+    return;
+  }
+  playSoundboardSound(sound.id).catch(() => {});
+}
+
 function sendSoundboardMessage(message) {
   if (!room || !message) return;
   const payload = JSON.stringify(message);